@@ -890,6 +890,15 @@ app.post('/api/auth/check-password', async (req, res) => {
   }
 });
 
+// End a session started by check-password
+app.post('/api/auth/logout', (req, res) => {
+  const sessionToken = req.body?.sessionToken || req.headers['x-session-token'];
+  if (sessionToken) {
+    authenticatedSessions.delete(sessionToken);
+  }
+  res.json(createSuccessResponse(null, ['Logged out']));
+});
+
 // Set password (initial setup only)
 app.post('/api/auth/set-password', async (req, res) => {
   try {
@@ -11,12 +11,13 @@ import HistoryView from './components/HistoryView';
 import AboutPanel from './components/AboutPanel';
 import PasswordGate from './components/PasswordGate';
 import PasswordSetup from './components/PasswordSetup';
+import RecoveryCodeNotice from './components/RecoveryCodeNotice';
 import logoIcon from './assets/sql-parrot-icon.png';
 
 function AppContent() {
   const [activeTab, setActiveTab] = useState('groups');
   const [isThemeSelectorOpen, setIsThemeSelectorOpen] = useState(false);
-  const { passwordStatus, isAuthenticated, isLoading, logout, refreshStatus } = usePassword();
+  const { passwordStatus, isAuthenticated, isLoading, logout, refreshStatus, recoveryCode } = usePassword();
   const [profileRefreshKey, setProfileRefreshKey] = useState(0);
   const profileSelectorRef = useRef(null);
 
@@ -65,6 +66,15 @@ function AppContent() {
     );
   }
 
+  // A password was just set - show its recovery code once before anything else
+  if (recoveryCode) {
+    return (
+      <ThemeProvider>
+        <RecoveryCodeNotice />
+      </ThemeProvider>
+    );
+  }
+
   // Show password setup only if status is 'not-set' (not 'skipped' or 'set')
   // Rely on backend status, not local state, to avoid showing setup screen incorrectly
   if (passwordStatus?.status === 'not-set') {
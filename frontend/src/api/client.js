@@ -151,14 +151,20 @@ const endpointToCommand = (endpoint, method) => {
 
   // Health check
   if (path === 'health') return 'check_health';
+  if (path === 'health/startup') return 'startup_health_check';
 
   // Connection management
   if (path === 'connection') return 'get_connection';
   if (path === 'test-connection') return 'test_connection';
+  if (path === 'suggest-snapshot-path') return 'suggest_snapshot_path';
   if (path === 'save-connection') return 'save_connection';
 
   // Databases
   if (path === 'databases') return 'get_databases';
+  if (path === 'resource-pressure') return 'get_resource_pressure';
+  if (path === 'server-snapshots') {
+    return method === 'DELETE' ? 'drop_all_server_snapshots' : 'list_server_snapshots';
+  }
 
   // Settings
   if (path === 'settings') {
@@ -168,10 +174,13 @@ const endpointToCommand = (endpoint, method) => {
   // Auth endpoints (UI Security)
   if (path === 'auth/password-status') return 'get_password_status';
   if (path === 'auth/check-password') return 'check_password';
+  if (path === 'auth/password-policy') return 'check_password_policy';
   if (path === 'auth/set-password') return 'set_password';
   if (path === 'auth/change-password') return 'change_password';
   if (path === 'auth/remove-password') return 'remove_password';
   if (path === 'auth/skip-password') return 'skip_password';
+  if (path === 'auth/reset-with-recovery') return 'reset_ui_password_with_recovery';
+  if (path === 'auth/logout') return 'logout';
 
   // History
   if (path === 'history') {
@@ -180,6 +189,7 @@ const endpointToCommand = (endpoint, method) => {
 
   // Test snapshot path
   if (path === 'test-snapshot-path') return 'test_snapshot_path';
+  if (path === 'test-snapshot-path-writable') return 'test_snapshot_path_writable';
 
   // Metadata endpoints
   if (path === 'metadata/status') return 'get_metadata_status';
@@ -187,11 +197,18 @@ const endpointToCommand = (endpoint, method) => {
 
   // Snapshots verification/cleanup
   if (path === 'snapshots/verify') return 'verify_snapshots';
+  if (path === 'snapshots/rollback-latest') return 'rollback_latest';
+
+  // Interrupted operations: operations/:id/resume, operations/:id/abandon
+  if (path === 'operations/interrupted') return 'get_interrupted_operations';
+  if (segments[0] === 'operations' && segments[2] === 'resume') return 'resume_operation';
+  if (segments[0] === 'operations' && segments[2] === 'abandon') return 'abandon_operation';
 
   // Groups
   if (path === 'groups') {
     return method === 'POST' ? 'create_group' : 'get_groups';
   }
+  if (path === 'groups/conflicts') return 'get_database_group_conflicts';
 
   // Group operations with ID: groups/:id
   if (segments[0] === 'groups' && segments.length === 2) {
@@ -208,8 +225,12 @@ const endpointToCommand = (endpoint, method) => {
   // Snapshot operations: snapshots/:id/...
   if (segments[0] === 'snapshots' && segments.length >= 2) {
     if (segments[2] === 'check-external') return 'check_external_snapshots';
+    if (segments[2] === 'acknowledge-external') return 'acknowledge_external_snapshots';
+    if (segments[2] === 'files') return 'get_snapshot_files';
     if (segments[2] === 'rollback') return 'rollback_snapshot';
+    if (segments[2] === 'rollback-preview') return 'preview_rollback';
     if (segments[2] === 'cleanup') return 'cleanup_snapshot';
+    if (segments[2] === 'resnapshot') return 'resnapshot';
     if (method === 'DELETE') return 'delete_snapshot';
   }
 
@@ -221,6 +242,7 @@ const endpointToCommand = (endpoint, method) => {
     if (segments.length === 2 && method === 'PUT') return 'update_profile';
     if (segments.length === 2 && method === 'DELETE') return 'delete_profile';
     if (segments[2] === 'activate' && method === 'POST') return 'set_active_profile';
+    if (segments[2] === 'connection-history') return 'get_connection_history';
   }
 
   // Fallback - convert path to snake_case command
@@ -254,6 +276,11 @@ const extractPathParams = (endpoint) => {
     params.id = segments[1];
   }
 
+  // operations/:id/...
+  if (segments[0] === 'operations' && segments.length >= 3) {
+    params.id = segments[1];
+  }
+
   // profiles/:id/... - use 'profileId' for all profile operations (Tauri v2 converts camelCase to snake_case)
   // Handle both direct operations (profiles/:id) and nested operations (profiles/:id/activate)
   if (segments[0] === 'profiles' && segments.length >= 2) {
@@ -286,8 +313,20 @@ export async function apiCall(endpoint, options = {}) {
       // Merge path params with body
       const args = { ...pathParams, ...body };
 
+      // Sensitive commands check the session token while a UI password is set; others ignore it
+      const sessionToken = sessionStorage.getItem('sessionToken');
+      if (sessionToken && args.sessionToken === undefined) {
+        args.sessionToken = sessionToken;
+      }
+
       // Use the Tauri global directly - available in Tauri v2 via window.__TAURI__.core
       const result = await window.__TAURI__.core.invoke(command, args);
+
+      // Match the HTTP path's 401 handling: the session is gone, so the user has to unlock again
+      if (result?.errorCode === 'AuthRequired') {
+        sessionStorage.removeItem('sessionToken');
+        return { ...normalizeResponse(endpoint, result), requiresAuth: true };
+      }
       return normalizeResponse(endpoint, result);
     } catch (error) {
       // Wrap Tauri errors in consistent format
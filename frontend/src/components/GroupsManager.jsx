@@ -650,8 +650,12 @@ const GroupsManager = ({ onGroupsChanged }) => {
 
   const handleRollbackSnapshot = async (snapshot) => {
     // Pre-check for external snapshots before showing confirmation
+    let simpleRecoveryDatabases = [];
     try {
       const checkData = await api.get(`/api/snapshots/${snapshot.id}/check-external`);
+      simpleRecoveryDatabases = (checkData.recoveryModels || [])
+        .filter(m => m.recoveryModel === 'SIMPLE')
+        .map(m => m.database);
 
       if (checkData.hasExternalSnapshots) {
         showConfirmation({
@@ -691,11 +695,22 @@ const GroupsManager = ({ onGroupsChanged }) => {
       // Continue with rollback confirmation if check fails
     }
 
+    // Other groups sharing a database lose their snapshots of it; list them in the confirmation
+    let crossGroupSnapshots = [];
+    try {
+      const preview = await api.get(`/api/snapshots/${snapshot.id}/rollback-preview`);
+      crossGroupSnapshots = preview.crossGroupSnapshots || [];
+    } catch (error) {
+      console.error('Error previewing rollback:', error);
+    }
+
     const initialCreateCheckpoint = settings.preferences?.autoCreateCheckpoint ?? true;
     setDiscardModal({
       open: true,
       snapshot,
-      createCheckpoint: initialCreateCheckpoint
+      createCheckpoint: initialCreateCheckpoint,
+      simpleRecoveryDatabases,
+      crossGroupSnapshots
     });
   };
 
@@ -707,7 +722,8 @@ const GroupsManager = ({ onGroupsChanged }) => {
     setLockedGroupId(snapshot.groupId);
     try {
       const data = await api.post(`/api/snapshots/${snapshot.id}/rollback`, {
-        autoCreateCheckpoint: discardModal.createCheckpoint
+        autoCreateCheckpoint: discardModal.createCheckpoint,
+        confirmCrossGroup: (discardModal.crossGroupSnapshots?.length ?? 0) > 0
       });
 
       // Handle external snapshots blocking rollback
@@ -869,6 +885,28 @@ const GroupsManager = ({ onGroupsChanged }) => {
     });
   };
 
+  // Take a new snapshot of the same databases as an existing one; the original stays as it is
+  const handleResnapshot = async (snapshot) => {
+    setOperationLoading(prev => ({ ...prev, createSnapshot: true }));
+    setLockedGroupId(snapshot.groupId);
+    try {
+      const data = await api.post(`/api/snapshots/${snapshot.id}/resnapshot`, { snapshotId: snapshot.id });
+
+      if (data.success) {
+        showSuccess(`Snapshot "${snapshot.displayName}" re-taken at the current state`);
+        await fetchSnapshots(snapshot.groupId, false, true);
+      } else {
+        showError(data.messages?.error?.[0] || data.message || 'Failed to re-snapshot. Please try again.');
+      }
+    } catch (error) {
+      console.error('Error re-snapshotting:', error);
+      showError('Failed to re-snapshot. Please try again.');
+    } finally {
+      setOperationLoading(prev => ({ ...prev, createSnapshot: false }));
+      setLockedGroupId(null);
+    }
+  };
+
   // Verification functions
   const runVerification = async () => {
     setIsVerifying(true);
@@ -878,6 +916,9 @@ const GroupsManager = ({ onGroupsChanged }) => {
       // Verify all groups - collect results from each
       let allOrphaned = [];
       let allStale = [];
+      let allRenamed = [];
+      // Leftover files are per server path, so every group on a profile reports the same ones
+      const allLeftoverFiles = new Set();
       let allVerified = true;
 
       for (const group of groups) {
@@ -911,6 +952,10 @@ const GroupsManager = ({ onGroupsChanged }) => {
           // Fallback for Rust/Tauri field names
           allStale.push(...result.staleMetadata);
         }
+        if (result.renamedOrMissing?.length > 0) {
+          allRenamed.push(...result.renamedOrMissing);
+        }
+        (result.leftoverFiles || []).forEach(file => allLeftoverFiles.add(file));
       }
       console.log('Verify totals:', { allOrphaned, allStale, allVerified });
 
@@ -919,6 +964,12 @@ const GroupsManager = ({ onGroupsChanged }) => {
       if (allOrphaned.length > 0) {
         issues.push(`${allOrphaned.length} external snapshot${allOrphaned.length === 1 ? '' : 's'} found on SQL Server`);
       }
+      if (allRenamed.length > 0) {
+        issues.push(`${allRenamed.length} snapshot${allRenamed.length === 1 ? '' : 's'} whose source database was renamed or dropped`);
+      }
+      if (allLeftoverFiles.size > 0) {
+        issues.push(`${allLeftoverFiles.size} leftover snapshot file${allLeftoverFiles.size === 1 ? '' : 's'} on disk: ${[...allLeftoverFiles].join(', ')}`);
+      }
       if (allStale.length > 0) {
         issues.push(`${allStale.length} stale metadata entr${allStale.length === 1 ? 'y' : 'ies'} (snapshots no longer on server)`);
       }
@@ -928,6 +979,8 @@ const GroupsManager = ({ onGroupsChanged }) => {
         issues,
         orphanedInSQL: allOrphaned,
         missingInSQL: allStale,
+        renamedOrMissing: allRenamed,
+        leftoverFiles: [...allLeftoverFiles],
         inaccessibleSnapshots: allStale // Same as stale for cleanup purposes
       };
 
@@ -1551,6 +1604,18 @@ const GroupsManager = ({ onGroupsChanged }) => {
                               >
                                 Discard Changes
                               </button>
+                              <button
+                                onClick={() => handleResnapshot(snapshot)}
+                                disabled={lockedGroupId === group.id}
+                                title="Take a new snapshot of the same databases at their current state. This snapshot is kept."
+                                className={`px-3 py-1 text-xs font-medium text-white rounded transition-colors ${
+                                  lockedGroupId === group.id
+                                    ? 'bg-primary-400 cursor-not-allowed'
+                                    : 'bg-primary-600 hover:bg-primary-700'
+                                }`}
+                              >
+                                Re-snapshot
+                              </button>
                             </>
                           ) : (
                             // No successful databases - show cleanup option
@@ -1708,6 +1773,12 @@ const GroupsManager = ({ onGroupsChanged }) => {
                   <li>All data changes made after this snapshot will be <strong>permanently lost</strong></li>
                   <li>All schema changes (stored procs, indexes, etc.) will be reverted</li>
                   <li>{(snapshots[discardModal.snapshot.groupId]?.length ?? 1) === 1 ? 'The snapshot' : `All ${snapshots[discardModal.snapshot.groupId]?.length ?? 0} snapshots`} in this group will be removed — you won&apos;t be able to restore to a different snapshot afterward</li>
+                  {discardModal.simpleRecoveryDatabases?.length > 0 && (
+                    <li>{discardModal.simpleRecoveryDatabases.join(', ')} {discardModal.simpleRecoveryDatabases.length === 1 ? 'uses' : 'use'} the SIMPLE recovery model — there are no log backups to recover these changes later</li>
+                  )}
+                  {discardModal.crossGroupSnapshots?.length > 0 && (
+                    <li>Snapshots from other groups that share these databases will also be dropped: {discardModal.crossGroupSnapshots.map(c => `${c.displayName} (${c.groupName}, ${c.database})`).join(', ')}</li>
+                  )}
                 </ul>
               </div>
 
@@ -158,6 +158,23 @@ const HistoryView = () => {
         return `Restored snapshot "${getProperty('snapshotName') || getProperty('displayName')}" for group "${getGroupName()}"`;
       case 'delete_snapshot':
         return `Deleted snapshot "${getProperty('snapshotName') || getProperty('displayName')}" from group "${getGroupName()}"`;
+      case 'renumber_snapshots':
+        return `Renumbered ${getProperty('renumberedCount')} of ${getProperty('snapshotCount')} snapshots in group "${getGroupName()}"`;
+      case 'import_group':
+        return `Imported group "${getGroupName()}" (${getProperty('databaseCount')} databases)`;
+      case 'move_group_to_profile':
+        return `Moved group "${getGroupName()}" to profile "${getProperty('profileName')}"`;
+      case 'bulk_create_groups':
+      case 'create_groups_by_regex':
+        return `Created ${(getProperty('groupNames') || []).length} group(s) in bulk`;
+      case 'reconcile_metadata':
+        return `Reconciled group "${getGroupName()}": removed ${(getProperty('removedStale') || []).length} stale snapshot(s), updated ${(getProperty('updated') || []).length}`;
+      case 'drop_orphaned_snapshots':
+        return `Dropped ${(getProperty('dropped') || []).length} orphaned snapshot(s) from group "${getGroupName()}"`;
+      case 'drop_all_server_snapshots':
+        return `Dropped ${getProperty('dropped')} snapshot(s) from the server (${getProperty('failed')} failed)`;
+      case 'retry_snapshot_database':
+        return `Retried snapshot of ${getProperty('database')} in "${getProperty('displayName')}" for group "${getGroupName()}"`;
       case 'trim_history':
         return `${getProperty('removedCount')} history entries removed by changing max from ${getProperty('previousCount')} to ${getProperty('newMaxEntries')}`;
       case 'migrate_config_to_profiles': {
@@ -173,6 +190,18 @@ const HistoryView = () => {
           return `Migrated ${profileCount} connections in config.json to profiles (${profileNames})`;
         }
       }
+      case 'update_settings':
+        return 'Updated settings';
+      case 'reset_settings':
+        return 'Reset settings to defaults';
+      case 'create_profile':
+        return `Created profile "${getProperty('profileName')}"`;
+      case 'update_profile':
+        return `Updated profile "${getProperty('profileName')}"`;
+      case 'delete_profile':
+        return `Deleted profile "${getProperty('profileName') || getProperty('profileId')}"`;
+      case 'set_active_profile':
+        return `Switched active profile to "${getProperty('profileName') || getProperty('profileId')}"`;
       default:
         return `Unknown operation: ${operation.type}`;
     }
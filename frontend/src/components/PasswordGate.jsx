@@ -7,7 +7,9 @@ const PasswordGate = () => {
   const [password, setPassword] = useState('');
   const [error, setError] = useState('');
   const [isLoading, setIsLoading] = useState(false);
-  const { checkPassword } = usePassword();
+  const [useRecoveryCode, setUseRecoveryCode] = useState(false);
+  const [recoveryCode, setRecoveryCode] = useState('');
+  const { checkPassword, resetWithRecoveryCode } = usePassword();
 
   const handleSubmit = async (e) => {
     e.preventDefault();
@@ -28,6 +30,30 @@ const PasswordGate = () => {
     }
   };
 
+  const handleRecovery = async (e) => {
+    e.preventDefault();
+    setError('');
+
+    if (!recoveryCode.trim()) {
+      setError('Recovery code is required');
+      return;
+    }
+
+    setIsLoading(true);
+    // On success the password is cleared and the app moves on to password setup
+    const result = await resetWithRecoveryCode(recoveryCode);
+    setIsLoading(false);
+
+    if (!result.success) {
+      setError(result.error || 'Invalid recovery code');
+    }
+  };
+
+  const toggleRecovery = () => {
+    setUseRecoveryCode(!useRecoveryCode);
+    setError('');
+  };
+
   return (
     <div className="min-h-screen bg-secondary-50 dark:bg-secondary-900 flex items-center justify-center px-4">
       <div className="max-w-md w-full">
@@ -40,32 +66,66 @@ const PasswordGate = () => {
               SQL Parrot
             </h1>
             <p className="text-secondary-600 dark:text-secondary-400">
-              Enter your password to continue
+              {useRecoveryCode
+                ? 'Enter the recovery code you saved when the password was set'
+                : 'Enter your password to continue'}
             </p>
           </div>
 
-          <form onSubmit={handleSubmit} className="space-y-4">
-            <FormInput
-              label="Password"
-              type="password"
-              value={password}
-              onChange={setPassword}
-              error={error}
-              touched={!!error}
-              placeholder="Enter password"
-              required
-              disabled={isLoading}
-              autoFocus
-            />
-
-            <button
-              type="submit"
-              disabled={isLoading || !password}
-              className="w-full btn-primary disabled:opacity-50 disabled:cursor-not-allowed"
-            >
-              {isLoading ? 'Verifying...' : 'Continue'}
-            </button>
-          </form>
+          {useRecoveryCode ? (
+            <form onSubmit={handleRecovery} className="space-y-4">
+              <FormInput
+                label="Recovery code"
+                value={recoveryCode}
+                onChange={setRecoveryCode}
+                error={error}
+                touched={!!error}
+                placeholder="XXXX-XXXX-XXXX-XXXX-XXXX-XXXX-XXXX-XXXX"
+                required
+                disabled={isLoading}
+                autoFocus
+              />
+
+              <button
+                type="submit"
+                disabled={isLoading || !recoveryCode.trim()}
+                className="w-full btn-primary disabled:opacity-50 disabled:cursor-not-allowed"
+              >
+                {isLoading ? 'Verifying...' : 'Reset Password'}
+              </button>
+            </form>
+          ) : (
+            <form onSubmit={handleSubmit} className="space-y-4">
+              <FormInput
+                label="Password"
+                type="password"
+                value={password}
+                onChange={setPassword}
+                error={error}
+                touched={!!error}
+                placeholder="Enter password"
+                required
+                disabled={isLoading}
+                autoFocus
+              />
+
+              <button
+                type="submit"
+                disabled={isLoading || !password}
+                className="w-full btn-primary disabled:opacity-50 disabled:cursor-not-allowed"
+              >
+                {isLoading ? 'Verifying...' : 'Continue'}
+              </button>
+            </form>
+          )}
+
+          <button
+            type="button"
+            onClick={toggleRecovery}
+            className="w-full mt-4 text-sm text-primary-600 hover:text-primary-700 dark:text-primary-400"
+          >
+            {useRecoveryCode ? 'Back to password' : 'Forgot password?'}
+          </button>
         </div>
       </div>
     </div>
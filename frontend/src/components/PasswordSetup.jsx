@@ -112,12 +112,13 @@ const PasswordSetup = ({ onComplete }) => {
                     Important: Password Recovery
                   </h3>
                   <p className="text-sm text-yellow-800 dark:text-yellow-300">
-                    If you forget your password, you will need to reset your SQL Parrot configuration.
+                    You'll be shown a one-time recovery code after setting the password. If you forget
+                    the password and lose that code, you will need to reset your SQL Parrot configuration.
                     This will delete all database connection profiles, groups, snapshot metadata,
                     operation history, and application settings.
                   </p>
                   <p className="text-sm text-yellow-800 dark:text-yellow-300 mt-2">
-                    <strong>Please remember your password or store it securely.</strong>
+                    <strong>Please remember your password and store the recovery code securely.</strong>
                   </p>
                 </div>
               </div>
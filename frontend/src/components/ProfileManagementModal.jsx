@@ -44,6 +44,15 @@ const EnvVarInfoPopover = () => {
   );
 };
 
+const DEFAULT_SNAPSHOT_PATH = '/var/opt/mssql/snapshots';
+
+// Select value for a profile's autoCreateCheckpoint override (null/undefined follows the global setting)
+const checkpointOverrideValue = (value) => {
+  if (value === true) return 'on';
+  if (value === false) return 'off';
+  return 'default';
+};
+
 const ProfileManagementModal = ({ isOpen, onClose, onSave, editingProfile }) => {
   const [formData, setFormData] = useState({
     name: '',
@@ -53,7 +62,11 @@ const ProfileManagementModal = ({ isOpen, onClose, onSave, editingProfile }) =>
     username: '',
     password: '',
     trustCertificate: true,
-    snapshotPath: '/var/opt/mssql/snapshots',
+    encryptionMode: 'on',
+    snapshotPath: DEFAULT_SNAPSHOT_PATH,
+    checkpointOverride: 'default',
+    snapshotNameTemplate: '',
+    applicationName: '',
     description: '',
     notes: ''
   });
@@ -66,6 +79,23 @@ const ProfileManagementModal = ({ isOpen, onClose, onSave, editingProfile }) =>
   const [testFailedMessage, setTestFailedMessage] = useState('');
   const { showError, showSuccess } = useNotification();
 
+  // For a new profile, replace an untouched default snapshot path with one suited to the server's OS
+  const suggestSnapshotPath = async (connectionData) => {
+    try {
+      const response = await api.post('/api/suggest-snapshot-path', connectionData);
+      const suggested = response.data?.snapshotPath;
+      if (response.success && suggested) {
+        setFormData(prev => {
+          const untouched = !prev.snapshotPath.trim() || prev.snapshotPath === DEFAULT_SNAPSHOT_PATH;
+          return untouched ? { ...prev, snapshotPath: suggested } : prev;
+        });
+      }
+    } catch (error) {
+      // A suggestion is optional; the user can still type a path
+      console.warn('Could not suggest a snapshot path:', error);
+    }
+  };
+
   // Use refs to track test state (synchronous, prevents race conditions with React state)
   const isTestingRef = useRef(false);
   const testFailedRef = useRef(false);
@@ -82,7 +112,11 @@ const ProfileManagementModal = ({ isOpen, onClose, onSave, editingProfile }) =>
           username: editingProfile.username || '',
           password: '', // Don't pre-fill password
           trustCertificate: editingProfile.trustCertificate ?? true,
-          snapshotPath: editingProfile.snapshotPath || '/var/opt/mssql/snapshots',
+          encryptionMode: editingProfile.encryptionMode || 'on',
+          snapshotPath: editingProfile.snapshotPath || DEFAULT_SNAPSHOT_PATH,
+          checkpointOverride: checkpointOverrideValue(editingProfile.overrides?.autoCreateCheckpoint),
+          snapshotNameTemplate: editingProfile.overrides?.snapshotNameTemplate || '',
+          applicationName: editingProfile.overrides?.applicationName || '',
           description: editingProfile.description || '',
           notes: editingProfile.notes || ''
         });
@@ -96,7 +130,11 @@ const ProfileManagementModal = ({ isOpen, onClose, onSave, editingProfile }) =>
           username: '',
           password: '',
           trustCertificate: true,
-          snapshotPath: '/var/opt/mssql/snapshots',
+          encryptionMode: 'on',
+          snapshotPath: DEFAULT_SNAPSHOT_PATH,
+          checkpointOverride: 'default',
+          snapshotNameTemplate: '',
+          applicationName: '',
           description: '',
           notes: ''
         });
@@ -198,6 +236,7 @@ const ProfileManagementModal = ({ isOpen, onClose, onSave, editingProfile }) =>
         username: formData.username.trim(),
         password: passwordToTest, // Empty string if not provided - backend will use saved password when editing
         trustCertificate: formData.trustCertificate,
+        encryptionMode: formData.encryptionMode,
         ...(editingProfile && { profileId: editingProfile.id }) // Always include profile ID when editing
       };
 
@@ -210,6 +249,9 @@ const ProfileManagementModal = ({ isOpen, onClose, onSave, editingProfile }) =>
         testFailedRef.current = false;
         setShowSaveConfirm(false);
         setTestFailedMessage('');
+        if (!editingProfile) {
+          await suggestSnapshotPath(testData);
+        }
       } else {
         setTestResult({
           success: false,
@@ -267,6 +309,7 @@ const ProfileManagementModal = ({ isOpen, onClose, onSave, editingProfile }) =>
           username: formData.username.trim(),
           password: passwordToTest, // Empty string if not provided - backend will use saved password when editing
           trustCertificate: formData.trustCertificate,
+          encryptionMode: formData.encryptionMode,
           ...(editingProfile && { profileId: editingProfile.id }) // Always include profile ID when editing
         };
 
@@ -338,7 +381,13 @@ const ProfileManagementModal = ({ isOpen, onClose, onSave, editingProfile }) =>
         username: formData.username.trim(),
         password: formData.password || undefined, // Only send if provided (for editing)
         trustCertificate: formData.trustCertificate,
+        encryptionMode: formData.encryptionMode,
         snapshotPath: formData.snapshotPath.trim(),
+        overrides: {
+          autoCreateCheckpoint: formData.checkpointOverride === 'default' ? null : formData.checkpointOverride === 'on',
+          snapshotNameTemplate: formData.snapshotNameTemplate.trim() || null,
+          applicationName: formData.applicationName.trim() || null
+        },
         description: formData.description.trim() || null,
         notes: formData.notes.trim() || null
       };
@@ -552,6 +601,74 @@ const ProfileManagementModal = ({ isOpen, onClose, onSave, editingProfile }) =>
             </label>
           </div>
 
+          {/* Encryption off (legacy servers only) */}
+          <div>
+            <div className="flex items-center space-x-2">
+              <input
+                type="checkbox"
+                id="encryptionOff"
+                checked={formData.encryptionMode === 'off'}
+                onChange={(e) => setFormData({ ...formData, encryptionMode: e.target.checked ? 'off' : 'on' })}
+                className="w-4 h-4 text-primary-600 bg-secondary-100 border-secondary-300 rounded focus:ring-primary-500 dark:focus:ring-primary-600 dark:ring-offset-secondary-800 focus:ring-2 dark:bg-secondary-700 dark:border-secondary-600"
+              />
+              <label htmlFor="encryptionOff" className="text-sm text-secondary-700 dark:text-secondary-300">
+                Turn off encryption (legacy servers only)
+              </label>
+            </div>
+            {formData.encryptionMode === 'off' && (
+              <p className="text-xs text-red-600 dark:text-red-400 mt-1">
+                The login and all data will be sent unencrypted. Use only for SQL Server 2008/2012 instances that can't complete a TLS handshake, on a trusted network.
+              </p>
+            )}
+          </div>
+
+          {/* Per-profile overrides of global settings */}
+          <div>
+            <label htmlFor="profile-checkpoint-override" className="block text-sm font-medium text-secondary-700 dark:text-secondary-300 mb-1">
+              Auto-create checkpoint after rollback
+            </label>
+            <select
+              id="profile-checkpoint-override"
+              value={formData.checkpointOverride}
+              onChange={(e) => setFormData({ ...formData, checkpointOverride: e.target.value })}
+              className="w-full px-3 py-2 border border-secondary-300 dark:border-secondary-600 rounded-lg bg-white dark:bg-secondary-700 text-secondary-900 dark:text-white focus:ring-2 focus:ring-primary-500 focus:border-primary-500"
+            >
+              <option value="default">Use global setting</option>
+              <option value="on">Always for this profile</option>
+              <option value="off">Never for this profile</option>
+            </select>
+          </div>
+
+          <div>
+            <label htmlFor="profile-name-template" className="block text-sm font-medium text-secondary-700 dark:text-secondary-300 mb-1">
+              Snapshot name template (optional)
+            </label>
+            <FormInput
+              id="profile-name-template"
+              value={formData.snapshotNameTemplate}
+              onChange={(value) => setFormData({ ...formData, snapshotNameTemplate: value })}
+              placeholder="{database}_snapshot_{group}_{sequence}"
+            />
+            <p className="text-xs text-secondary-500 dark:text-secondary-400 mt-1">
+              Leave blank to use the global template. Must include {'{database}'} and {'{sequence}'}; {'{group}'} is optional.
+            </p>
+          </div>
+
+          <div>
+            <label htmlFor="profile-application-name" className="block text-sm font-medium text-secondary-700 dark:text-secondary-300 mb-1">
+              Application name (optional)
+            </label>
+            <FormInput
+              id="profile-application-name"
+              value={formData.applicationName}
+              onChange={(value) => setFormData({ ...formData, applicationName: value })}
+              placeholder="SQL Parrot"
+            />
+            <p className="text-xs text-secondary-500 dark:text-secondary-400 mt-1">
+              Shown as program_name in sys.dm_exec_sessions for this profile's connections.
+            </p>
+          </div>
+
           {/* Description */}
           <div>
             <label htmlFor="profile-description" className="block text-sm font-medium text-secondary-700 dark:text-secondary-300 mb-1">
@@ -0,0 +1,58 @@
+import { useState } from 'react';
+import { KeyRound, Copy, Check } from 'lucide-react';
+import { usePassword } from '../contexts/PasswordContext';
+
+// Shows the recovery code returned when a UI password is set; it can't be retrieved again
+const RecoveryCodeNotice = () => {
+  const { recoveryCode, dismissRecoveryCode } = usePassword();
+  const [copied, setCopied] = useState(false);
+
+  const handleCopy = async () => {
+    try {
+      await navigator.clipboard.writeText(recoveryCode);
+      setCopied(true);
+    } catch (error) {
+      console.error('Error copying recovery code:', error);
+    }
+  };
+
+  return (
+    <div className="min-h-screen bg-secondary-50 dark:bg-secondary-900 flex items-center justify-center px-4">
+      <div className="max-w-md w-full">
+        <div className="bg-white dark:bg-secondary-800 rounded-lg shadow-lg p-8">
+          <div className="text-center mb-6">
+            <div className="inline-flex items-center justify-center w-16 h-16 bg-primary-100 dark:bg-primary-900 rounded-full mb-4">
+              <KeyRound className="w-8 h-8 text-primary-600 dark:text-primary-400" />
+            </div>
+            <h1 className="text-2xl font-bold text-secondary-900 dark:text-white mb-2">
+              Save Your Recovery Code
+            </h1>
+            <p className="text-secondary-600 dark:text-secondary-400">
+              If you forget your password, this code is the only way back in. It won't be shown again.
+            </p>
+          </div>
+
+          <div className="flex items-center justify-between gap-2 p-3 mb-6 rounded-md bg-secondary-100 dark:bg-secondary-700">
+            <code className="font-mono text-sm text-secondary-900 dark:text-white break-all">
+              {recoveryCode}
+            </code>
+            <button
+              type="button"
+              onClick={handleCopy}
+              className="p-2 text-secondary-600 hover:text-secondary-900 dark:text-secondary-300 dark:hover:text-white"
+              title="Copy recovery code"
+            >
+              {copied ? <Check className="w-4 h-4" /> : <Copy className="w-4 h-4" />}
+            </button>
+          </div>
+
+          <button type="button" onClick={dismissRecoveryCode} className="w-full btn-primary">
+            I've saved it
+          </button>
+        </div>
+      </div>
+    </div>
+  );
+};
+
+export default RecoveryCodeNotice;
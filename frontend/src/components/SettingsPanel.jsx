@@ -331,6 +331,21 @@ const SettingsPanel = () => {
         </h3>
 
         <div className="space-y-4">
+          {metadataStatus?.corruptBackup && (
+            <div className="border rounded-lg p-4 bg-red-50 dark:bg-red-900/20 border-red-200 dark:border-red-800">
+              <div className="flex items-start space-x-2">
+                <AlertCircle className="w-5 h-5 text-red-600 flex-shrink-0" />
+                <div className="text-sm text-red-800 dark:text-red-200">
+                  <p className="font-medium">The metadata database was corrupt and has been replaced</p>
+                  <p className="mt-1">
+                    Connection profiles, groups, snapshot records, and history were reset. Snapshots on SQL Server are untouched.
+                  </p>
+                  <p className="mt-1 font-mono text-xs break-all">{metadataStatus.corruptBackup}</p>
+                </div>
+              </div>
+            </div>
+          )}
+
           {metadataStatus && (
             <div className="border rounded-lg p-4 bg-green-50 dark:bg-green-900/20 border-green-200 dark:border-green-800">
               <div className="flex items-center justify-between mb-2">
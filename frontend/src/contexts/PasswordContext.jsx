@@ -16,6 +16,8 @@ export const PasswordProvider = ({ children }) => {
   const [passwordStatus, setPasswordStatus] = useState(null);
   const [isAuthenticated, setIsAuthenticated] = useState(false);
   const [isLoading, setIsLoading] = useState(true);
+  // Recovery code from set-password, kept here so it survives the status refresh that follows
+  const [recoveryCode, setRecoveryCode] = useState(null);
 
   const checkPasswordStatus = useCallback(async () => {
     try {
@@ -75,15 +77,15 @@ export const PasswordProvider = ({ children }) => {
       const response = await api.post('/api/auth/check-password', { password });
 
       // Handle both formats:
-      // - Node.js: { success: true, data: { authenticated: true, sessionToken: "..." } }
-      // - Tauri: { success: true, data: true } (just a boolean)
+      // - Node.js and Tauri: { success: true, data: { authenticated: true, sessionToken: "..." } }
+      // - Older backends: { success: true, data: true } (just a boolean)
       const isAuthenticated = response.success && (
         response.data?.authenticated === true ||
         response.data === true
       );
 
       if (isAuthenticated) {
-        // Store session token if provided (Node.js only, Tauri doesn't use sessions)
+        // Store session token if provided; sensitive commands require it while a password is set
         if (response.data?.sessionToken) {
           sessionStorage.setItem('sessionToken', response.data.sessionToken);
         }
@@ -108,6 +110,10 @@ export const PasswordProvider = ({ children }) => {
       const response = await api.post('/api/auth/set-password', { password, confirm });
 
       if (response.success) {
+        // Shown once by RecoveryCodeNotice; the backend only keeps its hash
+        if (response.data?.recoveryCode) {
+          setRecoveryCode(response.data.recoveryCode);
+        }
         // After setting password, user needs to authenticate
         setIsAuthenticated(false);
         sessionStorage.removeItem('sessionToken');
@@ -197,7 +203,37 @@ export const PasswordProvider = ({ children }) => {
     }
   };
 
-  const logout = () => {
+  const resetWithRecoveryCode = async (code) => {
+    try {
+      const response = await api.post('/api/auth/reset-with-recovery', { recoveryCode: code });
+
+      if (response.success) {
+        sessionStorage.removeItem('sessionToken');
+        await checkPasswordStatus();
+        return { success: true };
+      } else {
+        return {
+          success: false,
+          error: response.messages?.error?.[0] || 'Invalid recovery code'
+        };
+      }
+    } catch (error) {
+      return {
+        success: false,
+        error: error.message || 'Failed to reset password'
+      };
+    }
+  };
+
+  const logout = async () => {
+    const sessionToken = sessionStorage.getItem('sessionToken');
+    if (sessionToken) {
+      try {
+        await api.post('/api/auth/logout', { sessionToken });
+      } catch (error) {
+        console.error('Error ending session:', error);
+      }
+    }
     sessionStorage.removeItem('sessionToken');
     setIsAuthenticated(false);
   };
@@ -211,6 +247,9 @@ export const PasswordProvider = ({ children }) => {
     changePassword,
     removePassword,
     skipPassword,
+    resetWithRecoveryCode,
+    recoveryCode,
+    dismissRecoveryCode: () => setRecoveryCode(null),
     logout,
     refreshStatus: checkPasswordStatus
   };
@@ -30,9 +30,11 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             name TEXT NOT NULL,
             databases TEXT NOT NULL,
             profile_id TEXT,
+            description TEXT,
             created_by TEXT,
             created_at TEXT NOT NULL,
             updated_at TEXT NOT NULL,
+            sequence_high_water INTEGER NOT NULL DEFAULT 0,
             UNIQUE(name, profile_id)
         );
 
@@ -46,7 +48,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             created_by TEXT,
             database_snapshots TEXT NOT NULL,
             is_automatic INTEGER DEFAULT 0,
-            FOREIGN KEY (group_id) REFERENCES groups(id)
+            notes TEXT,
+            origin TEXT,
+            FOREIGN KEY (group_id) REFERENCES groups(id) ON DELETE CASCADE
         );
 
         -- History table
@@ -56,7 +60,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             timestamp TEXT NOT NULL,
             user_name TEXT,
             details TEXT,
-            results TEXT
+            results TEXT,
+            group_id TEXT
         );
 
         -- Settings table (single row)
@@ -86,14 +91,43 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             notes TEXT,
             is_active INTEGER DEFAULT 0,
             created_at TEXT NOT NULL,
-            updated_at TEXT NOT NULL
+            updated_at TEXT NOT NULL,
+            server_platform TEXT,
+            encryption_mode TEXT NOT NULL DEFAULT 'on',
+            overrides TEXT
+        );
+
+        -- Recent check_health results per profile
+        CREATE TABLE connection_checks (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            profile_id TEXT NOT NULL,
+            timestamp TEXT NOT NULL,
+            success INTEGER NOT NULL,
+            latency_ms INTEGER,
+            error TEXT,
+            FOREIGN KEY (profile_id) REFERENCES profiles(id) ON DELETE CASCADE
+        );
+
+        -- Unfinished multi-step operations
+        CREATE TABLE operations (
+            id TEXT PRIMARY KEY,
+            operation_type TEXT NOT NULL,
+            group_id TEXT NOT NULL,
+            snapshot_id TEXT,
+            status TEXT NOT NULL,
+            steps TEXT NOT NULL,
+            started_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            FOREIGN KEY (group_id) REFERENCES groups(id) ON DELETE CASCADE
         );
 
         -- Indexes
         CREATE INDEX idx_snapshots_group ON snapshots(group_id);
         CREATE INDEX idx_history_timestamp ON history(timestamp);
+        CREATE INDEX idx_history_group ON history(group_id, timestamp);
         CREATE INDEX idx_profiles_active ON profiles(is_active);
         CREATE INDEX idx_groups_profile_id ON groups(profile_id);
+        CREATE INDEX idx_connection_checks_profile ON connection_checks(profile_id, timestamp);
         "#,
     )?;
 
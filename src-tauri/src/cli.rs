@@ -0,0 +1,289 @@
+// ABOUTME: Headless command-line mode (`sql-parrot --cli ...`) for scripting snapshots from CI
+// ABOUTME: Runs the same command logic as the app and prints the ApiResponse as JSON to stdout
+
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::commands;
+use crate::db::MetadataStore;
+use crate::group_locks::GroupLocks;
+use crate::messages;
+use crate::webhooks;
+use crate::ApiResponse;
+
+const USAGE: &str = "Usage: sql-parrot --cli <command>
+
+While a UI password is set, pass it in the SQL_PARROT_PASSWORD environment variable.
+
+Commands:
+  snapshot create --group <id|name> [--name <display name>] [--notes <text>] [--atomic]
+  snapshot rollback --id <snapshot id> [--yes] [--checkpoint | --no-checkpoint] [--verify] [--cross-group]
+  snapshot list --group <id|name>
+  group list
+  profile list";
+
+/// Exit code for a command that ran and failed
+const EXIT_FAILED: i32 = 1;
+/// Exit code for arguments that couldn't be parsed
+const EXIT_USAGE: i32 = 2;
+
+/// Environment variable holding the UI password, required while one is set
+const PASSWORD_ENV: &str = "SQL_PARROT_PASSWORD";
+
+/// Longest the CLI waits for webhook notifications before exiting; each delivery times out at 10 seconds
+const CLI_WEBHOOK_WAIT: Duration = Duration::from_secs(15);
+
+#[derive(Debug, PartialEq)]
+enum CliCommand {
+    SnapshotCreate { group: String, name: Option<String>, notes: Option<String>, atomic: bool },
+    SnapshotRollback {
+        id: String,
+        confirmed: bool,
+        checkpoint: Option<bool>,
+        verify: bool,
+        cross_group: bool,
+    },
+    SnapshotList { group: String },
+    GroupList,
+    ProfileList,
+}
+
+/// Options after the subcommand: `--key value` pairs and bare `--switch` flags
+struct Options {
+    values: Vec<(String, String)>,
+    switches: Vec<String>,
+}
+
+impl Options {
+    fn parse(args: &[String], value_keys: &[&str], switch_keys: &[&str]) -> Result<Self, String> {
+        let mut options = Options {
+            values: Vec::new(),
+            switches: Vec::new(),
+        };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let key = arg.as_str();
+            if value_keys.contains(&key) {
+                let value = args.next().ok_or_else(|| format!("{} needs a value", key))?;
+                options.values.push((key.to_string(), value.clone()));
+            } else if switch_keys.contains(&key) {
+                options.switches.push(key.to_string());
+            } else {
+                return Err(format!("Unknown option: {}", key));
+            }
+        }
+        Ok(options)
+    }
+
+    fn value(&self, key: &str) -> Option<String> {
+        self.values.iter().rev().find(|(k, _)| k == key).map(|(_, v)| v.clone())
+    }
+
+    fn required(&self, key: &str) -> Result<String, String> {
+        self.value(key).ok_or_else(|| format!("{} is required", key))
+    }
+
+    fn switch(&self, key: &str) -> bool {
+        self.switches.iter().any(|s| s == key)
+    }
+}
+
+fn parse(args: &[String]) -> Result<CliCommand, String> {
+    let words: Vec<&str> = args.iter().take(2).map(String::as_str).collect();
+    match words.as_slice() {
+        ["snapshot", "create"] => {
+            let options = Options::parse(&args[2..], &["--group", "--name", "--notes"], &["--atomic"])?;
+            Ok(CliCommand::SnapshotCreate {
+                group: options.required("--group")?,
+                name: options.value("--name"),
+                notes: options.value("--notes"),
+                atomic: options.switch("--atomic"),
+            })
+        }
+        ["snapshot", "rollback"] => {
+            let options = Options::parse(
+                &args[2..],
+                &["--id"],
+                &["--yes", "--checkpoint", "--no-checkpoint", "--verify", "--cross-group"],
+            )?;
+            let checkpoint = match (options.switch("--checkpoint"), options.switch("--no-checkpoint")) {
+                (true, true) => return Err("Use only one of --checkpoint and --no-checkpoint".to_string()),
+                (true, false) => Some(true),
+                (false, true) => Some(false),
+                (false, false) => None,
+            };
+            Ok(CliCommand::SnapshotRollback {
+                id: options.required("--id")?,
+                confirmed: options.switch("--yes"),
+                checkpoint,
+                verify: options.switch("--verify"),
+                cross_group: options.switch("--cross-group"),
+            })
+        }
+        ["snapshot", "list"] => {
+            let options = Options::parse(&args[2..], &["--group"], &[])?;
+            Ok(CliCommand::SnapshotList {
+                group: options.required("--group")?,
+            })
+        }
+        ["group", "list"] => {
+            Options::parse(&args[2..], &[], &[])?;
+            Ok(CliCommand::GroupList)
+        }
+        ["profile", "list"] => {
+            Options::parse(&args[2..], &[], &[])?;
+            Ok(CliCommand::ProfileList)
+        }
+        [] => Err("No command given".to_string()),
+        _ => Err(format!("Unknown command: {}", args.join(" "))),
+    }
+}
+
+/// Group id for an id or a (case-insensitive) group name
+fn resolve_group_id(group: &str) -> Result<String, String> {
+    let store = MetadataStore::open().map_err(|e| messages::text(messages::STORE_OPEN_FAILED, &[&e]))?;
+    let groups = store
+        .get_groups()
+        .map_err(|e| messages::text(messages::GET_GROUPS_FAILED, &[&e]))?;
+    groups
+        .iter()
+        .find(|g| g.id == group)
+        .or_else(|| groups.iter().find(|g| g.name.eq_ignore_ascii_case(group)))
+        .map(|g| g.id.clone())
+        .ok_or_else(|| messages::text(messages::GROUP_NOT_FOUND, &[&group]))
+}
+
+/// Print the response as JSON and return the process exit code for it
+fn print<T: Serialize>(response: &ApiResponse<T>) -> i32 {
+    match serde_json::to_string_pretty(response) {
+        Ok(json) => println!("{}", json),
+        Err(e) => eprintln!("Failed to serialize response: {}", e),
+    }
+    if response.success {
+        0
+    } else {
+        EXIT_FAILED
+    }
+}
+
+async fn execute(command: CliCommand) -> i32 {
+    // The lock files keep this run off a group the app (or another run) is working on
+    let group_locks = GroupLocks::shared();
+
+    match command {
+        CliCommand::SnapshotCreate {
+            group,
+            name,
+            notes,
+            atomic,
+        } => match resolve_group_id(&group) {
+            Ok(group_id) => print(
+                &commands::snapshots::snapshot_group(&group_locks, group_id, name, atomic, notes, None).await,
+            ),
+            Err(e) => print(&ApiResponse::<()>::error(e)),
+        },
+        CliCommand::SnapshotRollback {
+            id,
+            confirmed,
+            checkpoint,
+            verify,
+            cross_group,
+        } => print(
+            &commands::snapshots::rollback_group(
+                &group_locks,
+                id,
+                checkpoint,
+                Some(confirmed),
+                Some(verify),
+                Some(cross_group),
+                None,
+            )
+            .await,
+        ),
+        CliCommand::SnapshotList { group } => match resolve_group_id(&group) {
+            Ok(group_id) => print(&commands::get_snapshots(group_id).await),
+            Err(e) => print(&ApiResponse::<()>::error(e)),
+        },
+        CliCommand::GroupList => print(&commands::get_groups().await),
+        CliCommand::ProfileList => print(&commands::get_profiles().await),
+    }
+}
+
+/// Run a CLI invocation (the arguments after `--cli`) and return the process exit code
+/// Webhook notifications still being sent are given up to CLI_WEBHOOK_WAIT before the process exits
+pub fn run(args: &[String]) -> i32 {
+    let command = match parse(args) {
+        Ok(c) => c,
+        Err(e) => {
+            print(&ApiResponse::<()>::error(e));
+            eprintln!("{}", USAGE);
+            return EXIT_USAGE;
+        }
+    };
+
+    // The CLI has no UI session, so it stands in for check_password
+    let authorized = MetadataStore::open()
+        .map_err(|e| messages::text(messages::STORE_OPEN_FAILED, &[&e]))
+        .and_then(|store| {
+            let password = std::env::var(PASSWORD_ENV).ok();
+            commands::settings::verify_cli_password(&store, password.as_deref())
+        });
+    if let Err(e) = authorized {
+        return print(&ApiResponse::<()>::error(e));
+    }
+
+    tauri::async_runtime::block_on(async {
+        let code = execute(command).await;
+        if !webhooks::wait_for_deliveries(CLI_WEBHOOK_WAIT).await {
+            eprintln!(
+                "Some webhook notifications were not delivered within {} seconds",
+                CLI_WEBHOOK_WAIT.as_secs()
+            );
+        }
+        code
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_parse_snapshot_commands() {
+        assert_eq!(
+            parse(&args("snapshot create --group QA --name Nightly --atomic")),
+            Ok(CliCommand::SnapshotCreate {
+                group: "QA".to_string(),
+                name: Some("Nightly".to_string()),
+                notes: None,
+                atomic: true,
+            })
+        );
+        assert_eq!(
+            parse(&args("snapshot rollback --id s1 --yes --no-checkpoint")),
+            Ok(CliCommand::SnapshotRollback {
+                id: "s1".to_string(),
+                confirmed: true,
+                checkpoint: Some(false),
+                verify: false,
+                cross_group: false,
+            })
+        );
+        assert_eq!(parse(&args("profile list")), Ok(CliCommand::ProfileList));
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_arguments() {
+        assert!(parse(&args("snapshot create")).unwrap_err().contains("--group"));
+        assert!(parse(&args("snapshot create --group")).unwrap_err().contains("needs a value"));
+        assert!(parse(&args("snapshot rollback --id s1 --force")).unwrap_err().contains("Unknown option"));
+        assert!(parse(&args("snapshot rollback --id s1 --checkpoint --no-checkpoint")).is_err());
+        assert!(parse(&args("database drop")).unwrap_err().contains("Unknown command"));
+        assert!(parse(&[]).is_err());
+    }
+}
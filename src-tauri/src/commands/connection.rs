@@ -1,30 +1,34 @@
 // ABOUTME: Connection-related Tauri commands
 // ABOUTME: Handles SQL Server connection testing and database listing
 
-use crate::config::ConnectionProfile;
-use crate::db::{MetadataStore, SqlServerConnection};
-use crate::models::DatabaseInfo;
-use crate::{ApiResponse, HealthResponse};
+use chrono::{DateTime, Duration, Utc};
+use tauri::Manager;
 
-/// Test connection to SQL Server using provided credentials
-/// If password is empty, uses the saved password from active profile (for security, passwords aren't shown in UI)
-#[tauri::command]
-#[allow(non_snake_case)]
-pub async fn test_connection(
-    host: String,
-    port: u16,
-    username: String,
+use crate::config::{ConnectionProfile, DEFAULT_APPLICATION_NAME};
+use crate::db::{diagnostics, ConnectionLimiter, MetadataStore, SqlServerConnection};
+use crate::messages;
+use crate::models::{
+    ConnectionCheck, DatabaseInfo, DiagnosticStep, EncryptionMode, PressureLevel, ResourcePressure, ServerInfo,
+};
+use crate::{timed, ApiResponse, HealthResponse};
+
+use super::snapshots::{get_active_connection_profile, to_connection_profile};
+
+/// The password to connect with for a connection test: the one typed, or when that's blank,
+/// the saved password of the profile being edited (or of the active profile when it's the same login)
+fn resolve_test_password(
     password: String,
-    trustCertificate: bool,
-    profile_id: Option<String>, // Optional profile ID when editing
-) -> ApiResponse<String> {
-    // If password is empty or whitespace, try to use saved password from profile (either specified or active)
-    let password = if password.trim().is_empty() {
+    host: &str,
+    port: u16,
+    username: &str,
+    profile_id: Option<&str>,
+) -> String {
+    if password.trim().is_empty() {
         match MetadataStore::open() {
             Ok(store) => {
                 // If profile_id is provided (editing mode), prioritize that profile
                 if let Some(pid) = profile_id {
-                    if let Ok(Some(profile)) = store.get_profile(&pid) {
+                    if let Ok(Some(profile)) = store.get_profile(pid) {
                         // When editing, always use saved password from the profile being edited
                         profile.password
                     } else {
@@ -48,123 +52,556 @@ pub async fn test_connection(
         }
     } else {
         password
-    };
+    }
+}
 
-    // Allow empty password - SQL Server might not require it (Windows auth, etc.)
-
-    let profile = ConnectionProfile {
-        name: "test".to_string(),
-        db_type: crate::config::DatabaseType::SqlServer,
-        host,
-        port,
-        username,
-        password,
-        trust_certificate: trustCertificate,
-        snapshot_path: String::new(),
-    };
+/// Test connection to SQL Server using provided credentials
+/// If password is empty, uses the saved password from active profile (for security, passwords aren't shown in UI)
+#[tauri::command]
+#[allow(non_snake_case)]
+pub async fn test_connection(
+    host: String,
+    port: u16,
+    username: String,
+    password: String,
+    trustCertificate: bool,
+    profile_id: Option<String>, // Optional profile ID when editing
+    encryptionMode: Option<EncryptionMode>,
+) -> ApiResponse<String> {
+    timed(async move {
+        let password = resolve_test_password(password, &host, port, &username, profile_id.as_deref());
 
-    match SqlServerConnection::connect(&profile).await {
-        Ok(mut conn) => match conn.test_connection().await {
-            Ok(version) => ApiResponse::success(version),
-            Err(e) => ApiResponse::error(format!("Connection test failed: {}", e)),
-        },
-        Err(e) => ApiResponse::error(format!("Failed to connect: {}", e)),
+        // Allow empty password - SQL Server might not require it (Windows auth, etc.)
+
+        let profile = ConnectionProfile {
+            name: "test".to_string(),
+            db_type: crate::config::DatabaseType::SqlServer,
+            host,
+            port,
+            username,
+            password,
+            trust_certificate: trustCertificate,
+            snapshot_path: String::new(),
+            server_platform: None,
+            encryption_mode: encryptionMode.unwrap_or_default(),
+            application_name: DEFAULT_APPLICATION_NAME.to_string(),
+        };
+
+        match SqlServerConnection::connect(&profile).await {
+            Ok(mut conn) => match conn.test_connection().await {
+                Ok(version) => {
+                    // Cache the host OS and clock skew on the profile being edited, as long as it still points
+                    // at this server
+                    let mut saved_profile_id = None;
+                    let store = MetadataStore::open();
+                    if let (Some(pid), Ok(store)) = (profile_id.as_deref(), store.as_ref()) {
+                        if let Ok(Some(saved)) = store.get_profile(pid) {
+                            if saved.host == profile.host && saved.port == profile.port {
+                                remember_server_platform(store, pid, &mut conn).await;
+                                saved_profile_id = Some(pid);
+                            }
+                        }
+                    }
+                    let skew = match store.as_ref() {
+                        Ok(store) => measure_clock_skew(store, saved_profile_id, &mut conn).await,
+                        Err(_) => None,
+                    };
+                    let mut response = ApiResponse::success(version);
+                    response.messages.warning.extend(skew.and_then(clock_skew_warning));
+                    response
+                }
+                Err(e) => ApiResponse::error(format!("Connection test failed: {}", e.user_message())),
+            },
+            Err(e) => ApiResponse::error_key(messages::CONNECT_FAILED, &[&e.user_message()]),
+        }
+    })
+    .await
+}
+
+/// A snapshot directory suggested for a server, with the platform it was based on
+#[derive(serde::Serialize)]
+pub struct SnapshotPathSuggestion {
+    #[serde(rename = "snapshotPath")]
+    pub snapshot_path: String,
+    #[serde(rename = "serverPlatform")]
+    pub server_platform: String,
+}
+
+/// Suggest a snapshot path for a server from its host OS: /var/opt/mssql/snapshots on Linux,
+/// the instance's default data directory on Windows
+/// Takes the same connection details (and saved-password fallback) as test_connection
+#[tauri::command]
+#[allow(non_snake_case)]
+pub async fn suggest_snapshot_path(
+    host: String,
+    port: u16,
+    username: String,
+    password: String,
+    trustCertificate: bool,
+    profile_id: Option<String>,
+    encryptionMode: Option<EncryptionMode>,
+) -> ApiResponse<SnapshotPathSuggestion> {
+    timed(async move {
+        let password = resolve_test_password(password, &host, port, &username, profile_id.as_deref());
+        let profile = ConnectionProfile {
+            name: "suggest".to_string(),
+            db_type: crate::config::DatabaseType::SqlServer,
+            host,
+            port,
+            username,
+            password,
+            trust_certificate: trustCertificate,
+            snapshot_path: String::new(),
+            server_platform: None,
+            encryption_mode: encryptionMode.unwrap_or_default(),
+            application_name: DEFAULT_APPLICATION_NAME.to_string(),
+        };
+
+        let mut conn = match SqlServerConnection::connect(&profile).await {
+            Ok(c) => c,
+            Err(e) => return ApiResponse::error_key(messages::CONNECT_FAILED, &[&e.user_message()]),
+        };
+        let server_platform = match conn.get_host_platform().await {
+            Ok(p) => p,
+            Err(e) => return ApiResponse::error(format!("Failed to detect server platform: {}", e)),
+        };
+        // Older servers don't report a default data path; the platform default is used instead
+        let default_data_path = conn.get_default_data_path().await.unwrap_or_else(|e| {
+            log::info!("Could not read InstanceDefaultDataPath: {}", e);
+            None
+        });
+
+        ApiResponse::success(SnapshotPathSuggestion {
+            snapshot_path: crate::config::suggested_snapshot_path(&server_platform, default_data_path.as_deref()),
+            server_platform,
+        })
+    })
+    .await
+}
+
+/// Run staged connection checks (DNS, TCP, TLS, login, SELECT 1, permissions) and report each one
+/// If password is empty, uses the active profile's saved password when host, port, and username match
+#[tauri::command]
+#[allow(non_snake_case)]
+pub async fn diagnose_connection(
+    host: String,
+    port: u16,
+    username: String,
+    password: String,
+    trustCertificate: bool,
+    encryptionMode: Option<EncryptionMode>,
+) -> ApiResponse<Vec<DiagnosticStep>> {
+    timed(async move {
+        let password = if password.trim().is_empty() {
+            MetadataStore::open()
+                .ok()
+                .and_then(|store| store.get_active_profile().ok().flatten())
+                .filter(|p| p.host == host && p.port == port && p.username == username)
+                .map(|p| p.password)
+                .unwrap_or_default()
+        } else {
+            password
+        };
+
+        let profile = ConnectionProfile {
+            name: "diagnose".to_string(),
+            db_type: crate::config::DatabaseType::SqlServer,
+            host,
+            port,
+            username,
+            password,
+            trust_certificate: trustCertificate,
+            snapshot_path: String::new(),
+            server_platform: None,
+            encryption_mode: encryptionMode.unwrap_or_default(),
+            application_name: DEFAULT_APPLICATION_NAME.to_string(),
+        };
+
+        ApiResponse::success(diagnostics::diagnose(&profile).await)
+    })
+    .await
+}
+
+/// Open SQL Server connections against the max_connections limit (for troubleshooting)
+#[derive(serde::Serialize)]
+pub struct ConnectionUsage {
+    #[serde(rename = "inUse")]
+    pub in_use: usize,
+    #[serde(rename = "maxConnections")]
+    pub max_connections: usize,
+}
+
+/// Debug: how many SQL Server connections are open right now
+#[tauri::command]
+pub async fn get_connection_usage(app: tauri::AppHandle) -> ApiResponse<ConnectionUsage> {
+    timed(async move {
+        let limiter = app.state::<ConnectionLimiter>();
+        ApiResponse::success(ConnectionUsage {
+            in_use: limiter.in_use(),
+            max_connections: limiter.limit(),
+        })
+    })
+    .await
+}
+
+/// Detect the server's host OS and cache it on the profile (used for snapshot paths and defaults)
+/// Failures are logged and ignored; detection is retried on the next connect
+pub(crate) async fn remember_server_platform(
+    store: &MetadataStore,
+    profile_id: &str,
+    conn: &mut SqlServerConnection,
+) {
+    match conn.get_host_platform().await {
+        Ok(platform) => {
+            if let Err(e) = store.set_profile_server_platform(profile_id, &platform) {
+                log::warn!("Failed to save server platform for profile {}: {}", profile_id, e);
+            }
+        }
+        Err(e) => log::warn!("Failed to detect server platform: {}", e),
     }
 }
 
 /// Get list of databases from SQL Server
+/// Optional name_filter narrows the list on the server to names containing it
 #[tauri::command]
-pub async fn get_databases() -> ApiResponse<Vec<DatabaseInfo>> {
+pub async fn get_databases(name_filter: Option<String>) -> ApiResponse<Vec<DatabaseInfo>> {
+    timed(fetch_databases(name_filter)).await
+}
+
+async fn fetch_databases(name_filter: Option<String>) -> ApiResponse<Vec<DatabaseInfo>> {
     // Get active profile from SQLite
     let store = match MetadataStore::open() {
         Ok(s) => s,
-        Err(e) => return ApiResponse::error(format!("Failed to open metadata store: {}", e)),
-    };
-
-    let profile = match store.get_active_profile() {
-        Ok(Some(p)) => p,
-        Ok(None) => return ApiResponse::error("No active connection profile configured".to_string()),
-        Err(e) => return ApiResponse::error(format!("Failed to get active profile: {}", e)),
+        Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
     };
 
-    // Convert Profile to ConnectionProfile for SqlServerConnection
-    let connection_profile = ConnectionProfile {
-        name: profile.name.clone(),
-        db_type: crate::config::DatabaseType::SqlServer,
-        host: profile.host.clone(),
-        port: profile.port,
-        username: profile.username.clone(),
-        password: profile.password.clone(),
-        trust_certificate: profile.trust_certificate,
-        snapshot_path: profile.snapshot_path.clone(),
+    let connection_profile = match get_active_connection_profile(&store) {
+        Ok(p) => p,
+        Err(e) => return ApiResponse::error(e),
     };
 
     match SqlServerConnection::connect(&connection_profile).await {
-        Ok(mut conn) => match conn.get_databases().await {
+        Ok(mut conn) => match conn.get_databases(name_filter.as_deref()).await {
             Ok(databases) => ApiResponse::success(databases),
             Err(e) => ApiResponse::error(format!("Failed to get databases: {}", e)),
         },
-        Err(e) => ApiResponse::error(format!("Failed to connect: {}", e)),
+        Err(e) => ApiResponse::error_key(messages::CONNECT_FAILED, &[&e]),
     }
 }
 
-/// Check overall health status - tests connection to active profile's SQL Server
+/// Search the active profile's databases by name (substring match done in SQL)
 #[tauri::command]
-pub async fn check_health() -> ApiResponse<HealthResponse> {
-    // Get active profile and test actual SQL connectivity
+pub async fn search_databases(query: String) -> ApiResponse<Vec<DatabaseInfo>> {
+    timed(async move {
+        get_databases(Some(query)).await
+    })
+    .await
+}
+
+/// Get edition, version, collation, hardware, and host platform of the active profile's server
+#[tauri::command]
+pub async fn get_server_info() -> ApiResponse<ServerInfo> {
+    timed(fetch_server_info()).await
+}
+
+async fn fetch_server_info() -> ApiResponse<ServerInfo> {
     let store = match MetadataStore::open() {
         Ok(s) => s,
-        Err(_) => {
-            return ApiResponse::success(HealthResponse {
-                connected: false,
-                version: env!("CARGO_PKG_VERSION").to_string(),
-                platform: std::env::consts::OS.to_string(),
-                sql_server_version: None,
-            });
-        }
+        Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
     };
 
-    let profile = match store.get_active_profile() {
-        Ok(Some(p)) if !p.password.is_empty() => p,
-        _ => {
-            return ApiResponse::success(HealthResponse {
-                connected: false,
-                version: env!("CARGO_PKG_VERSION").to_string(),
-                platform: std::env::consts::OS.to_string(),
-                sql_server_version: None,
-            });
-        }
-    };
-
-    // Actually test the SQL connection
-    let connection_profile = ConnectionProfile {
-        name: profile.name.clone(),
-        db_type: crate::config::DatabaseType::SqlServer,
-        host: profile.host.clone(),
-        port: profile.port,
-        username: profile.username.clone(),
-        password: profile.password.clone(),
-        trust_certificate: profile.trust_certificate,
-        snapshot_path: profile.snapshot_path.clone(),
+    let connection_profile = match get_active_connection_profile(&store) {
+        Ok(p) => p,
+        Err(e) => return ApiResponse::error(e),
     };
 
     match SqlServerConnection::connect(&connection_profile).await {
-        Ok(_) => ApiResponse::success(HealthResponse {
-            connected: true,
-            version: env!("CARGO_PKG_VERSION").to_string(),
-            platform: std::env::consts::OS.to_string(),
-            sql_server_version: Some("Connected".to_string()),
-        }),
+        Ok(mut conn) => match conn.get_server_info().await {
+            Ok(info) => ApiResponse::success(info),
+            Err(e) => ApiResponse::error(format!("Failed to get server info: {}", e)),
+        },
+        Err(e) => ApiResponse::error_key(messages::CONNECT_FAILED, &[&e]),
+    }
+}
+
+/// Percent of tempdb's allocated space in use at which pressure is a warning, and critical
+const TEMPDB_WARN_PERCENT: i64 = 75;
+const TEMPDB_CRITICAL_PERCENT: i64 = 90;
+/// Percent of the snapshot volume in use at which pressure is a warning, and critical
+const VOLUME_WARN_PERCENT: i64 = 85;
+const VOLUME_CRITICAL_PERCENT: i64 = 95;
+
+fn percent_level(used: i64, total: i64, warn: i64, critical: i64) -> PressureLevel {
+    if total <= 0 {
+        return PressureLevel::Ok;
+    }
+    let percent = used.saturating_mul(100) / total;
+    if percent >= critical {
+        PressureLevel::Critical
+    } else if percent >= warn {
+        PressureLevel::Warn
+    } else {
+        PressureLevel::Ok
+    }
+}
+
+fn gigabytes(bytes: i64) -> String {
+    format!("{:.1} GB", bytes as f64 / (1024.0 * 1024.0 * 1024.0))
+}
+
+/// Set the overall level and warnings from the figures that were read
+fn assess_pressure(pressure: &mut ResourcePressure) {
+    let mut level = PressureLevel::Ok;
+    pressure.warnings.clear();
+
+    if let (Some(used), Some(total)) = (pressure.tempdb_used_bytes, pressure.tempdb_total_bytes) {
+        let tempdb = percent_level(used, total, TEMPDB_WARN_PERCENT, TEMPDB_CRITICAL_PERCENT);
+        if tempdb > PressureLevel::Ok {
+            pressure
+                .warnings
+                .push(format!("tempdb is using {} of {}", gigabytes(used), gigabytes(total)));
+        }
+        level = level.max(tempdb);
+    }
+
+    if let (Some(free), Some(total)) = (pressure.snapshot_volume_free_bytes, pressure.snapshot_volume_total_bytes) {
+        let volume = percent_level(total - free, total, VOLUME_WARN_PERCENT, VOLUME_CRITICAL_PERCENT);
+        if volume > PressureLevel::Ok {
+            pressure.warnings.push(format!(
+                "Snapshot volume {} has {} free of {}",
+                pressure.snapshot_volume.as_deref().unwrap_or("?"),
+                gigabytes(free),
+                gigabytes(total)
+            ));
+        }
+        level = level.max(volume);
+    }
+
+    pressure.level = level;
+}
+
+/// The volume a path is on: the longest mount point it starts with
+/// Separators and case are ignored, since Windows paths may use either
+fn volume_for_path<'a>(path: &str, volumes: &'a [(String, i64, i64)]) -> Option<&'a (String, i64, i64)> {
+    let normalize = |p: &str| p.replace('\\', "/").to_lowercase();
+    let path = normalize(path);
+    volumes
+        .iter()
+        .filter(|(mount_point, _, _)| path.starts_with(&normalize(mount_point)))
+        .max_by_key(|(mount_point, _, _)| mount_point.len())
+}
+
+/// tempdb usage and free space where snapshots are written; figures the server won't report are left out
+pub(crate) async fn resource_pressure(conn: &mut SqlServerConnection, snapshot_path: &str) -> ResourcePressure {
+    let mut pressure = ResourcePressure::default();
+
+    match conn.get_tempdb_usage().await {
+        Ok((total, used)) => {
+            pressure.tempdb_total_bytes = Some(total);
+            pressure.tempdb_used_bytes = Some(used);
+        }
+        Err(e) => log::info!("Could not read tempdb usage: {}", e),
+    }
+
+    match conn.get_volume_space().await {
+        Ok(volumes) => {
+            if let Some((mount_point, free, total)) = volume_for_path(snapshot_path, &volumes) {
+                pressure.snapshot_volume = Some(mount_point.clone());
+                pressure.snapshot_volume_free_bytes = Some(*free);
+                pressure.snapshot_volume_total_bytes = Some(*total);
+            }
+        }
+        Err(e) => log::info!("Could not read volume free space: {}", e),
+    }
+
+    assess_pressure(&mut pressure);
+    pressure
+}
+
+/// tempdb usage and free space on the active profile's snapshot volume, as ok/warn/critical
+#[tauri::command]
+pub async fn get_resource_pressure() -> ApiResponse<ResourcePressure> {
+    timed(async move {
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
+
+        let connection_profile = match get_active_connection_profile(&store) {
+            Ok(p) => p,
+            Err(e) => return ApiResponse::error(e),
+        };
+
+        match SqlServerConnection::connect(&connection_profile).await {
+            Ok(mut conn) => ApiResponse::success(resource_pressure(&mut conn, &connection_profile.snapshot_path).await),
+            Err(e) => ApiResponse::error_key(messages::CONNECT_FAILED, &[&e]),
+        }
+    })
+    .await
+}
+
+/// Clock skew beyond which check_health and test_connection warn
+const CLOCK_SKEW_WARN_SECONDS: i64 = 60;
+
+/// How far the server's clock is ahead of this machine's, taking the server's reading to be from
+/// halfway through the round trip
+fn clock_skew(sent: DateTime<Utc>, server_now: DateTime<Utc>, received: DateTime<Utc>) -> Duration {
+    server_now - (sent + (received - sent) / 2)
+}
+
+fn clock_skew_warning(skew: Duration) -> Option<String> {
+    (skew.num_seconds().abs() > CLOCK_SKEW_WARN_SECONDS)
+        .then(|| messages::text(messages::CLOCK_SKEW, &[&skew.num_seconds()]))
+}
+
+/// Measure the server's clock skew, saving it on the profile when there is one
+/// Snapshot and history timestamps come from this machine; the saved skew lets them be compared
+/// with the server's own times
+async fn measure_clock_skew(
+    store: &MetadataStore,
+    profile_id: Option<&str>,
+    conn: &mut SqlServerConnection,
+) -> Option<Duration> {
+    let sent = Utc::now();
+    let server_now = match conn.get_server_utc_now().await {
+        Ok(now) => now,
         Err(e) => {
-            eprintln!("[check_health] SQL connection failed for profile '{}': {}", profile.name, e);
-            ApiResponse::success(HealthResponse {
-                connected: false,
-                version: env!("CARGO_PKG_VERSION").to_string(),
-                platform: std::env::consts::OS.to_string(),
-                sql_server_version: Some(format!("Error: {}", e)),
-            })
+            log::info!("Could not read the server's clock: {}", e);
+            return None;
+        }
+    };
+    let skew = clock_skew(sent, server_now, Utc::now());
+    if let Some(profile_id) = profile_id {
+        if let Err(e) = store.set_clock_skew(profile_id, skew.num_milliseconds()) {
+            log::warn!("Failed to save clock skew: {}", e);
         }
     }
+    Some(skew)
+}
+
+/// Check overall health status - tests connection to active profile's SQL Server
+#[tauri::command]
+pub async fn check_health() -> ApiResponse<HealthResponse> {
+    timed(async move {
+        // Get active profile and test actual SQL connectivity
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(_) => {
+                return ApiResponse::success(HealthResponse {
+                    connected: false,
+                    version: env!("CARGO_PKG_VERSION").to_string(),
+                    platform: std::env::consts::OS.to_string(),
+                    sql_server_version: None,
+                    clock_skew_seconds: None,
+                });
+            }
+        };
+
+        let profile = match store.get_active_profile() {
+            Ok(Some(p)) if !p.password.is_empty() => p,
+            _ => {
+                return ApiResponse::success(HealthResponse {
+                    connected: false,
+                    version: env!("CARGO_PKG_VERSION").to_string(),
+                    platform: std::env::consts::OS.to_string(),
+                    sql_server_version: None,
+                    clock_skew_seconds: None,
+                });
+            }
+        };
+
+        // Actually test the SQL connection
+        let connection_profile = match to_connection_profile(&profile) {
+            Ok(p) => p,
+            Err(e) => return ApiResponse::error(e),
+        };
+
+        let started = std::time::Instant::now();
+        let connected = SqlServerConnection::connect(&connection_profile).await;
+        record_connection_check(ConnectionCheck {
+            profile_id: profile.id.clone(),
+            timestamp: chrono::Utc::now(),
+            success: connected.is_ok(),
+            latency_ms: Some(started.elapsed().as_millis() as u64),
+            error: connected.as_ref().err().map(|e| e.user_message()),
+        });
+
+        match connected {
+            Ok(mut conn) => {
+                if profile.server_platform.is_none() {
+                    remember_server_platform(&store, &profile.id, &mut conn).await;
+                }
+                let skew = measure_clock_skew(&store, Some(&profile.id), &mut conn).await;
+                let mut response = ApiResponse::success(HealthResponse {
+                    connected: true,
+                    version: env!("CARGO_PKG_VERSION").to_string(),
+                    platform: std::env::consts::OS.to_string(),
+                    sql_server_version: Some("Connected".to_string()),
+                    clock_skew_seconds: skew.map(|s| s.num_seconds()),
+                });
+                response.messages.warning.extend(skew.and_then(clock_skew_warning));
+                response
+            }
+            Err(e) => {
+                eprintln!("[check_health] SQL connection failed for profile '{}': {}", profile.name, e);
+                ApiResponse::success(HealthResponse {
+                    connected: false,
+                    version: env!("CARGO_PKG_VERSION").to_string(),
+                    platform: std::env::consts::OS.to_string(),
+                    sql_server_version: Some(format!("Error: {}", e.user_message())),
+                    clock_skew_seconds: None,
+                })
+            }
+        }
+    })
+    .await
+}
+
+/// Store a check_health result in the background so a slow metadata write never delays the health check
+fn record_connection_check(check: ConnectionCheck) {
+    tauri::async_runtime::spawn(async move {
+        let result = MetadataStore::open().and_then(|store| store.add_connection_check(&check));
+        if let Err(e) = result {
+            log::warn!("Failed to record connection check: {}", e);
+        }
+    });
+}
+
+/// Checks returned by get_connection_history when no limit is given
+const DEFAULT_CONNECTION_HISTORY_LIMIT: u32 = 50;
+
+/// A profile's recent check_health results, newest first (at most the 500 kept per profile)
+#[tauri::command]
+#[allow(non_snake_case)]
+pub async fn get_connection_history(profileId: String, limit: Option<u32>) -> ApiResponse<Vec<ConnectionCheck>> {
+    timed(async move {
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
+
+        let limit = limit
+            .unwrap_or(DEFAULT_CONNECTION_HISTORY_LIMIT)
+            .min(crate::db::metadata::MAX_CONNECTION_CHECKS_PER_PROFILE);
+        match store.get_connection_checks(&profileId, limit) {
+            Ok(checks) => ApiResponse::success(checks),
+            Err(e) => ApiResponse::error(format!("Failed to get connection history: {}", e)),
+        }
+    })
+    .await
+}
+
+/// Warning returned with every save_connection response
+const SAVE_CONNECTION_DEPRECATION: &str = "save_connection is deprecated; use create_profile/update_profile";
+
+/// Successful save_connection response carrying the deprecation warning (logged once per run)
+fn deprecated_save_success() -> ApiResponse<()> {
+    static LOGGED: std::sync::Once = std::sync::Once::new();
+    LOGGED.call_once(|| log::warn!("{}", SAVE_CONNECTION_DEPRECATION));
+
+    let mut response = ApiResponse::success(());
+    response.messages.warning.push(SAVE_CONNECTION_DEPRECATION.to_string());
+    response
 }
 
 /// Save connection profile (DEPRECATED - use create_profile or update_profile instead)
@@ -179,93 +616,105 @@ pub async fn save_connection(
     trustCertificate: bool,
     snapshotPath: String,
 ) -> ApiResponse<()> {
-    // This command is deprecated - use create_profile or update_profile instead
-    // For backward compatibility, we'll find or create a profile matching host/port/username
-    let store = match MetadataStore::open() {
-        Ok(s) => s,
-        Err(e) => return ApiResponse::error(format!("Failed to open metadata store: {}", e)),
-    };
+    timed(async move {
+        // This command is deprecated - use create_profile or update_profile instead
+        // For backward compatibility, we'll find or create a profile matching host/port/username
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
 
-    // Try to find existing profile by host/port/username
-    let existing_profile = match store.find_profile_by_connection(&host, port, &username) {
-        Ok(Some(p)) => Some(p),
-        _ => None,
-    };
+        // Try to find existing profile by host/port/username
+        let existing_profile = match store.find_profile_by_connection(&host, port, &username) {
+            Ok(Some(p)) => Some(p),
+            _ => None,
+        };
 
-    use chrono::Utc;
-    use uuid::Uuid;
-    use crate::models::Profile;
+        use chrono::Utc;
+        use uuid::Uuid;
+        use crate::models::{Profile, ProfileOverrides};
 
-    if let Some(existing) = existing_profile {
-        // Update existing profile
-        let updated_profile = Profile {
-            id: existing.id,
-            name: existing.name,
-            platform_type: existing.platform_type,
-            host,
-            port,
-            username,
-            password,
-            trust_certificate: trustCertificate,
-            snapshot_path: snapshotPath,
-            description: existing.description,
-            notes: existing.notes,
-            is_active: true, // Set as active
-            created_at: existing.created_at,
-            updated_at: Utc::now(),
-        };
+        if let Some(existing) = existing_profile {
+            // Update existing profile
+            let updated_profile = Profile {
+                id: existing.id,
+                name: existing.name,
+                platform_type: existing.platform_type,
+                host,
+                port,
+                username,
+                password,
+                trust_certificate: trustCertificate,
+                snapshot_path: snapshotPath,
+                description: existing.description,
+                notes: existing.notes,
+                is_active: true, // Set as active
+                created_at: existing.created_at,
+                updated_at: Utc::now(),
+                server_platform: existing.server_platform,
+                encryption_mode: existing.encryption_mode,
+                overrides: existing.overrides,
+            };
 
-        match store.update_profile(&updated_profile) {
-            Ok(_) => ApiResponse::success(()),
-            Err(e) => ApiResponse::error(format!("Failed to update profile: {}", e)),
-        }
-    } else {
-        // Create new profile
-        let new_profile = Profile {
-            id: Uuid::new_v4().to_string(),
-            name: "Migrated".to_string(),
-            platform_type: "Microsoft SQL Server".to_string(),
-            host,
-            port,
-            username,
-            password,
-            trust_certificate: trustCertificate,
-            snapshot_path: snapshotPath,
-            description: None,
-            notes: None,
-            is_active: true,
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
-        };
+            match store.update_profile(&updated_profile) {
+                Ok(_) => deprecated_save_success(),
+                Err(e) => ApiResponse::error(format!("Failed to update profile: {}", e)),
+            }
+        } else {
+            // Create new profile
+            let new_profile = Profile {
+                id: Uuid::new_v4().to_string(),
+                name: "Migrated".to_string(),
+                platform_type: "Microsoft SQL Server".to_string(),
+                host,
+                port,
+                username,
+                password,
+                trust_certificate: trustCertificate,
+                snapshot_path: snapshotPath,
+                description: None,
+                notes: None,
+                is_active: true,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                server_platform: None,
+                encryption_mode: EncryptionMode::default(),
+                overrides: ProfileOverrides::default(),
+            };
 
-        match store.create_profile(&new_profile) {
-            Ok(_) => ApiResponse::success(()),
-            Err(e) => ApiResponse::error(format!("Failed to create profile: {}", e)),
+            match store.create_profile(&new_profile) {
+                Ok(_) => deprecated_save_success(),
+                Err(e) => ApiResponse::error(format!("Failed to create profile: {}", e)),
+            }
         }
-    }
+    })
+    .await
 }
 
 /// Get current connection profile (without password)
 #[tauri::command]
 pub async fn get_connection() -> ApiResponse<Option<ConnectionProfilePublic>> {
-    let store = match MetadataStore::open() {
-        Ok(s) => s,
-        Err(_) => return ApiResponse::success(None),
-    };
+    timed(async move {
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(_) => return ApiResponse::success(None),
+        };
 
-    match store.get_active_profile() {
-        Ok(Some(profile)) => {
-            ApiResponse::success(Some(ConnectionProfilePublic {
-                name: profile.name,
-                host: profile.host,
-                port: profile.port,
-                username: profile.username,
-                trust_certificate: profile.trust_certificate,
-                snapshot_path: profile.snapshot_path,
-            }))
+        match store.get_active_profile() {
+            Ok(Some(profile)) => {
+                ApiResponse::success(Some(ConnectionProfilePublic {
+                    name: profile.name,
+                    host: profile.host,
+                    port: profile.port,
+                    username: profile.username,
+                    trust_certificate: profile.trust_certificate,
+                    snapshot_path: profile.snapshot_path,
+                }))
+            }
+            _ => ApiResponse::success(None),
         }
-        _ => ApiResponse::success(None),
-    }
+    })
+    .await
 }
 
 /// Public connection profile (without password)
@@ -282,26 +731,29 @@ pub struct ConnectionProfilePublic {
 /// Get the current snapshot path configuration
 #[tauri::command]
 pub async fn test_snapshot_path() -> ApiResponse<SnapshotPathInfo> {
-    let store = match MetadataStore::open() {
-        Ok(s) => s,
-        Err(_) => {
-            return ApiResponse::success(SnapshotPathInfo {
+    timed(async move {
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(_) => {
+                return ApiResponse::success(SnapshotPathInfo {
+                    snapshot_path: "Not configured".to_string(),
+                    configured: false,
+                });
+            }
+        };
+
+        match store.get_active_profile() {
+            Ok(Some(profile)) => ApiResponse::success(SnapshotPathInfo {
+                snapshot_path: profile.snapshot_path,
+                configured: true,
+            }),
+            _ => ApiResponse::success(SnapshotPathInfo {
                 snapshot_path: "Not configured".to_string(),
                 configured: false,
-            });
+            }),
         }
-    };
-
-    match store.get_active_profile() {
-        Ok(Some(profile)) => ApiResponse::success(SnapshotPathInfo {
-            snapshot_path: profile.snapshot_path,
-            configured: true,
-        }),
-        _ => ApiResponse::success(SnapshotPathInfo {
-            snapshot_path: "Not configured".to_string(),
-            configured: false,
-        }),
-    }
+    })
+    .await
 }
 
 #[derive(serde::Serialize)]
@@ -310,3 +762,140 @@ pub struct SnapshotPathInfo {
     pub snapshot_path: String,
     pub configured: bool,
 }
+
+/// Result of checking that SQL Server can create snapshot files in the active profile's snapshot path
+#[derive(Debug, serde::Serialize)]
+pub struct SnapshotPathCheck {
+    pub path: String,
+    pub exists: bool,
+    pub writable: bool,
+    pub message: String,
+}
+
+/// Build the check result from the directory lookup (None when it couldn't be run) and the write probe
+fn snapshot_path_check(path: &str, exists: Option<bool>, write: Result<(), String>) -> SnapshotPathCheck {
+    let (exists, writable, message) = match (exists, write) {
+        (_, Ok(())) => (true, true, format!("SQL Server can create snapshot files in {}", path)),
+        (Some(false), Err(_)) => (
+            false,
+            false,
+            format!(
+                "{} does not exist on the server, or the SQL Server service account can't see it",
+                path
+            ),
+        ),
+        (exists, Err(e)) => (
+            exists.unwrap_or(false),
+            false,
+            format!("SQL Server could not create a snapshot file in {}: {}", path, e),
+        ),
+    };
+
+    SnapshotPathCheck {
+        path: path.to_string(),
+        exists,
+        writable,
+        message,
+    }
+}
+
+/// Check that the active profile's snapshot path exists on the server and that SQL Server can
+/// write snapshot files there, by creating and dropping a throwaway snapshot of msdb
+#[tauri::command]
+pub async fn test_snapshot_path_writable() -> ApiResponse<SnapshotPathCheck> {
+    timed(async move {
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
+
+        let profile = match get_active_connection_profile(&store) {
+            Ok(p) => p,
+            Err(e) => return ApiResponse::error(e),
+        };
+
+        let mut conn = match SqlServerConnection::connect(&profile).await {
+            Ok(c) => c,
+            Err(e) => return ApiResponse::error_key(messages::CONNECT_FAILED, &[&e.user_message()]),
+        };
+
+        // xp_fileexist can be denied to non-sysadmin logins; the write probe is the real test
+        let exists = match conn.directory_exists(&profile.snapshot_path).await {
+            Ok(exists) => Some(exists),
+            Err(e) => {
+                log::info!("Could not check {} with xp_fileexist: {}", profile.snapshot_path, e);
+                None
+            }
+        };
+        let write = conn
+            .probe_snapshot_path(&profile.snapshot_path)
+            .await
+            .map_err(|e| e.user_message());
+
+        ApiResponse::success(snapshot_path_check(&profile.snapshot_path, exists, write))
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resource_pressure_levels_and_volume_lookup() {
+        let gb = 1024 * 1024 * 1024;
+        let volumes = vec![
+            ("C:\\".to_string(), 50 * gb, 100 * gb),
+            ("D:\\".to_string(), 4 * gb, 100 * gb),
+            ("/".to_string(), 10 * gb, 20 * gb),
+            ("/var/opt/mssql/".to_string(), 2 * gb, 20 * gb),
+        ];
+        assert_eq!(volume_for_path("d:/Snapshots", &volumes).unwrap().0, "D:\\");
+        assert_eq!(volume_for_path("/var/opt/mssql/snapshots", &volumes).unwrap().0, "/var/opt/mssql/");
+        assert!(volume_for_path("E:\\Snapshots", &volumes).is_none());
+
+        let mut pressure = ResourcePressure {
+            tempdb_used_bytes: Some(10 * gb),
+            tempdb_total_bytes: Some(100 * gb),
+            ..Default::default()
+        };
+        assess_pressure(&mut pressure);
+        assert_eq!(pressure.level, PressureLevel::Ok);
+        assert!(pressure.warnings.is_empty());
+
+        pressure.tempdb_used_bytes = Some(80 * gb);
+        pressure.snapshot_volume = Some("D:\\".to_string());
+        pressure.snapshot_volume_free_bytes = Some(4 * gb);
+        pressure.snapshot_volume_total_bytes = Some(100 * gb);
+        assess_pressure(&mut pressure);
+        assert_eq!(pressure.level, PressureLevel::Critical);
+        assert_eq!(pressure.warnings.len(), 2);
+        assert!(pressure.warnings[1].contains("4.0 GB free"));
+    }
+
+    #[test]
+    fn test_snapshot_path_check_results() {
+        let check = snapshot_path_check("/var/opt/mssql/snapshots", None, Ok(()));
+        assert!(check.exists && check.writable);
+
+        let check = snapshot_path_check("D:\\Snapshots", Some(false), Err("Operating system error 3".to_string()));
+        assert!(!check.exists && !check.writable);
+        assert!(check.message.contains("does not exist"));
+
+        let check = snapshot_path_check("D:\\Snapshots", Some(true), Err("Access is denied".to_string()));
+        assert!(check.exists && !check.writable);
+        assert!(check.message.contains("Access is denied"));
+    }
+
+    #[test]
+    fn test_clock_skew_uses_round_trip_midpoint() {
+        let sent = Utc::now();
+        let received = sent + Duration::seconds(2);
+
+        assert_eq!(clock_skew(sent, sent + Duration::seconds(1), received), Duration::zero());
+        let behind = clock_skew(sent, sent - Duration::seconds(89), received);
+        assert_eq!(behind, Duration::seconds(-90));
+        assert!(clock_skew_warning(behind).unwrap().contains("-90"));
+        assert!(clock_skew_warning(Duration::seconds(60)).is_none());
+    }
+}
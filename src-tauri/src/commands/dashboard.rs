@@ -0,0 +1,235 @@
+// ABOUTME: Dashboard Tauri commands
+// ABOUTME: Aggregates home screen data and startup health checks into single round trips
+
+use tauri::Manager;
+
+use crate::db::MetadataStore;
+use crate::messages;
+use crate::models::{HistoryEntry, Operation, OperationStatus};
+use crate::shutdown::PreviousShutdown;
+use crate::{timed, ApiResponse, HealthResponse};
+
+/// Everything the home screen needs on startup
+#[derive(serde::Serialize)]
+pub struct DashboardSummary {
+    #[serde(rename = "groupCount")]
+    pub group_count: u32,
+    #[serde(rename = "snapshotCount")]
+    pub snapshot_count: u32,
+    #[serde(rename = "automaticSnapshotCount")]
+    pub automatic_snapshot_count: u32,
+    #[serde(rename = "manualSnapshotCount")]
+    pub manual_snapshot_count: u32,
+    #[serde(rename = "lastOperation")]
+    pub last_operation: Option<HistoryEntry>,
+    #[serde(rename = "activeProfileName")]
+    pub active_profile_name: Option<String>,
+    pub health: Option<HealthResponse>,
+}
+
+/// Get counts, latest activity, active profile, and connection health in one call
+#[tauri::command]
+pub async fn get_dashboard_summary() -> ApiResponse<DashboardSummary> {
+    timed(async move {
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
+
+        let (group_count, automatic_snapshot_count, manual_snapshot_count) =
+            match store.get_summary_counts() {
+                Ok(counts) => counts,
+                Err(e) => return ApiResponse::error(format!("Failed to count groups and snapshots: {}", e)),
+            };
+
+        let last_operation = store
+            .get_history(Some(1))
+            .unwrap_or_default()
+            .into_iter()
+            .next();
+
+        let active_profile_name = match store.get_active_profile() {
+            Ok(profile) => profile.map(|p| p.name),
+            Err(e) => return ApiResponse::error(format!("Failed to get active profile: {}", e)),
+        };
+
+        // Release the metadata connection before the (potentially slow) SQL Server check
+        drop(store);
+        let health = super::check_health().await.data;
+
+        ApiResponse::success(DashboardSummary {
+            group_count,
+            snapshot_count: automatic_snapshot_count + manual_snapshot_count,
+            automatic_snapshot_count,
+            manual_snapshot_count,
+            last_operation,
+            active_profile_name,
+            health,
+        })
+    })
+    .await
+}
+
+/// Whether local metadata agrees with itself and, when the server is reachable, with SQL Server
+#[derive(Debug, Default, serde::Serialize)]
+pub struct StartupHealthReport {
+    pub healthy: bool,
+    #[serde(rename = "settingsValid")]
+    pub settings_valid: bool,
+    #[serde(rename = "missingColumns")]
+    pub missing_columns: Vec<String>,
+    #[serde(rename = "pendingMigrations")]
+    pub pending_migrations: Vec<String>,
+    /// False when there's no active profile or its server couldn't be reached
+    #[serde(rename = "serverChecked")]
+    pub server_checked: bool,
+    #[serde(rename = "orphanedSnapshots")]
+    pub orphaned_snapshots: Vec<String>,
+    #[serde(rename = "staleMetadata")]
+    pub stale_metadata: Vec<String>,
+    /// The previous run crashed or was killed instead of exiting normally
+    #[serde(rename = "uncleanShutdown")]
+    pub unclean_shutdown: bool,
+    /// Rollbacks a crash or shutdown cut short, waiting to be resumed or abandoned
+    #[serde(rename = "interruptedOperations")]
+    pub interrupted_operations: Vec<Operation>,
+    /// One line per problem, for a banner
+    pub issues: Vec<String>,
+}
+
+impl StartupHealthReport {
+    fn describe_issues(&self) -> Vec<String> {
+        let mut issues = Vec::new();
+        if !self.settings_valid {
+            issues.push("Saved settings could not be read; re-save them from the Settings page".to_string());
+        }
+        if !self.missing_columns.is_empty() {
+            issues.push(format!(
+                "Metadata database is missing columns: {}",
+                self.missing_columns.join(", ")
+            ));
+        }
+        if !self.pending_migrations.is_empty() {
+            issues.push(format!(
+                "Metadata migrations not applied: {}",
+                self.pending_migrations.join(", ")
+            ));
+        }
+        if !self.orphaned_snapshots.is_empty() {
+            issues.push(format!(
+                "{} snapshot(s) on SQL Server that SQL Parrot has no record of",
+                self.orphaned_snapshots.len()
+            ));
+        }
+        if !self.stale_metadata.is_empty() {
+            issues.push(format!(
+                "{} recorded snapshot(s) no longer exist on SQL Server",
+                self.stale_metadata.len()
+            ));
+        }
+        if self.unclean_shutdown {
+            issues.push("SQL Parrot didn't shut down cleanly last time; verify your groups' snapshots".to_string());
+        }
+        if !self.interrupted_operations.is_empty() {
+            issues.push(format!(
+                "{} operation(s) were interrupted; resume or abandon them",
+                self.interrupted_operations.len()
+            ));
+        }
+        issues
+    }
+}
+
+/// Check settings, schema, migrations, and (for the active profile's groups, when reachable)
+/// orphaned and stale snapshots, in one call for the UI to run on launch
+#[tauri::command]
+pub async fn startup_health_check(app: tauri::AppHandle) -> ApiResponse<StartupHealthReport> {
+    timed(async move {
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
+
+        let mut report = StartupHealthReport {
+            settings_valid: store.get_settings().is_ok(),
+            unclean_shutdown: app.try_state::<PreviousShutdown>().is_some_and(|previous| !previous.clean),
+            ..Default::default()
+        };
+
+        match store.missing_columns() {
+            Ok(missing) => report.missing_columns = missing,
+            Err(e) => return ApiResponse::error(format!("Failed to inspect metadata schema: {}", e)),
+        }
+        match store.pending_migrations() {
+            Ok(pending) => report.pending_migrations = pending.into_iter().map(String::from).collect(),
+            Err(e) => return ApiResponse::error(format!("Failed to check migrations: {}", e)),
+        }
+        report.interrupted_operations = store
+            .get_operations()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|o| o.status == OperationStatus::Interrupted)
+            .collect();
+
+        let active_profile_id = store.get_active_profile().ok().flatten().map(|p| p.id);
+        let group_ids: Vec<String> = store
+            .get_groups()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|g| g.profile_id.is_none() || g.profile_id == active_profile_id)
+            .map(|g| g.id)
+            .collect();
+
+        // Release the metadata connection before the (potentially slow) SQL Server checks
+        drop(store);
+
+        let reachable = super::check_health().await.data.is_some_and(|h| h.connected);
+        if active_profile_id.is_some() && reachable {
+            report.server_checked = true;
+            for group_id in group_ids {
+                match super::verify_group_snapshots(group_id.clone()).await.data {
+                    Some(result) => {
+                        report.orphaned_snapshots.extend(result.orphaned_snapshots);
+                        report.stale_metadata.extend(result.stale_metadata);
+                    }
+                    None => log::warn!("Startup check could not verify group {}", group_id),
+                }
+            }
+        }
+
+        report.issues = report.describe_issues();
+        report.healthy = report.issues.is_empty();
+        ApiResponse::success(report)
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe_issues() {
+        assert!(StartupHealthReport {
+            settings_valid: true,
+            ..Default::default()
+        }
+        .describe_issues()
+        .is_empty());
+
+        let report = StartupHealthReport {
+            settings_valid: false,
+            missing_columns: vec!["snapshots.notes".to_string()],
+            stale_metadata: vec!["g1_1_Sales".to_string(), "g1_1_Orders".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            report.describe_issues(),
+            vec![
+                "Saved settings could not be read; re-save them from the Settings page".to_string(),
+                "Metadata database is missing columns: snapshots.notes".to_string(),
+                "2 recorded snapshot(s) no longer exist on SQL Server".to_string(),
+            ]
+        );
+    }
+}
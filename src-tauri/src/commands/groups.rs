@@ -2,54 +2,58 @@
 // ABOUTME: CRUD operations for snapshot groups
 
 use chrono::Utc;
+use regex::Regex;
+use tauri::Manager;
 use uuid::Uuid;
 
-use crate::config::ConnectionProfile;
+use super::{get_active_connection_profile, get_profile_for_group};
 use crate::db::{MetadataStore, SqlServerConnection};
-use crate::models::{Group, HistoryEntry};
-use crate::ApiResponse;
+use crate::group_locks::{group_busy, GroupLocks};
+use crate::messages;
+use crate::models::{DatabaseInfo, Group, GroupSummary, HistoryEntry, ServerSnapshot, Snapshot};
+use crate::sessions::{require_auth, Sessions};
+use crate::{timed, ApiResponse};
 
-/// Helper function to get profile from metadata database using group's profile_id
-/// and convert it to ConnectionProfile for SQL Server connection
-fn get_profile_for_group(
-    store: &MetadataStore,
-    group: &Group,
-) -> Result<ConnectionProfile, String> {
-    let profile_id = group
-        .profile_id
-        .as_ref()
-        .ok_or_else(|| "Group has no profile_id".to_string())?;
-
-    let profile = store
-        .get_profile(profile_id)
-        .map_err(|e| format!("Failed to get profile: {}", e))?
-        .ok_or_else(|| format!("Profile not found: {}", profile_id))?;
-
-    // Convert Profile to ConnectionProfile
-    Ok(ConnectionProfile {
-        name: profile.name.clone(),
-        db_type: crate::config::DatabaseType::SqlServer,
-        host: profile.host.clone(),
-        port: profile.port,
-        username: profile.username.clone(),
-        password: profile.password.clone(),
-        trust_certificate: profile.trust_certificate,
-        snapshot_path: profile.snapshot_path.clone(),
-    })
-}
-
-/// Get all groups
+/// Get all groups, with when each was last snapshotted and how many snapshots each has
 #[tauri::command]
-pub async fn get_groups() -> ApiResponse<Vec<Group>> {
-    let store = match MetadataStore::open() {
-        Ok(s) => s,
-        Err(e) => return ApiResponse::error(format!("Failed to open metadata store: {}", e)),
-    };
+pub async fn get_groups() -> ApiResponse<Vec<GroupSummary>> {
+    timed(async move {
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
 
-    match store.get_groups() {
-        Ok(groups) => ApiResponse::success(groups),
-        Err(e) => ApiResponse::error(format!("Failed to get groups: {}", e)),
-    }
+        let groups = match store.get_groups() {
+            Ok(groups) => groups,
+            Err(e) => return ApiResponse::error_key(messages::GET_GROUPS_FAILED, &[&e]),
+        };
+
+        let last_snapshot_times = match store.get_last_snapshot_times() {
+            Ok(times) => times,
+            Err(e) => return ApiResponse::error(format!("Failed to get last snapshot times: {}", e)),
+        };
+
+        let snapshot_counts = match store.get_snapshot_counts_by_group() {
+            Ok(counts) => counts,
+            Err(e) => return ApiResponse::error(format!("Failed to count snapshots: {}", e)),
+        };
+
+        ApiResponse::success(
+            groups
+                .into_iter()
+                .map(|group| {
+                    let (manual, automatic) = snapshot_counts.get(&group.id).copied().unwrap_or_default();
+                    GroupSummary {
+                        last_snapshot_at: last_snapshot_times.get(&group.id).copied(),
+                        manual_snapshot_count: manual,
+                        automatic_snapshot_count: automatic,
+                        group,
+                    }
+                })
+                .collect(),
+        )
+    })
+    .await
 }
 
 /// Create a new group
@@ -58,224 +62,1280 @@ pub async fn create_group(
     name: String,
     databases: Vec<String>,
     profile_id: Option<String>,
+    description: Option<String>,
 ) -> ApiResponse<Group> {
-    let store = match MetadataStore::open() {
-        Ok(s) => s,
-        Err(e) => return ApiResponse::error(format!("Failed to open metadata store: {}", e)),
-    };
+    timed(async move {
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
 
-    let now = Utc::now();
-    let group = Group {
-        id: Uuid::new_v4().to_string(),
-        name,
-        databases,
-        profile_id, // Use provided profile_id or let create_group use active profile
-        created_by: whoami::username_os().to_string_lossy().into_owned().into(),
-        created_at: now,
-        updated_at: now,
-    };
+        let now = Utc::now();
+        let group = Group {
+            id: Uuid::new_v4().to_string(),
+            name,
+            databases,
+            profile_id, // Use provided profile_id or let create_group use active profile
+            description: normalize_description(description),
+            created_by: whoami::username_os().to_string_lossy().into_owned().into(),
+            created_at: now,
+            updated_at: now,
+        };
 
-    match store.create_group(&group) {
-        Ok(_) => {
-            // Log to history
-            let history_entry = HistoryEntry {
-                id: Uuid::new_v4().to_string(),
-                operation_type: "create_group".to_string(),
-                timestamp: now,
-                user_name: Some(whoami::username_os().to_string_lossy().into_owned()),
-                details: Some(serde_json::json!({
-                    "groupId": group.id,
-                    "groupName": group.name,
-                    "databaseCount": group.databases.len()
-                })),
-                results: None,
-            };
-            let _ = store.add_history(&history_entry);
-            ApiResponse::success(group)
+        match store.create_group(&group) {
+            Ok(_) => {
+                // Log to history
+                let history_entry = HistoryEntry {
+                    id: Uuid::new_v4().to_string(),
+                    operation_type: "create_group".to_string(),
+                    timestamp: now,
+                    user_name: Some(whoami::username_os().to_string_lossy().into_owned()),
+                    details: Some(serde_json::json!({
+                        "groupId": group.id,
+                        "groupName": group.name,
+                        "databaseCount": group.databases.len(),
+                        "databases": group.databases,
+                        "profileId": group.profile_id
+                    })),
+                    results: None,
+                    group_id: Some(group.id.clone()),
+                };
+                let _ = store.add_history(&history_entry);
+                ApiResponse::success(group)
+            }
+            Err(e) => ApiResponse::error(format!("Failed to create group: {}", e)),
         }
-        Err(e) => ApiResponse::error(format!("Failed to create group: {}", e)),
-    }
+    })
+    .await
 }
 
 /// Update an existing group
+/// Omitting description keeps the current one; an empty description clears it
+/// While a UI password is set, `session_token` must come from check_password.
 #[tauri::command]
 pub async fn update_group(
+    app: tauri::AppHandle,
     id: String,
     name: String,
     databases: Vec<String>,
     profile_id: Option<String>,
+    description: Option<String>,
+    session_token: Option<String>,
 ) -> ApiResponse<Group> {
-    let store = match MetadataStore::open() {
-        Ok(s) => s,
-        Err(e) => return ApiResponse::error(format!("Failed to open metadata store: {}", e)),
-    };
+    timed(async move {
+        if let Some(response) = require_auth(&app.state::<Sessions>(), session_token.as_deref()) {
+            return response;
+        }
 
-    // Get existing group to preserve created_at and created_by
-    let existing_groups = match store.get_groups() {
-        Ok(g) => g,
-        Err(e) => return ApiResponse::error(format!("Failed to get groups: {}", e)),
-    };
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
 
-    let existing = match existing_groups.iter().find(|g| g.id == id) {
-        Some(g) => g.clone(),
-        None => return ApiResponse::error(format!("Group not found: {}", id)),
-    };
+        // Get existing group to preserve created_at and created_by
+        let existing_groups = match store.get_groups() {
+            Ok(g) => g,
+            Err(e) => return ApiResponse::error_key(messages::GET_GROUPS_FAILED, &[&e]),
+        };
 
-    // Find databases that were removed
-    let removed_databases: Vec<&String> = existing
-        .databases
-        .iter()
-        .filter(|db| !databases.contains(db))
-        .collect();
+        let existing = match existing_groups.iter().find(|g| g.id == id) {
+            Some(g) => g.clone(),
+            None => return ApiResponse::error_key(messages::GROUP_NOT_FOUND, &[&id]),
+        };
 
-    // If databases were removed, clean up their snapshots
-    if !removed_databases.is_empty() {
-        // Get profile from metadata database using group's profile_id
-        let profile = match get_profile_for_group(&store, &existing) {
-            Ok(p) => p,
-            Err(e) => return ApiResponse::error(e),
+        // Find databases that were removed
+        let removed_databases: Vec<&String> = existing
+            .databases
+            .iter()
+            .filter(|db| !databases.contains(db))
+            .collect();
+
+        // If databases were removed, clean up their snapshots
+        if !removed_databases.is_empty() {
+            // Get profile from metadata database using group's profile_id
+            let profile = match get_profile_for_group(&store, &existing) {
+                Ok(p) => p,
+                Err(e) => return ApiResponse::error(e),
+            };
+
+            // Connect to SQL Server
+            let mut conn = match SqlServerConnection::connect(&profile).await {
+                Ok(c) => c,
+                Err(e) => return ApiResponse::error_key(messages::CONNECT_FAILED, &[&e]),
+            };
+
+            // Delete all snapshots for this group since they're now incomplete
+            // (A partial snapshot isn't useful for rollback)
+            if let Ok(snapshots) = store.get_snapshots(&id) {
+                let snapshot_names: Vec<String> = tracked_snapshot_databases(&snapshots)
+                    .into_iter()
+                    .map(String::from)
+                    .collect();
+                match conn.drop_snapshots(&snapshot_names).await {
+                    Ok(failed) => {
+                        for (name, error) in failed {
+                            log::warn!("Failed to drop snapshot database {}: {}", name, error);
+                        }
+                    }
+                    Err(e) => log::warn!("Failed to drop snapshot databases: {}", e),
+                }
+
+                for snapshot in &snapshots {
+                    let _ = store.delete_snapshot(&snapshot.id);
+                }
+            }
+        }
+
+        let group = Group {
+            id,
+            name,
+            databases,
+            profile_id: profile_id.or(existing.profile_id.clone()), // Use provided profile_id or preserve existing
+            description: match description {
+                Some(d) => normalize_description(Some(d)),
+                None => existing.description.clone(),
+            },
+            created_by: existing.created_by.clone(),
+            created_at: existing.created_at,
+            updated_at: Utc::now(),
+        };
+
+        let added_databases: Vec<&String> = group
+            .databases
+            .iter()
+            .filter(|db| !existing.databases.contains(db))
+            .collect();
+
+        match store.update_group(&group) {
+            Ok(_) => {
+                // Log to history
+                let history_entry = HistoryEntry {
+                    id: Uuid::new_v4().to_string(),
+                    operation_type: "update_group".to_string(),
+                    timestamp: Utc::now(),
+                    user_name: Some(whoami::username_os().to_string_lossy().into_owned()),
+                    details: Some(serde_json::json!({
+                        "groupId": group.id,
+                        "groupName": group.name,
+                        "previousName": existing.name,
+                        "databaseCount": group.databases.len(),
+                        "addedDatabases": added_databases,
+                        "removedDatabases": removed_databases,
+                        "profileId": group.profile_id
+                    })),
+                    results: None,
+                    group_id: Some(group.id.clone()),
+                };
+                let _ = store.add_history(&history_entry);
+                ApiResponse::success(group)
+            }
+            Err(e) => ApiResponse::error(format!("Failed to update group: {}", e)),
+        }
+    })
+    .await
+}
+
+/// A group's shareable definition: what export_group writes and import_group reads
+/// Snapshots are server-specific and never included
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct GroupExport {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub databases: Vec<String>,
+}
+
+/// Write a group's name, description, and databases to a JSON file
+#[tauri::command]
+#[allow(non_snake_case)]
+pub async fn export_group(groupId: String, path: String) -> ApiResponse<()> {
+    timed(async move {
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
+
+        let group = match store.get_group(&groupId) {
+            Ok(Some(g)) => g,
+            Ok(None) => return ApiResponse::error_key(messages::GROUP_NOT_FOUND, &[&groupId]),
+            Err(e) => return ApiResponse::error(format!("Failed to get group: {}", e)),
+        };
+
+        let export = GroupExport {
+            name: group.name,
+            description: group.description,
+            databases: group.databases,
         };
+        let json = match serde_json::to_string_pretty(&export) {
+            Ok(j) => j,
+            Err(e) => return ApiResponse::error(format!("Failed to serialize group: {}", e)),
+        };
+
+        match std::fs::write(&path, json) {
+            Ok(_) => ApiResponse::success(()),
+            Err(e) => ApiResponse::error(format!("Failed to write {}: {}", path, e)),
+        }
+    })
+    .await
+}
+
+/// Recreate an exported group under the active profile with a fresh id
+/// Databases missing from the current server are reported as warnings; a name already in use is
+/// rejected unless renameOnConflict is true, which adds a numeric suffix instead
+/// While a UI password is set, `session_token` must come from check_password.
+#[tauri::command]
+#[allow(non_snake_case)]
+pub async fn import_group(
+    app: tauri::AppHandle,
+    path: String,
+    renameOnConflict: Option<bool>,
+    session_token: Option<String>,
+) -> ApiResponse<Group> {
+    timed(async move {
+        if let Some(response) = require_auth(&app.state::<Sessions>(), session_token.as_deref()) {
+            return response;
+        }
 
-        // Connect to SQL Server
-        let mut conn = match SqlServerConnection::connect(&profile).await {
+        let contents = match std::fs::read_to_string(&path) {
             Ok(c) => c,
-            Err(e) => return ApiResponse::error(format!("Failed to connect: {}", e)),
+            Err(e) => return ApiResponse::error(format!("Failed to read {}: {}", path, e)),
+        };
+
+        let export: GroupExport = match serde_json::from_str(&contents) {
+            Ok(g) => g,
+            Err(e) => return ApiResponse::error(format!("Not a group export file: {}", e)),
+        };
+        if export.name.trim().is_empty() {
+            return ApiResponse::error("Group export has no name".to_string());
+        }
+
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
+
+        let existing_names: Vec<String> = match store.get_groups() {
+            Ok(groups) => groups.into_iter().map(|g| g.name).collect(),
+            Err(e) => return ApiResponse::error_key(messages::GET_GROUPS_FAILED, &[&e]),
+        };
+        let name = unique_group_name(export.name.trim(), &existing_names);
+        if name != export.name.trim() && !renameOnConflict.unwrap_or(false) {
+            return ApiResponse::error(format!("A group named \"{}\" already exists", export.name.trim()));
+        }
+
+        let profile = match get_active_connection_profile(&store) {
+            Ok(p) => p,
+            Err(e) => return ApiResponse::error(e),
         };
 
-        // Get all snapshots for this group
-        if let Ok(snapshots) = store.get_snapshots(&id) {
-            for snapshot in snapshots {
-                // Find database snapshots for removed databases
-                for db_snapshot in &snapshot.database_snapshots {
-                    if removed_databases.contains(&&db_snapshot.database) && db_snapshot.success {
-                        // Drop the SQL Server snapshot
-                        let _ = conn.drop_snapshot(&db_snapshot.snapshot_name).await;
+        let mut warnings = Vec::new();
+        match SqlServerConnection::connect(&profile).await {
+            Ok(mut conn) => match conn.get_databases(None).await {
+                Ok(databases) => {
+                    let missing: Vec<&str> = export
+                        .databases
+                        .iter()
+                        .filter(|name| !databases.iter().any(|d| d.name.eq_ignore_ascii_case(name)))
+                        .map(String::as_str)
+                        .collect();
+                    if !missing.is_empty() {
+                        warnings.push(format!(
+                            "Databases not found on {}: {}",
+                            profile.name,
+                            missing.join(", ")
+                        ));
                     }
                 }
+                Err(e) => warnings.push(format!("Could not check databases: {}", e)),
+            },
+            Err(e) => warnings.push(format!("Could not check databases: {}", e.user_message())),
+        }
+
+        let now = Utc::now();
+        let group = Group {
+            id: Uuid::new_v4().to_string(),
+            name,
+            databases: export.databases,
+            profile_id: None, // create_group assigns the active profile
+            description: normalize_description(export.description),
+            created_by: Some(whoami::username_os().to_string_lossy().into_owned()),
+            created_at: now,
+            updated_at: now,
+        };
+
+        if let Err(e) = store.create_group(&group) {
+            return ApiResponse::error(format!("Failed to create group: {}", e));
+        }
+
+        let history_entry = HistoryEntry {
+            id: Uuid::new_v4().to_string(),
+            operation_type: "import_group".to_string(),
+            timestamp: now,
+            user_name: Some(whoami::username_os().to_string_lossy().into_owned()),
+            details: Some(serde_json::json!({
+                "groupId": group.id,
+                "groupName": group.name,
+                "databaseCount": group.databases.len(),
+                "databases": group.databases,
+                "sourceFile": path
+            })),
+            results: None,
+            group_id: Some(group.id.clone()),
+        };
+        let _ = store.add_history(&history_entry);
+
+        let mut response = ApiResponse::success(group);
+        response.messages.warning = warnings;
+        response
+    })
+    .await
+}
+
+/// Error code returned when moving a group that still has snapshots without `force`
+pub const GROUP_HAS_SNAPSHOTS: &str = "GroupHasSnapshots";
+
+/// Error code returned when the target profile already has a group with the moved group's name
+pub const GROUP_NAME_TAKEN: &str = "GroupNameTaken";
+
+/// Reassign a group to another connection profile
+/// Its snapshots physically live on the old profile's server, so a group with snapshots is only moved
+/// when `force` is true, and the response then carries a warning.
+/// A group whose name the target profile already uses is refused; rename one of them first.
+/// While a UI password is set, `session_token` must come from check_password.
+#[tauri::command]
+#[allow(non_snake_case)]
+pub async fn move_group_to_profile(
+    app: tauri::AppHandle,
+    groupId: String,
+    profileId: String,
+    force: Option<bool>,
+    session_token: Option<String>,
+) -> ApiResponse<Group> {
+    timed(async move {
+        if let Some(response) = require_auth(&app.state::<Sessions>(), session_token.as_deref()) {
+            return response;
+        }
+
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
+
+        // Look the group up directly: it may belong to a profile other than the active one
+        let existing = match store.get_group(&groupId) {
+            Ok(Some(g)) => g,
+            Ok(None) => return ApiResponse::error_key(messages::GROUP_NOT_FOUND, &[&groupId]),
+            Err(e) => return ApiResponse::error(format!("Failed to get group: {}", e)),
+        };
+
+        let target = match store.get_profile(&profileId) {
+            Ok(Some(p)) => p,
+            Ok(None) => return ApiResponse::error(format!("Profile not found: {}", profileId)),
+            Err(e) => return ApiResponse::error(format!("Failed to get profile: {}", e)),
+        };
+
+        if existing.profile_id.as_deref() == Some(target.id.as_str()) {
+            return ApiResponse::success(existing);
+        }
+
+        let group_locks = app.state::<GroupLocks>();
+        let _group_guard = match group_locks.try_lock(&existing.id) {
+            Some(guard) => guard,
+            None => return group_busy(&existing.name),
+        };
+
+        // Names are unique per profile; checking here keeps the store's constraint error out of the UI
+        match store.get_groups_for_profile(&target.id) {
+            Ok(groups) if groups.iter().any(|g| g.name.eq_ignore_ascii_case(&existing.name)) => {
+                return ApiResponse::error_with_code(
+                    GROUP_NAME_TAKEN,
+                    format!(
+                        "Profile \"{}\" already has a group named \"{}\". Rename one of them first.",
+                        target.name, existing.name
+                    ),
+                );
             }
+            Ok(_) => {}
+            Err(e) => return ApiResponse::error_key(messages::GET_GROUPS_FAILED, &[&e]),
+        }
 
-            // Delete all snapshots for this group since they're now incomplete
-            // (A partial snapshot isn't useful for rollback)
-            for snapshot in store.get_snapshots(&id).unwrap_or_default() {
-                let _ = store.delete_snapshot(&snapshot.id);
-                for db_snapshot in &snapshot.database_snapshots {
-                    if db_snapshot.success {
-                        let _ = conn.drop_snapshot(&db_snapshot.snapshot_name).await;
-                    }
-                }
+        let snapshot_count = match store.get_snapshots(&existing.id) {
+            Ok(s) => s.len(),
+            Err(e) => return ApiResponse::error_key(messages::GET_SNAPSHOTS_FAILED, &[&e]),
+        };
+
+        let snapshot_warning = (snapshot_count > 0).then(|| {
+            format!(
+                "Group \"{}\" has {} snapshot(s) on its previous profile's server; they can't be rolled back to from \"{}\"",
+                existing.name, snapshot_count, target.name
+            )
+        });
+        if let Some(warning) = &snapshot_warning {
+            if !force.unwrap_or(false) {
+                return ApiResponse::error_with_code(
+                    GROUP_HAS_SNAPSHOTS,
+                    format!("{}. Delete them first, or move anyway with force.", warning),
+                );
             }
         }
+
+        let group = Group {
+            profile_id: Some(target.id.clone()),
+            updated_at: Utc::now(),
+            ..existing.clone()
+        };
+
+        if let Err(e) = store.update_group(&group) {
+            return ApiResponse::error(format!("Failed to move group: {}", e));
+        }
+
+        let history_entry = HistoryEntry {
+            id: Uuid::new_v4().to_string(),
+            operation_type: "move_group_to_profile".to_string(),
+            timestamp: Utc::now(),
+            user_name: Some(whoami::username_os().to_string_lossy().into_owned()),
+            details: Some(serde_json::json!({
+                "groupId": group.id,
+                "groupName": group.name,
+                "previousProfileId": existing.profile_id,
+                "profileId": target.id,
+                "profileName": target.name,
+                "snapshotCount": snapshot_count
+            })),
+            results: None,
+            group_id: Some(group.id.clone()),
+        };
+        let _ = store.add_history(&history_entry);
+
+        let mut response = ApiResponse::success(group);
+        response.messages.warning = snapshot_warning.into_iter().collect();
+        response
+    })
+    .await
+}
+
+/// Trim a group description, treating a blank one as none
+fn normalize_description(description: Option<String>) -> Option<String> {
+    description
+        .map(|d| d.trim().to_string())
+        .filter(|d| !d.is_empty())
+}
+
+/// How bulk_create_groups splits the server's databases into groups
+#[derive(Debug, Clone, Copy, serde::Deserialize)]
+pub enum BulkGroupStrategy {
+    /// One group per database, named after it
+    OnePerDatabase,
+    /// One group per database category (User, Data Warehouse, Global), named after the category
+    OnePerCategory,
+    /// One group per database collation, named after the collation
+    OnePerCollation,
+}
+
+/// Name of the group OnePerCollation puts databases with no reported collation in
+const UNKNOWN_COLLATION_GROUP: &str = "Unknown collation";
+
+/// Groups created in bulk, the planned groups that couldn't be created,
+/// and how many databases were left out because a group already has them
+#[derive(serde::Serialize)]
+pub struct BulkCreateGroupsResult {
+    pub created: Vec<Group>,
+    pub failed: Vec<FailedGroup>,
+    pub skipped: usize,
+}
+
+/// A planned group that couldn't be created
+#[derive(Debug, serde::Serialize)]
+pub struct FailedGroup {
+    pub name: String,
+    pub databases: Vec<String>,
+    pub error: String,
+}
+
+impl BulkCreateGroupsResult {
+    /// Success when every planned group was created; otherwise an error that still carries both lists
+    fn into_response(self) -> ApiResponse<BulkCreateGroupsResult> {
+        if self.failed.is_empty() {
+            ApiResponse::success(self)
+        } else {
+            let planned = self.created.len() + self.failed.len();
+            ApiResponse::error_with_data(
+                format!("Failed to create {} of {} groups", self.failed.len(), planned),
+                self,
+            )
+        }
     }
+}
 
-    let group = Group {
-        id,
-        name,
-        databases,
-        profile_id: profile_id.or(existing.profile_id.clone()), // Use provided profile_id or preserve existing
-        created_by: existing.created_by.clone(),
-        created_at: existing.created_at,
-        updated_at: Utc::now(),
-    };
+/// Pick a name not already taken, adding " (2)", " (3)", ... when needed
+/// Also used for imported profile names
+pub(crate) fn unique_group_name(name: &str, taken: &[String]) -> String {
+    let is_taken = |candidate: &str| taken.iter().any(|t| t.eq_ignore_ascii_case(candidate));
+    if !is_taken(name) {
+        return name.to_string();
+    }
+    (2..)
+        .map(|n| format!("{} ({})", name, n))
+        .find(|candidate| !is_taken(candidate))
+        .unwrap()
+}
+
+/// Plan (name, databases) for each new group, leaving out databases an existing group already covers
+/// Returns the plan and the number of databases skipped
+fn plan_bulk_groups(
+    strategy: BulkGroupStrategy,
+    databases: &[DatabaseInfo],
+    existing_groups: &[Group],
+) -> (Vec<(String, Vec<String>)>, usize) {
+    let (uncovered, covered): (Vec<&DatabaseInfo>, Vec<&DatabaseInfo>) = databases
+        .iter()
+        .partition(|db| !existing_groups.iter().any(|g| g.databases.contains(&db.name)));
+
+    let mut plan: Vec<(String, Vec<String>)> = Vec::new();
+    for db in uncovered {
+        let key = match strategy {
+            BulkGroupStrategy::OnePerDatabase => {
+                plan.push((db.name.clone(), vec![db.name.clone()]));
+                continue;
+            }
+            BulkGroupStrategy::OnePerCategory => db.category.as_str(),
+            BulkGroupStrategy::OnePerCollation => db.collation.as_deref().unwrap_or(UNKNOWN_COLLATION_GROUP),
+        };
+        match plan.iter_mut().find(|(name, _)| name == key) {
+            Some((_, members)) => members.push(db.name.clone()),
+            None => plan.push((key.to_string(), vec![db.name.clone()])),
+        }
+    }
 
-    match store.update_group(&group) {
-        Ok(_) => {
-            // Log to history
-            let history_entry = HistoryEntry {
+    let mut taken: Vec<String> = existing_groups.iter().map(|g| g.name.clone()).collect();
+    for (name, _) in plan.iter_mut() {
+        *name = unique_group_name(name, &taken);
+        taken.push(name.clone());
+    }
+
+    (plan, covered.len())
+}
+
+/// Create groups for the active profile's databases in one step: one per database, category, or collation
+/// Databases already in a group are skipped so running it twice doesn't duplicate anything
+#[tauri::command]
+pub async fn bulk_create_groups(strategy: BulkGroupStrategy) -> ApiResponse<BulkCreateGroupsResult> {
+    timed(async move {
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
+
+        let profile = match get_active_connection_profile(&store) {
+            Ok(p) => p,
+            Err(e) => return ApiResponse::error(e),
+        };
+
+        let databases = match SqlServerConnection::connect(&profile).await {
+            Ok(mut conn) => match conn.get_databases(None).await {
+                Ok(databases) => databases,
+                Err(e) => return ApiResponse::error(format!("Failed to get databases: {}", e)),
+            },
+            Err(e) => return ApiResponse::error_key(messages::CONNECT_FAILED, &[&e]),
+        };
+
+        // Scoped to the active profile, like the groups the new ones are created under
+        let existing_groups = match store.get_groups() {
+            Ok(g) => g,
+            Err(e) => return ApiResponse::error_key(messages::GET_GROUPS_FAILED, &[&e]),
+        };
+
+        let (plan, skipped) = plan_bulk_groups(strategy, &databases, &existing_groups);
+
+        let (created, failed) = create_planned_groups(
+            &store,
+            plan,
+            "bulk_create_groups",
+            serde_json::json!({ "strategy": format!("{:?}", strategy), "skippedDatabases": skipped }),
+        );
+        BulkCreateGroupsResult {
+            created,
+            failed,
+            skipped,
+        }
+        .into_response()
+    })
+    .await
+}
+
+/// Create the planned (name, databases) groups under the active profile and log them to history:
+/// a create_group entry per group plus one `operation_type` entry, written together
+/// A group that fails doesn't stop the rest; returns the groups created and the ones that failed.
+/// `details` gets the created group names added under "groupNames", and failed ones under "failedGroupNames"
+fn create_planned_groups(
+    store: &MetadataStore,
+    plan: Vec<(String, Vec<String>)>,
+    operation_type: &str,
+    mut details: serde_json::Value,
+) -> (Vec<Group>, Vec<FailedGroup>) {
+    let now = Utc::now();
+    let user_name = whoami::username_os().to_string_lossy().into_owned();
+    let mut created = Vec::new();
+    let mut failed = Vec::new();
+    for (name, databases) in plan {
+        let group = Group {
+            id: Uuid::new_v4().to_string(),
+            name,
+            databases,
+            profile_id: None, // create_group assigns the active profile
+            description: None,
+            created_by: Some(user_name.clone()),
+            created_at: now,
+            updated_at: now,
+        };
+        match store.create_group(&group) {
+            Ok(_) => created.push(group),
+            Err(e) => failed.push(FailedGroup {
+                error: format!("Failed to create group \"{}\": {}", group.name, e),
+                name: group.name,
+                databases: group.databases,
+            }),
+        }
+    }
+
+    if !created.is_empty() || !failed.is_empty() {
+        let mut history: Vec<HistoryEntry> = created
+            .iter()
+            .map(|group| HistoryEntry {
                 id: Uuid::new_v4().to_string(),
-                operation_type: "update_group".to_string(),
-                timestamp: Utc::now(),
-                user_name: Some(whoami::username_os().to_string_lossy().into_owned()),
+                operation_type: "create_group".to_string(),
+                timestamp: now,
+                user_name: Some(user_name.clone()),
                 details: Some(serde_json::json!({
                     "groupId": group.id,
                     "groupName": group.name,
-                    "databaseCount": group.databases.len()
+                    "databaseCount": group.databases.len(),
+                    "databases": group.databases
                 })),
                 results: None,
-            };
-            let _ = store.add_history(&history_entry);
-            ApiResponse::success(group)
+                group_id: Some(group.id.clone()),
+            })
+            .collect();
+        details["groupNames"] = serde_json::json!(created.iter().map(|g| g.name.as_str()).collect::<Vec<_>>());
+        details["failedGroupNames"] = serde_json::json!(failed.iter().map(|g| g.name.as_str()).collect::<Vec<_>>());
+        history.push(HistoryEntry {
+            id: Uuid::new_v4().to_string(),
+            operation_type: operation_type.to_string(),
+            timestamp: now,
+            user_name: Some(user_name),
+            details: Some(details),
+            results: None,
+            group_id: None,
+        });
+        let _ = store.add_history_batch(&history);
+    }
+
+    (created, failed)
+}
+
+/// Plan one group per distinct capture value of `pattern`, leaving out unmatched databases
+/// and those an existing group already covers; returns the plan and the number of covered databases skipped
+fn plan_regex_groups(
+    pattern: &Regex,
+    capture: usize,
+    databases: &[DatabaseInfo],
+    existing_groups: &[Group],
+) -> (Vec<(String, Vec<String>)>, usize) {
+    let mut plan: Vec<(String, Vec<String>)> = Vec::new();
+    let mut skipped = 0;
+
+    for db in databases {
+        let Some(bucket) = pattern
+            .captures(&db.name)
+            .and_then(|c| c.get(capture))
+            .map(|m| m.as_str().to_string())
+            .filter(|b| !b.is_empty())
+        else {
+            continue;
+        };
+        if existing_groups.iter().any(|g| g.databases.contains(&db.name)) {
+            skipped += 1;
+            continue;
+        }
+        match plan.iter_mut().find(|(name, _)| name == &bucket) {
+            Some((_, members)) => members.push(db.name.clone()),
+            None => plan.push((bucket, vec![db.name.clone()])),
         }
-        Err(e) => ApiResponse::error(format!("Failed to update group: {}", e)),
     }
+
+    let mut taken: Vec<String> = existing_groups.iter().map(|g| g.name.clone()).collect();
+    for (name, _) in plan.iter_mut() {
+        *name = unique_group_name(name, &taken);
+        taken.push(name.clone());
+    }
+
+    (plan, skipped)
 }
 
-/// Delete a group and all its snapshots (including from SQL Server)
+/// Group the active profile's databases by a regex: each distinct value of the capture group
+/// becomes a group (e.g. `^(App\d+)_.*` puts App1_Sales and App1_Orders in "App1")
+/// nameFromCapture picks which capture group names the group (default 1); unmatched databases stay ungrouped
 #[tauri::command]
-pub async fn delete_group(id: String) -> ApiResponse<()> {
-    let store = match MetadataStore::open() {
-        Ok(s) => s,
-        Err(e) => return ApiResponse::error(format!("Failed to open metadata store: {}", e)),
+#[allow(non_snake_case)]
+pub async fn create_groups_by_regex(
+    pattern: String,
+    nameFromCapture: Option<usize>,
+) -> ApiResponse<BulkCreateGroupsResult> {
+    timed(async move {
+        let capture = nameFromCapture.unwrap_or(1);
+        let regex = match Regex::new(&pattern) {
+            Ok(r) => r,
+            Err(e) => return ApiResponse::error(format!("Invalid pattern: {}", e)),
+        };
+        if capture == 0 || capture >= regex.captures_len() {
+            return ApiResponse::error(format!(
+                "Pattern has no capture group {} to name groups from; wrap the part that names the group in parentheses",
+                capture
+            ));
+        }
+
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
+
+        let profile = match get_active_connection_profile(&store) {
+            Ok(p) => p,
+            Err(e) => return ApiResponse::error(e),
+        };
+
+        let databases = match SqlServerConnection::connect(&profile).await {
+            Ok(mut conn) => match conn.get_databases(None).await {
+                Ok(databases) => databases,
+                Err(e) => return ApiResponse::error(format!("Failed to get databases: {}", e)),
+            },
+            Err(e) => return ApiResponse::error_key(messages::CONNECT_FAILED, &[&e]),
+        };
+
+        let existing_groups = match store.get_groups() {
+            Ok(g) => g,
+            Err(e) => return ApiResponse::error_key(messages::GET_GROUPS_FAILED, &[&e]),
+        };
+
+        let (plan, skipped) = plan_regex_groups(&regex, capture, &databases, &existing_groups);
+
+        let (created, failed) = create_planned_groups(
+            &store,
+            plan,
+            "create_groups_by_regex",
+            serde_json::json!({ "pattern": pattern, "skippedDatabases": skipped }),
+        );
+        BulkCreateGroupsResult {
+            created,
+            failed,
+            skipped,
+        }
+        .into_response()
+    })
+    .await
+}
+
+/// What deleting a group would remove
+#[derive(Debug, serde::Serialize)]
+pub struct GroupDeleteImpact {
+    #[serde(rename = "groupId")]
+    pub group_id: String,
+    #[serde(rename = "groupName")]
+    pub group_name: String,
+    #[serde(rename = "snapshotCount")]
+    pub snapshot_count: usize,
+    #[serde(rename = "manualSnapshotCount")]
+    pub manual_snapshot_count: usize,
+    #[serde(rename = "automaticSnapshotCount")]
+    pub automatic_snapshot_count: usize,
+    /// SQL Server snapshot databases that would be dropped
+    #[serde(rename = "snapshotDatabases")]
+    pub snapshot_databases: Vec<String>,
+    /// Snapshots of the group's databases not created by this group (they block rollback)
+    #[serde(rename = "externalSnapshots")]
+    pub external_snapshots: Vec<String>,
+    /// False when SQL Server couldn't be reached; snapshot_databases then lists tracked names only
+    #[serde(rename = "serverChecked")]
+    pub server_checked: bool,
+}
+
+/// Snapshot database names tracked for a group that were successfully created
+fn tracked_snapshot_databases(snapshots: &[Snapshot]) -> Vec<&str> {
+    snapshots
+        .iter()
+        .flat_map(|s| s.database_snapshots.iter())
+        .filter(|ds| ds.success && !ds.snapshot_name.is_empty())
+        .map(|ds| ds.snapshot_name.as_str())
+        .collect()
+}
+
+/// Work out the impact of deleting a group from its snapshots and, when available,
+/// the snapshot list from SQL Server
+fn summarize_group_delete_impact(
+    group: &Group,
+    snapshots: &[Snapshot],
+    server_snapshots: Option<&[ServerSnapshot]>,
+) -> GroupDeleteImpact {
+    let automatic_snapshot_count = snapshots.iter().filter(|s| s.is_automatic).count();
+    let tracked = tracked_snapshot_databases(snapshots);
+
+    let (snapshot_databases, external_snapshots) = match server_snapshots {
+        Some(server) => {
+            let existing = tracked
+                .iter()
+                .filter(|name| server.iter().any(|s| s.name == **name))
+                .map(|name| name.to_string())
+                .collect();
+            let all_ours: Vec<&str> = snapshots
+                .iter()
+                .flat_map(|s| s.database_snapshots.iter().map(|ds| ds.snapshot_name.as_str()))
+                .collect();
+            let external = server
+                .iter()
+                .filter(|s| !all_ours.contains(&s.name.as_str()) && group.databases.contains(&s.source_database))
+                .map(|s| s.name.clone())
+                .collect();
+            (existing, external)
+        }
+        None => (tracked.iter().map(|name| name.to_string()).collect(), Vec::new()),
     };
 
-    // Get group info before deleting for history
-    let groups = store.get_groups().unwrap_or_default();
-    let group = groups.iter().find(|g| g.id == id);
-    let group_name = group.map(|g| g.name.clone()).unwrap_or_default();
+    GroupDeleteImpact {
+        group_id: group.id.clone(),
+        group_name: group.name.clone(),
+        snapshot_count: snapshots.len(),
+        manual_snapshot_count: snapshots.len() - automatic_snapshot_count,
+        automatic_snapshot_count,
+        snapshot_databases,
+        external_snapshots,
+        server_checked: server_snapshots.is_some(),
+    }
+}
+
+/// Preview what delete_group would remove, for a confirmation dialog
+#[tauri::command]
+#[allow(non_snake_case)]
+pub async fn get_group_delete_impact(groupId: String) -> ApiResponse<GroupDeleteImpact> {
+    timed(async move {
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
 
-    // Get all snapshots for this group to drop from SQL Server
-    let group_snapshots = store.get_snapshots(&id).unwrap_or_default();
-    let mut dropped_count = 0;
+        let groups = match store.get_groups() {
+            Ok(g) => g,
+            Err(e) => return ApiResponse::error_key(messages::GET_GROUPS_FAILED, &[&e]),
+        };
 
-    // If there are snapshots, we need to drop them from SQL Server first
-    if !group_snapshots.is_empty() {
-        let group = match group {
+        let group = match groups.iter().find(|g| g.id == groupId) {
             Some(g) => g,
-            None => return ApiResponse::error(format!("Group not found: {}", id)),
+            None => return ApiResponse::error_key(messages::GROUP_NOT_FOUND, &[&groupId]),
         };
 
-        // Get profile from metadata database using group's profile_id
-        let profile = match get_profile_for_group(&store, group) {
-            Ok(p) => p,
-            Err(e) => return ApiResponse::error(e),
+        let snapshots = match store.get_snapshots(&group.id) {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::GET_SNAPSHOTS_FAILED, &[&e]),
+        };
+
+        // Metadata counts are still useful when SQL Server is unreachable
+        let server_snapshots = match get_profile_for_group(&store, group) {
+            Ok(profile) => match SqlServerConnection::connect(&profile).await {
+                Ok(mut conn) => match conn.get_snapshots_with_source().await {
+                    Ok(s) => Some(s),
+                    Err(e) => {
+                        log::warn!("Could not list server snapshots: {}", e);
+                        None
+                    }
+                },
+                Err(e) => {
+                    log::warn!("Could not connect to SQL Server for delete impact: {}", e);
+                    None
+                }
+            },
+            Err(e) => {
+                log::warn!("{}", e);
+                None
+            }
         };
 
-        // Connect to SQL Server and drop each snapshot database
-        match crate::db::SqlServerConnection::connect(&profile).await {
-            Ok(mut conn) => {
-                for snapshot in &group_snapshots {
-                    for db_snapshot in &snapshot.database_snapshots {
-                        if db_snapshot.success && !db_snapshot.snapshot_name.is_empty() {
-                            if let Ok(_) = conn.drop_snapshot(&db_snapshot.snapshot_name).await {
-                                dropped_count += 1;
-                                log::info!("Dropped snapshot database: {}", db_snapshot.snapshot_name);
-                            } else {
-                                log::warn!("Failed to drop snapshot database: {} (may not exist)", db_snapshot.snapshot_name);
+        ApiResponse::success(summarize_group_delete_impact(
+            group,
+            &snapshots,
+            server_snapshots.as_deref(),
+        ))
+    })
+    .await
+}
+
+/// A group that a shared database belongs to
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct ConflictingGroup {
+    #[serde(rename = "groupId")]
+    pub group_id: String,
+    #[serde(rename = "groupName")]
+    pub group_name: String,
+}
+
+/// A database listed in more than one group
+/// Rolling back one of those groups drops every snapshot of the database, including the others' snapshots
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct DatabaseGroupConflict {
+    pub database: String,
+    pub groups: Vec<ConflictingGroup>,
+}
+
+/// Databases (compared case-insensitively, like SQL Server names) that appear in more than one group
+pub(crate) fn find_database_group_conflicts(groups: &[Group]) -> Vec<DatabaseGroupConflict> {
+    let mut conflicts: Vec<DatabaseGroupConflict> = Vec::new();
+    for group in groups {
+        for database in &group.databases {
+            let member = ConflictingGroup {
+                group_id: group.id.clone(),
+                group_name: group.name.clone(),
+            };
+            match conflicts.iter_mut().find(|c| c.database.eq_ignore_ascii_case(database)) {
+                Some(conflict) if conflict.groups.contains(&member) => {}
+                Some(conflict) => conflict.groups.push(member),
+                None => conflicts.push(DatabaseGroupConflict {
+                    database: database.clone(),
+                    groups: vec![member],
+                }),
+            }
+        }
+    }
+    conflicts.retain(|c| c.groups.len() > 1);
+    conflicts
+}
+
+/// List databases shared by more than one group under the active profile
+/// Read-only; meant to warn before a rollback drops another group's snapshots of a shared database
+#[tauri::command]
+pub async fn get_database_group_conflicts() -> ApiResponse<Vec<DatabaseGroupConflict>> {
+    timed(async move {
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
+
+        let active_profile_id = match store.get_active_profile() {
+            Ok(Some(profile)) => Some(profile.id),
+            Ok(None) => return ApiResponse::error_key(messages::NO_ACTIVE_PROFILE, &[]),
+            Err(e) => return ApiResponse::error(format!("Failed to get active profile: {}", e)),
+        };
+
+        let groups: Vec<Group> = match store.get_groups() {
+            Ok(groups) => groups
+                .into_iter()
+                .filter(|g| g.profile_id.is_none() || g.profile_id == active_profile_id)
+                .collect(),
+            Err(e) => return ApiResponse::error_key(messages::GET_GROUPS_FAILED, &[&e]),
+        };
+
+        ApiResponse::success(find_database_group_conflicts(&groups))
+    })
+    .await
+}
+
+/// Delete a group and all its snapshots (including from SQL Server)
+/// When the require_confirmation setting is on, `confirmed` must be true.
+/// While a UI password is set, `session_token` must come from check_password.
+#[tauri::command]
+pub async fn delete_group(
+    app: tauri::AppHandle,
+    id: String,
+    confirmed: Option<bool>,
+    session_token: Option<String>,
+) -> ApiResponse<()> {
+    timed(async move {
+        if let Some(response) = require_auth(&app.state::<Sessions>(), session_token.as_deref()) {
+            return response;
+        }
+
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
+
+        if let Some(response) = super::confirmation_error(&store, confirmed, "Deleting a group") {
+            return response;
+        }
+
+        // Get group info before deleting for history
+        let groups = store.get_groups().unwrap_or_default();
+        let group = groups.iter().find(|g| g.id == id);
+        let group_name = group.map(|g| g.name.clone()).unwrap_or_default();
+        let group_databases = group.map(|g| g.databases.clone()).unwrap_or_default();
+        let group_profile_id = group.and_then(|g| g.profile_id.clone());
+        // Full group definition so undo_last_operation can recreate it
+        let group_state = group.and_then(|g| serde_json::to_value(g).ok());
+
+        // Get all snapshots for this group to drop from SQL Server
+        let group_snapshots = store.get_snapshots(&id).unwrap_or_default();
+        let mut dropped_count = 0;
+
+        // If there are snapshots, we need to drop them from SQL Server first
+        if !group_snapshots.is_empty() {
+            let group = match group {
+                Some(g) => g,
+                None => return ApiResponse::error_key(messages::GROUP_NOT_FOUND, &[&id]),
+            };
+
+            // Get profile from metadata database using group's profile_id
+            let profile = match get_profile_for_group(&store, group) {
+                Ok(p) => p,
+                Err(e) => return ApiResponse::error(e),
+            };
+
+            // Connect to SQL Server and drop each snapshot database
+            match crate::db::SqlServerConnection::connect(&profile).await {
+                Ok(mut conn) => {
+                    let snapshot_names: Vec<String> = tracked_snapshot_databases(&group_snapshots)
+                        .into_iter()
+                        .map(String::from)
+                        .collect();
+                    match conn.drop_snapshots(&snapshot_names).await {
+                        Ok(failed) => {
+                            dropped_count = snapshot_names.len() - failed.len();
+                            for (name, error) in failed {
+                                log::warn!("Failed to drop snapshot database {}: {}", name, error);
                             }
+                            log::info!("Dropped {} snapshot database(s)", dropped_count);
                         }
+                        Err(e) => log::warn!("Failed to drop snapshot databases: {}", e),
                     }
                 }
+                Err(e) => {
+                    log::warn!("Could not connect to SQL Server to drop snapshots: {}", e);
+                    // Continue with metadata deletion even if we couldn't drop SQL Server snapshots
+                    // User will need to clean up orphans manually
+                }
             }
-            Err(e) => {
-                log::warn!("Could not connect to SQL Server to drop snapshots: {}", e);
-                // Continue with metadata deletion even if we couldn't drop SQL Server snapshots
-                // User will need to clean up orphans manually
+        }
+
+        // Snapshot metadata is removed along with the group via ON DELETE CASCADE
+        match store.delete_group(&id) {
+            Ok(_) => {
+                // Log to history
+                let history_entry = HistoryEntry {
+                    id: Uuid::new_v4().to_string(),
+                    operation_type: "delete_group".to_string(),
+                    timestamp: Utc::now(),
+                    user_name: Some(whoami::username_os().to_string_lossy().into_owned()),
+                    details: Some(serde_json::json!({
+                        "groupId": id,
+                        "groupName": group_name,
+                        "databases": group_databases,
+                        "profileId": group_profile_id,
+                        "droppedSnapshots": dropped_count,
+                        "reversible": group_state.is_some(),
+                        "group": group_state
+                    })),
+                    results: None,
+                    group_id: Some(id.clone()),
+                };
+                let _ = store.add_history(&history_entry);
+                ApiResponse::success(())
             }
+            Err(e) => ApiResponse::error(format!("Failed to delete group: {}", e)),
+        }
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::DatabaseSnapshot;
+
+    fn snapshot(id: &str, is_automatic: bool, names: &[(&str, bool)]) -> Snapshot {
+        Snapshot {
+            id: id.to_string(),
+            group_id: "group-1".to_string(),
+            display_name: id.to_string(),
+            sequence: 1,
+            created_at: Utc::now(),
+            created_by: None,
+            database_snapshots: names
+                .iter()
+                .map(|(name, success)| DatabaseSnapshot {
+                    database: "Sales".to_string(),
+                    snapshot_name: name.to_string(),
+                    success: *success,
+                    error: None,
+                    server_created_at: None,
+                })
+                .collect(),
+            is_automatic,
+            notes: None,
+            origin: None,
         }
     }
 
-    // Delete snapshot metadata
-    if let Err(e) = store.delete_snapshots_for_group(&id) {
-        return ApiResponse::error(format!("Failed to delete group snapshots: {}", e));
+    fn group() -> Group {
+        Group {
+            id: "group-1".to_string(),
+            name: "Sales".to_string(),
+            databases: vec!["Sales".to_string()],
+            profile_id: None,
+            description: None,
+            created_by: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
     }
 
-    match store.delete_group(&id) {
-        Ok(_) => {
-            // Log to history
-            let history_entry = HistoryEntry {
-                id: Uuid::new_v4().to_string(),
-                operation_type: "delete_group".to_string(),
-                timestamp: Utc::now(),
-                user_name: Some(whoami::username_os().to_string_lossy().into_owned()),
-                details: Some(serde_json::json!({
-                    "groupId": id,
-                    "groupName": group_name,
-                    "droppedSnapshots": dropped_count
-                })),
-                results: None,
-            };
-            let _ = store.add_history(&history_entry);
-            ApiResponse::success(())
+    fn server_snapshot(name: &str, source_database: &str) -> ServerSnapshot {
+        ServerSnapshot {
+            name: name.to_string(),
+            source_database: source_database.to_string(),
+            create_date: None,
         }
-        Err(e) => ApiResponse::error(format!("Failed to delete group: {}", e)),
+    }
+
+    #[test]
+    fn test_delete_impact_counts_snapshots_and_externals() {
+        let snapshots = vec![
+            snapshot("s1", false, &[("Sales_snap_1", true)]),
+            snapshot("s2", true, &[("Sales_snap_2", true)]),
+            snapshot("s3", false, &[("Sales_snap_3", false)]),
+        ];
+        let server = vec![
+            server_snapshot("Sales_snap_1", "Sales"),
+            server_snapshot("Sales_manual", "Sales"),
+            server_snapshot("Other_snap", "Other"),
+        ];
+
+        let impact = summarize_group_delete_impact(&group(), &snapshots, Some(&server));
+        assert_eq!(impact.snapshot_count, 3);
+        assert_eq!(impact.manual_snapshot_count, 2);
+        assert_eq!(impact.automatic_snapshot_count, 1);
+        assert_eq!(impact.snapshot_databases, vec!["Sales_snap_1".to_string()]);
+        assert_eq!(impact.external_snapshots, vec!["Sales_manual".to_string()]);
+        assert!(impact.server_checked);
+    }
+
+    #[test]
+    fn test_delete_impact_without_server_uses_tracked_names() {
+        let snapshots = vec![snapshot("s1", false, &[("Sales_snap_1", true), ("", false)])];
+
+        let impact = summarize_group_delete_impact(&group(), &snapshots, None);
+        assert_eq!(impact.snapshot_databases, vec!["Sales_snap_1".to_string()]);
+        assert!(impact.external_snapshots.is_empty());
+        assert!(!impact.server_checked);
+    }
+
+    #[test]
+    fn test_database_group_conflicts_lists_shared_databases() {
+        let with = |id: &str, databases: &[&str]| Group {
+            id: id.to_string(),
+            name: id.to_uppercase(),
+            databases: databases.iter().map(|d| d.to_string()).collect(),
+            ..group()
+        };
+        let groups = vec![
+            with("a", &["Sales", "Orders"]),
+            with("b", &["sales", "Reports"]),
+            with("c", &["Orders", "Orders"]),
+        ];
+
+        let conflicts = find_database_group_conflicts(&groups);
+        let summary: Vec<(&str, Vec<&str>)> = conflicts
+            .iter()
+            .map(|c| (c.database.as_str(), c.groups.iter().map(|g| g.group_id.as_str()).collect()))
+            .collect();
+        assert_eq!(summary, vec![("Sales", vec!["a", "b"]), ("Orders", vec!["a", "c"])]);
+        assert!(find_database_group_conflicts(&[with("c", &["Orders", "Orders"])]).is_empty());
+    }
+
+    #[test]
+    fn test_plan_bulk_groups_skips_covered_databases() {
+        let db = |name: &str, category: &str, collation: Option<&str>| DatabaseInfo {
+            name: name.to_string(),
+            category: category.to_string(),
+            create_date: Utc::now(),
+            collation: collation.map(String::from),
+            owner: None,
+        };
+        let databases = vec![
+            db("Sales", "User", Some("SQL_Latin1_General_CP1_CI_AS")),
+            db("Orders", "User", Some("Latin1_General_100_CS_AS")),
+            db("DW_Facts", "Data Warehouse", Some("SQL_Latin1_General_CP1_CI_AS")),
+            db("User", "User", None),
+        ];
+        let existing = vec![group()];
+
+        let (plan, skipped) = plan_bulk_groups(BulkGroupStrategy::OnePerDatabase, &databases, &existing);
+        assert_eq!(skipped, 1);
+        let names: Vec<&str> = plan.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["Orders", "DW_Facts", "User"]);
+
+        let (plan, skipped) = plan_bulk_groups(BulkGroupStrategy::OnePerCategory, &databases, &existing);
+        assert_eq!(skipped, 1);
+        assert_eq!(
+            plan,
+            vec![
+                ("User".to_string(), vec!["Orders".to_string(), "User".to_string()]),
+                ("Data Warehouse".to_string(), vec!["DW_Facts".to_string()]),
+            ]
+        );
+
+        let (plan, _) = plan_bulk_groups(BulkGroupStrategy::OnePerCollation, &databases, &existing);
+        assert_eq!(
+            plan,
+            vec![
+                ("Latin1_General_100_CS_AS".to_string(), vec!["Orders".to_string()]),
+                ("SQL_Latin1_General_CP1_CI_AS".to_string(), vec!["DW_Facts".to_string()]),
+                (UNKNOWN_COLLATION_GROUP.to_string(), vec!["User".to_string()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unique_group_name_adds_suffix() {
+        let taken = vec!["Sales".to_string(), "Sales (2)".to_string()];
+        assert_eq!(unique_group_name("Orders", &taken), "Orders");
+        assert_eq!(unique_group_name("sales", &taken), "sales (3)");
+    }
+
+    #[test]
+    fn test_plan_regex_groups_buckets_by_capture() {
+        let db = |name: &str| DatabaseInfo {
+            name: name.to_string(),
+            category: "User".to_string(),
+            create_date: Utc::now(),
+            collation: None,
+            owner: None,
+        };
+        let databases = vec![db("App1_Sales"), db("App1_Orders"), db("App2_Sales"), db("Sales"), db("Reports")];
+        let pattern = Regex::new(r"^(App\d+)_.*").unwrap();
+
+        let (plan, skipped) = plan_regex_groups(&pattern, 1, &databases, &[group()]);
+        assert_eq!(skipped, 0);
+        assert_eq!(
+            plan,
+            vec![
+                ("App1".to_string(), vec!["App1_Sales".to_string(), "App1_Orders".to_string()]),
+                ("App2".to_string(), vec!["App2_Sales".to_string()]),
+            ]
+        );
+
+        // A database already in a group is skipped even when it matches
+        let pattern = Regex::new(r"^(Sales|Reports)$").unwrap();
+        let (plan, skipped) = plan_regex_groups(&pattern, 1, &databases, &[group()]);
+        assert_eq!(skipped, 1);
+        assert_eq!(plan, vec![("Reports".to_string(), vec!["Reports".to_string()])]);
+    }
+
+    #[test]
+    fn test_group_export_round_trips_without_snapshots() {
+        let export = GroupExport {
+            name: "Sales".to_string(),
+            description: None,
+            databases: vec!["Sales".to_string(), "Orders".to_string()],
+        };
+
+        let json = serde_json::to_string(&export).unwrap();
+        assert_eq!(json, r#"{"name":"Sales","databases":["Sales","Orders"]}"#);
+        assert_eq!(serde_json::from_str::<GroupExport>(&json).unwrap(), export);
     }
 }
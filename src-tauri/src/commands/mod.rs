@@ -2,13 +2,19 @@
 // ABOUTME: Organizes all frontend-callable commands by category
 
 pub mod connection;
+pub mod dashboard;
 pub mod groups;
+pub mod operations;
 pub mod profiles;
 pub mod settings;
 pub mod snapshots;
+pub mod undo;
 
 pub use connection::*;
+pub use dashboard::*;
 pub use groups::*;
+pub use operations::*;
 pub use profiles::*;
 pub use settings::*;
 pub use snapshots::*;
+pub use undo::*;
@@ -0,0 +1,433 @@
+// ABOUTME: Crash-safe progress records for multi-step rollbacks, and the commands to resume or abandon them
+// ABOUTME: Each destructive step is saved as in progress before it runs and as done or failed after
+
+use chrono::Utc;
+use tauri::Manager;
+use uuid::Uuid;
+
+use super::get_profile_for_group;
+use crate::db::{MetadataStore, SqlServerConnection};
+use crate::group_locks::{group_busy, GroupLocks};
+use crate::messages;
+use crate::models::{
+    HistoryEntry, Operation, OperationAction, OperationResult, OperationStatus, OperationStep, PreRestoreState,
+    StepStatus,
+};
+use crate::sessions::{require_auth, Sessions};
+use crate::{timed, ApiResponse};
+
+/// An operation's record in the metadata store, updated around each step
+/// Failing to save is logged rather than stopping the operation; the record is a safety net
+pub(crate) struct OperationLog<'a> {
+    store: &'a MetadataStore,
+    operation: Operation,
+}
+
+impl<'a> OperationLog<'a> {
+    /// Record a new operation with every step pending
+    pub(crate) fn start(
+        store: &'a MetadataStore,
+        operation_type: &str,
+        group_id: &str,
+        snapshot_id: Option<&str>,
+        actions: Vec<OperationAction>,
+    ) -> Self {
+        let now = Utc::now();
+        let log = OperationLog {
+            store,
+            operation: Operation {
+                id: Uuid::new_v4().to_string(),
+                operation_type: operation_type.to_string(),
+                group_id: group_id.to_string(),
+                snapshot_id: snapshot_id.map(String::from),
+                status: OperationStatus::InProgress,
+                steps: actions
+                    .into_iter()
+                    .map(|action| OperationStep {
+                        action,
+                        status: StepStatus::Pending,
+                    })
+                    .collect(),
+                started_at: now,
+                updated_at: now,
+            },
+        };
+        log.save();
+        log
+    }
+
+    /// Continue an interrupted operation's record
+    fn resume(store: &'a MetadataStore, mut operation: Operation) -> Self {
+        operation.status = OperationStatus::InProgress;
+        let log = OperationLog { store, operation };
+        log.save();
+        log
+    }
+
+    fn save(&self) {
+        if let Err(e) = self.store.save_operation(&self.operation) {
+            log::warn!("Failed to record progress of operation {}: {}", self.operation.id, e);
+        }
+    }
+
+    /// Set a step's status and save it
+    pub(crate) fn mark(&mut self, index: usize, status: StepStatus) {
+        if let Some(step) = self.operation.steps.get_mut(index) {
+            step.status = status;
+            self.operation.updated_at = Utc::now();
+            self.save();
+        }
+    }
+
+    /// The operation stopped short; keep the record, with its step statuses, so it can be resumed again
+    fn interrupt(mut self) {
+        self.operation.status = OperationStatus::Interrupted;
+        self.operation.updated_at = Utc::now();
+        self.save();
+    }
+
+    /// The operation finished; its outcome lives in history, so the record goes
+    pub(crate) fn finish(self) {
+        if let Err(e) = self.store.delete_operation(&self.operation.id) {
+            log::warn!("Failed to remove record of operation {}: {}", self.operation.id, e);
+        }
+    }
+}
+
+/// Drop snapshots from the server and forget the group's own ones in metadata
+/// Returns the names that couldn't be dropped; missing snapshots count as dropped
+pub(crate) async fn run_drop_step(
+    conn: &mut SqlServerConnection,
+    store: &MetadataStore,
+    snapshot_names: &[String],
+    snapshot_ids: &[String],
+) -> Vec<String> {
+    let failed = match conn.drop_snapshots(snapshot_names).await {
+        Ok(failed) => failed
+            .into_iter()
+            .map(|(name, error)| {
+                log::warn!("Failed to drop snapshot {}: {}", name, error);
+                name
+            })
+            .collect(),
+        Err(e) => {
+            log::warn!("Failed to drop snapshots: {}", e);
+            snapshot_names.to_vec()
+        }
+    };
+    for snapshot_id in snapshot_ids {
+        let _ = store.delete_snapshot(snapshot_id);
+    }
+    failed
+}
+
+/// Kick everyone off a database and restore it from its snapshot
+/// The result's pre_state records the database's state beforehand and how each step went,
+/// so a restore that fails on a lock can be traced to the step that didn't clear it
+pub(crate) async fn run_restore_step(
+    conn: &mut SqlServerConnection,
+    database: &str,
+    snapshot_name: &str,
+) -> OperationResult {
+    let mut pre_state = PreRestoreState::default();
+    match conn.get_database_access(database).await {
+        Ok((state, user_access)) => {
+            if user_access.eq_ignore_ascii_case("SINGLE_USER") {
+                log::warn!("'{}' is already SINGLE_USER before the restore", database);
+            }
+            pre_state.state = Some(state);
+            pre_state.user_access = Some(user_access);
+        }
+        Err(e) => log::warn!("Failed to read the state of '{}': {}", database, e),
+    }
+
+    log::info!("Killing connections for '{}'", database);
+    match conn.kill_connections(database).await {
+        Ok(killed) => pre_state.connections_killed = Some(killed),
+        Err(e) => {
+            log::warn!("Failed to kill connections: {}", e);
+            pre_state.kill_error = Some(e.to_string());
+        }
+    }
+
+    log::info!("Setting '{}' to SINGLE_USER", database);
+    if let Err(e) = conn.set_single_user(database).await {
+        pre_state.single_user_error = Some(e.to_string());
+        return OperationResult {
+            pre_state: Some(pre_state),
+            ..super::snapshots::failed_result(database, format!("SINGLE_USER failed: {}", e), &e)
+        };
+    }
+    pre_state.single_user = true;
+
+    log::info!("Restoring database '{}' from snapshot '{}'", database, snapshot_name);
+    let result = match conn.restore_from_snapshot(database, snapshot_name).await {
+        Ok(_) => OperationResult {
+            database: database.to_string(),
+            success: true,
+            ..Default::default()
+        },
+        Err(e) => super::snapshots::failed_result(database, format!("Restore failed: {}", e), &e),
+    };
+    OperationResult {
+        pre_state: Some(pre_state),
+        ..result
+    }
+}
+
+/// Steps still to run when resuming: everything not done, with the final drop only once
+/// every restore has succeeded
+fn remaining_steps(steps: &[OperationStep]) -> Vec<usize> {
+    steps
+        .iter()
+        .enumerate()
+        .filter(|(_, step)| step.status != StepStatus::Done)
+        .map(|(index, _)| index)
+        .collect()
+}
+
+fn restores_done(steps: &[OperationStep]) -> bool {
+    steps
+        .iter()
+        .filter(|step| matches!(step.action, OperationAction::Restore { .. }))
+        .all(|step| step.status == StepStatus::Done)
+}
+
+/// Outcome of resume_operation
+#[derive(serde::Serialize)]
+pub struct ResumeResult {
+    #[serde(rename = "operationId")]
+    pub operation_id: String,
+    pub success: bool,
+    pub results: Vec<OperationResult>,
+}
+
+fn interrupted_operation(store: &MetadataStore, id: &str) -> Result<Operation, String> {
+    match store.get_operation(id) {
+        Ok(Some(operation)) if operation.status == OperationStatus::Interrupted => Ok(operation),
+        Ok(Some(_)) => Err(format!("Operation {} is still running", id)),
+        Ok(None) => Err(format!("Operation not found: {}", id)),
+        Err(e) => Err(format!("Failed to get operation: {}", e)),
+    }
+}
+
+fn add_operation_history(
+    store: &MetadataStore,
+    operation_type: &str,
+    operation: &Operation,
+    results: Option<Vec<OperationResult>>,
+) {
+    let history_entry = HistoryEntry {
+        id: Uuid::new_v4().to_string(),
+        operation_type: operation_type.to_string(),
+        timestamp: Utc::now(),
+        user_name: Some(whoami::username_os().to_string_lossy().into_owned()),
+        details: Some(serde_json::json!({
+            "operationId": operation.id,
+            "operationType": operation.operation_type,
+            "snapshotId": operation.snapshot_id,
+            "steps": operation.steps,
+        })),
+        results,
+        group_id: Some(operation.group_id.clone()),
+    };
+    let _ = store.add_history(&history_entry);
+}
+
+/// Operations an earlier run of the app didn't finish, oldest first
+#[tauri::command]
+pub async fn get_interrupted_operations() -> ApiResponse<Vec<Operation>> {
+    timed(async move {
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
+
+        match store.get_operations() {
+            Ok(operations) => ApiResponse::success(
+                operations
+                    .into_iter()
+                    .filter(|o| o.status == OperationStatus::Interrupted)
+                    .collect(),
+            ),
+            Err(e) => ApiResponse::error(format!("Failed to get operations: {}", e)),
+        }
+    })
+    .await
+}
+
+/// Run the steps an interrupted rollback didn't finish. Steps that already ran are skipped;
+/// a step that was in progress or failed runs again. No automatic checkpoint is taken.
+/// While a UI password is set, `session_token` must come from check_password.
+#[tauri::command]
+pub async fn resume_operation(
+    app: tauri::AppHandle,
+    id: String,
+    session_token: Option<String>,
+) -> ApiResponse<ResumeResult> {
+    timed(async move {
+        if let Some(response) = require_auth(&app.state::<Sessions>(), session_token.as_deref()) {
+            return response;
+        }
+
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
+        let operation = match interrupted_operation(&store, &id) {
+            Ok(o) => o,
+            Err(e) => return ApiResponse::error(e),
+        };
+
+        // By id rather than through get_groups, so switching profiles doesn't strand the operation
+        let group = match store.get_group(&operation.group_id) {
+            Ok(Some(g)) => g,
+            Ok(None) => return ApiResponse::error_key(messages::GROUP_NOT_FOUND, &[&operation.group_id]),
+            Err(e) => return ApiResponse::error(format!("Failed to get group: {}", e)),
+        };
+
+        let _group_guard = match app.state::<GroupLocks>().try_lock(&group.id) {
+            Some(guard) => guard,
+            None => return group_busy(&group.name),
+        };
+
+        let profile = match get_profile_for_group(&store, &group) {
+            Ok(p) => p,
+            Err(e) => return ApiResponse::error(e),
+        };
+        let mut conn = match SqlServerConnection::connect(&profile).await {
+            Ok(c) => c,
+            Err(e) => return ApiResponse::error_key(messages::CONNECT_FAILED, &[&e]),
+        };
+
+        let mut log = OperationLog::resume(&store, operation);
+        let mut results = Vec::new();
+        for index in remaining_steps(&log.operation.steps) {
+            let action = log.operation.steps[index].action.clone();
+            match action {
+                OperationAction::DropSnapshots {
+                    snapshot_names,
+                    snapshot_ids,
+                } => {
+                    log.mark(index, StepStatus::InProgress);
+                    run_drop_step(&mut conn, &store, &snapshot_names, &snapshot_ids).await;
+                    log.mark(index, StepStatus::Done);
+                }
+                OperationAction::Restore {
+                    database,
+                    snapshot_name,
+                } => {
+                    log.mark(index, StepStatus::InProgress);
+                    let result = run_restore_step(&mut conn, &database, &snapshot_name).await;
+                    log.mark(index, if result.success { StepStatus::Done } else { StepStatus::Failed });
+                    results.push(result);
+                }
+                OperationAction::DropTarget {
+                    snapshot_names,
+                    snapshot_id,
+                } => {
+                    if !restores_done(&log.operation.steps) {
+                        continue;
+                    }
+                    log.mark(index, StepStatus::InProgress);
+                    run_drop_step(&mut conn, &store, &snapshot_names, &[snapshot_id]).await;
+                    log.mark(index, StepStatus::Done);
+                }
+            }
+        }
+
+        let success = restores_done(&log.operation.steps);
+        add_operation_history(&store, "resume_operation", &log.operation, Some(results.clone()));
+        let operation_id = log.operation.id.clone();
+        if success {
+            log.finish();
+        } else {
+            log.interrupt();
+        }
+
+        let result = ResumeResult {
+            operation_id,
+            success,
+            results,
+        };
+        if success {
+            ApiResponse::success(result)
+        } else {
+            ApiResponse::error_with_data("Resumed operation did not restore every database".to_string(), result)
+        }
+    })
+    .await
+}
+
+/// Forget an interrupted operation without running its remaining steps
+/// The steps as they were left are written to history so the group's state can be checked by hand
+/// While a UI password is set, `session_token` must come from check_password.
+#[tauri::command]
+pub async fn abandon_operation(app: tauri::AppHandle, id: String, session_token: Option<String>) -> ApiResponse<()> {
+    timed(async move {
+        if let Some(response) = require_auth(&app.state::<Sessions>(), session_token.as_deref()) {
+            return response;
+        }
+
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
+        let operation = match interrupted_operation(&store, &id) {
+            Ok(o) => o,
+            Err(e) => return ApiResponse::error(e),
+        };
+
+        if let Err(e) = store.delete_operation(&operation.id) {
+            return ApiResponse::error(format!("Failed to abandon operation: {}", e));
+        }
+        add_operation_history(&store, "abandon_operation", &operation, None);
+        ApiResponse::success(())
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(action: OperationAction, status: StepStatus) -> OperationStep {
+        OperationStep { action, status }
+    }
+
+    fn restore(database: &str) -> OperationAction {
+        OperationAction::Restore {
+            database: database.to_string(),
+            snapshot_name: format!("{}_snapshot_1", database),
+        }
+    }
+
+    #[test]
+    fn test_remaining_steps_skip_done_and_wait_for_restores() {
+        let drop_target = OperationAction::DropTarget {
+            snapshot_names: vec!["Sales_snapshot_1".to_string()],
+            snapshot_id: "s1".to_string(),
+        };
+        let steps = vec![
+            step(
+                OperationAction::DropSnapshots {
+                    snapshot_names: vec![],
+                    snapshot_ids: vec![],
+                },
+                StepStatus::Done,
+            ),
+            step(restore("Sales"), StepStatus::Done),
+            step(restore("Orders"), StepStatus::InProgress),
+            step(restore("Billing"), StepStatus::Failed),
+            step(drop_target, StepStatus::Pending),
+        ];
+
+        assert_eq!(remaining_steps(&steps), vec![2, 3, 4]);
+        assert!(!restores_done(&steps));
+
+        let mut finished = steps.clone();
+        finished[2].status = StepStatus::Done;
+        finished[3].status = StepStatus::Done;
+        assert!(restores_done(&finished));
+    }
+}
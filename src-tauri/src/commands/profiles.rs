@@ -2,103 +2,158 @@
 // ABOUTME: CRUD operations for database connection profiles
 
 use chrono::Utc;
+use tauri::{Emitter, Manager};
 use uuid::Uuid;
 
 use crate::db::MetadataStore;
-use crate::models::Profile;
-use crate::ApiResponse;
+use crate::messages;
+use crate::models::{EncryptionMode, HistoryEntry, Profile, ProfileOverrides};
+use crate::sessions::{require_auth, Sessions};
+use crate::{timed, ApiResponse};
+
+/// Profile fields safe to record in history (never the password)
+fn profile_history_details(profile: &Profile) -> serde_json::Value {
+    serde_json::json!({
+        "profileId": profile.id,
+        "profileName": profile.name,
+        "platformType": profile.platform_type,
+        "host": profile.host,
+        "port": profile.port,
+        "username": profile.username,
+        "snapshotPath": profile.snapshot_path,
+        "encryptionMode": profile.encryption_mode,
+        "overrides": profile.overrides,
+        "isActive": profile.is_active
+    })
+}
+
+/// Reject an override template the snapshot commands couldn't use
+fn validate_overrides(overrides: Option<&ProfileOverrides>) -> Result<(), String> {
+    match overrides.and_then(|o| o.snapshot_name_template.as_deref()) {
+        Some(template) if !template.trim().is_empty() => super::snapshots::validate_name_template(template),
+        _ => Ok(()),
+    }
+}
+
+/// Log a profile operation to history
+fn add_profile_history(store: &MetadataStore, operation_type: &str, details: serde_json::Value) {
+    let history_entry = HistoryEntry {
+        id: Uuid::new_v4().to_string(),
+        operation_type: operation_type.to_string(),
+        timestamp: Utc::now(),
+        user_name: Some(whoami::username_os().to_string_lossy().into_owned()),
+        details: Some(details),
+        results: None,
+        group_id: None,
+    };
+    let _ = store.add_history(&history_entry);
+}
 
 /// Get all profiles (without passwords for security) with group counts
 #[tauri::command]
 pub async fn get_profiles() -> ApiResponse<Vec<crate::models::ProfilePublic>> {
-    let store = match MetadataStore::open() {
-        Ok(s) => s,
-        Err(e) => return ApiResponse::error(format!("Failed to open metadata store: {}", e)),
-    };
+    timed(async move {
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
 
-    // Ensure at least one profile is active (if profiles exist)
-    let _ = store.ensure_active_profile();
+        // Ensure at least one profile is active (if profiles exist)
+        let _ = store.ensure_active_profile();
 
-    // Get group counts per profile
-    let group_counts = store.get_group_counts_by_profile().unwrap_or_default();
+        // Get group counts per profile
+        let group_counts = store.get_group_counts_by_profile().unwrap_or_default();
 
-    match store.get_profiles() {
-        Ok(profiles) => {
-            // Convert to public profiles (without passwords) with group counts
-            let public_profiles: Vec<crate::models::ProfilePublic> = profiles
-                .into_iter()
-                .map(|p| {
-                    let group_count = group_counts.get(&p.id).copied().unwrap_or(0);
-                    crate::models::ProfilePublic {
-                        id: p.id.clone(),
-                        name: p.name,
-                        platform_type: p.platform_type,
-                        host: p.host,
-                        port: p.port,
-                        username: p.username,
-                        trust_certificate: p.trust_certificate,
-                        snapshot_path: p.snapshot_path,
-                        description: p.description,
-                        notes: p.notes,
-                        is_active: p.is_active,
-                        group_count,
-                        created_at: p.created_at,
-                        updated_at: p.updated_at,
-                    }
-                })
-                .collect();
-            ApiResponse::success(public_profiles)
+        match store.get_profiles() {
+            Ok(profiles) => {
+                // Convert to public profiles (without passwords) with group counts
+                let public_profiles: Vec<crate::models::ProfilePublic> = profiles
+                    .into_iter()
+                    .map(|p| {
+                        let group_count = group_counts.get(&p.id).copied().unwrap_or(0);
+                        crate::models::ProfilePublic {
+                            id: p.id.clone(),
+                            name: p.name,
+                            platform_type: p.platform_type,
+                            host: p.host,
+                            port: p.port,
+                            username: p.username,
+                            trust_certificate: p.trust_certificate,
+                            snapshot_path: p.snapshot_path,
+                            description: p.description,
+                            notes: p.notes,
+                            is_active: p.is_active,
+                            group_count,
+                            created_at: p.created_at,
+                            updated_at: p.updated_at,
+                            server_platform: p.server_platform,
+                            encryption_mode: p.encryption_mode,
+                            overrides: p.overrides,
+                        }
+                    })
+                    .collect();
+                ApiResponse::success(public_profiles)
+            }
+            Err(e) => ApiResponse::error(format!("Failed to get profiles: {}", e)),
         }
-        Err(e) => ApiResponse::error(format!("Failed to get profiles: {}", e)),
-    }
+    })
+    .await
 }
 
 /// Get a single profile by ID (without password for security)
 #[tauri::command]
 pub async fn get_profile(profile_id: String) -> ApiResponse<Option<crate::models::ProfilePublic>> {
-    let store = match MetadataStore::open() {
-        Ok(s) => s,
-        Err(e) => return ApiResponse::error(format!("Failed to open metadata store: {}", e)),
-    };
+    timed(async move {
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
 
-    // Get group counts per profile
-    let group_counts = store.get_group_counts_by_profile().unwrap_or_default();
+        // Get group counts per profile
+        let group_counts = store.get_group_counts_by_profile().unwrap_or_default();
 
-    match store.get_profiles() {
-        Ok(profiles) => {
-            let profile = profiles.into_iter().find(|p| p.id == profile_id);
-            match profile {
-                Some(p) => {
-                    let group_count = group_counts.get(&p.id).copied().unwrap_or(0);
-                    let public_profile = crate::models::ProfilePublic {
-                        id: p.id.clone(),
-                        name: p.name,
-                        platform_type: p.platform_type,
-                        host: p.host,
-                        port: p.port,
-                        username: p.username,
-                        trust_certificate: p.trust_certificate,
-                        snapshot_path: p.snapshot_path,
-                        description: p.description,
-                        notes: p.notes,
-                        is_active: p.is_active,
-                        group_count,
-                        created_at: p.created_at,
-                        updated_at: p.updated_at,
-                    };
-                    ApiResponse::success(Some(public_profile))
+        match store.get_profiles() {
+            Ok(profiles) => {
+                let profile = profiles.into_iter().find(|p| p.id == profile_id);
+                match profile {
+                    Some(p) => {
+                        let group_count = group_counts.get(&p.id).copied().unwrap_or(0);
+                        let public_profile = crate::models::ProfilePublic {
+                            id: p.id.clone(),
+                            name: p.name,
+                            platform_type: p.platform_type,
+                            host: p.host,
+                            port: p.port,
+                            username: p.username,
+                            trust_certificate: p.trust_certificate,
+                            snapshot_path: p.snapshot_path,
+                            description: p.description,
+                            notes: p.notes,
+                            is_active: p.is_active,
+                            group_count,
+                            created_at: p.created_at,
+                            updated_at: p.updated_at,
+                            server_platform: p.server_platform,
+                            encryption_mode: p.encryption_mode,
+                            overrides: p.overrides,
+                        };
+                        ApiResponse::success(Some(public_profile))
+                    }
+                    None => ApiResponse::success(None),
                 }
-                None => ApiResponse::success(None),
             }
+            Err(e) => ApiResponse::error(format!("Failed to get profile: {}", e)),
         }
-        Err(e) => ApiResponse::error(format!("Failed to get profile: {}", e)),
-    }
+    })
+    .await
 }
 
 /// Create a new profile
+/// While a UI password is set, `session_token` must come from check_password.
 #[tauri::command]
 #[allow(non_snake_case)]
 pub async fn create_profile(
+    app: tauri::AppHandle,
     name: String,
     platformType: String,
     host: String,
@@ -110,73 +165,99 @@ pub async fn create_profile(
     description: Option<String>,
     notes: Option<String>,
     isActive: Option<bool>, // Optional - if None, will auto-activate if it's the only profile
+    encryptionMode: Option<EncryptionMode>,
+    overrides: Option<ProfileOverrides>,
+    session_token: Option<String>,
 ) -> ApiResponse<crate::models::ProfilePublic> {
-    let store = match MetadataStore::open() {
-        Ok(s) => s,
-        Err(e) => return ApiResponse::error(format!("Failed to open metadata store: {}", e)),
-    };
+    timed(async move {
+        if let Some(response) = require_auth(&app.state::<Sessions>(), session_token.as_deref()) {
+            return response;
+        }
 
-    // Determine if this profile should be active
-    // If explicitly set, use that; otherwise, activate if it's the only profile
-    let should_be_active = if let Some(explicit) = isActive {
-        explicit
-    } else {
-        // Check if this will be the only profile
-        match store.get_profiles() {
-            Ok(profiles) => profiles.is_empty(), // Activate if it's the first profile
-            Err(_) => false, // On error, don't activate
+        if snapshotPath.trim().is_empty() {
+            return ApiResponse::error("Snapshot path is required".to_string());
+        }
+        if let Err(e) = validate_overrides(overrides.as_ref()) {
+            return ApiResponse::error(e);
         }
-    };
 
-    let now = Utc::now();
-    let profile = Profile {
-        id: Uuid::new_v4().to_string(),
-        name,
-        platform_type: platformType,
-        host,
-        port,
-        username,
-        password,
-        trust_certificate: trustCertificate,
-        snapshot_path: snapshotPath,
-        description,
-        notes,
-        is_active: should_be_active,
-        created_at: now,
-        updated_at: now,
-    };
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
 
-    match store.create_profile(&profile) {
-        Ok(_) => {
-            // Ensure at least one profile is active after creation
-            let _ = store.ensure_active_profile();
-
-            let public_profile = crate::models::ProfilePublic {
-                id: profile.id,
-                name: profile.name,
-                platform_type: profile.platform_type,
-                host: profile.host,
-                port: profile.port,
-                username: profile.username,
-                trust_certificate: profile.trust_certificate,
-                snapshot_path: profile.snapshot_path,
-                description: profile.description,
-                notes: profile.notes,
-                is_active: profile.is_active,
-                group_count: 0, // New profile has no groups yet
-                created_at: profile.created_at,
-                updated_at: profile.updated_at,
-            };
-            ApiResponse::success(public_profile)
+        // Determine if this profile should be active
+        // If explicitly set, use that; otherwise, activate if it's the only profile
+        let should_be_active = if let Some(explicit) = isActive {
+            explicit
+        } else {
+            // Check if this will be the only profile
+            match store.get_profiles() {
+                Ok(profiles) => profiles.is_empty(), // Activate if it's the first profile
+                Err(_) => false, // On error, don't activate
+            }
+        };
+
+        let now = Utc::now();
+        let profile = Profile {
+            id: Uuid::new_v4().to_string(),
+            name,
+            platform_type: platformType,
+            host,
+            port,
+            username,
+            password,
+            trust_certificate: trustCertificate,
+            snapshot_path: snapshotPath,
+            description,
+            notes,
+            is_active: should_be_active,
+            created_at: now,
+            updated_at: now,
+            server_platform: None,
+            encryption_mode: encryptionMode.unwrap_or_default(),
+            overrides: overrides.unwrap_or_default(),
+        };
+
+        match store.create_profile(&profile) {
+            Ok(_) => {
+                // Ensure at least one profile is active after creation
+                let _ = store.ensure_active_profile();
+                add_profile_history(&store, "create_profile", profile_history_details(&profile));
+
+                let public_profile = crate::models::ProfilePublic {
+                    id: profile.id,
+                    name: profile.name,
+                    platform_type: profile.platform_type,
+                    host: profile.host,
+                    port: profile.port,
+                    username: profile.username,
+                    trust_certificate: profile.trust_certificate,
+                    snapshot_path: profile.snapshot_path,
+                    description: profile.description,
+                    notes: profile.notes,
+                    is_active: profile.is_active,
+                    group_count: 0, // New profile has no groups yet
+                    created_at: profile.created_at,
+                    updated_at: profile.updated_at,
+                    server_platform: profile.server_platform,
+                    encryption_mode: profile.encryption_mode,
+                    overrides: profile.overrides,
+                };
+                ApiResponse::success(public_profile)
+            }
+            Err(e) => ApiResponse::error(format!("Failed to create profile: {}", e)),
         }
-        Err(e) => ApiResponse::error(format!("Failed to create profile: {}", e)),
-    }
+    })
+    .await
 }
 
 /// Update an existing profile
+/// While a UI password is set, `session_token` must come from check_password.
 #[tauri::command]
 #[allow(non_snake_case)]
 pub async fn update_profile(
+    app: tauri::AppHandle,
     profile_id: String,
     name: String,
     platformType: String,
@@ -189,128 +270,499 @@ pub async fn update_profile(
     description: Option<String>,
     notes: Option<String>,
     isActive: Option<bool>, // Optional - if None, preserve existing value
+    encryptionMode: Option<EncryptionMode>, // Optional - if None, preserve existing value
+    overrides: Option<ProfileOverrides>,    // Optional - if None, preserve existing overrides
+    session_token: Option<String>,
 ) -> ApiResponse<crate::models::ProfilePublic> {
-    let store = match MetadataStore::open() {
-        Ok(s) => s,
-        Err(e) => return ApiResponse::error(format!("Failed to open metadata store: {}", e)),
-    };
+    timed(async move {
+        if let Some(response) = require_auth(&app.state::<Sessions>(), session_token.as_deref()) {
+            return response;
+        }
 
-    // Get existing profile to preserve password if not provided
-    let existing_profiles = match store.get_profiles() {
-        Ok(p) => p,
-        Err(e) => return ApiResponse::error(format!("Failed to get profiles: {}", e)),
-    };
+        if let Err(e) = validate_overrides(overrides.as_ref()) {
+            return ApiResponse::error(e);
+        }
 
-    let existing_profile = match existing_profiles.iter().find(|p| p.id == profile_id) {
-        Some(p) => p,
-        None => return ApiResponse::error("Profile not found".to_string()),
-    };
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
 
-    let password_to_use = password.unwrap_or_else(|| existing_profile.password.clone());
-    // Preserve existing is_active if not explicitly provided
-    let is_active = isActive.unwrap_or(existing_profile.is_active);
-
-    let profile = Profile {
-        id: profile_id,
-        name,
-        platform_type: platformType,
-        host,
-        port,
-        username,
-        password: password_to_use,
-        trust_certificate: trustCertificate,
-        snapshot_path: snapshotPath,
-        description,
-        notes,
-        is_active,
-        created_at: existing_profile.created_at,
-        updated_at: Utc::now(),
-    };
+        // Get existing profile to preserve password if not provided
+        let existing_profiles = match store.get_profiles() {
+            Ok(p) => p,
+            Err(e) => return ApiResponse::error(format!("Failed to get profiles: {}", e)),
+        };
 
-    // Get group count for this profile
-    let group_counts = store.get_group_counts_by_profile().unwrap_or_default();
-    let group_count = group_counts.get(&profile.id).copied().unwrap_or(0);
-
-    match store.update_profile(&profile) {
-        Ok(_) => {
-            // Ensure at least one profile is active after update
-            let _ = store.ensure_active_profile();
-
-            // Re-fetch profile to get updated is_active status
-            let updated_profiles = store.get_profiles().unwrap_or_default();
-            let updated_profile = updated_profiles.iter().find(|p| p.id == profile.id);
-
-            let public_profile = if let Some(p) = updated_profile {
-                crate::models::ProfilePublic {
-                    id: p.id.clone(),
-                    name: p.name.clone(),
-                    platform_type: p.platform_type.clone(),
-                    host: p.host.clone(),
-                    port: p.port,
-                    username: p.username.clone(),
-                    trust_certificate: p.trust_certificate,
-                    snapshot_path: p.snapshot_path.clone(),
-                    description: p.description.clone(),
-                    notes: p.notes.clone(),
-                    is_active: p.is_active,
-                    group_count,
-                    created_at: p.created_at,
-                    updated_at: p.updated_at,
-                }
-            } else {
-                // Fallback to original profile data if re-fetch fails
-                crate::models::ProfilePublic {
-                    id: profile.id,
-                    name: profile.name,
-                    platform_type: profile.platform_type,
-                    host: profile.host,
-                    port: profile.port,
-                    username: profile.username,
-                    trust_certificate: profile.trust_certificate,
-                    snapshot_path: profile.snapshot_path,
-                    description: profile.description,
-                    notes: profile.notes,
-                    is_active: profile.is_active,
-                    group_count,
-                    created_at: profile.created_at,
-                    updated_at: profile.updated_at,
+        let existing_profile = match existing_profiles.iter().find(|p| p.id == profile_id) {
+            Some(p) => p,
+            None => return ApiResponse::error("Profile not found".to_string()),
+        };
+
+        let password_changed = password
+            .as_ref()
+            .is_some_and(|p| *p != existing_profile.password);
+        let password_to_use = password.unwrap_or_else(|| existing_profile.password.clone());
+        // What was learned about the old server (its OS, its clock) doesn't carry over to a new one
+        let server_changed = !host.eq_ignore_ascii_case(&existing_profile.host) || port != existing_profile.port;
+        let server_platform = if server_changed {
+            None
+        } else {
+            existing_profile.server_platform.clone()
+        };
+        // A blank path falls back to the conventional location for the server's OS, once known
+        let snapshot_path = match &server_platform {
+            Some(platform) if snapshotPath.trim().is_empty() => {
+                crate::config::default_snapshot_path_for(platform)
+            }
+            _ => snapshotPath,
+        };
+        if snapshot_path.trim().is_empty() {
+            return ApiResponse::error("Snapshot path is required".to_string());
+        }
+        // Preserve existing is_active if not explicitly provided
+        let is_active = isActive.unwrap_or(existing_profile.is_active);
+
+        let profile = Profile {
+            id: profile_id,
+            name,
+            platform_type: platformType,
+            host,
+            port,
+            username,
+            password: password_to_use,
+            trust_certificate: trustCertificate,
+            snapshot_path,
+            description,
+            notes,
+            is_active,
+            created_at: existing_profile.created_at,
+            updated_at: Utc::now(),
+            server_platform,
+            encryption_mode: encryptionMode.unwrap_or(existing_profile.encryption_mode),
+            overrides: overrides.unwrap_or_else(|| existing_profile.overrides.clone()),
+        };
+
+        // Get group count for this profile
+        let group_counts = store.get_group_counts_by_profile().unwrap_or_default();
+        let group_count = group_counts.get(&profile.id).copied().unwrap_or(0);
+
+        match store.update_profile(&profile) {
+            Ok(_) => {
+                // Ensure at least one profile is active after update
+                let _ = store.ensure_active_profile();
+                if server_changed {
+                    let _ = store.clear_clock_skew(&profile.id);
                 }
-            };
-            ApiResponse::success(public_profile)
+
+                let mut details = profile_history_details(&profile);
+                details["previousName"] = serde_json::json!(existing_profile.name);
+                details["passwordChanged"] = serde_json::json!(password_changed);
+                add_profile_history(&store, "update_profile", details);
+
+                // Re-fetch profile to get updated is_active status
+                let updated_profiles = store.get_profiles().unwrap_or_default();
+                let updated_profile = updated_profiles.iter().find(|p| p.id == profile.id);
+
+                let public_profile = if let Some(p) = updated_profile {
+                    crate::models::ProfilePublic {
+                        id: p.id.clone(),
+                        name: p.name.clone(),
+                        platform_type: p.platform_type.clone(),
+                        host: p.host.clone(),
+                        port: p.port,
+                        username: p.username.clone(),
+                        trust_certificate: p.trust_certificate,
+                        snapshot_path: p.snapshot_path.clone(),
+                        description: p.description.clone(),
+                        notes: p.notes.clone(),
+                        is_active: p.is_active,
+                        group_count,
+                        created_at: p.created_at,
+                        updated_at: p.updated_at,
+                        server_platform: p.server_platform.clone(),
+                        encryption_mode: p.encryption_mode,
+                        overrides: p.overrides.clone(),
+                    }
+                } else {
+                    // Fallback to original profile data if re-fetch fails
+                    crate::models::ProfilePublic {
+                        id: profile.id,
+                        name: profile.name,
+                        platform_type: profile.platform_type,
+                        host: profile.host,
+                        port: profile.port,
+                        username: profile.username,
+                        trust_certificate: profile.trust_certificate,
+                        snapshot_path: profile.snapshot_path,
+                        description: profile.description,
+                        notes: profile.notes,
+                        is_active: profile.is_active,
+                        group_count,
+                        created_at: profile.created_at,
+                        updated_at: profile.updated_at,
+                        server_platform: profile.server_platform,
+                        encryption_mode: profile.encryption_mode,
+                        overrides: profile.overrides,
+                    }
+                };
+                ApiResponse::success(public_profile)
+            }
+            Err(e) => ApiResponse::error(format!("Failed to update profile: {}", e)),
         }
-        Err(e) => ApiResponse::error(format!("Failed to update profile: {}", e)),
-    }
+    })
+    .await
 }
 
 /// Delete a profile
+/// While a UI password is set, `session_token` must come from check_password.
 #[tauri::command]
-pub async fn delete_profile(profile_id: String) -> ApiResponse<()> {
-    let store = match MetadataStore::open() {
-        Ok(s) => s,
-        Err(e) => return ApiResponse::error(format!("Failed to open metadata store: {}", e)),
-    };
+pub async fn delete_profile(
+    app: tauri::AppHandle,
+    profile_id: String,
+    session_token: Option<String>,
+) -> ApiResponse<()> {
+    timed(async move {
+        if let Some(response) = require_auth(&app.state::<Sessions>(), session_token.as_deref()) {
+            return response;
+        }
+
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
+
+        // Capture the profile before it's gone so history can name it
+        let deleted_profile = store.get_profile(&profile_id).ok().flatten();
+
+        match store.delete_profile(&profile_id) {
+            Ok(_) => {
+                // Ensure at least one profile is active after deletion (if profiles still exist)
+                let _ = store.ensure_active_profile();
 
-    match store.delete_profile(&profile_id) {
-        Ok(_) => {
-            // Ensure at least one profile is active after deletion (if profiles still exist)
-            let _ = store.ensure_active_profile();
-            ApiResponse::success(())
+                let details = match &deleted_profile {
+                    Some(p) => profile_history_details(p),
+                    None => serde_json::json!({ "profileId": profile_id }),
+                };
+                add_profile_history(&store, "delete_profile", details);
+                ApiResponse::success(())
+            }
+            Err(e) => ApiResponse::error(format!("Failed to delete profile: {}", e)),
         }
-        Err(e) => ApiResponse::error(format!("Failed to delete profile: {}", e)),
-    }
+    })
+    .await
+}
+
+/// Event emitted after the active profile changes
+pub const PROFILE_CHANGED_EVENT: &str = "profile-changed";
+
+/// Payload of the profile-changed event
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ProfileChanged {
+    #[serde(rename = "profileId")]
+    pub profile_id: String,
+    #[serde(rename = "previousProfileId")]
+    pub previous_profile_id: Option<String>,
 }
 
-/// Set a profile as active (deactivates all others)
+/// Set a profile as active (deactivates all others) and emit profile-changed
+/// Commands open a fresh connection to the active profile on every call, so there is no cached
+/// database list or pooled connection to invalidate; the event lets open views reload
+/// While a UI password is set, `session_token` must come from check_password.
 #[tauri::command]
-pub async fn set_active_profile(profile_id: String) -> ApiResponse<()> {
-    let store = match MetadataStore::open() {
-        Ok(s) => s,
-        Err(e) => return ApiResponse::error(format!("Failed to open metadata store: {}", e)),
+pub async fn set_active_profile(
+    app: tauri::AppHandle,
+    profile_id: String,
+    session_token: Option<String>,
+) -> ApiResponse<()> {
+    timed(async move {
+        if let Some(response) = require_auth(&app.state::<Sessions>(), session_token.as_deref()) {
+            return response;
+        }
+
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
+
+        let previous_profile = store.get_active_profile().ok().flatten();
+
+        match store.set_active_profile(&profile_id) {
+            Ok(_) => {
+                let profile_name = store
+                    .get_profile(&profile_id)
+                    .ok()
+                    .flatten()
+                    .map(|p| p.name);
+                add_profile_history(
+                    &store,
+                    "set_active_profile",
+                    serde_json::json!({
+                        "profileId": profile_id,
+                        "profileName": profile_name,
+                        "previousProfileId": previous_profile.as_ref().map(|p| p.id.clone()),
+                        "previousProfileName": previous_profile.as_ref().map(|p| p.name.clone())
+                    }),
+                );
+                let event = ProfileChanged {
+                    profile_id,
+                    previous_profile_id: previous_profile.map(|p| p.id),
+                };
+                if let Err(e) = app.emit(PROFILE_CHANGED_EVENT, event) {
+                    log::warn!("Failed to emit {} event: {}", PROFILE_CHANGED_EVENT, e);
+                }
+                ApiResponse::success(())
+            }
+            Err(e) => ApiResponse::error(format!("Failed to set active profile: {}", e)),
+        }
+    })
+    .await
+}
+
+/// One connection in the Express backend's mssql config shape
+#[derive(Debug, serde::Deserialize)]
+struct ExpressConnection {
+    #[serde(default)]
+    name: Option<String>,
+    server: String,
+    #[serde(default)]
+    port: Option<ExpressPort>,
+    #[serde(default)]
+    user: String,
+    #[serde(default)]
+    password: String,
+    #[serde(rename = "snapshotPath", default)]
+    snapshot_path: Option<String>,
+    #[serde(default)]
+    options: ExpressOptions,
+}
+
+/// Express read the port with parseInt, so configs have it as a number or a string
+#[derive(Debug, serde::Deserialize)]
+#[serde(untagged)]
+enum ExpressPort {
+    Number(u16),
+    Text(String),
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+struct ExpressOptions {
+    #[serde(rename = "trustServerCertificate", default)]
+    trust_server_certificate: Option<bool>,
+}
+
+/// An Express config file holds one connection object or a list of them
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum ExpressConfigFile {
+    One(ExpressConnection),
+    Many(Vec<ExpressConnection>),
+}
+
+/// Parse an Express-style config into profiles ready to insert (ids and timestamps filled, none active)
+fn parse_express_config(contents: &str) -> Result<Vec<Profile>, String> {
+    let connections = match serde_json::from_str::<ExpressConfigFile>(contents)
+        .map_err(|e| format!("Not an Express config file: {}", e))?
+    {
+        ExpressConfigFile::One(connection) => vec![connection],
+        ExpressConfigFile::Many(connections) => connections,
     };
 
-    match store.set_active_profile(&profile_id) {
-        Ok(_) => ApiResponse::success(()),
-        Err(e) => ApiResponse::error(format!("Failed to set active profile: {}", e)),
-    }
+    let now = Utc::now();
+    connections
+        .into_iter()
+        .map(|c| {
+            let port = match c.port {
+                None => 1433,
+                Some(ExpressPort::Number(port)) => port,
+                Some(ExpressPort::Text(text)) => text
+                    .trim()
+                    .parse()
+                    .map_err(|_| format!("Invalid port for {}: {}", c.server, text))?,
+            };
+            let name = c
+                .name
+                .filter(|n| !n.trim().is_empty())
+                .unwrap_or_else(|| format!("Imported ({}:{})", c.server, port));
+            let snapshot_path = c
+                .snapshot_path
+                .filter(|p| !p.trim().is_empty())
+                .unwrap_or_else(|| crate::config::default_snapshot_path_for(""));
+
+            Ok(Profile {
+                id: Uuid::new_v4().to_string(),
+                name,
+                platform_type: "Microsoft SQL Server".to_string(),
+                host: c.server,
+                port,
+                username: c.user,
+                password: c.password,
+                // The Express backend trusted the certificate unless told otherwise
+                trust_certificate: c.options.trust_server_certificate.unwrap_or(true),
+                snapshot_path,
+                description: Some("Imported from Express config".to_string()),
+                notes: None,
+                is_active: false,
+                created_at: now,
+                updated_at: now,
+                server_platform: None,
+                encryption_mode: EncryptionMode::default(),
+                overrides: ProfileOverrides::default(),
+            })
+        })
+        .collect()
+}
+
+#[derive(serde::Serialize)]
+pub struct ExpressImportResult {
+    pub imported: usize,
+    /// Connections skipped because a profile with the same host, port, and username exists
+    pub skipped: usize,
+}
+
+/// Create profiles from an Express/Node backend config file (server/port/user/password/snapshotPath)
+/// Connections that match an existing profile's host, port, and username are skipped,
+/// names already in use get a " (2)"-style suffix, and the profiles are created all or nothing
+/// While a UI password is set, `session_token` must come from check_password.
+#[tauri::command]
+pub async fn import_express_config(
+    app: tauri::AppHandle,
+    path: String,
+    session_token: Option<String>,
+) -> ApiResponse<ExpressImportResult> {
+    timed(async move {
+        if let Some(response) = require_auth(&app.state::<Sessions>(), session_token.as_deref()) {
+            return response;
+        }
+
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(c) => c,
+            Err(e) => return ApiResponse::error(format!("Failed to read {}: {}", path, e)),
+        };
+
+        let profiles = match parse_express_config(&contents) {
+            Ok(p) => p,
+            Err(e) => return ApiResponse::error(e),
+        };
+
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
+
+        let mut taken_names: Vec<String> = match store.get_profiles() {
+            Ok(existing) => existing.into_iter().map(|p| p.name).collect(),
+            Err(e) => return ApiResponse::error(format!("Failed to get profiles: {}", e)),
+        };
+
+        let mut to_create: Vec<Profile> = Vec::new();
+        let mut skipped = 0;
+        for mut profile in profiles {
+            let repeated = to_create
+                .iter()
+                .any(|p| p.host == profile.host && p.port == profile.port && p.username == profile.username);
+            match store.find_profile_by_connection(&profile.host, profile.port, &profile.username) {
+                Ok(Some(_)) => {
+                    skipped += 1;
+                    continue;
+                }
+                Ok(None) if repeated => {
+                    skipped += 1;
+                    continue;
+                }
+                Ok(None) => {}
+                Err(e) => return ApiResponse::error(format!("Failed to check existing profiles: {}", e)),
+            }
+
+            // Profile names are unique, so a clash gets a numeric suffix instead of failing the import
+            profile.name = super::unique_group_name(&profile.name, &taken_names);
+            taken_names.push(profile.name.clone());
+            to_create.push(profile);
+        }
+
+        if let Err(e) = store.create_profiles(&to_create) {
+            return ApiResponse::error(format!("Failed to create profiles: {}", e));
+        }
+        for profile in &to_create {
+            let mut details = profile_history_details(profile);
+            details["sourceFile"] = serde_json::json!(path);
+            add_profile_history(&store, "import_express_config", details);
+        }
+        let imported = to_create.len();
+
+        // The first imported profile becomes active when there was none
+        let _ = store.ensure_active_profile();
+
+        ApiResponse::success(ExpressImportResult { imported, skipped })
+    })
+    .await
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_history_details_never_include_password() {
+        let now = Utc::now();
+        let profile = Profile {
+            id: "profile-1".to_string(),
+            name: "Local".to_string(),
+            platform_type: "Microsoft SQL Server".to_string(),
+            host: "localhost".to_string(),
+            port: 1433,
+            username: "sa".to_string(),
+            password: "hunter2-secret".to_string(),
+            trust_certificate: true,
+            snapshot_path: "/var/opt/mssql/snapshots".to_string(),
+            description: None,
+            notes: None,
+            is_active: true,
+            created_at: now,
+            updated_at: now,
+            server_platform: None,
+            encryption_mode: EncryptionMode::default(),
+            overrides: ProfileOverrides::default(),
+        };
+
+        let details = profile_history_details(&profile);
+        assert_eq!(details["profileName"], "Local");
+        assert!(details.get("password").is_none());
+        assert!(!details.to_string().contains("hunter2-secret"));
+    }
+
+    #[test]
+    fn test_parse_express_config_single_and_list() {
+        let single = r#"{
+            "server": "sql.example.com",
+            "port": "1434",
+            "user": "parrot",
+            "password": "pw",
+            "snapshotPath": "D:\\Snapshots",
+            "options": { "trustServerCertificate": false }
+        }"#;
+        let profiles = parse_express_config(single).unwrap();
+        assert_eq!(profiles.len(), 1);
+        assert_eq!(profiles[0].host, "sql.example.com");
+        assert_eq!(profiles[0].port, 1434);
+        assert_eq!(profiles[0].username, "parrot");
+        assert_eq!(profiles[0].snapshot_path, "D:\\Snapshots");
+        assert!(!profiles[0].trust_certificate);
+        assert_eq!(profiles[0].name, "Imported (sql.example.com:1434)");
+
+        let list = r#"[
+            { "name": "Dev", "server": "dev", "user": "sa", "password": "x" },
+            { "server": "qa", "port": 1500, "user": "sa", "password": "y" }
+        ]"#;
+        let profiles = parse_express_config(list).unwrap();
+        assert_eq!(profiles.len(), 2);
+        assert_eq!(profiles[0].name, "Dev");
+        assert_eq!(profiles[0].port, 1433);
+        assert!(profiles[0].trust_certificate);
+        assert_eq!(profiles[0].snapshot_path, "/var/opt/mssql/snapshots");
+        assert_eq!(profiles[1].port, 1500);
+
+        assert!(parse_express_config(r#"{ "server": "x", "port": "abc" }"#).is_err());
+        assert!(parse_express_config(r#"{ "host": "x" }"#).is_err());
+    }
+}
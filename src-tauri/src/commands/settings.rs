@@ -2,120 +2,516 @@
 // ABOUTME: Manages app settings and operation history
 // ABOUTME: UI Security - password protection for SQL Parrot UI (NOT database profile passwords)
 
-use crate::db::MetadataStore;
-use crate::models::{HistoryEntry, Settings};
-use crate::ApiResponse;
-use bcrypt::{hash, verify, DEFAULT_COST};
+use chrono::{DateTime, Utc};
+use tauri::Manager;
+use uuid::Uuid;
+
+use crate::db::{ConnectionLimiter, MetadataStore};
+use crate::export::csv_line;
+use crate::messages;
+use crate::models::{AutoVerification, HistoryEntry, PasswordPolicy, Settings, SettingsPreferences};
+use crate::sessions::{require_auth, Sessions};
+use crate::timezone;
+use crate::webhooks::{self, WebhookPayload};
+use crate::{timed, ApiResponse};
+use bcrypt::{hash, verify, HashParts};
+
+/// Wrong UI passwords allowed before a lockout, and how long the lockout lasts
+const MAX_PASSWORD_ATTEMPTS: u32 = 5;
+const PASSWORD_LOCKOUT_SECONDS: i64 = 300;
+
+/// Error code returned while password attempts are locked out
+pub const PASSWORD_LOCKED: &str = "PasswordLocked";
+
+/// Range the password_cost setting is clamped to: below 8 is too weak, above 15 makes each login take seconds
+const MIN_PASSWORD_COST: u32 = 8;
+const MAX_PASSWORD_COST: u32 = 15;
+
+/// bcrypt only uses the first 72 bytes, so a longer minimum could never be told apart
+const MAX_PASSWORD_MIN_LENGTH: u32 = 72;
+
+/// Error code returned when a new UI password doesn't meet the password policy
+/// Each unmet requirement is its own entry in messages.error
+pub const PASSWORD_POLICY_UNMET: &str = "PasswordPolicyUnmet";
+
+/// Bounds for the auto-verification interval (1 minute to 1 day)
+const MIN_VERIFICATION_INTERVAL_MINUTES: u32 = 1;
+const MAX_VERIFICATION_INTERVAL_MINUTES: u32 = 1440;
+const MIN_MAX_CONNECTIONS: u32 = 1;
+const MAX_MAX_CONNECTIONS: u32 = 64;
+
+/// Error code returned when a destructive operation is called without `confirmed: true`
+/// while the require_confirmation setting is on
+pub const CONFIRMATION_REQUIRED: &str = "ConfirmationRequired";
+
+/// Whether settings demand confirmation that the caller didn't give
+fn is_confirmation_missing(settings: &Settings, confirmed: Option<bool>) -> bool {
+    settings.preferences.require_confirmation && !confirmed.unwrap_or(false)
+}
+
+/// Build the ConfirmationRequired error for a destructive operation when settings demand
+/// confirmation that the caller didn't give
+pub(crate) fn confirmation_error<T>(
+    store: &MetadataStore,
+    confirmed: Option<bool>,
+    operation: &str,
+) -> Option<ApiResponse<T>> {
+    let settings = store.get_settings().unwrap_or_default();
+    is_confirmation_missing(&settings, confirmed).then(|| {
+        ApiResponse::error_with_code(
+            CONFIRMATION_REQUIRED,
+            messages::text(messages::CONFIRMATION_REQUIRED, &[&operation]),
+        )
+    })
+}
+
+/// Validate user-supplied settings before they are stored
+fn validate_settings(
+    preferences: &SettingsPreferences,
+    auto_verification: &AutoVerification,
+) -> Result<(), String> {
+    if auto_verification.interval_minutes < MIN_VERIFICATION_INTERVAL_MINUTES
+        || auto_verification.interval_minutes > MAX_VERIFICATION_INTERVAL_MINUTES
+    {
+        return Err(format!(
+            "Auto-verification interval must be between {} and {} minutes (got {})",
+            MIN_VERIFICATION_INTERVAL_MINUTES,
+            MAX_VERIFICATION_INTERVAL_MINUTES,
+            auto_verification.interval_minutes
+        ));
+    }
+
+    if preferences.max_history_entries == 0 {
+        return Err("Max history entries must be at least 1".to_string());
+    }
+
+    if !messages::SUPPORTED_LOCALES.contains(&preferences.locale.as_str()) {
+        return Err(format!(
+            "Locale must be one of {} (got {})",
+            messages::SUPPORTED_LOCALES.join(", "),
+            preferences.locale
+        ));
+    }
+
+    if let Some(url) = preferences.webhook_url.as_deref().filter(|url| !url.trim().is_empty()) {
+        webhooks::validate_url(url)?;
+    }
+
+    if preferences.stale_after_hours == Some(0) {
+        return Err("Stale-after hours must be at least 1 (leave it unset to turn stale flagging off)".to_string());
+    }
+
+    if let Some(name) = preferences.display_timezone.as_deref().filter(|name| !name.trim().is_empty()) {
+        timezone::parse(name)?;
+    }
+
+    if let Some(template) = preferences.snapshot_name_template.as_deref().filter(|t| !t.trim().is_empty()) {
+        super::snapshots::validate_name_template(template)?;
+    }
+
+    if preferences.metrics_enabled && preferences.metrics_port == 0 {
+        return Err("Metrics port must be between 1 and 65535".to_string());
+    }
+
+    let min_length = preferences.password_policy.min_length;
+    if min_length == 0 || min_length > MAX_PASSWORD_MIN_LENGTH {
+        return Err(format!(
+            "Password minimum length must be between 1 and {} (got {})",
+            MAX_PASSWORD_MIN_LENGTH, min_length
+        ));
+    }
+
+    if preferences.max_connections < MIN_MAX_CONNECTIONS || preferences.max_connections > MAX_MAX_CONNECTIONS {
+        return Err(format!(
+            "Max connections must be between {} and {} (got {})",
+            MIN_MAX_CONNECTIONS, MAX_MAX_CONNECTIONS, preferences.max_connections
+        ));
+    }
+
+    Ok(())
+}
 
 /// Get application settings
 #[tauri::command]
 pub async fn get_settings() -> ApiResponse<Settings> {
-    let store = match MetadataStore::open() {
-        Ok(s) => s,
-        Err(e) => return ApiResponse::error(format!("Failed to open metadata store: {}", e)),
-    };
+    timed(async move {
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
+
+        match store.get_settings() {
+            Ok(settings) => ApiResponse::success(settings),
+            Err(e) => ApiResponse::error_key(messages::GET_SETTINGS_FAILED, &[&e]),
+        }
+    })
+    .await
+}
+
+/// Overlay the keys present in `patch` onto `base`; nested objects (e.g. passwordPolicy) are merged
+/// key by key, anything else is replaced
+fn merge_json(base: &mut serde_json::Value, patch: serde_json::Value) {
+    match (base, patch) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(patch)) => {
+            for (key, value) in patch {
+                merge_json(base.entry(key).or_insert(serde_json::Value::Null), value);
+            }
+        }
+        (base, patch) => *base = patch,
+    }
+}
+
+/// Apply a partial preferences / auto-verification patch to the stored settings
+/// Fields the caller didn't send keep their stored values
+fn apply_settings_patch(
+    settings: &mut Settings,
+    preferences: serde_json::Value,
+    auto_verification: Option<serde_json::Value>,
+) -> Result<(), String> {
+    let mut merged = serde_json::to_value(&settings.preferences).map_err(|e| e.to_string())?;
+    merge_json(&mut merged, preferences);
+    let preferences: SettingsPreferences =
+        serde_json::from_value(merged).map_err(|e| format!("Invalid preferences: {}", e))?;
+
+    let mut merged = serde_json::to_value(&settings.auto_verification).map_err(|e| e.to_string())?;
+    if let Some(patch) = auto_verification {
+        merge_json(&mut merged, patch);
+    }
+    let auto_verification: AutoVerification =
+        serde_json::from_value(merged).map_err(|e| format!("Invalid auto-verification settings: {}", e))?;
+
+    validate_settings(&preferences, &auto_verification)?;
+    settings.preferences = preferences;
+    settings.auto_verification = auto_verification;
+    Ok(())
+}
 
-    match store.get_settings() {
-        Ok(settings) => ApiResponse::success(settings),
-        Err(e) => ApiResponse::error(format!("Failed to get settings: {}", e)),
+/// Preferences as recorded in history: the webhook URL can carry a token, so only whether it changed is kept
+fn audited_preferences(preferences: &SettingsPreferences, previous_webhook_url: Option<&str>) -> serde_json::Value {
+    let mut audited = serde_json::to_value(preferences).unwrap_or_default();
+    if let Some(fields) = audited.as_object_mut() {
+        fields.remove("webhookUrl");
+        fields.insert(
+            "webhookUrlChanged".to_string(),
+            serde_json::Value::Bool(preferences.webhook_url.as_deref() != previous_webhook_url),
+        );
     }
+    audited
 }
 
 /// Update application settings
 /// Note: Takes individual fields to match the API client's request format
-/// Preserves password fields (not updated through this endpoint)
+/// Both are partial: only the fields sent are changed, so a screen that edits a few preferences
+/// doesn't reset the rest. Password fields are not updated through this endpoint.
+/// While a UI password is set, `session_token` must come from check_password.
 #[tauri::command]
 #[allow(non_snake_case)]
 pub async fn update_settings(
-    preferences: crate::models::SettingsPreferences,
-    autoVerification: crate::models::AutoVerification,
+    app: tauri::AppHandle,
+    preferences: serde_json::Value,
+    autoVerification: Option<serde_json::Value>,
+    session_token: Option<String>,
 ) -> ApiResponse<Settings> {
-    let store = match MetadataStore::open() {
-        Ok(s) => s,
-        Err(e) => return ApiResponse::error(format!("Failed to open metadata store: {}", e)),
-    };
+    timed(async move {
+        if let Some(response) = require_auth(&app.state::<Sessions>(), session_token.as_deref()) {
+            return response;
+        }
 
-    // Get current settings to preserve password fields
-    let current_settings = match store.get_settings() {
-        Ok(s) => s,
-        Err(e) => return ApiResponse::error(format!("Failed to get current settings: {}", e)),
-    };
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
 
-    let settings = Settings {
-        preferences,
-        auto_verification: autoVerification,
-        connection: Default::default(),
-        // Preserve password fields
-        password_hash: current_settings.password_hash,
-        password_skipped: current_settings.password_skipped,
-    };
+        // Merged inside one transaction, so the password fields (and a failed attempt recorded
+        // meanwhile) are never overwritten with a stale copy
+        let (previous_webhook_url, settings) = match store.try_modify_settings(|settings| {
+            let previous_webhook_url = settings.preferences.webhook_url.clone();
+            apply_settings_patch(settings, preferences, autoVerification)
+                .map(|_| (previous_webhook_url, settings.clone()))
+        }) {
+            Ok(Ok(updated)) => updated,
+            Ok(Err(e)) => return ApiResponse::error(e),
+            Err(e) => return ApiResponse::error(format!("Failed to update settings: {}", e)),
+        };
 
-    match store.update_settings(&settings) {
-        Ok(_) => ApiResponse::success(settings),
-        Err(e) => ApiResponse::error(format!("Failed to update settings: {}", e)),
-    }
+        ConnectionLimiter::shared().set_limit(settings.preferences.max_connections as usize);
+        messages::set_locale(&settings.preferences.locale);
+
+        // Password fields are deliberately left out of the audit trail, and the webhook URL is redacted
+        let history_entry = HistoryEntry {
+            id: Uuid::new_v4().to_string(),
+            operation_type: "update_settings".to_string(),
+            timestamp: Utc::now(),
+            user_name: Some(whoami::username_os().to_string_lossy().into_owned()),
+            details: Some(serde_json::json!({
+                "preferences": audited_preferences(&settings.preferences, previous_webhook_url.as_deref()),
+                "autoVerification": settings.auto_verification
+            })),
+            results: None,
+            group_id: None,
+        };
+        let _ = store.add_history(&history_entry);
+        ApiResponse::success(settings)
+    })
+    .await
+}
+
+/// Reset settings to defaults
+/// Preserves password fields (UI password is managed through its own commands)
+/// While a UI password is set, `session_token` must come from check_password.
+#[tauri::command]
+pub async fn reset_settings(app: tauri::AppHandle, session_token: Option<String>) -> ApiResponse<Settings> {
+    timed(async move {
+        if let Some(response) = require_auth(&app.state::<Sessions>(), session_token.as_deref()) {
+            return response;
+        }
+
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
+
+        // Built inside the settings transaction so a password change or failed attempt in the meantime is kept
+        let reset = store.modify_settings(|current| {
+            *current = Settings {
+                password_hash: current.password_hash.take(),
+                password_skipped: current.password_skipped,
+                password_cost: current.password_cost,
+                recovery_code_hash: current.recovery_code_hash.take(),
+                failed_password_attempts: current.failed_password_attempts,
+                last_failed_password_at: current.last_failed_password_at,
+                ..Settings::default()
+            };
+            current.clone()
+        });
+        let settings = match reset {
+            Ok(settings) => settings,
+            Err(e) => return ApiResponse::error(format!("Failed to reset settings: {}", e)),
+        };
+        ConnectionLimiter::shared().set_limit(settings.preferences.max_connections as usize);
+        messages::set_locale(&settings.preferences.locale);
+
+        let history_entry = HistoryEntry {
+            id: Uuid::new_v4().to_string(),
+            operation_type: "reset_settings".to_string(),
+            timestamp: Utc::now(),
+            user_name: Some(whoami::username_os().to_string_lossy().into_owned()),
+            details: None,
+            results: None,
+            group_id: None,
+        };
+        let _ = store.add_history(&history_entry);
+
+        ApiResponse::success(settings)
+    })
+    .await
 }
 
 /// Get operation history
+/// `userName` keeps only that user's entries; `noUser: true` keeps only entries recorded without a user
 #[tauri::command]
-pub async fn get_history(limit: Option<u32>) -> ApiResponse<Vec<HistoryEntry>> {
-    let store = match MetadataStore::open() {
-        Ok(s) => s,
-        Err(e) => return ApiResponse::error(format!("Failed to open metadata store: {}", e)),
-    };
+#[allow(non_snake_case)]
+pub async fn get_history(
+    limit: Option<u32>,
+    userName: Option<String>,
+    noUser: Option<bool>,
+) -> ApiResponse<Vec<HistoryEntry>> {
+    timed(async move {
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
+
+        let history = if noUser.unwrap_or(false) {
+            store.get_history_for_user(None, limit)
+        } else {
+            match userName.as_deref().map(str::trim).filter(|u| !u.is_empty()) {
+                Some(user_name) => store.get_history_for_user(Some(user_name), limit),
+                None => store.get_history(limit),
+            }
+        };
+
+        match history {
+            Ok(history) => ApiResponse::success(history),
+            Err(e) => ApiResponse::error(format!("Failed to get history: {}", e)),
+        }
+    })
+    .await
+}
+
+/// Get operation history for a single group
+#[tauri::command]
+#[allow(non_snake_case)]
+pub async fn get_history_for_group(groupId: String, limit: Option<u32>) -> ApiResponse<Vec<HistoryEntry>> {
+    timed(async move {
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
 
-    match store.get_history(limit) {
-        Ok(history) => ApiResponse::success(history),
-        Err(e) => ApiResponse::error(format!("Failed to get history: {}", e)),
+        match store.get_history_for_group(&groupId, limit) {
+            Ok(history) => ApiResponse::success(history),
+            Err(e) => ApiResponse::error(format!("Failed to get history for group: {}", e)),
+        }
+    })
+    .await
+}
+
+/// History as CSV, newest first, with timestamps shown in the given timezone
+fn history_csv(history: &[HistoryEntry], display_timezone: Option<&str>) -> String {
+    let mut csv = String::from("timestamp,type,userName,groupId,details\n");
+    for entry in history {
+        let mut details = entry.details.clone();
+        // Settings entries recorded before the webhook URL was redacted still carry it
+        if let Some(preferences) = details
+            .as_mut()
+            .and_then(|d| d.get_mut("preferences"))
+            .and_then(|p| p.as_object_mut())
+        {
+            if preferences.remove("webhookUrl").is_some() {
+                preferences.insert("webhookUrl".to_string(), serde_json::json!("[redacted]"));
+            }
+        }
+        let details = details.map(|d| d.to_string()).unwrap_or_default();
+        let row = [
+            timezone::format(entry.timestamp, display_timezone),
+            entry.operation_type.clone(),
+            entry.user_name.clone().unwrap_or_default(),
+            entry.group_id.clone().unwrap_or_default(),
+            details,
+        ];
+        csv.push_str(&csv_line(&row));
     }
+    csv
+}
+
+/// Write operation history to a CSV file, with timestamps in the display_timezone setting
+#[tauri::command]
+pub async fn export_history_csv(path: String, limit: Option<u32>) -> ApiResponse<()> {
+    timed(async move {
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
+
+        let history = match store.get_history(limit) {
+            Ok(h) => h,
+            Err(e) => return ApiResponse::error(format!("Failed to get history: {}", e)),
+        };
+        drop(store);
+
+        match std::fs::write(&path, history_csv(&history, timezone::configured().as_deref())) {
+            Ok(_) => ApiResponse::success(()),
+            Err(e) => ApiResponse::error(format!("Failed to write {}: {}", path, e)),
+        }
+    })
+    .await
 }
 
 /// Clear all history
 #[tauri::command]
 pub async fn clear_history() -> ApiResponse<()> {
-    let store = match MetadataStore::open() {
-        Ok(s) => s,
-        Err(e) => return ApiResponse::error(format!("Failed to open metadata store: {}", e)),
-    };
+    timed(async move {
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
 
-    match store.clear_history() {
-        Ok(_) => ApiResponse::success(()),
-        Err(e) => ApiResponse::error(format!("Failed to clear history: {}", e)),
-    }
+        match store.clear_history() {
+            Ok(_) => ApiResponse::success(()),
+            Err(e) => ApiResponse::error(format!("Failed to clear history: {}", e)),
+        }
+    })
+    .await
 }
 
 /// Trim history to max entries based on settings
 #[tauri::command]
 pub async fn trim_history() -> ApiResponse<u32> {
-    let store = match MetadataStore::open() {
-        Ok(s) => s,
-        Err(e) => return ApiResponse::error(format!("Failed to open metadata store: {}", e)),
-    };
+    timed(async move {
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
 
-    let settings = match store.get_settings() {
-        Ok(s) => s,
-        Err(e) => return ApiResponse::error(format!("Failed to get settings: {}", e)),
-    };
+        let settings = match store.get_settings() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::GET_SETTINGS_FAILED, &[&e]),
+        };
 
-    match store.trim_history(settings.preferences.max_history_entries) {
-        Ok(deleted) => ApiResponse::success(deleted),
-        Err(e) => ApiResponse::error(format!("Failed to trim history: {}", e)),
-    }
+        match store.trim_history(settings.preferences.max_history_entries) {
+            Ok(deleted) => ApiResponse::success(deleted),
+            Err(e) => ApiResponse::error(format!("Failed to trim history: {}", e)),
+        }
+    })
+    .await
+}
+
+/// Send a test payload to a webhook URL (or the configured one) and report the HTTP status
+#[tauri::command]
+pub async fn test_webhook(url: Option<String>) -> ApiResponse<WebhookTestResult> {
+    timed(async move {
+        let url = match url
+            .map(|u| u.trim().to_string())
+            .filter(|u| !u.is_empty())
+            .or_else(webhooks::configured_url)
+        {
+            Some(u) => u,
+            None => return ApiResponse::error("No webhook URL configured".to_string()),
+        };
+
+        if let Err(e) = webhooks::validate_url(&url) {
+            return ApiResponse::error(e);
+        }
+
+        match webhooks::deliver(&url, &WebhookPayload::new("test_webhook", None, Vec::new(), true, None)).await {
+            Ok(status) => ApiResponse::success(WebhookTestResult { url, status }),
+            Err(e) => ApiResponse::error(format!("Webhook test failed: {}", e)),
+        }
+    })
+    .await
+}
+
+#[derive(serde::Serialize)]
+pub struct WebhookTestResult {
+    pub url: String,
+    /// HTTP status the webhook answered with
+    pub status: u16,
 }
 
 /// Get metadata status
 #[tauri::command]
 pub async fn get_metadata_status() -> ApiResponse<MetadataStatusResponse> {
-    let db_path = match MetadataStore::db_path() {
-        Ok(p) => p.to_string_lossy().to_string(),
-        Err(_) => "Unknown".to_string(),
-    };
+    timed(async move {
+        let db_path = match MetadataStore::db_path() {
+            Ok(p) => p.to_string_lossy().to_string(),
+            Err(_) => "Unknown".to_string(),
+        };
+
+        // Opening the store is what detects corruption, so this also reports a database replaced just now
+        let corrupt_backup = match MetadataStore::open() {
+            Ok(store) => store.take_corrupt_backup_path().unwrap_or_else(|e| {
+                log::warn!("Could not read corrupt database notice: {}", e);
+                None
+            }),
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
 
-    ApiResponse::success(MetadataStatusResponse {
-        mode: "sqlite".to_string(),
-        database: Some(db_path),
-        user_name: Some(whoami::username_os().to_string_lossy().into_owned()),
+        let mut response = ApiResponse::success(MetadataStatusResponse {
+            mode: "sqlite".to_string(),
+            database: Some(db_path),
+            user_name: Some(whoami::username_os().to_string_lossy().into_owned()),
+            corrupt_backup: corrupt_backup.clone(),
+        });
+        if let Some(backup) = corrupt_backup {
+            response.messages.warning.push(format!(
+                "The metadata database was corrupt and has been replaced with an empty one. Connection profiles, groups, snapshot records, and history were reset; snapshots on SQL Server are untouched. The damaged file was kept at {}",
+                backup
+            ));
+        }
+        response
     })
+    .await
 }
 
 #[derive(serde::Serialize)]
@@ -123,6 +519,9 @@ pub struct MetadataStatusResponse {
     pub mode: String,
     pub database: Option<String>,
     pub user_name: Option<String>,
+    /// Where a corrupt metadata database was moved when it was replaced, reported once
+    #[serde(rename = "corruptBackup", skip_serializing_if = "Option::is_none")]
+    pub corrupt_backup: Option<String>,
 }
 
 #[derive(serde::Serialize)]
@@ -134,114 +533,404 @@ pub struct PasswordStatus {
     pub password_skipped: bool,
 }
 
+/// Result of setting the initial UI password
+#[derive(serde::Serialize)]
+pub struct PasswordSetResult {
+    /// Shown once; reset_ui_password_with_recovery accepts it if the password is forgotten
+    #[serde(rename = "recoveryCode")]
+    pub recovery_code: String,
+}
+
+/// Result of a successful password check
+#[derive(serde::Serialize)]
+pub struct PasswordCheck {
+    pub authenticated: bool,
+    /// Pass as `sessionToken` to commands that require an unlocked UI
+    #[serde(rename = "sessionToken")]
+    pub session_token: String,
+}
+
+/// One rule of the password policy and whether a password meets it
+#[derive(serde::Serialize)]
+pub struct PasswordRequirement {
+    pub rule: String, // "minLength" | "mixedCase" | "digit" | "symbol"
+    pub message: String,
+    pub met: bool,
+}
+
+/// Each rule the policy turns on, checked against the password
+fn password_requirements(password: &str, policy: &PasswordPolicy) -> Vec<PasswordRequirement> {
+    let requirement = |rule: &str, message: String, met: bool| PasswordRequirement {
+        rule: rule.to_string(),
+        message,
+        met,
+    };
+
+    let mut requirements = vec![requirement(
+        "minLength",
+        format!("Password must be at least {} characters", policy.min_length),
+        password.chars().count() >= policy.min_length as usize,
+    )];
+    if policy.require_mixed_case {
+        requirements.push(requirement(
+            "mixedCase",
+            "Password must include both upper and lower case letters".to_string(),
+            password.chars().any(char::is_uppercase) && password.chars().any(char::is_lowercase),
+        ));
+    }
+    if policy.require_digit {
+        requirements.push(requirement(
+            "digit",
+            "Password must include a digit".to_string(),
+            password.chars().any(|c| c.is_ascii_digit()),
+        ));
+    }
+    if policy.require_symbol {
+        requirements.push(requirement(
+            "symbol",
+            "Password must include a symbol".to_string(),
+            password.chars().any(|c| !c.is_alphanumeric() && !c.is_whitespace()),
+        ));
+    }
+    requirements
+}
+
+/// PasswordPolicyUnmet error listing every unmet requirement; None when the password meets the policy
+fn password_policy_error<T>(password: &str, policy: &PasswordPolicy) -> Option<ApiResponse<T>> {
+    let unmet: Vec<String> = password_requirements(password, policy)
+        .into_iter()
+        .filter(|r| !r.met)
+        .map(|r| r.message)
+        .collect();
+    if unmet.is_empty() {
+        return None;
+    }
+
+    let mut response = ApiResponse::error_with_code(PASSWORD_POLICY_UNMET, unmet[0].clone());
+    response.messages.error = unmet;
+    Some(response)
+}
+
 // ===== UI Security Password Commands =====
 
+/// Check a candidate password against the password policy, for a live checklist while typing
+#[tauri::command]
+pub async fn check_password_policy(password: String) -> ApiResponse<Vec<PasswordRequirement>> {
+    timed(async move {
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
+
+        match store.get_settings() {
+            Ok(settings) => {
+                ApiResponse::success(password_requirements(&password, &settings.preferences.password_policy))
+            }
+            Err(e) => ApiResponse::error_key(messages::GET_SETTINGS_FAILED, &[&e]),
+        }
+    })
+    .await
+}
+
 /// Get password status
 #[tauri::command]
 pub async fn get_password_status() -> ApiResponse<PasswordStatus> {
-    let store = match MetadataStore::open() {
-        Ok(s) => s,
-        Err(e) => return ApiResponse::error(format!("Failed to open metadata store: {}", e)),
+    timed(async move {
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
+
+        match store.get_settings() {
+            Ok(settings) => {
+                let password_hash = settings.password_hash.clone();
+                let password_skipped = settings.password_skipped;
+
+                let status = if password_hash.is_some() {
+                    "set".to_string()
+                } else if password_skipped {
+                    "skipped".to_string()
+                } else {
+                    "not-set".to_string()
+                };
+
+                ApiResponse::success(PasswordStatus {
+                    status,
+                    password_set: password_hash.is_some(),
+                    password_skipped,
+                })
+            }
+            Err(e) => ApiResponse::error_key(messages::GET_SETTINGS_FAILED, &[&e]),
+        }
+    })
+    .await
+}
+
+/// Seconds the caller must wait before the next password attempt, given the failures so far.
+/// Each failure doubles the delay (1s, 2s, 4s, ...) until MAX_PASSWORD_ATTEMPTS triggers a lockout.
+fn password_retry_wait_seconds(
+    failed_attempts: u32,
+    last_failed_at: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> i64 {
+    let last_failed_at = match last_failed_at {
+        Some(t) if failed_attempts > 0 => t,
+        _ => return 0,
+    };
+
+    let delay = if failed_attempts >= MAX_PASSWORD_ATTEMPTS {
+        PASSWORD_LOCKOUT_SECONDS
+    } else {
+        1i64 << (failed_attempts - 1)
     };
 
-    match store.get_settings() {
-        Ok(settings) => {
-            let password_hash = settings.password_hash.clone();
-            let password_skipped = settings.password_skipped;
+    (delay - (now - last_failed_at).num_seconds()).max(0)
+}
+
+/// Why a password or recovery code check didn't pass
+#[derive(Debug)]
+enum SecretRefused {
+    /// Still locked out from earlier failures, for this many more seconds
+    Locked(i64),
+    /// Wrong secret; the new number of failures
+    Invalid(u32),
+    /// Nothing to check against, or the check itself failed
+    Failed(String),
+}
+
+impl SecretRefused {
+    /// User-facing text, with `invalid` as the wrong-secret message
+    fn message(&self, invalid: &str) -> String {
+        match self {
+            SecretRefused::Locked(wait) => format!("Too many failed attempts. Try again in {} seconds", wait),
+            SecretRefused::Invalid(failed) if *failed >= MAX_PASSWORD_ATTEMPTS => format!(
+                "{}. Too many failed attempts. Try again in {} seconds",
+                invalid, PASSWORD_LOCKOUT_SECONDS
+            ),
+            SecretRefused::Invalid(_) => invalid.to_string(),
+            SecretRefused::Failed(message) => message.clone(),
+        }
+    }
+
+    fn into_response<T>(self, invalid: &str) -> ApiResponse<T> {
+        let locked = match self {
+            SecretRefused::Locked(_) => true,
+            SecretRefused::Invalid(failed) => failed >= MAX_PASSWORD_ATTEMPTS,
+            SecretRefused::Failed(_) => false,
+        };
+        if locked {
+            ApiResponse::error_with_code(PASSWORD_LOCKED, self.message(invalid))
+        } else {
+            ApiResponse::error(self.message(invalid))
+        }
+    }
+}
+
+/// Lockout check, verification and failure bookkeeping for one attempt, applied to `settings`
+/// Ok(Ok) is a match (the failure count is cleared), Ok(Err) a counted failure; both are to be saved.
+/// Err means nothing changed.
+fn apply_secret_check(
+    settings: &mut Settings,
+    secret: &str,
+    now: DateTime<Utc>,
+    stored_hash: impl FnOnce(&Settings) -> Result<String, String>,
+) -> Result<Result<Settings, u32>, SecretRefused> {
+    let secret_hash = stored_hash(settings).map_err(SecretRefused::Failed)?;
+    let wait = password_retry_wait_seconds(settings.failed_password_attempts, settings.last_failed_password_at, now);
+    if wait > 0 {
+        return Err(SecretRefused::Locked(wait));
+    }
 
-            let status = if password_hash.is_some() {
-                "set".to_string()
-            } else if password_skipped {
-                "skipped".to_string()
+    match verify(secret, &secret_hash) {
+        Ok(true) => {
+            settings.failed_password_attempts = 0;
+            settings.last_failed_password_at = None;
+            Ok(Ok(settings.clone()))
+        }
+        Ok(false) => {
+            // A lockout that has run out starts a fresh round of attempts
+            settings.failed_password_attempts = if settings.failed_password_attempts >= MAX_PASSWORD_ATTEMPTS {
+                1
             } else {
-                "not-set".to_string()
+                settings.failed_password_attempts + 1
             };
-
-            ApiResponse::success(PasswordStatus {
-                status,
-                password_set: password_hash.is_some(),
-                password_skipped,
-            })
+            settings.last_failed_password_at = Some(now);
+            Ok(Err(settings.failed_password_attempts))
         }
-        Err(e) => ApiResponse::error(format!("Failed to get settings: {}", e)),
+        Err(e) => Err(SecretRefused::Failed(format!("Verification failed: {}", e))),
     }
 }
 
+/// Check a password or recovery code against the hash `stored_hash` picks, under the retry delays and lockout
+/// Everything happens in one settings transaction, so simultaneous guesses queue up and each one counts.
+/// Returns the settings as saved after a match.
+fn check_secret(
+    store: &MetadataStore,
+    secret: &str,
+    now: DateTime<Utc>,
+    stored_hash: impl FnOnce(&Settings) -> Result<String, String>,
+) -> Result<Settings, SecretRefused> {
+    let outcome = store
+        .try_modify_settings(|settings| apply_secret_check(settings, secret, now, stored_hash))
+        .map_err(|e| SecretRefused::Failed(format!("Failed to record password attempt: {}", e)))??;
+    outcome.map_err(SecretRefused::Invalid)
+}
+
+/// The UI password hash, for check_secret
+fn ui_password_hash(settings: &Settings) -> Result<String, String> {
+    settings.password_hash.clone().ok_or_else(|| "Password not set".to_string())
+}
+
+/// bcrypt cost to hash the UI password with, from the password_cost setting
+fn configured_password_cost(preferences: &SettingsPreferences) -> u32 {
+    preferences.password_cost.clamp(MIN_PASSWORD_COST, MAX_PASSWORD_COST)
+}
+
+/// Cost the stored hash was made with, read from the hash itself when it wasn't recorded
+fn stored_password_cost(settings: &Settings) -> Option<u32> {
+    settings.password_cost.or_else(|| {
+        let parts: HashParts = settings.password_hash.as_deref()?.parse().ok()?;
+        Some(parts.get_cost())
+    })
+}
+
+/// A random recovery code in groups of four, e.g. `3F9A-...`: 32 hex digits from a v4 UUID
+fn generate_recovery_code() -> String {
+    let digits = Uuid::new_v4().simple().to_string().to_uppercase();
+    digits
+        .as_bytes()
+        .chunks(4)
+        .map(|chunk| String::from_utf8_lossy(chunk).into_owned())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// A recovery code as typed, reduced to the form that was hashed (no dashes or spaces, upper case)
+fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+/// Whether the stored hash should be replaced with one at the configured cost
+fn needs_rehash(settings: &Settings) -> bool {
+    stored_password_cost(settings) != Some(configured_password_cost(&settings.preferences))
+}
+
 /// Check password (verify and return success)
+/// Repeated failures are delayed and then locked out; the error says how long to wait
+/// A successful check rehashes the password when the password_cost setting has changed,
+/// and starts a session whose token unlocks the sensitive commands
 #[tauri::command]
-pub async fn check_password(password: String) -> ApiResponse<bool> {
-    let store = match MetadataStore::open() {
-        Ok(s) => s,
-        Err(e) => return ApiResponse::error(format!("Failed to open metadata store: {}", e)),
-    };
+pub async fn check_password(app: tauri::AppHandle, password: String) -> ApiResponse<PasswordCheck> {
+    timed(async move {
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
 
-    match store.get_settings() {
-        Ok(settings) => {
-            let password_hash = match settings.password_hash {
-                Some(hash) => hash,
-                None => return ApiResponse::error("Password not set".to_string()),
-            };
+        let now = Utc::now();
+        let settings = match check_secret(&store, &password, now, ui_password_hash) {
+            Ok(settings) => settings,
+            Err(refused) => return refused.into_response("Invalid password"),
+        };
 
-            match verify(&password, &password_hash) {
-                Ok(valid) => {
-                    if valid {
-                        ApiResponse::success(true)
-                    } else {
-                        ApiResponse::error("Invalid password".to_string())
-                    }
+        if needs_rehash(&settings) {
+            let cost = configured_password_cost(&settings.preferences);
+            match hash(&password, cost) {
+                Ok(new_hash) => {
+                    let _ = store.modify_settings(|settings| {
+                        settings.password_hash = Some(new_hash);
+                        settings.password_cost = Some(cost);
+                    });
                 }
-                Err(e) => ApiResponse::error(format!("Password verification failed: {}", e)),
+                Err(e) => log::warn!("Failed to rehash the UI password at cost {}: {}", cost, e),
             }
         }
-        Err(e) => ApiResponse::error(format!("Failed to get settings: {}", e)),
+
+        ApiResponse::success(PasswordCheck {
+            authenticated: true,
+            session_token: app.state::<Sessions>().issue(now),
+        })
+    })
+    .await
+}
+
+/// Gate for the headless CLI, which has no UI session: while a UI password is set,
+/// the caller must pass it (the CLI reads SQL_PARROT_PASSWORD), under the same lockout as check_password
+pub(crate) fn verify_cli_password(store: &MetadataStore, password: Option<&str>) -> Result<(), String> {
+    let settings = store
+        .get_settings()
+        .map_err(|e| messages::text(messages::GET_SETTINGS_FAILED, &[&e]))?;
+    if settings.password_hash.is_none() {
+        return Ok(());
     }
+    let password = password.ok_or_else(|| {
+        "A UI password is set. Set SQL_PARROT_PASSWORD to run commands from the command line".to_string()
+    })?;
+
+    check_secret(store, password, Utc::now(), ui_password_hash)
+        .map(|_| ())
+        .map_err(|refused| refused.message("Invalid password"))
 }
 
 /// Set password (initial setup only)
+/// Returns a recovery code that is only shown this once; only its hash is stored
 #[tauri::command]
-pub async fn set_password(password: String, confirm: String) -> ApiResponse<()> {
-    if password != confirm {
-        return ApiResponse::error("Passwords do not match".to_string());
-    }
-
-    if password.len() < 6 {
-        return ApiResponse::error("Password must be at least 6 characters".to_string());
-    }
+pub async fn set_password(password: String, confirm: String) -> ApiResponse<PasswordSetResult> {
+    timed(async move {
+        if password != confirm {
+            return ApiResponse::error("Passwords do not match".to_string());
+        }
 
-    let store = match MetadataStore::open() {
-        Ok(s) => s,
-        Err(e) => return ApiResponse::error(format!("Failed to open metadata store: {}", e)),
-    };
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
 
-    // Check if password already exists
-    match store.get_settings() {
-        Ok(settings) => {
-            if settings.password_hash.is_some() {
-                return ApiResponse::error("Password already set. Use change_password instead.".to_string());
+        // Check if password already exists
+        let cost = match store.get_settings() {
+            Ok(settings) => {
+                if settings.password_hash.is_some() {
+                    return ApiResponse::error("Password already set. Use change_password instead.".to_string());
+                }
+                if let Some(response) = password_policy_error(&password, &settings.preferences.password_policy) {
+                    return response;
+                }
+                configured_password_cost(&settings.preferences)
             }
-        }
-        Err(e) => return ApiResponse::error(format!("Failed to get settings: {}", e)),
-    }
+            Err(e) => return ApiResponse::error_key(messages::GET_SETTINGS_FAILED, &[&e]),
+        };
 
-    // Hash password
-    let password_hash = match hash(&password, DEFAULT_COST) {
-        Ok(hash) => hash,
-        Err(e) => return ApiResponse::error(format!("Failed to hash password: {}", e)),
-    };
+        // Hash password
+        let password_hash = match hash(&password, cost) {
+            Ok(hash) => hash,
+            Err(e) => return ApiResponse::error(format!("Failed to hash password: {}", e)),
+        };
+        let recovery_code = generate_recovery_code();
+        let recovery_code_hash = match hash(normalize_recovery_code(&recovery_code), cost) {
+            Ok(hash) => hash,
+            Err(e) => return ApiResponse::error(format!("Failed to hash recovery code: {}", e)),
+        };
 
-    // Update settings
-    match store.get_settings() {
-        Ok(mut settings) => {
-            settings.password_hash = Some(password_hash);
-            settings.password_skipped = false;
+        // Update settings
+        match store.get_settings() {
+            Ok(mut settings) => {
+                settings.password_hash = Some(password_hash);
+                settings.password_cost = Some(cost);
+                settings.recovery_code_hash = Some(recovery_code_hash);
+                settings.password_skipped = false;
 
-            match store.update_settings(&settings) {
-                Ok(_) => ApiResponse::success(()),
-                Err(e) => ApiResponse::error(format!("Failed to update settings: {}", e)),
+                match store.update_settings(&settings) {
+                    Ok(_) => ApiResponse::success(PasswordSetResult { recovery_code }),
+                    Err(e) => ApiResponse::error(format!("Failed to update settings: {}", e)),
+                }
             }
+            Err(e) => ApiResponse::error_key(messages::GET_SETTINGS_FAILED, &[&e]),
         }
-        Err(e) => ApiResponse::error(format!("Failed to get settings: {}", e)),
-    }
+    })
+    .await
 }
 
 /// Change password (requires current password)
@@ -251,120 +940,478 @@ pub async fn change_password(
     new_password: String,
     confirm: String,
 ) -> ApiResponse<()> {
-    if new_password != confirm {
-        return ApiResponse::error("New passwords do not match".to_string());
-    }
-
-    if new_password.len() < 6 {
-        return ApiResponse::error("Password must be at least 6 characters".to_string());
-    }
+    timed(async move {
+        if new_password != confirm {
+            return ApiResponse::error("New passwords do not match".to_string());
+        }
 
-    let store = match MetadataStore::open() {
-        Ok(s) => s,
-        Err(e) => return ApiResponse::error(format!("Failed to open metadata store: {}", e)),
-    };
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
 
-    // Verify current password
-    match store.get_settings() {
-        Ok(settings) => {
-            let password_hash = match settings.password_hash.as_ref() {
-                Some(hash) => hash.clone(),
-                None => return ApiResponse::error("Password not set. Use set_password instead.".to_string()),
-            };
-
-            match verify(&current_password, &password_hash) {
-                Ok(valid) => {
-                    if !valid {
-                        return ApiResponse::error("Current password is incorrect".to_string());
-                    }
-                }
-                Err(e) => return ApiResponse::error(format!("Password verification failed: {}", e)),
-            }
+        // Verify current password
+        let settings = match check_secret(&store, &current_password, Utc::now(), |settings| {
+            settings
+                .password_hash
+                .clone()
+                .ok_or_else(|| "Password not set. Use set_password instead.".to_string())
+        }) {
+            Ok(settings) => settings,
+            Err(refused) => return refused.into_response("Current password is incorrect"),
+        };
 
-            // Hash new password
-            let new_password_hash = match hash(&new_password, DEFAULT_COST) {
-                Ok(hash) => hash,
-                Err(e) => return ApiResponse::error(format!("Failed to hash password: {}", e)),
-            };
+        if let Some(response) = password_policy_error(&new_password, &settings.preferences.password_policy) {
+            return response;
+        }
 
-            // Update settings
-            let mut updated_settings = settings;
-            updated_settings.password_hash = Some(new_password_hash);
-            updated_settings.password_skipped = false;
+        // Hash new password
+        let cost = configured_password_cost(&settings.preferences);
+        let new_password_hash = match hash(&new_password, cost) {
+            Ok(hash) => hash,
+            Err(e) => return ApiResponse::error(format!("Failed to hash password: {}", e)),
+        };
 
-            match store.update_settings(&updated_settings) {
-                Ok(_) => ApiResponse::success(()),
-                Err(e) => ApiResponse::error(format!("Failed to update settings: {}", e)),
-            }
+        let updated = store.modify_settings(|settings| {
+            settings.password_hash = Some(new_password_hash);
+            settings.password_cost = Some(cost);
+            settings.password_skipped = false;
+        });
+        match updated {
+            Ok(_) => ApiResponse::success(()),
+            Err(e) => ApiResponse::error(format!("Failed to update settings: {}", e)),
         }
-        Err(e) => ApiResponse::error(format!("Failed to get settings: {}", e)),
-    }
+    })
+    .await
 }
 
 /// Remove password protection (requires current password)
 #[tauri::command]
 pub async fn remove_password(current_password: String) -> ApiResponse<()> {
-    let store = match MetadataStore::open() {
-        Ok(s) => s,
-        Err(e) => return ApiResponse::error(format!("Failed to open metadata store: {}", e)),
-    };
+    timed(async move {
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
 
-    // Verify current password
-    match store.get_settings() {
-        Ok(settings) => {
-            let password_hash = match settings.password_hash.as_ref() {
-                Some(hash) => hash.clone(),
-                None => return ApiResponse::error("Password not set".to_string()),
-            };
+        // Verify current password
+        if let Err(refused) = check_secret(&store, &current_password, Utc::now(), ui_password_hash) {
+            return refused.into_response("Current password is incorrect");
+        }
 
-            match verify(&current_password, &password_hash) {
-                Ok(valid) => {
-                    if !valid {
-                        return ApiResponse::error("Current password is incorrect".to_string());
-                    }
-                }
-                Err(e) => return ApiResponse::error(format!("Password verification failed: {}", e)),
-            }
+        let updated = store.modify_settings(|settings| {
+            settings.password_hash = None;
+            settings.password_cost = None;
+            settings.recovery_code_hash = None;
+            settings.password_skipped = true;
+        });
+        match updated {
+            Ok(_) => ApiResponse::success(()),
+            Err(e) => ApiResponse::error(format!("Failed to update settings: {}", e)),
+        }
+    })
+    .await
+}
 
-            // Remove password
-            let mut updated_settings = settings;
-            updated_settings.password_hash = None;
-            updated_settings.password_skipped = true;
+/// Clear a forgotten UI password using the recovery code set_password returned
+/// Wrong codes count toward the same delays and lockout as wrong passwords. On success the
+/// password and recovery code are removed, so the app asks for a new password at next launch.
+#[tauri::command]
+#[allow(non_snake_case)]
+pub async fn reset_ui_password_with_recovery(recoveryCode: String) -> ApiResponse<()> {
+    timed(async move {
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
 
-            match store.update_settings(&updated_settings) {
-                Ok(_) => ApiResponse::success(()),
-                Err(e) => ApiResponse::error(format!("Failed to update settings: {}", e)),
+        let now = Utc::now();
+        let checked = check_secret(&store, &normalize_recovery_code(&recoveryCode), now, |settings| {
+            match (&settings.password_hash, &settings.recovery_code_hash) {
+                (None, _) => Err("Password not set".to_string()),
+                (Some(_), None) => Err("No recovery code was saved with this password \
+                     (it was set before recovery codes existed)"
+                    .to_string()),
+                (Some(_), Some(hash)) => Ok(hash.clone()),
             }
+        });
+        if let Err(refused) = checked {
+            return refused.into_response("Invalid recovery code");
         }
-        Err(e) => ApiResponse::error(format!("Failed to get settings: {}", e)),
-    }
+
+        let reset = store.modify_settings(|settings| {
+            settings.password_hash = None;
+            settings.password_cost = None;
+            settings.recovery_code_hash = None;
+            settings.password_skipped = false;
+        });
+        if let Err(e) = reset {
+            return ApiResponse::error(format!("Failed to update settings: {}", e));
+        }
+
+        let history_entry = HistoryEntry {
+            id: Uuid::new_v4().to_string(),
+            operation_type: "reset_ui_password".to_string(),
+            timestamp: now,
+            user_name: Some(whoami::username_os().to_string_lossy().into_owned()),
+            details: None,
+            results: None,
+            group_id: None,
+        };
+        let _ = store.add_history(&history_entry);
+
+        ApiResponse::success(())
+    })
+    .await
+}
+
+/// End the session started by check_password
+#[tauri::command]
+pub async fn logout(app: tauri::AppHandle, session_token: String) -> ApiResponse<()> {
+    timed(async move {
+        app.state::<Sessions>().revoke(&session_token);
+        ApiResponse::success(())
+    })
+    .await
 }
 
 /// Skip password protection (first launch only)
 #[tauri::command]
 pub async fn skip_password() -> ApiResponse<()> {
-    let store = match MetadataStore::open() {
-        Ok(s) => s,
-        Err(e) => return ApiResponse::error(format!("Failed to open metadata store: {}", e)),
-    };
+    timed(async move {
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
 
-    // Check if password already exists
-    match store.get_settings() {
-        Ok(settings) => {
-            if settings.password_hash.is_some() {
-                return ApiResponse::error("Password already set. Cannot skip.".to_string());
-            }
+        // Check if password already exists
+        match store.get_settings() {
+            Ok(settings) => {
+                if settings.password_hash.is_some() {
+                    return ApiResponse::error("Password already set. Cannot skip.".to_string());
+                }
 
-            // Skip password
-            let mut updated_settings = settings;
-            updated_settings.password_hash = None;
-            updated_settings.password_skipped = true;
+                // Skip password
+                let mut updated_settings = settings;
+                updated_settings.password_hash = None;
+                updated_settings.password_skipped = true;
 
-            match store.update_settings(&updated_settings) {
-                Ok(_) => ApiResponse::success(()),
-                Err(e) => ApiResponse::error(format!("Failed to update settings: {}", e)),
+                match store.update_settings(&updated_settings) {
+                    Ok(_) => ApiResponse::success(()),
+                    Err(e) => ApiResponse::error(format!("Failed to update settings: {}", e)),
+                }
             }
+            Err(e) => ApiResponse::error_key(messages::GET_SETTINGS_FAILED, &[&e]),
+        }
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn verification(interval_minutes: u32) -> AutoVerification {
+        AutoVerification {
+            enabled: true,
+            interval_minutes,
         }
-        Err(e) => ApiResponse::error(format!("Failed to get settings: {}", e)),
+    }
+
+    #[test]
+    fn test_validate_settings_accepts_interval_bounds() {
+        let preferences = SettingsPreferences::default();
+        assert!(validate_settings(&preferences, &verification(1)).is_ok());
+        assert!(validate_settings(&preferences, &verification(1440)).is_ok());
+    }
+
+    #[test]
+    fn test_validate_settings_rejects_out_of_range_interval() {
+        let preferences = SettingsPreferences::default();
+        assert!(validate_settings(&preferences, &verification(0)).is_err());
+        assert!(validate_settings(&preferences, &verification(1441)).is_err());
+    }
+
+    #[test]
+    fn test_validate_settings_rejects_zero_max_history() {
+        let preferences = SettingsPreferences {
+            max_history_entries: 0,
+            ..Default::default()
+        };
+        assert!(validate_settings(&preferences, &verification(15)).is_err());
+
+        let preferences = SettingsPreferences {
+            max_history_entries: 1,
+            ..Default::default()
+        };
+        assert!(validate_settings(&preferences, &verification(15)).is_ok());
+    }
+
+    #[test]
+    fn test_validate_settings_checks_webhook_url() {
+        let preferences = SettingsPreferences {
+            webhook_url: Some("hooks.example.com".to_string()),
+            ..Default::default()
+        };
+        assert!(validate_settings(&preferences, &verification(15)).is_err());
+
+        for webhook_url in [None, Some(String::new()), Some("https://hooks.example.com/x".to_string())] {
+            let preferences = SettingsPreferences {
+                webhook_url,
+                ..Default::default()
+            };
+            assert!(validate_settings(&preferences, &verification(15)).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_validate_settings_checks_display_timezone() {
+        let preferences = SettingsPreferences {
+            display_timezone: Some("Atlantis/Central".to_string()),
+            ..Default::default()
+        };
+        assert!(validate_settings(&preferences, &verification(15)).is_err());
+
+        let preferences = SettingsPreferences {
+            display_timezone: Some("Australia/Sydney".to_string()),
+            ..Default::default()
+        };
+        assert!(validate_settings(&preferences, &verification(15)).is_ok());
+    }
+
+    #[test]
+    fn test_history_csv_quotes_fields_and_uses_timezone() {
+        let entry = HistoryEntry {
+            id: "h1".to_string(),
+            operation_type: "create_snapshots".to_string(),
+            timestamp: "2026-07-01T12:00:00Z".parse().unwrap(),
+            user_name: Some("Smith, Jo".to_string()),
+            details: None,
+            results: None,
+            group_id: Some("g1".to_string()),
+        };
+
+        assert_eq!(
+            history_csv(&[entry], Some("Europe/Paris")),
+            "timestamp,type,userName,groupId,details\n2026-07-01T14:00:00+02:00,create_snapshots,\"Smith, Jo\",g1,\n"
+        );
+    }
+
+    #[test]
+    fn test_history_csv_redacts_webhook_url_of_older_settings_entries() {
+        let entry = HistoryEntry {
+            id: "h1".to_string(),
+            operation_type: "update_settings".to_string(),
+            timestamp: "2026-07-01T12:00:00Z".parse().unwrap(),
+            user_name: None,
+            details: Some(serde_json::json!({ "preferences": { "webhookUrl": "https://hooks.example.com/secret" } })),
+            results: None,
+            group_id: None,
+        };
+
+        let csv = history_csv(&[entry], None);
+        assert!(!csv.contains("secret"));
+        assert!(csv.contains("[redacted]"));
+    }
+
+    #[test]
+    fn test_validate_settings_checks_metrics_port() {
+        let preferences = SettingsPreferences {
+            metrics_enabled: true,
+            metrics_port: 0,
+            ..Default::default()
+        };
+        assert!(validate_settings(&preferences, &verification(15)).is_err());
+
+        let preferences = SettingsPreferences {
+            metrics_enabled: true,
+            ..Default::default()
+        };
+        assert!(validate_settings(&preferences, &verification(15)).is_ok());
+    }
+
+    #[test]
+    fn test_validate_settings_checks_max_connections() {
+        for (max_connections, valid) in [(0, false), (1, true), (64, true), (65, false)] {
+            let preferences = SettingsPreferences {
+                max_connections,
+                ..Default::default()
+            };
+            assert_eq!(validate_settings(&preferences, &verification(15)).is_ok(), valid);
+        }
+    }
+
+    #[test]
+    fn test_default_settings_are_valid() {
+        let settings = Settings::default();
+        assert!(validate_settings(&settings.preferences, &settings.auto_verification).is_ok());
+    }
+
+    #[test]
+    fn test_settings_patch_leaves_unsent_fields_alone() {
+        let mut settings = Settings::default();
+        settings.preferences.require_confirmation = true;
+        settings.preferences.webhook_url = Some("https://chat.example.com/hooks/abc".to_string());
+        settings.preferences.protected_databases = Some(vec!["Prod".to_string()]);
+        settings.preferences.password_policy.require_digit = true;
+        settings.auto_verification.enabled = true;
+
+        // What the settings panel sends: a few preferences and no other fields
+        apply_settings_patch(
+            &mut settings,
+            serde_json::json!({ "maxHistoryEntries": 50, "passwordPolicy": { "minLength": 12 } }),
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(settings.preferences.max_history_entries, 50);
+        assert_eq!(settings.preferences.password_policy.min_length, 12);
+        assert!(settings.preferences.password_policy.require_digit);
+        assert!(settings.preferences.require_confirmation);
+        assert_eq!(settings.preferences.webhook_url.as_deref(), Some("https://chat.example.com/hooks/abc"));
+        assert_eq!(settings.preferences.protected_databases, Some(vec!["Prod".to_string()]));
+        assert!(settings.auto_verification.enabled);
+
+        // An invalid patch is refused and changes nothing
+        assert!(apply_settings_patch(&mut settings, serde_json::json!({ "maxHistoryEntries": 0 }), None).is_err());
+        assert_eq!(settings.preferences.max_history_entries, 50);
+    }
+
+    #[test]
+    fn test_audited_preferences_redact_webhook_url() {
+        let preferences = SettingsPreferences {
+            webhook_url: Some("https://hooks.example.com/T0/secret-token".to_string()),
+            ..Default::default()
+        };
+
+        let audited = audited_preferences(&preferences, None);
+        assert!(!audited.to_string().contains("secret-token"));
+        assert_eq!(audited["webhookUrlChanged"], true);
+        assert_eq!(audited["maxHistoryEntries"], preferences.max_history_entries);
+
+        let unchanged = audited_preferences(&preferences, preferences.webhook_url.as_deref());
+        assert_eq!(unchanged["webhookUrlChanged"], false);
+    }
+
+    #[test]
+    fn test_is_confirmation_missing() {
+        let mut settings = Settings::default();
+        assert!(!is_confirmation_missing(&settings, None));
+
+        settings.preferences.require_confirmation = true;
+        assert!(is_confirmation_missing(&settings, None));
+        assert!(is_confirmation_missing(&settings, Some(false)));
+        assert!(!is_confirmation_missing(&settings, Some(true)));
+    }
+
+    #[test]
+    fn test_password_retry_wait_grows_then_locks_out() {
+        let now = Utc::now();
+        assert_eq!(password_retry_wait_seconds(0, None, now), 0);
+        assert_eq!(password_retry_wait_seconds(1, Some(now), now), 1);
+        assert_eq!(password_retry_wait_seconds(3, Some(now), now), 4);
+        assert_eq!(
+            password_retry_wait_seconds(MAX_PASSWORD_ATTEMPTS, Some(now), now),
+            PASSWORD_LOCKOUT_SECONDS
+        );
+    }
+
+    #[test]
+    fn test_password_retry_wait_counts_down() {
+        let now = Utc::now();
+        let last = now - chrono::Duration::seconds(100);
+        assert_eq!(
+            password_retry_wait_seconds(MAX_PASSWORD_ATTEMPTS, Some(last), now),
+            PASSWORD_LOCKOUT_SECONDS - 100
+        );
+
+        let expired = now - chrono::Duration::seconds(PASSWORD_LOCKOUT_SECONDS + 1);
+        assert_eq!(password_retry_wait_seconds(MAX_PASSWORD_ATTEMPTS, Some(expired), now), 0);
+    }
+
+    #[test]
+    fn test_password_policy_reports_each_unmet_rule() {
+        let policy = PasswordPolicy::default();
+        assert!(password_policy_error::<()>("secret", &policy).is_none());
+        let response = password_policy_error::<()>("short", &policy).unwrap();
+        assert_eq!(response.error_code.as_deref(), Some(PASSWORD_POLICY_UNMET));
+        assert_eq!(response.messages.error, vec!["Password must be at least 6 characters"]);
+
+        let policy = PasswordPolicy {
+            min_length: 8,
+            require_mixed_case: true,
+            require_digit: true,
+            require_symbol: true,
+        };
+        let unmet: Vec<String> = password_requirements("parrot", &policy)
+            .into_iter()
+            .filter(|r| !r.met)
+            .map(|r| r.rule)
+            .collect();
+        assert_eq!(unmet, vec!["minLength", "mixedCase", "digit", "symbol"]);
+        assert!(password_requirements("Parrot-42", &policy).iter().all(|r| r.met));
+
+        let preferences = SettingsPreferences {
+            password_policy: PasswordPolicy { min_length: 0, ..policy },
+            ..Default::default()
+        };
+        assert!(validate_settings(&preferences, &verification(15)).is_err());
+    }
+
+    #[test]
+    fn test_recovery_code_matches_however_it_is_typed() {
+        let code = generate_recovery_code();
+        assert_eq!(code.len(), 39);
+        assert_eq!(code.matches('-').count(), 7);
+
+        let stored = hash(normalize_recovery_code(&code), 4).unwrap();
+        let typed = format!(" {} ", code.to_lowercase().replace('-', " "));
+        assert!(verify(normalize_recovery_code(&typed), &stored).unwrap());
+        assert!(!verify(normalize_recovery_code(&generate_recovery_code()), &stored).unwrap());
+    }
+
+    #[test]
+    fn test_password_cost_is_clamped_and_triggers_rehash() {
+        let mut settings = Settings::default();
+        settings.preferences.password_cost = 4;
+        assert_eq!(configured_password_cost(&settings.preferences), MIN_PASSWORD_COST);
+        settings.preferences.password_cost = 31;
+        assert_eq!(configured_password_cost(&settings.preferences), MAX_PASSWORD_COST);
+
+        // An older hash with no recorded cost is read from the hash itself
+        settings.password_hash = Some(hash("secret", 4).unwrap());
+        assert_eq!(stored_password_cost(&settings), Some(4));
+        assert!(needs_rehash(&settings));
+
+        settings.preferences.password_cost = 10;
+        settings.password_cost = Some(10);
+        assert!(!needs_rehash(&settings));
+    }
+
+    #[test]
+    fn test_secret_check_counts_failures_and_honours_lockout() {
+        let now = Utc::now();
+        let mut settings = Settings {
+            password_hash: Some(hash("secret", 4).unwrap()),
+            ..Default::default()
+        };
+
+        let wrong = apply_secret_check(&mut settings, "guess", now, ui_password_hash);
+        assert!(matches!(wrong, Ok(Err(1))));
+        assert_eq!(settings.last_failed_password_at, Some(now));
+
+        // Even the right password is refused, unverified, until the delay has passed
+        let early = apply_secret_check(&mut settings, "secret", now, ui_password_hash);
+        assert!(matches!(early, Err(SecretRefused::Locked(_))));
+        assert_eq!(settings.failed_password_attempts, 1);
+
+        let later = now + chrono::Duration::seconds(PASSWORD_LOCKOUT_SECONDS);
+        let right = apply_secret_check(&mut settings, "secret", later, ui_password_hash);
+        assert!(matches!(right, Ok(Ok(_))));
+        assert_eq!(settings.failed_password_attempts, 0);
+        assert_eq!(settings.last_failed_password_at, None);
+
+        let unset = apply_secret_check(&mut Settings::default(), "secret", now, ui_password_hash);
+        assert!(matches!(unset, Err(SecretRefused::Failed(_))));
     }
 }
@@ -1,80 +1,387 @@
 // ABOUTME: Snapshot management Tauri commands
 // ABOUTME: Create, list, delete, and rollback database snapshots
 
-use chrono::Utc;
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Utc};
+use tauri::Manager;
 use uuid::Uuid;
 
-use crate::config::ConnectionProfile;
+use super::operations::{run_drop_step, run_restore_step, OperationLog};
+use super::{find_database_group_conflicts, DatabaseGroupConflict};
+use crate::config::{ConnectionProfile, DatabaseType};
+use crate::db::sqlserver::{quote_ident, SqlServerError};
 use crate::db::{MetadataStore, SqlServerConnection};
-use crate::models::{DatabaseSnapshot, HistoryEntry, OperationResult, Snapshot};
-use crate::ApiResponse;
-
-/// Helper function to get profile from metadata database using group's profile_id
-/// and convert it to ConnectionProfile for SQL Server connection
-fn get_profile_for_group(
-    store: &MetadataStore,
-    group: &crate::models::Group,
-) -> Result<ConnectionProfile, String> {
-    let profile_id = group
-        .profile_id
-        .as_ref()
-        .ok_or_else(|| "Group has no profile_id".to_string())?;
-
-    let profile = store
-        .get_profile(profile_id)
-        .map_err(|e| format!("Failed to get profile: {}", e))?
-        .ok_or_else(|| format!("Profile not found: {}", profile_id))?;
-
-    // Convert Profile to ConnectionProfile
+use crate::external_overrides::{Acknowledgement, ExternalOverrides};
+use crate::group_locks::{group_busy, GroupLocks};
+use crate::messages;
+use crate::metrics;
+use crate::models::{
+    AvailabilityInfo, DatabaseSnapshot, Group, HistoryEntry, NameCollision, OperationAction, OperationResult,
+    PressureLevel, Profile, ProfileOverrides, RenumberResult, ServerSnapshot, Snapshot, SnapshotFile, SnapshotSummary,
+    StepStatus,
+};
+use crate::sessions::{require_auth, Sessions};
+use crate::webhooks::{self, DatabaseOutcome, WebhookPayload};
+use crate::{timed, ApiResponse};
+
+/// Convert a saved profile to the ConnectionProfile SqlServerConnection expects
+/// This is where a profile's platform_type is checked; only SQL Server is supported so far
+pub(crate) fn to_connection_profile(profile: &Profile) -> Result<ConnectionProfile, String> {
+    let db_type = DatabaseType::from_platform_type(&profile.platform_type)
+        .map_err(|platform| messages::text(messages::PLATFORM_NOT_SUPPORTED, &[&platform]))?;
     Ok(ConnectionProfile {
         name: profile.name.clone(),
-        db_type: crate::config::DatabaseType::SqlServer,
+        db_type,
         host: profile.host.clone(),
         port: profile.port,
         username: profile.username.clone(),
         password: profile.password.clone(),
         trust_certificate: profile.trust_certificate,
         snapshot_path: profile.snapshot_path.clone(),
+        server_platform: profile.server_platform.clone(),
+        encryption_mode: profile.encryption_mode,
+        application_name: profile.overrides.application_name().to_string(),
     })
 }
 
+/// The active profile from the metadata database, ready to connect with
+/// This is the only source of the active profile for commands; config.json is read by migration only
+pub(crate) fn get_active_connection_profile(store: &MetadataStore) -> Result<ConnectionProfile, String> {
+    store
+        .get_active_profile()
+        .map_err(|e| format!("Failed to get active profile: {}", e))?
+        .ok_or_else(|| messages::text(messages::NO_ACTIVE_PROFILE, &[]))
+        .and_then(|profile| to_connection_profile(&profile))
+}
+
+/// Helper function to get profile from metadata database using group's profile_id
+/// and convert it to ConnectionProfile for SQL Server connection
+pub(crate) fn get_profile_for_group(
+    store: &MetadataStore,
+    group: &crate::models::Group,
+) -> Result<ConnectionProfile, String> {
+    // Groups saved before profiles existed have no profile_id and run against the active profile
+    match group.profile_id.as_deref() {
+        Some(profile_id) => store
+            .get_profile(profile_id)
+            .map_err(|e| format!("Failed to get profile: {}", e))?
+            .ok_or_else(|| format!("Profile not found: {}", profile_id))
+            .and_then(|profile| to_connection_profile(&profile)),
+        None => get_active_connection_profile(store),
+    }
+}
+
 /// Get snapshots for a group
 #[tauri::command]
 #[allow(non_snake_case)]
-pub async fn get_snapshots(groupId: String) -> ApiResponse<Vec<Snapshot>> {
-    let store = match MetadataStore::open() {
-        Ok(s) => s,
-        Err(e) => return ApiResponse::error(format!("Failed to open metadata store: {}", e)),
-    };
+pub async fn get_snapshots(groupId: String) -> ApiResponse<Vec<SnapshotSummary>> {
+    timed(async move {
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
 
-    let snapshots = match store.get_snapshots(&groupId) {
-        Ok(s) => s,
-        Err(e) => return ApiResponse::error(format!("Failed to get snapshots: {}", e)),
-    };
+        let snapshots = match store.get_snapshots(&groupId) {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::GET_SNAPSHOTS_FAILED, &[&e]),
+        };
+
+        let stale_after_hours = store
+            .get_settings()
+            .map(|s| s.preferences.stale_after_hours)
+            .unwrap_or_default();
+        let now = Utc::now();
+
+        ApiResponse::success(
+            snapshots
+                .into_iter()
+                .map(|snapshot| summarize_snapshot(snapshot, now, stale_after_hours))
+                .collect(),
+        )
+    })
+    .await
+}
+
+/// App flavor recorded in snapshot origins; the Express backend would be "npm" or "docker"
+const APP_FLAVOR: &str = "exe";
+
+/// This install's snapshot origin, `exe:<install id>`; None (and a warning) if the id can't be read
+pub(crate) fn snapshot_origin(store: &MetadataStore) -> Option<String> {
+    match store.install_id() {
+        Ok(install_id) => Some(format!("{}:{}", APP_FLAVOR, install_id)),
+        Err(e) => {
+            log::warn!("Failed to read install id; snapshots won't record their origin: {}", e);
+            None
+        }
+    }
+}
+
+/// Readable description of a snapshot origin for messages and the UI
+fn describe_origin(origin: &str, own_origin: Option<&str>) -> String {
+    if own_origin == Some(origin) {
+        return "this SQL Parrot install".to_string();
+    }
+    match origin.split_once(':') {
+        Some((flavor, install_id)) => format!(
+            "SQL Parrot ({}) install {}",
+            flavor,
+            install_id.chars().take(8).collect::<String>()
+        ),
+        None => origin.to_string(),
+    }
+}
+
+/// Described origins of the given server snapshots, for those tagged when they were created
+async fn server_snapshot_origins(
+    conn: &mut SqlServerConnection,
+    snapshot_names: &[String],
+    own_origin: Option<&str>,
+) -> HashMap<String, String> {
+    let mut origins = HashMap::new();
+    for name in snapshot_names {
+        match conn.get_snapshot_origin(name).await {
+            Ok(Some(origin)) => {
+                origins.insert(name.clone(), describe_origin(&origin, own_origin));
+            }
+            Ok(None) => {}
+            Err(e) => log::warn!("Could not read origin of snapshot {}: {}", name, e),
+        }
+    }
+    origins
+}
 
-    ApiResponse::success(snapshots)
+/// Annotate a snapshot with its age and whether it's past the stale threshold
+fn summarize_snapshot(snapshot: Snapshot, now: DateTime<Utc>, stale_after_hours: Option<u32>) -> SnapshotSummary {
+    let age = now - snapshot.created_at;
+    SnapshotSummary {
+        age_seconds: age.num_seconds(),
+        is_stale: stale_after_hours.is_some_and(|hours| age > chrono::Duration::hours(hours as i64)),
+        snapshot,
+    }
 }
 
 /// Create a new snapshot for all databases in a group
+/// While a UI password is set, `session_token` must come from check_password.
+#[tauri::command]
+#[allow(non_snake_case)]
+pub async fn create_snapshot(
+    app: tauri::AppHandle,
+    groupId: String,
+    snapshotName: Option<String>,
+    atomic: Option<bool>,
+    notes: Option<String>,
+    session_token: Option<String>,
+) -> ApiResponse<Snapshot> {
+    if let Some(response) = require_auth(&app.state::<Sessions>(), session_token.as_deref()) {
+        return response;
+    }
+
+    snapshot_group(
+        &app.state::<GroupLocks>(),
+        groupId,
+        snapshotName,
+        atomic.unwrap_or(false),
+        notes,
+        None,
+    )
+    .await
+}
+
+/// Take a brand-new snapshot of the databases an existing snapshot captured, at the current point
+/// in time; the original is left intact and the new one gets its own id and sequence
+/// While a UI password is set, `session_token` must come from check_password.
 #[tauri::command]
 #[allow(non_snake_case)]
-pub async fn create_snapshot(groupId: String, snapshotName: Option<String>) -> ApiResponse<Snapshot> {
-    let group_id = groupId;
-    let display_name = snapshotName;
+pub async fn resnapshot(
+    app: tauri::AppHandle,
+    snapshotId: String,
+    atomic: Option<bool>,
+    session_token: Option<String>,
+) -> ApiResponse<Snapshot> {
+    timed(async move {
+        if let Some(response) = require_auth(&app.state::<Sessions>(), session_token.as_deref()) {
+            return response;
+        }
+
+        let group = match group_of_snapshot(&snapshotId) {
+            Some(g) => g,
+            None => return ApiResponse::error_key(messages::SNAPSHOT_NOT_FOUND, &[&snapshotId]),
+        };
+
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
+        let source = match store.get_snapshots(&group.id) {
+            Ok(snapshots) => snapshots.into_iter().find(|s| s.id == snapshotId),
+            Err(e) => return ApiResponse::error_key(messages::GET_SNAPSHOTS_FAILED, &[&e]),
+        };
+        let source = match source {
+            Some(s) => s,
+            None => return ApiResponse::error_key(messages::SNAPSHOT_NOT_FOUND, &[&snapshotId]),
+        };
+
+        let databases = resnapshot_databases(&source);
+        if databases.is_empty() {
+            return ApiResponse::error(format!(
+                "Snapshot '{}' has no successfully snapshotted databases to re-snapshot",
+                source.display_name
+            ));
+        }
+
+        snapshot_group(
+            &app.state::<GroupLocks>(),
+            group.id,
+            Some(source.display_name),
+            atomic.unwrap_or(false),
+            None,
+            Some(databases),
+        )
+        .await
+    })
+    .await
+}
+
+/// Databases a re-snapshot covers: the ones the source snapshot captured successfully
+fn resnapshot_databases(snapshot: &Snapshot) -> Vec<String> {
+    snapshot
+        .database_snapshots
+        .iter()
+        .filter(|ds| ds.success)
+        .map(|ds| ds.database.clone())
+        .collect()
+}
+
+/// Create a group snapshot and record metrics and the webhook notification; shared with the CLI
+/// `databases` narrows the snapshot to those databases instead of the group's current list
+pub(crate) async fn snapshot_group(
+    group_locks: &GroupLocks,
+    group_id: String,
+    display_name: Option<String>,
+    atomic: bool,
+    notes: Option<String>,
+    databases: Option<Vec<String>>,
+) -> ApiResponse<Snapshot> {
+    let response = timed(create_group_snapshot(
+        group_locks,
+        group_id.clone(),
+        display_name,
+        atomic,
+        normalize_notes(notes),
+        databases,
+    ))
+    .await;
+    if response.success {
+        metrics::record_snapshot_created();
+    }
+    let databases = response
+        .data
+        .iter()
+        .flat_map(|snapshot| &snapshot.database_snapshots)
+        .map(|ds| DatabaseOutcome {
+            database: ds.database.clone(),
+            success: ds.success,
+        })
+        .collect();
+    notify_webhook("create_snapshot", stored_group(&group_id).as_ref(), databases, &response);
+    response
+}
+
+/// Trimmed notes, with blank notes stored as none
+fn normalize_notes(notes: Option<String>) -> Option<String> {
+    notes.map(|n| n.trim().to_string()).filter(|n| !n.is_empty())
+}
+
+/// Set or clear the notes on a snapshot
+/// While a UI password is set, `session_token` must come from check_password.
+#[tauri::command]
+pub async fn update_snapshot_notes(
+    app: tauri::AppHandle,
+    id: String,
+    notes: Option<String>,
+    session_token: Option<String>,
+) -> ApiResponse<()> {
+    timed(async move {
+        if let Some(response) = require_auth(&app.state::<Sessions>(), session_token.as_deref()) {
+            return response;
+        }
+
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
+
+        match store.update_snapshot_notes(&id, normalize_notes(notes).as_deref()) {
+            Ok(true) => ApiResponse::success(()),
+            Ok(false) => ApiResponse::error_key(messages::SNAPSHOT_NOT_FOUND, &[&id]),
+            Err(e) => ApiResponse::error(format!("Failed to update snapshot notes: {}", e)),
+        }
+    })
+    .await
+}
+
+/// Group by id straight from the store, for notifications sent after a command finishes
+fn stored_group(group_id: &str) -> Option<Group> {
+    MetadataStore::open().ok()?.get_group(group_id).ok()?
+}
+
+/// Group that owns a snapshot, looked up before an operation that may remove the snapshot's metadata
+fn group_of_snapshot(snapshot_id: &str) -> Option<Group> {
+    let store = MetadataStore::open().ok()?;
+    store.get_groups().ok()?.into_iter().find(|group| {
+        store
+            .get_snapshots(&group.id)
+            .map(|snapshots| snapshots.iter().any(|s| s.id == snapshot_id))
+            .unwrap_or(false)
+    })
+}
+
+/// Report a finished command, and how each database it touched fared, to the configured webhook, if any
+fn notify_webhook<T>(
+    operation: &str,
+    group: Option<&Group>,
+    databases: Vec<DatabaseOutcome>,
+    response: &ApiResponse<T>,
+) {
+    webhooks::notify(WebhookPayload::new(
+        operation,
+        group,
+        databases,
+        response.success,
+        response.messages.error.first().cloned(),
+    ));
+}
+
+/// With `atomic`, a failure on any database drops the snapshots already created and records nothing
+async fn create_group_snapshot(
+    group_locks: &GroupLocks,
+    group_id: String,
+    display_name: Option<String>,
+    atomic: bool,
+    notes: Option<String>,
+    databases: Option<Vec<String>>,
+) -> ApiResponse<Snapshot> {
     let store = match MetadataStore::open() {
         Ok(s) => s,
-        Err(e) => return ApiResponse::error(format!("Failed to open metadata store: {}", e)),
+        Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
     };
 
     // Get the group
     let groups = match store.get_groups() {
         Ok(g) => g,
-        Err(e) => return ApiResponse::error(format!("Failed to get groups: {}", e)),
+        Err(e) => return ApiResponse::error_key(messages::GET_GROUPS_FAILED, &[&e]),
     };
 
     let group = match groups.iter().find(|g| g.id == group_id) {
         Some(g) => g,
-        None => return ApiResponse::error(format!("Group not found: {}", group_id)),
+        None => return ApiResponse::error_key(messages::GROUP_NOT_FOUND, &[&group_id]),
+    };
+
+    // Only one operation at a time per group; the guard releases the lock on every return path
+    let _group_guard = match group_locks.try_lock(&group.id) {
+        Some(guard) => guard,
+        None => return group_busy(&group.name),
     };
 
     // Get profile from metadata database using group's profile_id
@@ -83,15 +390,25 @@ pub async fn create_snapshot(groupId: String, snapshotName: Option<String>) -> A
         Err(e) => return ApiResponse::error(e),
     };
 
+    // A blank path would produce file names SQL Server rejects with an opaque error
+    if profile.snapshot_path.trim().is_empty() {
+        return ApiResponse::error("Snapshot path is not configured for this profile".to_string());
+    }
+
     // Get next sequence number
-    let sequence = match store.get_next_sequence(&group_id) {
+    let mut sequence = match store.get_next_sequence(&group_id) {
         Ok(s) => s,
         Err(e) => return ApiResponse::error(format!("Failed to get sequence: {}", e)),
     };
 
     let snapshot_id = Uuid::new_v4().to_string();
     let now = Utc::now();
-    let name = display_name.unwrap_or_else(|| format!("Snapshot {}", sequence));
+    let settings = store.get_settings().unwrap_or_default();
+    let on_name_collision = settings.preferences.on_name_collision;
+    let overrides = profile_overrides_for_group(&store, group);
+    let name_template = overrides.snapshot_name_template(&settings.preferences);
+    let protected = protected_databases(&store);
+    let origin = snapshot_origin(&store);
 
     // Connect to SQL Server
     let mut conn = match SqlServerConnection::connect(&profile).await {
@@ -99,33 +416,121 @@ pub async fn create_snapshot(groupId: String, snapshotName: Option<String>) -> A
         Err(e) => return ApiResponse::error(format!("Failed to connect to SQL Server: {}", e)),
     };
 
+    // Snapshot file paths depend on the server's OS; cache it on the profile the first time
+    if profile.server_platform.is_none() {
+        if let Some(profile_id) = group.profile_id.as_deref() {
+            super::remember_server_platform(&store, profile_id, &mut conn).await;
+        }
+    }
+
+    let databases = databases.unwrap_or_else(|| group.databases.clone());
+
+    // Snapshots made outside this app (e.g. by the Express backend) can already use this sequence's names;
+    // with suffixing on, skip to a sequence that's free for every database rather than suffixing each one
+    if on_name_collision == NameCollision::Suffix {
+        match conn.get_snapshots_with_source().await {
+            Ok(server_snapshots) => {
+                let taken: HashSet<String> = server_snapshots.iter().map(|s| s.name.to_lowercase()).collect();
+                let names_for = |candidate: u32| -> Vec<String> {
+                    databases
+                        .iter()
+                        .map(|database| snapshot_base_name(name_template, database, &group.name, candidate))
+                        .collect()
+                };
+                match first_free_sequence(sequence, &taken, names_for) {
+                    Ok(free) if free != sequence => {
+                        log::info!(
+                            "Snapshot names for sequence {} of group {} already exist on the server; using sequence {}",
+                            sequence,
+                            group.name,
+                            free
+                        );
+                        sequence = free;
+                    }
+                    Ok(_) => {}
+                    Err(e) => return ApiResponse::error(e),
+                }
+            }
+            Err(e) => log::warn!("Could not list server snapshots to check sequence {}: {}", sequence, e),
+        }
+    }
+    let name = display_name.unwrap_or_else(|| format!("Snapshot {}", sequence));
+
     // Create snapshot for each database
     let mut database_snapshots = Vec::new();
     let mut results = Vec::new();
+    let mut warnings = Vec::new();
 
-    for database in &group.databases {
-        let snapshot_name = format!(
-            "{}_snapshot_{}_{}",
-            database,
-            group.name.replace(' ', "_"),
-            sequence
-        );
+    // Running out of space part way leaves a half-made snapshot, so say so up front (but carry on)
+    let pressure = super::resource_pressure(&mut conn, &profile.snapshot_path).await;
+    if pressure.level > PressureLevel::Ok {
+        log::warn!("Resource pressure is {:?} before snapshotting {}", pressure.level, group.name);
+        warnings.extend(pressure.warnings);
+    }
+
+    for database in &databases {
+        if let Ok(info) = conn.get_availability_info(database).await {
+            if let Some(warning) = availability_warning(database, &info) {
+                warnings.push(warning);
+            }
+        }
+
+        let base_name = snapshot_base_name(name_template, database, &group.name, sequence);
+
+        if let Some(error_msg) = protected_database_error(database, &protected) {
+            database_snapshots.push(DatabaseSnapshot {
+                database: database.clone(),
+                snapshot_name: base_name,
+                success: false,
+                error: Some(error_msg.clone()),
+                server_created_at: None,
+            });
+            results.push(OperationResult {
+                database: database.clone(),
+                success: false,
+                error: Some(error_msg),
+                ..Default::default()
+            });
+            continue;
+        }
+
+        // A leftover snapshot with the same name would make CREATE DATABASE fail with a vague error
+        let snapshot_name = match free_snapshot_name(&mut conn, &base_name, on_name_collision).await {
+            Ok(name) => name,
+            Err(error_msg) => {
+                database_snapshots.push(DatabaseSnapshot {
+                    database: database.clone(),
+                    snapshot_name: base_name,
+                    success: false,
+                    error: Some(error_msg.clone()),
+                    server_created_at: None,
+                });
+                results.push(OperationResult {
+                    database: database.clone(),
+                    success: false,
+                    error: Some(error_msg),
+                    ..Default::default()
+                });
+                continue;
+            }
+        };
 
         match conn
-            .create_snapshot(database, &snapshot_name, &profile.snapshot_path)
+            .create_snapshot(database, &snapshot_name, &profile.snapshot_path, origin.as_deref())
             .await
         {
-            Ok(_) => {
+            Ok(server_created_at) => {
                 database_snapshots.push(DatabaseSnapshot {
                     database: database.clone(),
                     snapshot_name: snapshot_name.clone(),
                     success: true,
                     error: None,
+                    server_created_at,
                 });
                 results.push(OperationResult {
                     database: database.clone(),
                     success: true,
-                    error: None,
+                    ..Default::default()
                 });
             }
             Err(e) => {
@@ -135,13 +540,29 @@ pub async fn create_snapshot(groupId: String, snapshotName: Option<String>) -> A
                     snapshot_name: snapshot_name.clone(),
                     success: false,
                     error: Some(error_msg.clone()),
+                    server_created_at: None,
                 });
-                results.push(OperationResult {
-                    database: database.clone(),
-                    success: false,
-                    error: Some(error_msg),
-                });
+                results.push(failed_result(database, error_msg, &e));
+            }
+        }
+    }
+
+    if atomic {
+        if let Some(message) = atomic_failure_message(&results) {
+            let created: Vec<String> = database_snapshots
+                .iter()
+                .filter(|ds| ds.success)
+                .map(|ds| ds.snapshot_name.clone())
+                .collect();
+            match conn.drop_snapshots(&created).await {
+                Ok(failed) => {
+                    for (name, error) in failed {
+                        log::warn!("Failed to drop partial snapshot {}: {}", name, error);
+                    }
+                }
+                Err(e) => log::warn!("Failed to drop partial snapshots: {}", e),
             }
+            return ApiResponse::error(message);
         }
     }
 
@@ -154,6 +575,8 @@ pub async fn create_snapshot(groupId: String, snapshotName: Option<String>) -> A
         created_by: Some(whoami::username_os().to_string_lossy().into_owned()),
         database_snapshots,
         is_automatic: false,
+        notes,
+        origin,
     };
 
     // Save snapshot metadata
@@ -171,150 +594,843 @@ pub async fn create_snapshot(groupId: String, snapshotName: Option<String>) -> A
             "groupId": group_id,
             "groupName": group.name,
             "snapshotId": snapshot.id,
-            "displayName": snapshot.display_name
+            "displayName": snapshot.display_name,
+            "reversible": true
         })),
         results: Some(results),
+        group_id: Some(group_id.clone()),
     };
     let _ = store.add_history(&history_entry);
 
-    ApiResponse::success(snapshot)
+    let mut response = ApiResponse::success(snapshot);
+    response.messages.warning = warnings;
+    response
 }
 
-/// Delete a snapshot
-#[tauri::command]
-pub async fn delete_snapshot(id: String) -> ApiResponse<()> {
-    let snapshot_id = id;
-    let store = match MetadataStore::open() {
-        Ok(s) => s,
-        Err(e) => return ApiResponse::error(format!("Failed to open metadata store: {}", e)),
-    };
+/// Names of the snapshot databases that were successfully created for these snapshots
+fn created_snapshot_names(snapshots: &[&Snapshot]) -> Vec<String> {
+    snapshots
+        .iter()
+        .flat_map(|s| s.database_snapshots.iter())
+        .filter(|ds| ds.success)
+        .map(|ds| ds.snapshot_name.clone())
+        .collect()
+}
 
-    // Get the snapshot to find its database snapshots
-    let groups = match store.get_groups() {
-        Ok(g) => g,
-        Err(e) => return ApiResponse::error(format!("Failed to get groups: {}", e)),
-    };
+/// Error for an atomic snapshot that failed on some databases, or None when every database succeeded
+fn atomic_failure_message(results: &[OperationResult]) -> Option<String> {
+    let failures: Vec<String> = results
+        .iter()
+        .filter(|r| !r.success)
+        .map(|r| format!("{} ({})", r.database, r.error.as_deref().unwrap_or("unknown error")))
+        .collect();
+    if failures.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "Snapshot was not created because it failed for: {}. Snapshots already taken for the other databases were dropped.",
+        failures.join(", ")
+    ))
+}
 
-    let mut snapshot_to_delete: Option<Snapshot> = None;
-    let mut group_for_snapshot: Option<&crate::models::Group> = None;
-    for group in &groups {
-        if let Ok(snapshots) = store.get_snapshots(&group.id) {
-            if let Some(s) = snapshots.into_iter().find(|s| s.id == snapshot_id) {
-                snapshot_to_delete = Some(s);
-                group_for_snapshot = Some(group);
-                break;
-            }
+/// Databases refused by default: SQL Server's system databases and SQL Parrot's own metadata database
+const DEFAULT_PROTECTED_DATABASES: [&str; 5] = ["master", "model", "msdb", "tempdb", "sqlparrot"];
+
+/// The protectedDatabases setting, or the built-in list when it isn't set
+fn protected_databases(store: &MetadataStore) -> Vec<String> {
+    store
+        .get_settings()
+        .ok()
+        .and_then(|s| s.preferences.protected_databases)
+        .unwrap_or_else(|| DEFAULT_PROTECTED_DATABASES.iter().map(|d| d.to_string()).collect())
+}
+
+/// Error for a database that's on the protected list (compared case-insensitively), if it is
+fn protected_database_error(database: &str, protected: &[String]) -> Option<String> {
+    protected.iter().any(|p| p.eq_ignore_ascii_case(database)).then(|| {
+        format!(
+            "{} is a protected database and can't be snapshotted (see the protectedDatabases setting)",
+            database
+        )
+    })
+}
+
+/// Snapshot database naming when neither the profile nor the settings set a template
+const DEFAULT_SNAPSHOT_NAME_TEMPLATE: &str = "{database}_snapshot_{group}_{sequence}";
+
+/// Placeholders a snapshot name template may use
+const NAME_TEMPLATE_PLACEHOLDERS: [&str; 3] = ["{database}", "{group}", "{sequence}"];
+
+/// A template must name the database and the sequence so names stay unique within and across snapshots
+pub(crate) fn validate_name_template(template: &str) -> Result<(), String> {
+    for required in ["{database}", "{sequence}"] {
+        if !template.contains(required) {
+            return Err(format!("Snapshot name template must include {} (got {})", required, template));
         }
     }
 
-    let snapshot = match snapshot_to_delete {
-        Some(s) => s,
-        None => return ApiResponse::error(format!("Snapshot not found: {}", snapshot_id)),
-    };
-
-    let group = match group_for_snapshot {
-        Some(g) => g,
-        None => return ApiResponse::error(format!("Group not found for snapshot: {}", snapshot_id)),
-    };
+    // Braces left after removing the known placeholders are a typo like {databse}
+    let leftover = NAME_TEMPLATE_PLACEHOLDERS
+        .iter()
+        .fold(template.to_string(), |rest, placeholder| rest.replace(placeholder, ""));
+    if leftover.contains(['{', '}']) {
+        return Err(format!(
+            "Snapshot name template can only use {} (got {})",
+            NAME_TEMPLATE_PLACEHOLDERS.join(", "),
+            template
+        ));
+    }
+    Ok(())
+}
 
-    // Get profile from metadata database using group's profile_id
-    let profile = match get_profile_for_group(&store, group) {
-        Ok(p) => p,
-        Err(e) => return ApiResponse::error(e),
-    };
+/// Snapshot database name from a template, or the built-in one when None
+/// Spaces in the group name become underscores, as they always have
+fn snapshot_base_name(template: Option<&str>, database: &str, group_name: &str, sequence: u32) -> String {
+    template
+        .unwrap_or(DEFAULT_SNAPSHOT_NAME_TEMPLATE)
+        .replace("{database}", database)
+        .replace("{group}", &group_name.replace(' ', "_"))
+        .replace("{sequence}", &sequence.to_string())
+}
 
-    // Connect and drop SQL Server snapshots
-    let mut conn = match SqlServerConnection::connect(&profile).await {
-        Ok(c) => c,
-        Err(e) => return ApiResponse::error(format!("Failed to connect: {}", e)),
+/// Overrides of the profile a group runs against: its own, or the active profile for legacy groups
+fn profile_overrides_for_group(store: &MetadataStore, group: &Group) -> ProfileOverrides {
+    let profile = match group.profile_id.as_deref() {
+        Some(profile_id) => store.get_profile(profile_id).ok().flatten(),
+        None => store.get_active_profile().ok().flatten(),
     };
+    profile.map(|p| p.overrides).unwrap_or_default()
+}
 
-    for db_snapshot in &snapshot.database_snapshots {
-        if db_snapshot.success {
-            if let Err(e) = conn.drop_snapshot(&db_snapshot.snapshot_name).await {
-                // Log but continue - snapshot might already be gone
-                eprintln!(
-                    "Warning: Failed to drop snapshot {}: {}",
-                    db_snapshot.snapshot_name, e
-                );
-            }
+/// Most suffixes tried before giving up on finding a free snapshot name
+const MAX_NAME_SUFFIX: u32 = 100;
+
+/// Most sequence numbers tried before giving up on one whose names are all free
+const MAX_SEQUENCE_BUMPS: u32 = 100;
+
+/// First sequence from `start` none of whose snapshot names are in `taken` (lowercased names)
+fn first_free_sequence(
+    start: u32,
+    taken: &HashSet<String>,
+    names_for: impl Fn(u32) -> Vec<String>,
+) -> Result<u32, String> {
+    let mut sequence = start;
+    for _ in 0..MAX_SEQUENCE_BUMPS {
+        if !names_for(sequence).iter().any(|name| taken.contains(&name.to_lowercase())) {
+            return Ok(sequence);
         }
+        sequence = sequence
+            .checked_add(1)
+            .ok_or_else(|| "Snapshot sequence would overflow; run renumber_snapshots to repair".to_string())?;
     }
+    Err(format!(
+        "No free snapshot sequence found after {} (tried {})",
+        start, MAX_SEQUENCE_BUMPS
+    ))
+}
 
-    // Get group info for history
-    let group = groups.iter().find(|g| g.id == snapshot.group_id);
-    let group_name = group.map(|g| g.name.clone()).unwrap_or_default();
+/// Name to try for the given attempt: the base name first, then `_2`, `_3`, ...
+fn suffixed_snapshot_name(base_name: &str, attempt: u32) -> String {
+    if attempt <= 1 {
+        base_name.to_string()
+    } else {
+        format!("{}_{}", base_name, attempt)
+    }
+}
 
-    // Delete from metadata
-    match store.delete_snapshot(&snapshot_id) {
-        Ok(_) => {
-            // Log to history
-            let history_entry = HistoryEntry {
-                id: Uuid::new_v4().to_string(),
-                operation_type: "delete_snapshot".to_string(),
-                timestamp: Utc::now(),
-                user_name: Some(whoami::username_os().to_string_lossy().into_owned()),
-                details: Some(serde_json::json!({
-                    "groupId": snapshot.group_id,
-                    "groupName": group_name,
-                    "snapshotId": snapshot_id,
-                    "displayName": snapshot.display_name
-                })),
-                results: None,
-            };
-            let _ = store.add_history(&history_entry);
-            ApiResponse::success(())
+/// Snapshot name that isn't taken on the server, or an error when the collision setting says to fail
+/// If the existence check itself fails, the base name is used and CREATE DATABASE reports any conflict
+async fn free_snapshot_name(
+    conn: &mut SqlServerConnection,
+    base_name: &str,
+    on_name_collision: NameCollision,
+) -> Result<String, String> {
+    for attempt in 1..=MAX_NAME_SUFFIX {
+        let candidate = suffixed_snapshot_name(base_name, attempt);
+        match conn.snapshot_exists(&candidate).await {
+            Ok(false) => return Ok(candidate),
+            Ok(true) if on_name_collision == NameCollision::Error => {
+                return Err(format!(
+                    "A snapshot named {} already exists on the server; drop it or set onNameCollision to \"suffix\"",
+                    candidate
+                ));
+            }
+            Ok(true) => continue,
+            Err(e) => {
+                log::warn!("Could not check whether snapshot {} exists: {}", candidate, e);
+                return Ok(candidate);
+            }
         }
-        Err(e) => ApiResponse::error(format!("Failed to keep changes (metadata): {}", e)),
     }
+    Err(format!(
+        "No free snapshot name found for {} after {} attempts",
+        base_name, MAX_NAME_SUFFIX
+    ))
 }
 
-/// Restore databases to a snapshot's state (UI: "Discard Changes").
-/// Optional auto_create_checkpoint overrides the setting for this action only.
-#[tauri::command]
-pub async fn rollback_snapshot(id: String, auto_create_checkpoint: Option<bool>) -> ApiResponse<RollbackResult> {
-    let snapshot_id = id;
-    let store = match MetadataStore::open() {
-        Ok(s) => s,
-        Err(e) => return ApiResponse::error(format!("Failed to open metadata store: {}", e)),
-    };
-
-    // Find the snapshot and its group
-    let groups = match store.get_groups() {
-        Ok(g) => g,
-        Err(e) => return ApiResponse::error(format!("Failed to get groups: {}", e)),
-    };
+/// Failed per-database result, keeping the SQL Server error number/severity/state for support
+pub(crate) fn failed_result(database: &str, message: String, error: &SqlServerError) -> OperationResult {
+    let code = error.sql_error_code();
+    OperationResult {
+        database: database.to_string(),
+        success: false,
+        error: Some(message),
+        error_number: code.map(|(number, _, _)| number),
+        error_severity: code.map(|(_, severity, _)| severity),
+        error_state: code.map(|(_, _, state)| state),
+        ..Default::default()
+    }
+}
 
-    let mut target_snapshot: Option<Snapshot> = None;
-    let mut target_group: Option<&crate::models::Group> = None;
+/// Warning to show before restoring a database in SIMPLE recovery
+fn recovery_model_warning(database: &str, recovery_model: &str) -> Option<String> {
+    if recovery_model.eq_ignore_ascii_case("SIMPLE") {
+        Some(format!(
+            "{} uses the SIMPLE recovery model: there are no log backups to recover changes made since the snapshot, so they are gone for good once it is restored",
+            database
+        ))
+    } else {
+        None
+    }
+}
 
-    for group in &groups {
-        if let Ok(snapshots) = store.get_snapshots(&group.id) {
-            if let Some(s) = snapshots.into_iter().find(|s| s.id == snapshot_id) {
-                target_snapshot = Some(s);
-                target_group = Some(group);
-                break;
+/// Recovery model of each database, plus a warning for each in SIMPLE recovery
+async fn recovery_models(
+    conn: &mut SqlServerConnection,
+    databases: &[String],
+) -> (Vec<RecoveryModelInfo>, Vec<String>) {
+    let mut models = Vec::new();
+    let mut warnings = Vec::new();
+    for database in databases {
+        match conn.get_recovery_model(database).await {
+            Ok(recovery_model) => {
+                warnings.extend(recovery_model_warning(database, &recovery_model));
+                models.push(RecoveryModelInfo {
+                    database: database.clone(),
+                    recovery_model,
+                });
             }
+            Err(e) => log::warn!("Could not read recovery model of {}: {}", database, e),
         }
     }
+    (models, warnings)
+}
 
-    let snapshot = match target_snapshot {
-        Some(s) => s,
-        None => return ApiResponse::error(format!("Snapshot not found: {}", snapshot_id)),
-    };
-
-    let group = target_group.unwrap();
+/// Warning to show when a snapshot target is part of an Always On availability group
+fn availability_warning(database: &str, info: &AvailabilityInfo) -> Option<String> {
+    if !info.in_availability_group {
+        return None;
+    }
 
-    // Get profile from metadata database using group's profile_id
-    let profile = match get_profile_for_group(&store, group) {
-        Ok(p) => p,
-        Err(e) => return ApiResponse::error(e),
+    let group = info.group_name.as_deref().unwrap_or("an availability group");
+    let message = match info.replica_role.as_deref() {
+        Some("SECONDARY") => format!(
+            "{} is a secondary replica in {}; snapshots of secondary databases can't be used to roll back the primary",
+            database, group
+        ),
+        Some(role) => format!(
+            "{} is in availability group {} (local replica is {}); restoring from a snapshot is not supported on AG databases",
+            database, group, role
+        ),
+        None => format!("{} is in availability group {}", database, group),
+    };
+    Some(message)
+}
+
+/// Delete a snapshot
+/// While a UI password is set, `session_token` must come from check_password.
+#[tauri::command]
+pub async fn delete_snapshot(app: tauri::AppHandle, id: String, session_token: Option<String>) -> ApiResponse<()> {
+    timed(async move {
+        if let Some(response) = require_auth(&app.state::<Sessions>(), session_token.as_deref()) {
+            return response;
+        }
+
+        let snapshot_id = id;
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
+
+        // Get the snapshot to find its database snapshots
+        let groups = match store.get_groups() {
+            Ok(g) => g,
+            Err(e) => return ApiResponse::error_key(messages::GET_GROUPS_FAILED, &[&e]),
+        };
+
+        let mut snapshot_to_delete: Option<Snapshot> = None;
+        let mut group_for_snapshot: Option<&crate::models::Group> = None;
+        for group in &groups {
+            if let Ok(snapshots) = store.get_snapshots(&group.id) {
+                if let Some(s) = snapshots.into_iter().find(|s| s.id == snapshot_id) {
+                    snapshot_to_delete = Some(s);
+                    group_for_snapshot = Some(group);
+                    break;
+                }
+            }
+        }
+
+        let snapshot = match snapshot_to_delete {
+            Some(s) => s,
+            None => return ApiResponse::error_key(messages::SNAPSHOT_NOT_FOUND, &[&snapshot_id]),
+        };
+
+        let group = match group_for_snapshot {
+            Some(g) => g,
+            None => return ApiResponse::error(format!("Group not found for snapshot: {}", snapshot_id)),
+        };
+
+        // Only one operation at a time per group; the guard releases the lock on every return path
+        let group_locks = app.state::<GroupLocks>();
+        let _group_guard = match group_locks.try_lock(&group.id) {
+            Some(guard) => guard,
+            None => return group_busy(&group.name),
+        };
+
+        // Get profile from metadata database using group's profile_id
+        let profile = match get_profile_for_group(&store, group) {
+            Ok(p) => p,
+            Err(e) => return ApiResponse::error(e),
+        };
+
+        // Connect and drop SQL Server snapshots
+        let mut conn = match SqlServerConnection::connect(&profile).await {
+            Ok(c) => c,
+            Err(e) => return ApiResponse::error_key(messages::CONNECT_FAILED, &[&e]),
+        };
+
+        // Log but continue - snapshots might already be gone
+        match conn.drop_snapshots(&created_snapshot_names(&[&snapshot])).await {
+            Ok(failed) => {
+                for (name, error) in failed {
+                    log::warn!("Failed to drop snapshot {}: {}", name, error);
+                }
+            }
+            Err(e) => log::warn!("Failed to drop snapshots: {}", e),
+        }
+
+        // Get group info for history
+        let group = groups.iter().find(|g| g.id == snapshot.group_id);
+        let group_name = group.map(|g| g.name.clone()).unwrap_or_default();
+
+        // Delete from metadata
+        match store.delete_snapshot(&snapshot_id) {
+            Ok(_) => {
+                // Log to history
+                let history_entry = HistoryEntry {
+                    id: Uuid::new_v4().to_string(),
+                    operation_type: "delete_snapshot".to_string(),
+                    timestamp: Utc::now(),
+                    user_name: Some(whoami::username_os().to_string_lossy().into_owned()),
+                    details: Some(serde_json::json!({
+                        "groupId": snapshot.group_id,
+                        "groupName": group_name,
+                        "snapshotId": snapshot_id,
+                        "displayName": snapshot.display_name
+                    })),
+                    results: None,
+                    group_id: Some(snapshot.group_id.clone()),
+                };
+                let _ = store.add_history(&history_entry);
+                ApiResponse::success(())
+            }
+            Err(e) => ApiResponse::error(format!("Failed to keep changes (metadata): {}", e)),
+        }
+    })
+    .await
+}
+
+/// Restore databases to a snapshot's state (UI: "Discard Changes").
+/// Optional auto_create_checkpoint overrides the setting for this action only.
+/// verify_first runs DBCC CHECKDB on each snapshot database and aborts before any drops if one fails.
+/// When the require_confirmation setting is on, `confirmed` must be true.
+/// When another group has snapshots of a database being restored, `confirm_cross_group` must be true
+/// (SQL Server requires dropping them; see preview_rollback).
+/// External snapshots block the rollback unless `override_token` comes from acknowledge_external_snapshots;
+/// the acknowledged external snapshots are then dropped too.
+/// While a UI password is set, `session_token` must come from check_password.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn rollback_snapshot(
+    app: tauri::AppHandle,
+    id: String,
+    auto_create_checkpoint: Option<bool>,
+    confirmed: Option<bool>,
+    verify_first: Option<bool>,
+    confirm_cross_group: Option<bool>,
+    override_token: Option<String>,
+    session_token: Option<String>,
+) -> ApiResponse<RollbackResult> {
+    if let Some(response) = require_auth(&app.state::<Sessions>(), session_token.as_deref()) {
+        return response;
+    }
+
+    rollback_group(
+        &app.state::<GroupLocks>(),
+        id,
+        auto_create_checkpoint,
+        confirmed,
+        verify_first,
+        confirm_cross_group,
+        override_token,
+    )
+    .await
+}
+
+/// Roll back to a snapshot and record history, metrics and the webhook notification; shared with the CLI
+pub(crate) async fn rollback_group(
+    group_locks: &GroupLocks,
+    snapshot_id: String,
+    auto_create_checkpoint: Option<bool>,
+    confirmed: Option<bool>,
+    verify_first: Option<bool>,
+    confirm_cross_group: Option<bool>,
+    override_token: Option<String>,
+) -> ApiResponse<RollbackResult> {
+    let mut history = Vec::new();
+    let response = rollback_group_recording(
+        group_locks,
+        snapshot_id,
+        auto_create_checkpoint,
+        confirmed,
+        verify_first,
+        confirm_cross_group,
+        override_token,
+        &mut history,
+    )
+    .await;
+    if let Err(e) = MetadataStore::open().and_then(|store| store.add_history_batch(&history)) {
+        log::warn!("Failed to record rollback history: {}", e);
+    }
+    response
+}
+
+/// rollback_group, leaving its history entries in `history` so several rollbacks can be written together
+#[allow(clippy::too_many_arguments)]
+async fn rollback_group_recording(
+    group_locks: &GroupLocks,
+    snapshot_id: String,
+    auto_create_checkpoint: Option<bool>,
+    confirmed: Option<bool>,
+    verify_first: Option<bool>,
+    confirm_cross_group: Option<bool>,
+    override_token: Option<String>,
+    history: &mut Vec<HistoryEntry>,
+) -> ApiResponse<RollbackResult> {
+    let group = group_of_snapshot(&snapshot_id);
+    let response = timed(rollback_to_snapshot(
+        group_locks,
+        snapshot_id,
+        auto_create_checkpoint,
+        confirmed,
+        verify_first,
+        confirm_cross_group,
+        override_token,
+        history,
+    ))
+    .await;
+    // Coded errors (confirmation required, group busy, cross-group) are refusals rather than failed rollbacks
+    if response.error_code.is_none() {
+        metrics::record_rollback(response.success);
+        let databases = response
+            .data
+            .iter()
+            .flat_map(|result| &result.results)
+            .map(DatabaseOutcome::from)
+            .collect();
+        notify_webhook("rollback_snapshot", group.as_ref(), databases, &response);
+    }
+    response
+}
+
+/// Roll several groups back to their most recent snapshot, one group at a time (e.g. a full
+/// environment reset). Each group takes its own lock and gets its own history entries, written together
+/// at the end; a group that fails or has nothing to roll back to doesn't stop the rest.
+#[tauri::command]
+#[allow(non_snake_case)]
+pub async fn rollback_latest(
+    app: tauri::AppHandle,
+    groupIds: Vec<String>,
+    auto_create_checkpoint: Option<bool>,
+    confirmed: Option<bool>,
+    verify_first: Option<bool>,
+    confirm_cross_group: Option<bool>,
+    session_token: Option<String>,
+) -> ApiResponse<Vec<GroupRollbackResult>> {
+    timed(async move {
+        if let Some(response) = require_auth(&app.state::<Sessions>(), session_token.as_deref()) {
+            return response;
+        }
+
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
+
+        // Ask once for the whole batch rather than refusing every group separately
+        if let Some(response) = super::confirmation_error(&store, confirmed, "Rollback") {
+            return response;
+        }
+
+        let group_locks = app.state::<GroupLocks>();
+        let mut results = Vec::new();
+        let mut history = Vec::new();
+        for group_id in groupIds {
+            let latest = match store.get_snapshots(&group_id) {
+                Ok(snapshots) => latest_restorable_snapshot(&snapshots).map(|s| s.id.clone()),
+                Err(e) => {
+                    results.push(GroupRollbackResult::failed(group_id, e.to_string()));
+                    continue;
+                }
+            };
+            let Some(snapshot_id) = latest else {
+                results.push(GroupRollbackResult::failed(group_id, "No snapshot to roll back to".to_string()));
+                continue;
+            };
+
+            let response = rollback_group_recording(
+                &group_locks,
+                snapshot_id.clone(),
+                auto_create_checkpoint,
+                confirmed,
+                verify_first,
+                confirm_cross_group,
+                None,
+                &mut history,
+            )
+            .await;
+            results.push(GroupRollbackResult {
+                group_id,
+                snapshot_id: Some(snapshot_id),
+                success: response.success,
+                error: response.messages.error.first().cloned(),
+                rollback: response.data,
+            });
+        }
+        if let Err(e) = store.add_history_batch(&history) {
+            log::warn!("Failed to record rollback history: {}", e);
+        }
+
+        let failed = results.iter().filter(|r| !r.success).count();
+        if failed == 0 {
+            ApiResponse::success(results)
+        } else {
+            ApiResponse::error_with_data(
+                format!("Rollback failed for {} of {} groups", failed, results.len()),
+                results,
+            )
+        }
+    })
+    .await
+}
+
+/// The newest snapshot with at least one database that can be restored
+fn latest_restorable_snapshot(snapshots: &[Snapshot]) -> Option<&Snapshot> {
+    snapshots
+        .iter()
+        .filter(|s| s.database_snapshots.iter().any(|ds| ds.success))
+        .max_by_key(|s| (s.created_at, s.sequence))
+}
+
+/// Error code returned when a rollback would drop snapshots belonging to other groups that share
+/// one of its databases and the caller didn't pass `confirm_cross_group: true`
+pub const CROSS_GROUP_CONFIRMATION_REQUIRED: &str = "CrossGroupConfirmationRequired";
+
+/// Another group's snapshot database that a rollback has to drop because the groups share the database
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct CrossGroupSnapshot {
+    #[serde(rename = "groupId")]
+    pub group_id: String,
+    #[serde(rename = "groupName")]
+    pub group_name: String,
+    #[serde(rename = "snapshotId")]
+    pub snapshot_id: String,
+    #[serde(rename = "displayName")]
+    pub display_name: String,
+    pub database: String,
+    #[serde(rename = "snapshotName")]
+    pub snapshot_name: String,
+}
+
+/// Other groups' successful snapshot databases for the restored databases that `group_id` shares with them
+fn cross_group_entries(
+    group_id: &str,
+    restored: &[String],
+    conflicts: &[DatabaseGroupConflict],
+    other_snapshots: &[(&Group, Vec<Snapshot>)],
+) -> Vec<CrossGroupSnapshot> {
+    let mut entries = Vec::new();
+    let shared = conflicts.iter().filter(|c| {
+        c.groups.iter().any(|g| g.group_id == group_id) && restored.iter().any(|d| d.eq_ignore_ascii_case(&c.database))
+    });
+    for conflict in shared {
+        let sharing = other_snapshots
+            .iter()
+            .filter(|(g, _)| g.id != group_id && conflict.groups.iter().any(|m| m.group_id == g.id));
+        for (other, snapshots) in sharing {
+            for snapshot in snapshots {
+                let dropped = snapshot
+                    .database_snapshots
+                    .iter()
+                    .filter(|ds| ds.success && ds.database.eq_ignore_ascii_case(&conflict.database));
+                for ds in dropped {
+                    entries.push(CrossGroupSnapshot {
+                        group_id: other.id.clone(),
+                        group_name: other.name.clone(),
+                        snapshot_id: snapshot.id.clone(),
+                        display_name: snapshot.display_name.clone(),
+                        database: ds.database.clone(),
+                        snapshot_name: ds.snapshot_name.clone(),
+                    });
+                }
+            }
+        }
+    }
+    entries
+}
+
+/// Other groups' snapshots that restoring these databases of `group` would drop, from metadata
+/// Only groups on the same profile count; groups without a profile_id run against the active profile
+fn cross_group_snapshots(
+    store: &MetadataStore,
+    group: &Group,
+    groups: &[Group],
+    restored: &[String],
+) -> Vec<CrossGroupSnapshot> {
+    let active_profile_id = store.get_active_profile().ok().flatten().map(|p| p.id);
+    let profile_of = |g: &Group| g.profile_id.clone().or_else(|| active_profile_id.clone());
+    let same_profile: Vec<Group> = groups
+        .iter()
+        .filter(|g| profile_of(g) == profile_of(group))
+        .cloned()
+        .collect();
+
+    let conflicts = find_database_group_conflicts(&same_profile);
+    let other_snapshots: Vec<(&Group, Vec<Snapshot>)> = same_profile
+        .iter()
+        .filter(|g| g.id != group.id && conflicts.iter().any(|c| c.groups.iter().any(|m| m.group_id == g.id)))
+        .map(|g| (g, store.get_snapshots(&g.id).unwrap_or_default()))
+        .collect();
+    cross_group_entries(&group.id, restored, &conflicts, &other_snapshots)
+}
+
+/// Update other groups' metadata after a rollback dropped their snapshots of shared databases:
+/// the dropped entries are marked missing, and a snapshot with nothing left is removed
+fn invalidate_cross_group_snapshots(store: &MetadataStore, dropped: &[CrossGroupSnapshot]) {
+    let mut snapshot_ids: Vec<(&str, &str)> = dropped
+        .iter()
+        .map(|c| (c.group_id.as_str(), c.snapshot_id.as_str()))
+        .collect();
+    snapshot_ids.sort_unstable();
+    snapshot_ids.dedup();
+
+    for (group_id, snapshot_id) in snapshot_ids {
+        let Some(snapshot) = store
+            .get_snapshots(group_id)
+            .ok()
+            .and_then(|snapshots| snapshots.into_iter().find(|s| s.id == snapshot_id))
+        else {
+            continue;
+        };
+        let remaining: Vec<&str> = snapshot
+            .database_snapshots
+            .iter()
+            .map(|ds| ds.snapshot_name.as_str())
+            .filter(|name| !dropped.iter().any(|c| c.snapshot_name == *name))
+            .collect();
+        match reconcile_snapshot(&snapshot, &remaining) {
+            Reconciliation::Keep => {}
+            Reconciliation::Remove => {
+                let _ = store.delete_snapshot(&snapshot.id);
+            }
+            Reconciliation::Update(entries) => {
+                let _ = store.update_snapshot_databases(&snapshot.id, &entries);
+            }
+        }
+    }
+}
+
+/// Snapshots on the server of the group's databases that SQL Parrot doesn't track: neither this
+/// group's own nor other groups' snapshots of shared databases. SQL Server won't restore while they exist.
+fn external_snapshot_names(
+    server_snapshots: &[ServerSnapshot],
+    group: &Group,
+    group_snapshots: &[Snapshot],
+    cross_group: &[CrossGroupSnapshot],
+) -> Vec<String> {
+    let ours: Vec<&str> = group_snapshots
+        .iter()
+        .flat_map(|s| s.database_snapshots.iter().map(|ds| ds.snapshot_name.as_str()))
+        .collect();
+    server_snapshots
+        .iter()
+        .filter(|s| {
+            !ours.contains(&s.name.as_str())
+                && !cross_group.iter().any(|c| c.snapshot_name == s.name)
+                && group.databases.contains(&s.source_database)
+        })
+        .map(|s| s.name.clone())
+        .collect()
+}
+
+/// What rollback_snapshot would do, from metadata only
+#[derive(serde::Serialize)]
+pub struct RollbackPreview {
+    #[serde(rename = "snapshotId")]
+    pub snapshot_id: String,
+    #[serde(rename = "groupId")]
+    pub group_id: String,
+    #[serde(rename = "groupName")]
+    pub group_name: String,
+    /// Databases restored from the snapshot
+    #[serde(rename = "restoreDatabases")]
+    pub restore_databases: Vec<String>,
+    /// This group's snapshot databases that are dropped, including the one restored from
+    #[serde(rename = "droppedSnapshots")]
+    pub dropped_snapshots: Vec<String>,
+    /// Other groups' snapshots dropped because they share a restored database
+    #[serde(rename = "crossGroupSnapshots")]
+    pub cross_group_snapshots: Vec<CrossGroupSnapshot>,
+}
+
+/// Preview a rollback for a confirmation dialog; rollback_snapshot needs `confirm_cross_group`
+/// when crossGroupSnapshots isn't empty
+#[tauri::command]
+pub async fn preview_rollback(id: String) -> ApiResponse<RollbackPreview> {
+    timed(async move {
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
+
+        let groups = match store.get_groups() {
+            Ok(g) => g,
+            Err(e) => return ApiResponse::error_key(messages::GET_GROUPS_FAILED, &[&e]),
+        };
+
+        for group in &groups {
+            let group_snapshots = store.get_snapshots(&group.id).unwrap_or_default();
+            let Some(snapshot) = group_snapshots.iter().find(|s| s.id == id) else {
+                continue;
+            };
+
+            let restore_databases: Vec<String> = snapshot
+                .database_snapshots
+                .iter()
+                .filter(|ds| ds.success)
+                .map(|ds| ds.database.clone())
+                .collect();
+            let cross_group_snapshots = cross_group_snapshots(&store, group, &groups, &restore_databases);
+            return ApiResponse::success(RollbackPreview {
+                snapshot_id: snapshot.id.clone(),
+                group_id: group.id.clone(),
+                group_name: group.name.clone(),
+                dropped_snapshots: created_snapshot_names(&group_snapshots.iter().collect::<Vec<_>>()),
+                restore_databases,
+                cross_group_snapshots,
+            });
+        }
+
+        ApiResponse::error_key(messages::SNAPSHOT_NOT_FOUND, &[&id])
+    })
+    .await
+}
+
+/// The rollback's history entries are added to `history` for the caller to write
+#[allow(clippy::too_many_arguments)]
+async fn rollback_to_snapshot(
+    group_locks: &GroupLocks,
+    snapshot_id: String,
+    auto_create_checkpoint: Option<bool>,
+    confirmed: Option<bool>,
+    verify_first: Option<bool>,
+    confirm_cross_group: Option<bool>,
+    override_token: Option<String>,
+    history: &mut Vec<HistoryEntry>,
+) -> ApiResponse<RollbackResult> {
+    let store = match MetadataStore::open() {
+        Ok(s) => s,
+        Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+    };
+
+    if let Some(response) = super::confirmation_error(&store, confirmed, "Rollback") {
+        return response;
+    }
+
+    // Find the snapshot and its group
+    let groups = match store.get_groups() {
+        Ok(g) => g,
+        Err(e) => return ApiResponse::error_key(messages::GET_GROUPS_FAILED, &[&e]),
+    };
+
+    let mut target_snapshot: Option<Snapshot> = None;
+    let mut target_group: Option<&crate::models::Group> = None;
+
+    for group in &groups {
+        if let Ok(snapshots) = store.get_snapshots(&group.id) {
+            if let Some(s) = snapshots.into_iter().find(|s| s.id == snapshot_id) {
+                target_snapshot = Some(s);
+                target_group = Some(group);
+                break;
+            }
+        }
+    }
+
+    let snapshot = match target_snapshot {
+        Some(s) => s,
+        None => return ApiResponse::error_key(messages::SNAPSHOT_NOT_FOUND, &[&snapshot_id]),
+    };
+
+    let group = target_group.unwrap();
+
+    // Only one operation at a time per group; the guard releases the lock on every return path
+    let _group_guard = match group_locks.try_lock(&group.id) {
+        Some(guard) => guard,
+        None => return group_busy(&group.name),
+    };
+
+    let restored: Vec<String> = snapshot
+        .database_snapshots
+        .iter()
+        .filter(|ds| ds.success)
+        .map(|ds| ds.database.clone())
+        .collect();
+
+    // Other groups' snapshots of a shared database must be dropped too, which invalidates them
+    let cross_group = cross_group_snapshots(&store, group, &groups, &restored);
+    if !cross_group.is_empty() && !confirm_cross_group.unwrap_or(false) {
+        let affected: Vec<String> = cross_group
+            .iter()
+            .map(|c| format!("{} ({} in group '{}')", c.snapshot_name, c.display_name, c.group_name))
+            .collect();
+        return ApiResponse::error_with_code(
+            CROSS_GROUP_CONFIRMATION_REQUIRED,
+            format!(
+                "Discarding changes would also drop snapshots of shared databases that belong to other groups: {}. Confirm the cross-group impact to continue.",
+                affected.join(", ")
+            ),
+        );
+    }
+
+    // Those groups are locked too, so none of their operations runs while their snapshots are dropped
+    let _cross_group_guards = match group_locks.try_lock_all(cross_group.iter().map(|c| c.group_id.as_str())) {
+        Ok(guards) => guards,
+        Err(busy_id) => {
+            let busy = cross_group.iter().find(|c| c.group_id == busy_id);
+            return group_busy(busy.map_or(busy_id, |c| c.group_name.as_str()));
+        }
+    };
+
+    // Get profile from metadata database using group's profile_id
+    let profile = match get_profile_for_group(&store, group) {
+        Ok(p) => p,
+        Err(e) => return ApiResponse::error(e),
     };
 
     // Connect to SQL Server
     let mut conn = match SqlServerConnection::connect(&profile).await {
         Ok(c) => c,
-        Err(e) => return ApiResponse::error(format!("Failed to connect: {}", e)),
+        Err(e) => return ApiResponse::error_key(messages::CONNECT_FAILED, &[&e]),
     };
 
     // Check for external snapshots that would block rollback
@@ -325,94 +1441,158 @@ pub async fn rollback_snapshot(id: String, auto_create_checkpoint: Option<bool>)
         Err(e) => return ApiResponse::error(format!("Failed to check snapshots: {}", e)),
     };
 
-    // Get all our tracked snapshot names for this group
+    // Find external snapshots for our databases using actual source database
+    // (other groups' snapshots of shared databases were confirmed above and aren't external)
     let group_snapshots = store.get_snapshots(&group.id).unwrap_or_default();
-    let our_snapshot_names: Vec<String> = group_snapshots
-        .iter()
-        .flat_map(|s| s.database_snapshots.iter().map(|ds| ds.snapshot_name.clone()))
-        .collect();
+    let external_snapshots =
+        external_snapshot_names(&server_snapshots_with_source, group, &group_snapshots, &cross_group);
+
+    // An acknowledgement token lets the rollback continue, dropping exactly the external snapshots it names
+    let external_override: Option<Acknowledgement> = match (external_snapshots.is_empty(), override_token) {
+        (true, _) => None,
+        (false, None) => {
+            let origins =
+                server_snapshot_origins(&mut conn, &external_snapshots, snapshot_origin(&store).as_deref()).await;
+            return ApiResponse::error(format!(
+                "Cannot discard changes: external snapshots exist for databases in this group: {:?}. {} Please delete them manually or from the originating instance before discarding changes.",
+                external_snapshots,
+                external_origin_note(&external_snapshots, &origins)
+            ));
+        }
+        (false, Some(token)) => {
+            match ExternalOverrides::shared().redeem(&token, &snapshot.id, &external_snapshots, Utc::now()) {
+                Ok(acknowledgement) => Some(acknowledgement),
+                Err(e) => return ApiResponse::error(format!("Cannot discard changes: {}", e)),
+            }
+        }
+    };
 
-    // Find external snapshots for our databases using actual source database
-    let external_snapshots: Vec<String> = server_snapshots_with_source
-        .iter()
-        .filter(|(name, source_db)| {
-            !our_snapshot_names.contains(name) && group.databases.contains(source_db)
-        })
-        .map(|(name, _)| name.clone())
-        .collect();
+    // Confirm every snapshot database we'll restore from is still on the server before dropping anything;
+    // if one was dropped out-of-band, continuing would discard the other snapshots and then fail the restore
+    let mut missing_snapshots = Vec::new();
+    for db_snapshot in snapshot.database_snapshots.iter().filter(|ds| ds.success) {
+        match conn.snapshot_exists(&db_snapshot.snapshot_name).await {
+            Ok(true) => {}
+            Ok(false) => missing_snapshots.push(db_snapshot.snapshot_name.clone()),
+            Err(e) => {
+                return ApiResponse::error(format!(
+                    "Failed to check snapshot {}: {}",
+                    db_snapshot.snapshot_name, e
+                ))
+            }
+        }
+    }
 
-    if !external_snapshots.is_empty() {
+    if !missing_snapshots.is_empty() {
         return ApiResponse::error(format!(
-            "Cannot discard changes: external snapshots exist for databases in this group: {:?}. These may have been created by another instance of SQL Parrot (npm, Docker, or exe). Please delete them manually or from the originating instance before discarding changes.",
-            external_snapshots
+            "Cannot discard changes: snapshot databases no longer exist on the server: {}. Nothing was dropped or restored.",
+            missing_snapshots.join(", ")
         ));
     }
 
+    // Optionally prove the snapshot is intact before anything destructive happens
+    let verification = if verify_first.unwrap_or(false) {
+        let mut checked = Vec::new();
+        let mut failures = Vec::new();
+        for db_snapshot in snapshot.database_snapshots.iter().filter(|ds| ds.success) {
+            log::info!("Checking integrity of snapshot '{}'", db_snapshot.snapshot_name);
+            match conn.check_snapshot_integrity(&db_snapshot.snapshot_name).await {
+                Ok(()) => checked.push(db_snapshot.snapshot_name.clone()),
+                Err(e) => failures.push(format!("{}: {}", db_snapshot.snapshot_name, e)),
+            }
+        }
+
+        if !failures.is_empty() {
+            return ApiResponse::error(format!(
+                "Cannot discard changes: snapshot integrity check failed. Nothing was dropped or restored. {}",
+                failures.join("; ")
+            ));
+        }
+
+        serde_json::json!({ "performed": true, "passed": true, "checked": checked })
+    } else {
+        serde_json::json!({ "performed": false })
+    };
+
     let mut results = Vec::new();
 
+    let (_, warnings) = recovery_models(&mut conn, &restored).await;
+
+    // Skip the target snapshot we're restoring from
+    let other_snapshots: Vec<&Snapshot> = group_snapshots.iter().filter(|s| s.id != snapshot.id).collect();
+    let mut drop_names = created_snapshot_names(&other_snapshots);
+    drop_names.extend(cross_group.iter().map(|c| c.snapshot_name.clone()));
+    if let Some(acknowledgement) = &external_override {
+        log::warn!(
+            "Dropping external snapshots acknowledged by {}: {:?}",
+            acknowledgement.acknowledged_by,
+            acknowledgement.external_snapshots
+        );
+        drop_names.extend(acknowledgement.external_snapshots.iter().cloned());
+    }
+
+    // Record every step before the first drop, so a crash part way through can be resumed
+    let mut actions = vec![OperationAction::DropSnapshots {
+        snapshot_names: drop_names.clone(),
+        snapshot_ids: other_snapshots.iter().map(|s| s.id.clone()).collect(),
+    }];
+    actions.extend(
+        snapshot
+            .database_snapshots
+            .iter()
+            .filter(|ds| ds.success)
+            .map(|ds| OperationAction::Restore {
+                database: ds.database.clone(),
+                snapshot_name: ds.snapshot_name.clone(),
+            }),
+    );
+    actions.push(OperationAction::DropTarget {
+        snapshot_names: created_snapshot_names(&[&snapshot]),
+        snapshot_id: snapshot.id.clone(),
+    });
+    let target_step = actions.len() - 1;
+    let mut operation_log = OperationLog::start(&store, "rollback", &group.id, Some(&snapshot.id), actions);
+
     // Step 1: Drop all OTHER snapshots for databases in this group BEFORE restoring
-    // SQL Server requires ALL snapshots for a database to be dropped before restoring from any one
+    // SQL Server requires ALL snapshots for a database to be dropped before restoring from any one,
+    // so the pre-rollback state can't be kept as a snapshot (see TODO.md)
     log::info!("Dropping other snapshots before restore...");
-    for other_snapshot in &group_snapshots {
-        // Skip the target snapshot we're restoring from
-        if other_snapshot.id == snapshot.id {
-            continue;
-        }
-        for db_snap in &other_snapshot.database_snapshots {
-            if db_snap.success {
-                log::info!("Dropping snapshot '{}' before restore", db_snap.snapshot_name);
-                if let Err(e) = conn.drop_snapshot(&db_snap.snapshot_name).await {
-                    log::warn!("Failed to drop snapshot {}: {}", db_snap.snapshot_name, e);
-                }
-            }
-        }
-        // Also remove from metadata
-        let _ = store.delete_snapshot(&other_snapshot.id);
-    }
+    operation_log.mark(0, StepStatus::InProgress);
+    let failed_drops = run_drop_step(
+        &mut conn,
+        &store,
+        &drop_names,
+        &other_snapshots.iter().map(|s| s.id.clone()).collect::<Vec<_>>(),
+    )
+    .await;
+    operation_log.mark(0, StepStatus::Done);
+    let invalidated: Vec<CrossGroupSnapshot> = cross_group
+        .into_iter()
+        .filter(|c| !failed_drops.contains(&c.snapshot_name))
+        .collect();
+    invalidate_cross_group_snapshots(&store, &invalidated);
 
     // Step 2: Perform rollback for each database
+    let mut restore_step = 1;
     for db_snapshot in &snapshot.database_snapshots {
         if !db_snapshot.success {
             results.push(OperationResult {
                 database: db_snapshot.database.clone(),
                 success: false,
                 error: Some("Original snapshot failed".to_string()),
+                ..Default::default()
             });
             continue;
         }
 
-        // Kill connections
-        log::info!("Killing connections for '{}'", db_snapshot.database);
-        if let Err(e) = conn.kill_connections(&db_snapshot.database).await {
-            log::warn!("Failed to kill connections: {}", e);
-        }
-
-        // Restore from snapshot (includes SINGLE_USER/MULTI_USER in same batch)
-        log::info!(
-            "Restoring database '{}' from snapshot '{}'",
-            db_snapshot.database,
-            db_snapshot.snapshot_name
+        operation_log.mark(restore_step, StepStatus::InProgress);
+        let result = run_restore_step(&mut conn, &db_snapshot.database, &db_snapshot.snapshot_name).await;
+        operation_log.mark(
+            restore_step,
+            if result.success { StepStatus::Done } else { StepStatus::Failed },
         );
-        let restore_result = conn
-            .restore_from_snapshot(&db_snapshot.database, &db_snapshot.snapshot_name)
-            .await;
-
-        match restore_result {
-            Ok(_) => {
-                results.push(OperationResult {
-                    database: db_snapshot.database.clone(),
-                    success: true,
-                    error: None,
-                });
-            }
-            Err(e) => {
-                results.push(OperationResult {
-                    database: db_snapshot.database.clone(),
-                    success: false,
-                    error: Some(format!("Restore failed: {}", e)),
-                });
-            }
-        }
+        results.push(result);
+        restore_step += 1;
     }
 
     let success_count = results.iter().filter(|r| r.success).count();
@@ -422,12 +1602,15 @@ pub async fn rollback_snapshot(id: String, auto_create_checkpoint: Option<bool>)
     // (Other snapshots were already dropped before restore)
     // After rollback, the database state matches the target snapshot, making it stale
     if success_count == total_count && total_count > 0 {
-        for db_snapshot in &snapshot.database_snapshots {
-            if db_snapshot.success {
-                let _ = conn.drop_snapshot(&db_snapshot.snapshot_name).await;
-            }
-        }
-        let _ = store.delete_snapshot(&snapshot.id);
+        operation_log.mark(target_step, StepStatus::InProgress);
+        run_drop_step(
+            &mut conn,
+            &store,
+            &created_snapshot_names(&[&snapshot]),
+            std::slice::from_ref(&snapshot.id),
+        )
+        .await;
+        operation_log.mark(target_step, StepStatus::Done);
     }
 
     // Log rollback to history
@@ -440,21 +1623,27 @@ pub async fn rollback_snapshot(id: String, auto_create_checkpoint: Option<bool>)
             "groupId": group.id,
             "groupName": group.name,
             "snapshotId": snapshot.id,
-            "displayName": snapshot.display_name
+            "displayName": snapshot.display_name,
+            "verification": verification,
+            "crossGroupSnapshots": invalidated,
+            "externalOverride": external_override
         })),
         results: Some(results.clone()),
+        group_id: Some(group.id.clone()),
     };
-    let _ = store.add_history(&history_entry);
+    history.push(history_entry);
+    operation_log.finish();
 
     // Check if we should auto-create a checkpoint after successful rollback
-    // Request body override takes precedence over setting
+    // Request body override takes precedence over the profile's override, then the global setting
     let settings = store.get_settings().unwrap_or_default();
-    let should_create_checkpoint = auto_create_checkpoint
-        .unwrap_or(settings.preferences.auto_create_checkpoint);
+    let profile_overrides = profile_overrides_for_group(&store, group);
+    let checkpoint_setting = profile_overrides.auto_create_checkpoint(&settings.preferences);
+    let should_create_checkpoint = auto_create_checkpoint.unwrap_or(checkpoint_setting);
     log::info!(
         "Auto-create check: override={:?}, setting={}, success={}/{}",
         auto_create_checkpoint,
-        settings.preferences.auto_create_checkpoint,
+        checkpoint_setting,
         success_count,
         total_count
     );
@@ -466,33 +1655,38 @@ pub async fn rollback_snapshot(id: String, auto_create_checkpoint: Option<bool>)
         };
         let now = Utc::now();
         let auto_snapshot_id = Uuid::new_v4().to_string();
+        let origin = snapshot_origin(&store);
 
         let mut auto_database_snapshots = Vec::new();
         let mut auto_results = Vec::new();
 
         for database in &group.databases {
             let auto_snapshot_name = format!(
-                "{}_snapshot_{}_{}_auto",
-                database,
-                group.name.replace(' ', "_"),
-                new_sequence
+                "{}_auto",
+                snapshot_base_name(
+                    profile_overrides.snapshot_name_template(&settings.preferences),
+                    database,
+                    &group.name,
+                    new_sequence
+                )
             );
 
             match conn
-                .create_snapshot(database, &auto_snapshot_name, &profile.snapshot_path)
+                .create_snapshot(database, &auto_snapshot_name, &profile.snapshot_path, origin.as_deref())
                 .await
             {
-                Ok(_) => {
+                Ok(server_created_at) => {
                     auto_database_snapshots.push(DatabaseSnapshot {
                         database: database.clone(),
                         snapshot_name: auto_snapshot_name,
                         success: true,
                         error: None,
+                        server_created_at,
                     });
                     auto_results.push(OperationResult {
                         database: database.clone(),
                         success: true,
-                        error: None,
+                        ..Default::default()
                     });
                 }
                 Err(e) => {
@@ -501,12 +1695,9 @@ pub async fn rollback_snapshot(id: String, auto_create_checkpoint: Option<bool>)
                         snapshot_name: auto_snapshot_name,
                         success: false,
                         error: Some(e.to_string()),
+                        server_created_at: None,
                     });
-                    auto_results.push(OperationResult {
-                        database: database.clone(),
-                        success: false,
-                        error: Some(e.to_string()),
-                    });
+                    auto_results.push(failed_result(database, e.to_string(), &e));
                 }
             }
         }
@@ -520,6 +1711,8 @@ pub async fn rollback_snapshot(id: String, auto_create_checkpoint: Option<bool>)
             created_by: Some(whoami::username_os().to_string_lossy().into_owned()),
             database_snapshots: auto_database_snapshots,
             is_automatic: true,
+            notes: None,
+            origin,
         };
 
         let _ = store.add_snapshot(&auto_snapshot);
@@ -537,8 +1730,9 @@ pub async fn rollback_snapshot(id: String, auto_create_checkpoint: Option<bool>)
                 "displayName": "Automatic"
             })),
             results: Some(auto_results),
+            group_id: Some(group.id.clone()),
         };
-        let _ = store.add_history(&auto_history);
+        history.push(auto_history);
     }
 
     let result = RollbackResult {
@@ -548,38 +1742,124 @@ pub async fn rollback_snapshot(id: String, auto_create_checkpoint: Option<bool>)
         results,
     };
 
-    if result.success {
+    let mut response = if result.success {
         ApiResponse::success(result)
     } else {
         ApiResponse::error_with_data(
             format!("Discard changes failed: {}/{} databases restored", success_count, total_count),
             result,
         )
-    }
+    };
+    response.messages.warning = warnings;
+    response
 }
 
-/// Verify snapshots exist in SQL Server
+/// Reassign contiguous sequence numbers to a group's snapshots in creation order,
+/// repairing gaps left by deletes and duplicates from imported snapshots
+/// Server snapshot names keep their old numbers, so new snapshots continue above the highest one issued
+/// While a UI password is set, `session_token` must come from check_password.
 #[tauri::command]
 #[allow(non_snake_case)]
-pub async fn verify_snapshots(groupId: String) -> ApiResponse<VerificationResult> {
-    let group_id = groupId;
-    let store = match MetadataStore::open() {
-        Ok(s) => s,
-        Err(e) => return ApiResponse::error(format!("Failed to open metadata store: {}", e)),
-    };
+pub async fn renumber_snapshots(
+    app: tauri::AppHandle,
+    groupId: String,
+    session_token: Option<String>,
+) -> ApiResponse<RenumberResult> {
+    timed(async move {
+        if let Some(response) = require_auth(&app.state::<Sessions>(), session_token.as_deref()) {
+            return response;
+        }
 
-    // Get the group to find its profile_id
-    let groups = match store.get_groups() {
-        Ok(g) => g,
-        Err(e) => return ApiResponse::error(format!("Failed to get groups: {}", e)),
-    };
+        let group_id = groupId;
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
 
-    let group = match groups.iter().find(|g| g.id == group_id) {
-        Some(g) => g,
-        None => return ApiResponse::error(format!("Group not found: {}", group_id)),
-    };
+        let groups = match store.get_groups() {
+            Ok(g) => g,
+            Err(e) => return ApiResponse::error_key(messages::GET_GROUPS_FAILED, &[&e]),
+        };
 
-    // Get profile from metadata database using group's profile_id
+        let group = match groups.iter().find(|g| g.id == group_id) {
+            Some(g) => g,
+            None => return ApiResponse::error_key(messages::GROUP_NOT_FOUND, &[&group_id]),
+        };
+
+        // Don't renumber while a snapshot is being created for this group
+        let group_locks = app.state::<GroupLocks>();
+        let _group_guard = match group_locks.try_lock(&group.id) {
+            Some(guard) => guard,
+            None => return group_busy(&group.name),
+        };
+
+        match store.renumber_snapshots(&group.id) {
+            Ok(result) => {
+                let history_entry = HistoryEntry {
+                    id: Uuid::new_v4().to_string(),
+                    operation_type: "renumber_snapshots".to_string(),
+                    timestamp: Utc::now(),
+                    user_name: Some(whoami::username_os().to_string_lossy().into_owned()),
+                    details: Some(serde_json::json!({
+                        "groupId": group.id,
+                        "groupName": group.name,
+                        "snapshotCount": result.snapshot_count,
+                        "renumberedCount": result.renumbered_count,
+                        "duplicateCount": result.duplicate_count
+                    })),
+                    results: None,
+                    group_id: Some(group.id.clone()),
+                };
+                let _ = store.add_history(&history_entry);
+
+                ApiResponse::success(result)
+            }
+            Err(e) => ApiResponse::error(format!("Failed to renumber snapshots: {}", e)),
+        }
+    })
+    .await
+}
+
+/// Verify snapshots exist in SQL Server
+#[tauri::command]
+#[allow(non_snake_case)]
+pub async fn verify_snapshots(groupId: String) -> ApiResponse<VerificationResult> {
+    let response = timed(verify_group_snapshots(groupId.clone())).await;
+    if let Some(result) = response.data.as_ref().filter(|r| !r.verified) {
+        webhooks::notify(WebhookPayload::new(
+            "verify_snapshots",
+            stored_group(&groupId).as_ref(),
+            Vec::new(),
+            false,
+            Some(format!(
+                "{} orphaned snapshot(s), {} stale metadata entry(ies), {} renamed or missing source(s)",
+                result.orphaned_snapshots.len(),
+                result.stale_metadata.len(),
+                result.renamed_or_missing.len()
+            )),
+        ));
+    }
+    response
+}
+
+pub(crate) async fn verify_group_snapshots(group_id: String) -> ApiResponse<VerificationResult> {
+    let store = match MetadataStore::open() {
+        Ok(s) => s,
+        Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+    };
+
+    // Get the group to find its profile_id
+    let groups = match store.get_groups() {
+        Ok(g) => g,
+        Err(e) => return ApiResponse::error_key(messages::GET_GROUPS_FAILED, &[&e]),
+    };
+
+    let group = match groups.iter().find(|g| g.id == group_id) {
+        Some(g) => g,
+        None => return ApiResponse::error_key(messages::GROUP_NOT_FOUND, &[&group_id]),
+    };
+
+    // Get profile from metadata database using group's profile_id
     let profile = match get_profile_for_group(&store, group) {
         Ok(p) => p,
         Err(e) => return ApiResponse::error(e),
@@ -587,25 +1867,31 @@ pub async fn verify_snapshots(groupId: String) -> ApiResponse<VerificationResult
 
     let mut conn = match SqlServerConnection::connect(&profile).await {
         Ok(c) => c,
-        Err(e) => return ApiResponse::error(format!("Failed to connect: {}", e)),
+        Err(e) => return ApiResponse::error_key(messages::CONNECT_FAILED, &[&e]),
     };
 
     // Get snapshots with their actual source database from SQL Server metadata
     // This works regardless of naming convention (Express vs Rust format)
     let server_snapshots_with_source = match conn.get_snapshots_with_source().await {
         Ok(s) => s,
-        Err(e) => return ApiResponse::error(format!("Failed to get snapshots: {}", e)),
+        Err(e) => return ApiResponse::error_key(messages::GET_SNAPSHOTS_FAILED, &[&e]),
+    };
+
+    let current_databases: Vec<String> = match conn.get_databases(None).await {
+        Ok(databases) => databases.into_iter().map(|d| d.name).collect(),
+        Err(e) => return ApiResponse::error(format!("Failed to get databases: {}", e)),
     };
 
     let metadata_snapshots = store.get_snapshots(&group_id).unwrap_or_default();
 
-    let mut orphaned = Vec::new();
     let mut stale = Vec::new();
+    let renamed_or_missing =
+        find_renamed_or_missing(&metadata_snapshots, &server_snapshots_with_source, &current_databases);
 
     // Build set of server snapshot names for quick lookup
     let server_snapshot_names: Vec<String> = server_snapshots_with_source
         .iter()
-        .map(|(name, _)| name.clone())
+        .map(|s| s.name.clone())
         .collect();
 
     // Check for stale metadata (snapshots in metadata but not on server)
@@ -617,235 +1903,1580 @@ pub async fn verify_snapshots(groupId: String) -> ApiResponse<VerificationResult
         }
     }
 
-    // Check for orphaned snapshots (on server but not in metadata)
-    // Use actual source database from SQL Server instead of name prefix matching
-    let metadata_names: Vec<String> = metadata_snapshots
+    // Check for orphaned snapshots (on server but not in any group's metadata)
+    let orphaned =
+        find_orphaned_snapshots(group, &tracked_snapshots(&store, &groups), &server_snapshots_with_source);
+    let origins = server_snapshot_origins(&mut conn, &orphaned, snapshot_origin(&store).as_deref()).await;
+
+    // Files left on disk by failed drops; they take up space but don't affect the group's snapshots
+    let leftover_files = conn.find_leftover_snapshot_files(&profile.snapshot_path).await;
+
+    ApiResponse::success(VerificationResult {
+        verified: orphaned.is_empty() && stale.is_empty() && renamed_or_missing.is_empty(),
+        orphaned_snapshots: orphaned,
+        stale_metadata: stale,
+        renamed_or_missing,
+        leftover_files,
+        origins,
+    })
+}
+
+/// Snapshots recorded by every group, so a snapshot another group tracks of a shared database
+/// isn't taken for an orphan
+fn tracked_snapshots(store: &MetadataStore, groups: &[Group]) -> Vec<Snapshot> {
+    groups
+        .iter()
+        .flat_map(|g| store.get_snapshots(&g.id).unwrap_or_default())
+        .collect()
+}
+
+/// Server snapshots whose source database is in the group but that no snapshot in metadata recorded
+/// `metadata_snapshots` should be every group's (see tracked_snapshots), since groups can share databases
+/// Uses the actual source database from SQL Server instead of name prefix matching
+fn find_orphaned_snapshots(
+    group: &crate::models::Group,
+    metadata_snapshots: &[Snapshot],
+    server_snapshots_with_source: &[ServerSnapshot],
+) -> Vec<String> {
+    let metadata_names: Vec<&str> = metadata_snapshots
         .iter()
-        .flat_map(|s| s.database_snapshots.iter().map(|ds| ds.snapshot_name.clone()))
+        .flat_map(|s| s.database_snapshots.iter().map(|ds| ds.snapshot_name.as_str()))
         .collect();
 
-    let groups = store.get_groups().unwrap_or_default();
-    let group = groups.iter().find(|g| g.id == group_id);
+    server_snapshots_with_source
+        .iter()
+        .filter(|s| group.databases.contains(&s.source_database) && !metadata_names.contains(&s.name.as_str()))
+        .map(|s| s.name.clone())
+        .collect()
+}
 
-    if let Some(group) = group {
-        for (snapshot_name, source_db) in &server_snapshots_with_source {
-            // Check if this snapshot's source database is in our group
-            if group.databases.contains(source_db) && !metadata_names.contains(snapshot_name) {
-                orphaned.push(snapshot_name.clone());
-            }
+/// Snapshots whose stored source database no longer resolves on the server
+/// A snapshot still on the server reports its source's current name, so a mismatch there means a rename;
+/// otherwise the stored name must still be a current database
+fn find_renamed_or_missing(
+    metadata_snapshots: &[Snapshot],
+    server_snapshots_with_source: &[ServerSnapshot],
+    current_databases: &[String],
+) -> Vec<RenamedSource> {
+    let mut result = Vec::new();
+
+    for snapshot in metadata_snapshots {
+        for db_snapshot in snapshot.database_snapshots.iter().filter(|ds| ds.success) {
+            let server_source = server_snapshots_with_source
+                .iter()
+                .find(|s| s.name == db_snapshot.snapshot_name)
+                .map(|s| &s.source_database);
+
+            let current_source = match server_source {
+                Some(source) if source.eq_ignore_ascii_case(&db_snapshot.database) => continue,
+                Some(source) => Some(source.clone()),
+                None if current_databases
+                    .iter()
+                    .any(|d| d.eq_ignore_ascii_case(&db_snapshot.database)) =>
+                {
+                    continue
+                }
+                None => None,
+            };
+
+            result.push(RenamedSource {
+                snapshot_name: db_snapshot.snapshot_name.clone(),
+                database: db_snapshot.database.clone(),
+                current_source,
+            });
         }
     }
 
-    ApiResponse::success(VerificationResult {
-        verified: orphaned.is_empty() && stale.is_empty(),
-        orphaned_snapshots: orphaned,
-        stale_metadata: stale,
-    })
+    result
 }
 
 /// Cleanup an invalid/failed snapshot - drops any existing SQL Server snapshots and removes metadata
+/// While a UI password is set, `session_token` must come from check_password.
 #[tauri::command]
-pub async fn cleanup_snapshot(id: String) -> ApiResponse<CleanupResult> {
-    let snapshot_id = id;
-    let store = match MetadataStore::open() {
-        Ok(s) => s,
-        Err(e) => return ApiResponse::error(format!("Failed to open metadata store: {}", e)),
-    };
+pub async fn cleanup_snapshot(
+    app: tauri::AppHandle,
+    id: String,
+    session_token: Option<String>,
+) -> ApiResponse<CleanupResult> {
+    timed(async move {
+        if let Some(response) = require_auth(&app.state::<Sessions>(), session_token.as_deref()) {
+            return response;
+        }
 
-    // Find the snapshot
-    let groups = match store.get_groups() {
-        Ok(g) => g,
-        Err(e) => return ApiResponse::error(format!("Failed to get groups: {}", e)),
-    };
+        let snapshot_id = id;
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
 
-    let mut snapshot_to_cleanup: Option<Snapshot> = None;
-    let mut group_for_snapshot: Option<&crate::models::Group> = None;
-    for group in &groups {
-        if let Ok(snapshots) = store.get_snapshots(&group.id) {
-            if let Some(s) = snapshots.into_iter().find(|s| s.id == snapshot_id) {
-                snapshot_to_cleanup = Some(s);
-                group_for_snapshot = Some(group);
-                break;
+        // Find the snapshot
+        let groups = match store.get_groups() {
+            Ok(g) => g,
+            Err(e) => return ApiResponse::error_key(messages::GET_GROUPS_FAILED, &[&e]),
+        };
+
+        let mut snapshot_to_cleanup: Option<Snapshot> = None;
+        let mut group_for_snapshot: Option<&crate::models::Group> = None;
+        for group in &groups {
+            if let Ok(snapshots) = store.get_snapshots(&group.id) {
+                if let Some(s) = snapshots.into_iter().find(|s| s.id == snapshot_id) {
+                    snapshot_to_cleanup = Some(s);
+                    group_for_snapshot = Some(group);
+                    break;
+                }
             }
         }
-    }
 
-    let snapshot = match snapshot_to_cleanup {
-        Some(s) => s,
-        None => return ApiResponse::error(format!("Snapshot not found: {}", snapshot_id)),
-    };
+        let snapshot = match snapshot_to_cleanup {
+            Some(s) => s,
+            None => return ApiResponse::error_key(messages::SNAPSHOT_NOT_FOUND, &[&snapshot_id]),
+        };
 
-    let group = match group_for_snapshot {
-        Some(g) => g,
-        None => return ApiResponse::error(format!("Group not found for snapshot: {}", snapshot_id)),
-    };
+        let group = match group_for_snapshot {
+            Some(g) => g,
+            None => return ApiResponse::error(format!("Group not found for snapshot: {}", snapshot_id)),
+        };
 
-    // Get profile from metadata database using group's profile_id
-    let profile = match get_profile_for_group(&store, group) {
-        Ok(p) => p,
-        Err(e) => return ApiResponse::error(e),
-    };
+        // Only one operation at a time per group; the guard releases the lock on every return path
+        let group_locks = app.state::<GroupLocks>();
+        let _group_guard = match group_locks.try_lock(&group.id) {
+            Some(guard) => guard,
+            None => return group_busy(&group.name),
+        };
 
-    // Connect to SQL Server
-    let mut conn = match SqlServerConnection::connect(&profile).await {
-        Ok(c) => c,
-        Err(e) => return ApiResponse::error(format!("Failed to connect: {}", e)),
-    };
+        // Get profile from metadata database using group's profile_id
+        let profile = match get_profile_for_group(&store, group) {
+            Ok(p) => p,
+            Err(e) => return ApiResponse::error(e),
+        };
 
-    // Drop all snapshot databases (even if marked as failed - they might exist)
-    let mut dropped_count = 0;
-    for db_snapshot in &snapshot.database_snapshots {
+        // Connect to SQL Server
+        let mut conn = match SqlServerConnection::connect(&profile).await {
+            Ok(c) => c,
+            Err(e) => return ApiResponse::error_key(messages::CONNECT_FAILED, &[&e]),
+        };
+
+        // Drop all snapshot databases (even if marked as failed - they might exist)
         // Try to drop even if success is false - the snapshot might exist
-        if !db_snapshot.snapshot_name.is_empty() {
-            if let Ok(_) = conn.drop_snapshot(&db_snapshot.snapshot_name).await {
-                dropped_count += 1;
-                log::info!("Cleaned up snapshot database: {}", db_snapshot.snapshot_name);
+        let snapshot_names: Vec<String> = snapshot
+            .database_snapshots
+            .iter()
+            .filter(|ds| !ds.snapshot_name.is_empty())
+            .map(|ds| ds.snapshot_name.clone())
+            .collect();
+        let dropped_count = match conn.drop_snapshots(&snapshot_names).await {
+            Ok(failed) => {
+                for (name, error) in &failed {
+                    log::warn!("Failed to clean up snapshot database {}: {}", name, error);
+                }
+                snapshot_names.len() - failed.len()
             }
-        }
-    }
+            Err(e) => {
+                log::warn!("Failed to clean up snapshot databases: {}", e);
+                0
+            }
+        };
+        log::info!("Cleaned up {} snapshot database(s)", dropped_count);
 
-    // Remove from metadata
-    if let Err(e) = store.delete_snapshot(&snapshot_id) {
-        return ApiResponse::error(format!("Failed to delete snapshot metadata: {}", e));
-    }
+        // Remove from metadata
+        if let Err(e) = store.delete_snapshot(&snapshot_id) {
+            return ApiResponse::error(format!("Failed to delete snapshot metadata: {}", e));
+        }
 
-    // Log to history
-    let history_entry = HistoryEntry {
-        id: Uuid::new_v4().to_string(),
-        operation_type: "cleanup_snapshot".to_string(),
-        timestamp: Utc::now(),
-        user_name: Some(whoami::username_os().to_string_lossy().into_owned()),
-        details: Some(serde_json::json!({
-            "snapshotId": snapshot_id,
-            "displayName": snapshot.display_name,
-            "droppedDatabases": dropped_count
-        })),
-        results: None,
-    };
-    let _ = store.add_history(&history_entry);
+        // Log to history
+        let history_entry = HistoryEntry {
+            id: Uuid::new_v4().to_string(),
+            operation_type: "cleanup_snapshot".to_string(),
+            timestamp: Utc::now(),
+            user_name: Some(whoami::username_os().to_string_lossy().into_owned()),
+            details: Some(serde_json::json!({
+                "snapshotId": snapshot_id,
+                "displayName": snapshot.display_name,
+                "droppedDatabases": dropped_count
+            })),
+            results: None,
+            group_id: Some(snapshot.group_id.clone()),
+        };
+        let _ = store.add_history(&history_entry);
 
-    ApiResponse::success(CleanupResult {
-        success: true,
-        message: format!("Snapshot \"{}\" cleaned up successfully", snapshot.display_name),
-        dropped_databases: dropped_count,
+        ApiResponse::success(CleanupResult {
+            success: true,
+            message: format!("Snapshot \"{}\" cleaned up successfully", snapshot.display_name),
+            dropped_databases: dropped_count,
+        })
     })
+    .await
 }
 
-#[derive(serde::Serialize)]
-pub struct CleanupResult {
-    pub success: bool,
-    pub message: String,
-    #[serde(rename = "droppedDatabases")]
-    pub dropped_databases: usize,
-}
-
-#[derive(serde::Serialize)]
-pub struct RollbackResult {
-    pub success: bool,
-    #[serde(rename = "databasesRestored")]
-    pub databases_restored: usize,
-    #[serde(rename = "databasesFailed")]
-    pub databases_failed: usize,
-    pub results: Vec<OperationResult>,
+/// Index of the database's entry in a snapshot, as long as that entry failed and can be retried
+fn retryable_entry(snapshot: &Snapshot, database: &str) -> Result<usize, String> {
+    let index = snapshot
+        .database_snapshots
+        .iter()
+        .position(|ds| ds.database.eq_ignore_ascii_case(database))
+        .ok_or_else(|| {
+            format!(
+                "Database {} is not part of snapshot \"{}\"",
+                database, snapshot.display_name
+            )
+        })?;
+    if snapshot.database_snapshots[index].success {
+        return Err(format!(
+            "Database {} already has a snapshot in \"{}\"",
+            database, snapshot.display_name
+        ));
+    }
+    Ok(index)
 }
 
-/// Check for external snapshots that would block operations on a snapshot
+/// Re-attempt the snapshot of one database that failed within a group snapshot
+/// The snapshot's entry for that database is updated in place, so the rest of the group isn't re-snapshotted
+/// While a UI password is set, `session_token` must come from check_password.
 #[tauri::command]
-pub async fn check_external_snapshots(id: String) -> ApiResponse<ExternalSnapshotCheck> {
-    let snapshot_id = id;
-    let store = match MetadataStore::open() {
-        Ok(s) => s,
-        Err(e) => return ApiResponse::error(format!("Failed to open metadata store: {}", e)),
-    };
+#[allow(non_snake_case)]
+pub async fn retry_snapshot_database(
+    app: tauri::AppHandle,
+    snapshotId: String,
+    database: String,
+    session_token: Option<String>,
+) -> ApiResponse<Snapshot> {
+    timed(async move {
+        if let Some(response) = require_auth(&app.state::<Sessions>(), session_token.as_deref()) {
+            return response;
+        }
 
-    // Find the snapshot and its group
-    let groups = match store.get_groups() {
-        Ok(g) => g,
-        Err(e) => return ApiResponse::error(format!("Failed to get groups: {}", e)),
-    };
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
 
-    let mut target_snapshot: Option<Snapshot> = None;
-    let mut target_group: Option<&crate::models::Group> = None;
+        let groups = match store.get_groups() {
+            Ok(g) => g,
+            Err(e) => return ApiResponse::error_key(messages::GET_GROUPS_FAILED, &[&e]),
+        };
 
-    for group in &groups {
-        if let Ok(snapshots) = store.get_snapshots(&group.id) {
-            if let Some(s) = snapshots.into_iter().find(|s| s.id == snapshot_id) {
-                target_snapshot = Some(s);
-                target_group = Some(group);
-                break;
+        let mut found: Option<(Snapshot, &crate::models::Group)> = None;
+        for group in &groups {
+            if let Ok(snapshots) = store.get_snapshots(&group.id) {
+                if let Some(s) = snapshots.into_iter().find(|s| s.id == snapshotId) {
+                    found = Some((s, group));
+                    break;
+                }
             }
         }
-    }
 
-    let _snapshot = match target_snapshot {
-        Some(s) => s,
-        None => return ApiResponse::error(format!("Snapshot not found: {}", snapshot_id)),
-    };
+        let (mut snapshot, group) = match found {
+            Some(found) => found,
+            None => return ApiResponse::error_key(messages::SNAPSHOT_NOT_FOUND, &[&snapshotId]),
+        };
 
-    let group = target_group.unwrap();
+        let index = match retryable_entry(&snapshot, &database) {
+            Ok(i) => i,
+            Err(e) => return ApiResponse::error(e),
+        };
+        let protected = protected_databases(&store);
+        if let Some(e) = protected_database_error(&snapshot.database_snapshots[index].database, &protected) {
+            return ApiResponse::error(e);
+        }
 
-    // Get profile from metadata database using group's profile_id
-    let profile = match get_profile_for_group(&store, group) {
-        Ok(p) => p,
-        Err(e) => return ApiResponse::error(e),
-    };
+        // Only one operation at a time per group; the guard releases the lock on every return path
+        let group_locks = app.state::<GroupLocks>();
+        let _group_guard = match group_locks.try_lock(&group.id) {
+            Some(guard) => guard,
+            None => return group_busy(&group.name),
+        };
 
-    // Connect to SQL Server
-    let mut conn = match SqlServerConnection::connect(&profile).await {
-        Ok(c) => c,
-        Err(e) => return ApiResponse::error(format!("Failed to connect: {}", e)),
-    };
+        let profile = match get_profile_for_group(&store, group) {
+            Ok(p) => p,
+            Err(e) => return ApiResponse::error(e),
+        };
 
-    // Get snapshots with their source database
-    let server_snapshots = match conn.get_snapshots_with_source().await {
-        Ok(s) => s,
-        Err(e) => return ApiResponse::error(format!("Failed to check snapshots: {}", e)),
-    };
+        if profile.snapshot_path.trim().is_empty() {
+            return ApiResponse::error("Snapshot path is not configured for this profile".to_string());
+        }
 
-    // Get all our tracked snapshot names for this group
-    let group_snapshots = store.get_snapshots(&group.id).unwrap_or_default();
-    let our_snapshot_names: Vec<String> = group_snapshots
-        .iter()
-        .flat_map(|s| s.database_snapshots.iter().map(|ds| ds.snapshot_name.clone()))
-        .collect();
+        let mut conn = match SqlServerConnection::connect(&profile).await {
+            Ok(c) => c,
+            Err(e) => return ApiResponse::error_key(messages::CONNECT_FAILED, &[&e]),
+        };
 
-    // Find external snapshots for our databases
-    let external_snapshots: Vec<String> = server_snapshots
-        .iter()
-        .filter(|(name, source_db)| {
-            !our_snapshot_names.contains(name) && group.databases.contains(source_db)
-        })
-        .map(|(name, _)| name.clone())
-        .collect();
+        let on_name_collision = store
+            .get_settings()
+            .map(|s| s.preferences.on_name_collision)
+            .unwrap_or_default();
+
+        // The failed attempt may have left the original name taken, so pick a free one the same way create does
+        let entry_database = snapshot.database_snapshots[index].database.clone();
+        let base_name = snapshot.database_snapshots[index].snapshot_name.clone();
+        let outcome = match free_snapshot_name(&mut conn, &base_name, on_name_collision).await {
+            Ok(snapshot_name) => conn
+                .create_snapshot(
+                    &entry_database,
+                    &snapshot_name,
+                    &profile.snapshot_path,
+                    snapshot_origin(&store).as_deref(),
+                )
+                .await
+                .map(|server_created_at| (snapshot_name, server_created_at))
+                .map_err(|e| e.to_string()),
+            Err(e) => Err(e),
+        };
 
-    // Generate DROP commands for the external snapshots
-    let drop_commands: Vec<String> = external_snapshots
-        .iter()
-        .map(|name| format!("DROP DATABASE [{}];", name))
-        .collect();
+        let entry = &mut snapshot.database_snapshots[index];
+        let result = match &outcome {
+            Ok((snapshot_name, server_created_at)) => {
+                entry.snapshot_name = snapshot_name.clone();
+                entry.success = true;
+                entry.error = None;
+                entry.server_created_at = *server_created_at;
+                OperationResult {
+                    database: entry_database.clone(),
+                    success: true,
+                    ..Default::default()
+                }
+            }
+            Err(e) => {
+                entry.error = Some(e.clone());
+                OperationResult {
+                    database: entry_database.clone(),
+                    success: false,
+                    error: Some(e.clone()),
+                    ..Default::default()
+                }
+            }
+        };
+
+        if let Err(e) = store.update_snapshot_databases(&snapshot.id, &snapshot.database_snapshots) {
+            return ApiResponse::error(format!("Failed to update snapshot metadata: {}", e));
+        }
+
+        let history_entry = HistoryEntry {
+            id: Uuid::new_v4().to_string(),
+            operation_type: "retry_snapshot_database".to_string(),
+            timestamp: Utc::now(),
+            user_name: Some(whoami::username_os().to_string_lossy().into_owned()),
+            details: Some(serde_json::json!({
+                "groupId": group.id,
+                "groupName": group.name,
+                "snapshotId": snapshot.id,
+                "displayName": snapshot.display_name,
+                "database": entry_database
+            })),
+            results: Some(vec![result]),
+            group_id: Some(group.id.clone()),
+        };
+        let _ = store.add_history(&history_entry);
 
-    ApiResponse::success(ExternalSnapshotCheck {
-        has_external_snapshots: !external_snapshots.is_empty(),
-        external_snapshots,
-        drop_commands,
+        match outcome {
+            Ok(_) => ApiResponse::success(snapshot),
+            Err(e) => ApiResponse::error(format!("Retry failed for {}: {}", entry_database, e)),
+        }
     })
+    .await
 }
 
-#[derive(serde::Serialize)]
-pub struct ExternalSnapshotCheck {
-    #[serde(rename = "hasExternalSnapshots")]
-    pub has_external_snapshots: bool,
-    #[serde(rename = "externalSnapshots")]
-    pub external_snapshots: Vec<String>,
-    #[serde(rename = "dropCommands")]
-    pub drop_commands: Vec<String>,
+/// Drop every orphaned snapshot of a group (on the server for one of its databases, but not in any
+/// group's metadata)
+/// When the require_confirmation setting is on, `confirmed` must be true.
+/// While a UI password is set, `session_token` must come from check_password.
+#[tauri::command]
+#[allow(non_snake_case)]
+pub async fn drop_orphaned_snapshots(
+    app: tauri::AppHandle,
+    groupId: String,
+    confirmed: Option<bool>,
+    session_token: Option<String>,
+) -> ApiResponse<DropOrphanedResult> {
+    timed(async move {
+        if let Some(response) = require_auth(&app.state::<Sessions>(), session_token.as_deref()) {
+            return response;
+        }
+
+        let group_id = groupId;
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
+
+        if let Some(response) = super::confirmation_error(&store, confirmed, "Dropping orphaned snapshots") {
+            return response;
+        }
+
+        let groups = match store.get_groups() {
+            Ok(g) => g,
+            Err(e) => return ApiResponse::error_key(messages::GET_GROUPS_FAILED, &[&e]),
+        };
+
+        let group = match groups.iter().find(|g| g.id == group_id) {
+            Some(g) => g,
+            None => return ApiResponse::error_key(messages::GROUP_NOT_FOUND, &[&group_id]),
+        };
+
+        let group_locks = app.state::<GroupLocks>();
+        let _group_guard = match group_locks.try_lock(&group.id) {
+            Some(guard) => guard,
+            None => return group_busy(&group.name),
+        };
+
+        let profile = match get_profile_for_group(&store, group) {
+            Ok(p) => p,
+            Err(e) => return ApiResponse::error(e),
+        };
+
+        let mut conn = match SqlServerConnection::connect(&profile).await {
+            Ok(c) => c,
+            Err(e) => return ApiResponse::error_key(messages::CONNECT_FAILED, &[&e]),
+        };
+
+        // Recompute orphans now rather than trusting an earlier verify
+        let server_snapshots_with_source = match conn.get_snapshots_with_source().await {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::GET_SNAPSHOTS_FAILED, &[&e]),
+        };
+        let orphaned =
+            find_orphaned_snapshots(group, &tracked_snapshots(&store, &groups), &server_snapshots_with_source);
+
+        if orphaned.is_empty() {
+            return ApiResponse::success(DropOrphanedResult::default());
+        }
+
+        let failed: Vec<DropFailure> = match conn.drop_snapshots(&orphaned).await {
+            Ok(failed) => failed
+                .into_iter()
+                .map(|(snapshot_name, error)| DropFailure { snapshot_name, error })
+                .collect(),
+            Err(e) => return ApiResponse::error(format!("Failed to drop snapshots: {}", e)),
+        };
+        let dropped: Vec<String> = orphaned
+            .into_iter()
+            .filter(|name| !failed.iter().any(|f| &f.snapshot_name == name))
+            .collect();
+
+        let history_entry = HistoryEntry {
+            id: Uuid::new_v4().to_string(),
+            operation_type: "drop_orphaned_snapshots".to_string(),
+            timestamp: Utc::now(),
+            user_name: Some(whoami::username_os().to_string_lossy().into_owned()),
+            details: Some(serde_json::json!({
+                "groupName": group.name,
+                "dropped": dropped,
+                "failed": failed
+            })),
+            results: None,
+            group_id: Some(group.id.clone()),
+        };
+        let _ = store.add_history(&history_entry);
+
+        ApiResponse::success(DropOrphanedResult { dropped, failed })
+    })
+    .await
 }
 
-#[derive(serde::Serialize)]
-pub struct VerificationResult {
+/// How reconciling changes one snapshot's metadata
+#[derive(Debug)]
+enum Reconciliation {
+    /// Every database snapshot it recorded is still on the server
+    Keep,
+    /// None of its database snapshots are left, so the snapshot is gone
+    Remove,
+    /// Some database snapshots are gone; these are the corrected entries
+    Update(Vec<DatabaseSnapshot>),
+}
+
+/// Compare a snapshot's successful database entries against the snapshots on the server
+fn reconcile_snapshot(snapshot: &Snapshot, server_snapshot_names: &[&str]) -> Reconciliation {
+    let is_missing =
+        |ds: &DatabaseSnapshot| ds.success && !server_snapshot_names.contains(&ds.snapshot_name.as_str());
+
+    let recorded = snapshot.database_snapshots.iter().filter(|ds| ds.success).count();
+    let missing = snapshot.database_snapshots.iter().filter(|ds| is_missing(ds)).count();
+
+    if missing == 0 {
+        Reconciliation::Keep
+    } else if missing == recorded {
+        Reconciliation::Remove
+    } else {
+        Reconciliation::Update(
+            snapshot
+                .database_snapshots
+                .iter()
+                .map(|ds| {
+                    if is_missing(ds) {
+                        DatabaseSnapshot {
+                            success: false,
+                            error: Some("Snapshot database no longer exists on the server".to_string()),
+                            ..ds.clone()
+                        }
+                    } else {
+                        ds.clone()
+                    }
+                })
+                .collect(),
+        )
+    }
+}
+
+/// Make a group's snapshot metadata match the server: remove snapshots whose databases are all gone,
+/// mark partially missing ones, and report orphans that are still on the server
+/// While a UI password is set, `session_token` must come from check_password.
+#[tauri::command]
+#[allow(non_snake_case)]
+pub async fn reconcile_metadata(
+    app: tauri::AppHandle,
+    groupId: String,
+    session_token: Option<String>,
+) -> ApiResponse<ReconcileResult> {
+    timed(async move {
+        if let Some(response) = require_auth(&app.state::<Sessions>(), session_token.as_deref()) {
+            return response;
+        }
+
+        let group_id = groupId;
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
+
+        let groups = match store.get_groups() {
+            Ok(g) => g,
+            Err(e) => return ApiResponse::error_key(messages::GET_GROUPS_FAILED, &[&e]),
+        };
+
+        let group = match groups.iter().find(|g| g.id == group_id) {
+            Some(g) => g,
+            None => return ApiResponse::error_key(messages::GROUP_NOT_FOUND, &[&group_id]),
+        };
+
+        let group_locks = app.state::<GroupLocks>();
+        let _group_guard = match group_locks.try_lock(&group.id) {
+            Some(guard) => guard,
+            None => return group_busy(&group.name),
+        };
+
+        let profile = match get_profile_for_group(&store, group) {
+            Ok(p) => p,
+            Err(e) => return ApiResponse::error(e),
+        };
+
+        let mut conn = match SqlServerConnection::connect(&profile).await {
+            Ok(c) => c,
+            Err(e) => return ApiResponse::error_key(messages::CONNECT_FAILED, &[&e]),
+        };
+
+        let server_snapshots_with_source = match conn.get_snapshots_with_source().await {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::GET_SNAPSHOTS_FAILED, &[&e]),
+        };
+        let server_snapshot_names: Vec<&str> = server_snapshots_with_source
+            .iter()
+            .map(|s| s.name.as_str())
+            .collect();
+
+        let metadata_snapshots = match store.get_snapshots(&group_id) {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::GET_SNAPSHOTS_FAILED, &[&e]),
+        };
+
+        let mut removed_stale = Vec::new();
+        let mut updated = Vec::new();
+        for snapshot in &metadata_snapshots {
+            match reconcile_snapshot(snapshot, &server_snapshot_names) {
+                Reconciliation::Keep => {}
+                Reconciliation::Remove => {
+                    if let Err(e) = store.delete_snapshot(&snapshot.id) {
+                        return ApiResponse::error(format!("Failed to delete snapshot metadata: {}", e));
+                    }
+                    removed_stale.push(snapshot.display_name.clone());
+                }
+                Reconciliation::Update(database_snapshots) => {
+                    if let Err(e) = store.update_snapshot_databases(&snapshot.id, &database_snapshots) {
+                        return ApiResponse::error(format!("Failed to update snapshot metadata: {}", e));
+                    }
+                    updated.push(snapshot.display_name.clone());
+                }
+            }
+        }
+
+        // Removed or updated snapshots don't change which server snapshots metadata knows about
+        let remaining_orphans =
+            find_orphaned_snapshots(group, &tracked_snapshots(&store, &groups), &server_snapshots_with_source);
+
+        if !removed_stale.is_empty() || !updated.is_empty() {
+            let history_entry = HistoryEntry {
+                id: Uuid::new_v4().to_string(),
+                operation_type: "reconcile_metadata".to_string(),
+                timestamp: Utc::now(),
+                user_name: Some(whoami::username_os().to_string_lossy().into_owned()),
+                details: Some(serde_json::json!({
+                    "groupName": group.name,
+                    "removedStale": removed_stale,
+                    "updated": updated,
+                    "remainingOrphans": remaining_orphans
+                })),
+                results: None,
+                group_id: Some(group.id.clone()),
+            };
+            let _ = store.add_history(&history_entry);
+        }
+
+        ApiResponse::success(ReconcileResult {
+            removed_stale,
+            updated,
+            remaining_orphans,
+        })
+    })
+    .await
+}
+
+#[derive(serde::Serialize)]
+pub struct ReconcileResult {
+    /// Display names of snapshots removed because none of their databases remain
+    #[serde(rename = "removedStale")]
+    pub removed_stale: Vec<String>,
+    /// Display names of snapshots with some databases marked missing
+    pub updated: Vec<String>,
+    /// Server snapshots for the group's databases that metadata doesn't know about
+    #[serde(rename = "remainingOrphans")]
+    pub remaining_orphans: Vec<String>,
+}
+
+#[derive(Default, serde::Serialize)]
+pub struct DropOrphanedResult {
+    pub dropped: Vec<String>,
+    pub failed: Vec<DropFailure>,
+}
+
+#[derive(serde::Serialize)]
+pub struct DropFailure {
+    #[serde(rename = "snapshotName")]
+    pub snapshot_name: String,
+    pub error: String,
+}
+
+#[derive(serde::Serialize)]
+pub struct CleanupResult {
+    pub success: bool,
+    pub message: String,
+    #[serde(rename = "droppedDatabases")]
+    pub dropped_databases: usize,
+}
+
+#[derive(serde::Serialize)]
+pub struct RollbackResult {
+    pub success: bool,
+    #[serde(rename = "databasesRestored")]
+    pub databases_restored: usize,
+    #[serde(rename = "databasesFailed")]
+    pub databases_failed: usize,
+    pub results: Vec<OperationResult>,
+}
+
+/// One group's outcome in rollback_latest
+#[derive(serde::Serialize)]
+pub struct GroupRollbackResult {
+    #[serde(rename = "groupId")]
+    pub group_id: String,
+    /// The snapshot rolled back to, when the group had one
+    #[serde(rename = "snapshotId")]
+    pub snapshot_id: Option<String>,
+    pub success: bool,
+    pub error: Option<String>,
+    pub rollback: Option<RollbackResult>,
+}
+
+impl GroupRollbackResult {
+    fn failed(group_id: String, error: String) -> Self {
+        Self {
+            group_id,
+            snapshot_id: None,
+            success: false,
+            error: Some(error),
+            rollback: None,
+        }
+    }
+}
+
+/// Check for external snapshots that would block operations on a snapshot
+#[tauri::command]
+pub async fn check_external_snapshots(id: String) -> ApiResponse<ExternalSnapshotCheck> {
+    timed(async move {
+        let snapshot_id = id;
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
+
+        // Find the snapshot and its group
+        let groups = match store.get_groups() {
+            Ok(g) => g,
+            Err(e) => return ApiResponse::error_key(messages::GET_GROUPS_FAILED, &[&e]),
+        };
+
+        let mut target_snapshot: Option<Snapshot> = None;
+        let mut target_group: Option<&crate::models::Group> = None;
+
+        for group in &groups {
+            if let Ok(snapshots) = store.get_snapshots(&group.id) {
+                if let Some(s) = snapshots.into_iter().find(|s| s.id == snapshot_id) {
+                    target_snapshot = Some(s);
+                    target_group = Some(group);
+                    break;
+                }
+            }
+        }
+
+        let snapshot = match target_snapshot {
+            Some(s) => s,
+            None => return ApiResponse::error_key(messages::SNAPSHOT_NOT_FOUND, &[&snapshot_id]),
+        };
+
+        let group = target_group.unwrap();
+
+        // Get profile from metadata database using group's profile_id
+        let profile = match get_profile_for_group(&store, group) {
+            Ok(p) => p,
+            Err(e) => return ApiResponse::error(e),
+        };
+
+        // Connect to SQL Server
+        let mut conn = match SqlServerConnection::connect(&profile).await {
+            Ok(c) => c,
+            Err(e) => return ApiResponse::error_key(messages::CONNECT_FAILED, &[&e]),
+        };
+
+        // Get snapshots with their source database
+        let server_snapshots = match conn.get_snapshots_with_source().await {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error(format!("Failed to check snapshots: {}", e)),
+        };
+
+        // Databases the rollback would restore
+        let restored: Vec<String> = snapshot
+            .database_snapshots
+            .iter()
+            .filter(|ds| ds.success)
+            .map(|ds| ds.database.clone())
+            .collect();
+
+        // Find external snapshots for our databases; other groups' snapshots of shared databases are
+        // reported by preview_rollback instead
+        let group_snapshots = store.get_snapshots(&group.id).unwrap_or_default();
+        let cross_group = cross_group_snapshots(&store, group, &groups, &restored);
+        let external_snapshots = external_snapshot_names(&server_snapshots, group, &group_snapshots, &cross_group);
+
+        // Generate DROP commands for the external snapshots
+        let drop_commands: Vec<String> = external_snapshots
+            .iter()
+            .map(|name| format!("DROP DATABASE {};", quote_ident(name)))
+            .collect();
+
+        let (recovery_models, warnings) = recovery_models(&mut conn, &restored).await;
+        let origins = server_snapshot_origins(&mut conn, &external_snapshots, snapshot_origin(&store).as_deref()).await;
+
+        let mut response = ApiResponse::success(ExternalSnapshotCheck {
+            has_external_snapshots: !external_snapshots.is_empty(),
+            external_snapshots,
+            drop_commands,
+            recovery_models,
+            origins,
+        });
+        response.messages.warning = warnings;
+        response
+    })
+    .await
+}
+
+/// Sentence on where external snapshots came from: their recorded origins, or the generic guess
+fn external_origin_note(external_snapshots: &[String], origins: &HashMap<String, String>) -> String {
+    let known: Vec<String> = external_snapshots
+        .iter()
+        .filter_map(|name| origins.get(name).map(|origin| format!("{} by {}", name, origin)))
+        .collect();
+    if known.is_empty() {
+        "These may have been created by another instance of SQL Parrot (npm, Docker, or exe).".to_string()
+    } else {
+        format!("Created: {}.", known.join("; "))
+    }
+}
+
+/// Token and details returned by acknowledge_external_snapshots
+#[derive(serde::Serialize)]
+pub struct ExternalSnapshotAcknowledgement {
+    #[serde(rename = "overrideToken")]
+    pub override_token: String,
+    #[serde(flatten)]
+    pub acknowledgement: Acknowledgement,
+}
+
+/// Acknowledge the external snapshots blocking a rollback to this snapshot and get a short-lived
+/// token for rollback_snapshot's `override_token`. The token works once, for this snapshot and this
+/// exact set of external snapshots; the acknowledgement is recorded in history.
+#[tauri::command]
+pub async fn acknowledge_external_snapshots(id: String) -> ApiResponse<ExternalSnapshotAcknowledgement> {
+    timed(async move {
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
+
+        let groups = match store.get_groups() {
+            Ok(g) => g,
+            Err(e) => return ApiResponse::error_key(messages::GET_GROUPS_FAILED, &[&e]),
+        };
+
+        let found = groups.iter().find_map(|group| {
+            let group_snapshots = store.get_snapshots(&group.id).ok()?;
+            let snapshot = group_snapshots.iter().find(|s| s.id == id)?.clone();
+            Some((group, snapshot, group_snapshots))
+        });
+        let (group, snapshot, group_snapshots) = match found {
+            Some(found) => found,
+            None => return ApiResponse::error_key(messages::SNAPSHOT_NOT_FOUND, &[&id]),
+        };
+
+        let profile = match get_profile_for_group(&store, group) {
+            Ok(p) => p,
+            Err(e) => return ApiResponse::error(e),
+        };
+
+        let mut conn = match SqlServerConnection::connect(&profile).await {
+            Ok(c) => c,
+            Err(e) => return ApiResponse::error_key(messages::CONNECT_FAILED, &[&e]),
+        };
+
+        let server_snapshots = match conn.get_snapshots_with_source().await {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error(format!("Failed to check snapshots: {}", e)),
+        };
+
+        let restored: Vec<String> = snapshot
+            .database_snapshots
+            .iter()
+            .filter(|ds| ds.success)
+            .map(|ds| ds.database.clone())
+            .collect();
+        let cross_group = cross_group_snapshots(&store, group, &groups, &restored);
+        let external_snapshots = external_snapshot_names(&server_snapshots, group, &group_snapshots, &cross_group);
+        if external_snapshots.is_empty() {
+            return ApiResponse::error("No external snapshots block a rollback to this snapshot".to_string());
+        }
+
+        let user_name = whoami::username_os().to_string_lossy().into_owned();
+        let (override_token, acknowledgement) =
+            ExternalOverrides::shared().issue(&snapshot.id, &external_snapshots, &user_name, Utc::now());
+
+        let history_entry = HistoryEntry {
+            id: Uuid::new_v4().to_string(),
+            operation_type: "acknowledge_external_snapshots".to_string(),
+            timestamp: acknowledgement.acknowledged_at,
+            user_name: Some(user_name),
+            details: Some(serde_json::json!({
+                "groupId": group.id,
+                "groupName": group.name,
+                "snapshotId": snapshot.id,
+                "displayName": snapshot.display_name,
+                "externalSnapshots": acknowledgement.external_snapshots,
+                "expiresAt": acknowledgement.expires_at
+            })),
+            results: None,
+            group_id: Some(group.id.clone()),
+        };
+        let _ = store.add_history(&history_entry);
+
+        ApiResponse::success(ExternalSnapshotAcknowledgement {
+            override_token,
+            acknowledgement,
+        })
+    })
+    .await
+}
+
+/// List every database snapshot on the active profile's server with its source database,
+/// including ones SQL Parrot didn't create
+#[tauri::command]
+pub async fn list_server_snapshots() -> ApiResponse<Vec<ServerSnapshot>> {
+    timed(async move {
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
+
+        let profile = match get_active_connection_profile(&store) {
+            Ok(p) => p,
+            Err(e) => return ApiResponse::error(e),
+        };
+
+        match SqlServerConnection::connect(&profile).await {
+            Ok(mut conn) => match conn.get_snapshots_with_source().await {
+                Ok(snapshots) => ApiResponse::success(snapshots),
+                Err(e) => ApiResponse::error_key(messages::GET_SNAPSHOTS_FAILED, &[&e]),
+            },
+            Err(e) => ApiResponse::error_key(messages::CONNECT_FAILED, &[&e]),
+        }
+    })
+    .await
+}
+
+/// Outcome of drop_all_server_snapshots
+#[derive(serde::Serialize)]
+pub struct DropAllServerSnapshotsResult {
+    /// One entry per server snapshot; `database` holds the snapshot's name
+    pub results: Vec<OperationResult>,
+    /// Display names of snapshots whose metadata was removed or marked partially missing
+    #[serde(rename = "updatedMetadata")]
+    pub updated_metadata: Vec<String>,
+}
+
+/// Drop every database snapshot on the active profile's server, including ones SQL Parrot didn't
+/// create, then bring the metadata of snapshots that lost databases in line with the server.
+/// Always needs `confirm: true`, whatever require_confirmation says.
+/// While a UI password is set, `session_token` must come from check_password.
+#[tauri::command]
+pub async fn drop_all_server_snapshots(
+    app: tauri::AppHandle,
+    confirm: bool,
+    session_token: Option<String>,
+) -> ApiResponse<DropAllServerSnapshotsResult> {
+    timed(async move {
+        if let Some(response) = require_auth(&app.state::<Sessions>(), session_token.as_deref()) {
+            return response;
+        }
+        if !confirm {
+            return ApiResponse::error_with_code(
+                super::settings::CONFIRMATION_REQUIRED,
+                messages::text(messages::CONFIRMATION_REQUIRED, &[&"Dropping every snapshot on the server"]),
+            );
+        }
+
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
+        let profile = match get_active_connection_profile(&store) {
+            Ok(p) => p,
+            Err(e) => return ApiResponse::error(e),
+        };
+        let mut conn = match SqlServerConnection::connect(&profile).await {
+            Ok(c) => c,
+            Err(e) => return ApiResponse::error_key(messages::CONNECT_FAILED, &[&e]),
+        };
+        let server_snapshots = match conn.get_snapshots_with_source().await {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::GET_SNAPSHOTS_FAILED, &[&e]),
+        };
+
+        let mut results = Vec::new();
+        for server_snapshot in &server_snapshots {
+            log::info!("Dropping snapshot '{}' of '{}'", server_snapshot.name, server_snapshot.source_database);
+            results.push(match conn.drop_snapshot(&server_snapshot.name).await {
+                Ok(()) => OperationResult {
+                    database: server_snapshot.name.clone(),
+                    success: true,
+                    ..Default::default()
+                },
+                Err(e) => failed_result(&server_snapshot.name, format!("Drop failed: {}", e), &e),
+            });
+        }
+
+        // Snapshots that failed to drop are still on the server; only metadata touching a dropped one changes
+        let remaining: Vec<&str> = results.iter().filter(|r| !r.success).map(|r| r.database.as_str()).collect();
+        let dropped: HashSet<&str> = results.iter().filter(|r| r.success).map(|r| r.database.as_str()).collect();
+        let groups = store.get_groups().unwrap_or_default();
+        let now = Utc::now();
+        let user_name = whoami::username_os().to_string_lossy().into_owned();
+        let mut updated_metadata = Vec::new();
+        // Each group whose metadata changed gets its own entry, as if it had been reconciled
+        let mut history = Vec::new();
+        for group in &groups {
+            let mut removed_stale = Vec::new();
+            let mut updated = Vec::new();
+            for snapshot in store.get_snapshots(&group.id).unwrap_or_default() {
+                if !snapshot.database_snapshots.iter().any(|ds| dropped.contains(ds.snapshot_name.as_str())) {
+                    continue;
+                }
+                let saved = match reconcile_snapshot(&snapshot, &remaining) {
+                    Reconciliation::Keep => Ok(()),
+                    Reconciliation::Remove => store
+                        .delete_snapshot(&snapshot.id)
+                        .map(|_| removed_stale.push(snapshot.display_name.clone())),
+                    Reconciliation::Update(database_snapshots) => store
+                        .update_snapshot_databases(&snapshot.id, &database_snapshots)
+                        .map(|_| updated.push(snapshot.display_name.clone())),
+                };
+                match saved {
+                    Ok(()) => updated_metadata.push(snapshot.display_name.clone()),
+                    Err(e) => log::warn!("Failed to update metadata of snapshot {}: {}", snapshot.id, e),
+                }
+            }
+            if !removed_stale.is_empty() || !updated.is_empty() {
+                history.push(HistoryEntry {
+                    id: Uuid::new_v4().to_string(),
+                    operation_type: "reconcile_metadata".to_string(),
+                    timestamp: now,
+                    user_name: Some(user_name.clone()),
+                    details: Some(serde_json::json!({
+                        "groupName": group.name,
+                        "removedStale": removed_stale,
+                        "updated": updated,
+                        "cause": "drop_all_server_snapshots"
+                    })),
+                    results: None,
+                    group_id: Some(group.id.clone()),
+                });
+            }
+        }
+
+        let failed = remaining.len();
+        history.push(HistoryEntry {
+            id: Uuid::new_v4().to_string(),
+            operation_type: "drop_all_server_snapshots".to_string(),
+            timestamp: now,
+            user_name: Some(user_name),
+            details: Some(serde_json::json!({
+                "profileName": profile.name,
+                "dropped": dropped.len(),
+                "failed": failed,
+                "updatedMetadata": updated_metadata
+            })),
+            results: Some(results.clone()),
+            group_id: None,
+        });
+        let _ = store.add_history_batch(&history);
+
+        let result = DropAllServerSnapshotsResult {
+            results,
+            updated_metadata,
+        };
+        if failed == 0 {
+            ApiResponse::success(result)
+        } else {
+            ApiResponse::error_with_data(format!("{} snapshot(s) could not be dropped", failed), result)
+        }
+    })
+    .await
+}
+
+/// Whether a file sits directly in the snapshot directory
+/// Separators and case are ignored so Windows and Linux style paths compare alike
+fn is_in_snapshot_path(physical_name: &str, snapshot_path: &str) -> bool {
+    let normalize = |path: &str| path.replace('\\', "/").to_lowercase();
+    let directory = normalize(snapshot_path.trim_end_matches(['/', '\\']));
+    normalize(physical_name)
+        .rsplit_once('/')
+        .is_some_and(|(parent, _)| parent == directory)
+}
+
+/// Physical files of each database in a snapshot, for tracking down disk usage and stray .ss files
+#[tauri::command]
+#[allow(non_snake_case)]
+pub async fn get_snapshot_files(snapshotId: String) -> ApiResponse<SnapshotFilesResult> {
+    timed(async move {
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
+
+        let groups = match store.get_groups() {
+            Ok(g) => g,
+            Err(e) => return ApiResponse::error_key(messages::GET_GROUPS_FAILED, &[&e]),
+        };
+
+        let found = groups.iter().find_map(|group| {
+            store
+                .get_snapshots(&group.id)
+                .ok()?
+                .into_iter()
+                .find(|s| s.id == snapshotId)
+                .map(|snapshot| (group, snapshot))
+        });
+        let (group, snapshot) = match found {
+            Some(found) => found,
+            None => return ApiResponse::error_key(messages::SNAPSHOT_NOT_FOUND, &[&snapshotId]),
+        };
+
+        let profile = match get_profile_for_group(&store, group) {
+            Ok(p) => p,
+            Err(e) => return ApiResponse::error(e),
+        };
+
+        let mut conn = match SqlServerConnection::connect(&profile).await {
+            Ok(c) => c,
+            Err(e) => return ApiResponse::error_key(messages::CONNECT_FAILED, &[&e]),
+        };
+
+        let mut databases = Vec::new();
+        for db_snapshot in snapshot.database_snapshots.iter().filter(|ds| ds.success) {
+            let (files, error) = match conn.get_snapshot_files(&db_snapshot.snapshot_name).await {
+                Ok(files) => (files, None),
+                Err(e) => (Vec::new(), Some(e.to_string())),
+            };
+            databases.push(DatabaseSnapshotFiles {
+                database: db_snapshot.database.clone(),
+                snapshot_name: db_snapshot.snapshot_name.clone(),
+                exists: !files.is_empty(),
+                in_snapshot_path: files
+                    .iter()
+                    .all(|f| is_in_snapshot_path(&f.physical_name, &profile.snapshot_path)),
+                files,
+                error,
+            });
+        }
+
+        ApiResponse::success(SnapshotFilesResult {
+            snapshot_id: snapshot.id,
+            snapshot_path: profile.snapshot_path,
+            databases,
+        })
+    })
+    .await
+}
+
+#[derive(serde::Serialize)]
+pub struct SnapshotFilesResult {
+    #[serde(rename = "snapshotId")]
+    pub snapshot_id: String,
+    /// The profile's snapshot path the files are compared against
+    #[serde(rename = "snapshotPath")]
+    pub snapshot_path: String,
+    pub databases: Vec<DatabaseSnapshotFiles>,
+}
+
+#[derive(serde::Serialize)]
+pub struct DatabaseSnapshotFiles {
+    pub database: String,
+    #[serde(rename = "snapshotName")]
+    pub snapshot_name: String,
+    /// False when the snapshot database is no longer on the server (or couldn't be queried)
+    pub exists: bool,
+    /// False when a file lies outside the profile's snapshot path, e.g. a snapshot created by
+    /// another instance configured with a different path
+    #[serde(rename = "inSnapshotPath")]
+    pub in_snapshot_path: bool,
+    pub files: Vec<SnapshotFile>,
+    pub error: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+pub struct ExternalSnapshotCheck {
+    #[serde(rename = "hasExternalSnapshots")]
+    pub has_external_snapshots: bool,
+    #[serde(rename = "externalSnapshots")]
+    pub external_snapshots: Vec<String>,
+    #[serde(rename = "dropCommands")]
+    pub drop_commands: Vec<String>,
+    /// Recovery model of each database the rollback would restore
+    #[serde(rename = "recoveryModels")]
+    pub recovery_models: Vec<RecoveryModelInfo>,
+    /// Which install created each external snapshot, where it recorded one
+    pub origins: HashMap<String, String>,
+}
+
+#[derive(serde::Serialize)]
+pub struct RecoveryModelInfo {
+    pub database: String,
+    #[serde(rename = "recoveryModel")]
+    pub recovery_model: String,
+}
+
+#[derive(serde::Serialize)]
+pub struct VerificationResult {
     pub verified: bool,
     #[serde(rename = "orphanedSnapshots")]
     pub orphaned_snapshots: Vec<String>,
     #[serde(rename = "staleMetadata")]
     pub stale_metadata: Vec<String>,
+    #[serde(rename = "renamedOrMissing")]
+    pub renamed_or_missing: Vec<RenamedSource>,
+    /// Snapshot files in the profile's snapshot path that no database is using
+    #[serde(rename = "leftoverFiles")]
+    pub leftover_files: Vec<String>,
+    /// Which install created each orphaned snapshot, where it recorded one
+    pub origins: HashMap<String, String>,
+}
+
+/// A snapshot whose stored source database was renamed or dropped on the server
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct RenamedSource {
+    #[serde(rename = "snapshotName")]
+    pub snapshot_name: String,
+    pub database: String,
+    /// The source's current name when the snapshot still exists on the server
+    #[serde(rename = "currentSource")]
+    pub current_source: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server_snapshot(name: &str, source_database: &str) -> ServerSnapshot {
+        ServerSnapshot {
+            name: name.to_string(),
+            source_database: source_database.to_string(),
+            create_date: None,
+        }
+    }
+
+    fn db_snapshot(database: &str, snapshot_name: &str, success: bool) -> DatabaseSnapshot {
+        DatabaseSnapshot {
+            database: database.to_string(),
+            snapshot_name: snapshot_name.to_string(),
+            success,
+            error: None,
+            server_created_at: None,
+        }
+    }
+
+    /// A manual snapshot of group g1, taken now
+    fn snapshot(id: &str, sequence: u32, database_snapshots: Vec<DatabaseSnapshot>) -> Snapshot {
+        Snapshot {
+            id: id.to_string(),
+            group_id: "g1".to_string(),
+            display_name: format!("Snapshot {}", sequence),
+            sequence,
+            created_at: Utc::now(),
+            created_by: None,
+            database_snapshots,
+            is_automatic: false,
+            notes: None,
+            origin: None,
+        }
+    }
+
+    fn group(id: &str, name: &str, databases: &[&str]) -> Group {
+        Group {
+            id: id.to_string(),
+            name: name.to_string(),
+            databases: databases.iter().map(|d| d.to_string()).collect(),
+            profile_id: None,
+            description: None,
+            created_by: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_normalize_notes() {
+        assert_eq!(normalize_notes(Some("  before the data fix ".to_string())), Some("before the data fix".to_string()));
+        assert_eq!(normalize_notes(Some("   ".to_string())), None);
+        assert_eq!(normalize_notes(None), None);
+    }
+
+    #[test]
+    fn test_summarize_snapshot_age_and_staleness() {
+        let now = Utc::now();
+        let snapshot = Snapshot {
+            created_at: now - chrono::Duration::hours(30),
+            ..snapshot("s1", 1, Vec::new())
+        };
+
+        let summary = summarize_snapshot(snapshot.clone(), now, Some(24));
+        assert_eq!(summary.age_seconds, 30 * 3600);
+        assert!(summary.is_stale);
+
+        assert!(!summarize_snapshot(snapshot.clone(), now, Some(48)).is_stale);
+        assert!(!summarize_snapshot(snapshot, now, None).is_stale);
+    }
+
+    #[test]
+    fn test_retryable_entry_only_for_failed_databases() {
+        let snapshot = snapshot(
+            "s1",
+            1,
+            vec![
+                db_snapshot("Sales", "Sales_snapshot_QA_1", true),
+                db_snapshot("Billing", "Billing_snapshot_QA_1", false),
+            ],
+        );
+
+        assert_eq!(retryable_entry(&snapshot, "billing"), Ok(1));
+        assert!(retryable_entry(&snapshot, "Sales").unwrap_err().contains("already has a snapshot"));
+        assert!(retryable_entry(&snapshot, "Orders").unwrap_err().contains("not part of"));
+        assert_eq!(resnapshot_databases(&snapshot), vec!["Sales".to_string()]);
+    }
+
+    #[test]
+    fn test_latest_restorable_snapshot() {
+        let aged = |id: &str, sequence: u32, hours_ago: i64, success: bool| Snapshot {
+            created_at: Utc::now() - chrono::Duration::hours(hours_ago),
+            ..snapshot(
+                id,
+                sequence,
+                vec![db_snapshot("Sales", &format!("Sales_snapshot_QA_{}", sequence), success)],
+            )
+        };
+
+        let snapshots = vec![aged("s1", 1, 3, true), aged("s2", 2, 2, true), aged("s3", 3, 1, false)];
+        assert_eq!(latest_restorable_snapshot(&snapshots).map(|s| s.id.as_str()), Some("s2"));
+        assert!(latest_restorable_snapshot(&snapshots[2..]).is_none());
+    }
+
+    #[test]
+    fn test_is_in_snapshot_path() {
+        assert!(is_in_snapshot_path(r"C:\Snapshots\Sales_snapshot_QA_1.ss", r"c:\snapshots\"));
+        assert!(is_in_snapshot_path("/var/opt/mssql/snapshots/Sales_1.ss", "/var/opt/mssql/snapshots"));
+        assert!(!is_in_snapshot_path(r"D:\Other\Sales_1.ss", r"C:\Snapshots"));
+        assert!(!is_in_snapshot_path(r"C:\Snapshots\nested\Sales_1.ss", r"C:\Snapshots"));
+    }
+
+    #[test]
+    fn test_cross_group_entries_only_for_shared_restored_databases() {
+        let entry = |database: &str, success: bool| db_snapshot(database, &format!("{}_snapshot_B_1", database), success);
+        let groups = vec![group("a", "A", &["Sales", "Orders"]), group("b", "B", &["sales", "Orders", "Reports"])];
+        let b_snapshots = vec![Snapshot {
+            group_id: "b".to_string(),
+            ..snapshot("b1", 1, vec![entry("sales", true), entry("Orders", false), entry("Reports", true)])
+        }];
+        let conflicts = find_database_group_conflicts(&groups);
+        let others = vec![(&groups[1], b_snapshots)];
+
+        let restored = vec!["Sales".to_string(), "Orders".to_string()];
+        let entries = cross_group_entries("a", &restored, &conflicts, &others);
+        let names: Vec<&str> = entries.iter().map(|c| c.snapshot_name.as_str()).collect();
+        assert_eq!(names, vec!["sales_snapshot_B_1"]);
+        assert_eq!(entries[0].group_name, "B");
+
+        // A shared database that isn't being restored leaves the other group alone
+        assert!(cross_group_entries("a", &["Orders".to_string()], &conflicts, &others).is_empty());
+    }
+
+    #[test]
+    fn test_external_snapshot_names_skip_tracked_and_cross_group() {
+        let group = group("g1", "QA", &["Sales"]);
+        let ours = snapshot("s1", 1, vec![db_snapshot("Sales", "Sales_snapshot_QA_1", true)]);
+        let shared = CrossGroupSnapshot {
+            group_id: "g2".to_string(),
+            group_name: "Dev".to_string(),
+            snapshot_id: "s9".to_string(),
+            display_name: "Dev baseline".to_string(),
+            database: "Sales".to_string(),
+            snapshot_name: "Sales_snapshot_Dev_1".to_string(),
+        };
+        let server = vec![
+            server_snapshot("Sales_snapshot_QA_1", "Sales"),
+            server_snapshot("Sales_snapshot_Dev_1", "Sales"),
+            server_snapshot("Sales_manual", "Sales"),
+            server_snapshot("Orders_manual", "Orders"),
+        ];
+
+        assert_eq!(
+            external_snapshot_names(&server, &group, &[ours], &[shared]),
+            vec!["Sales_manual".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_atomic_failure_message() {
+        let ok = OperationResult {
+            database: "Sales".to_string(),
+            success: true,
+            ..Default::default()
+        };
+        assert!(atomic_failure_message(std::slice::from_ref(&ok)).is_none());
+
+        let failed = OperationResult {
+            database: "Billing".to_string(),
+            success: false,
+            error: Some("disk full".to_string()),
+            ..Default::default()
+        };
+        let message = atomic_failure_message(&[ok, failed]).unwrap();
+        assert!(message.contains("Billing (disk full)"));
+        assert!(!message.contains("Sales"));
+    }
+
+    #[test]
+    fn test_protected_database_error() {
+        let defaults: Vec<String> = DEFAULT_PROTECTED_DATABASES.iter().map(|d| d.to_string()).collect();
+
+        assert!(protected_database_error("MASTER", &defaults).unwrap().contains("protected"));
+        assert!(protected_database_error("sqlparrot", &defaults).is_some());
+        assert!(protected_database_error("Sales", &defaults).is_none());
+        assert!(protected_database_error("tempdb", &[]).is_none());
+    }
+
+    #[test]
+    fn test_snapshot_name_templates() {
+        assert_eq!(snapshot_base_name(None, "Sales", "QA Team", 3), "Sales_snapshot_QA_Team_3");
+        assert_eq!(
+            snapshot_base_name(Some("{group}_{database}_v{sequence}"), "Sales", "QA", 3),
+            "QA_Sales_v3"
+        );
+
+        assert!(validate_name_template("{database}_{sequence}").is_ok());
+        assert!(validate_name_template("{group}_{sequence}").unwrap_err().contains("{database}"));
+        assert!(validate_name_template("{database}_{sequence}_{user}").is_err());
+
+        let preferences = crate::models::SettingsPreferences {
+            snapshot_name_template: Some("{database}_{sequence}".to_string()),
+            ..Default::default()
+        };
+        let overrides = ProfileOverrides {
+            auto_create_checkpoint: Some(false),
+            snapshot_name_template: None,
+            application_name: Some("  ".to_string()),
+        };
+        assert!(!overrides.auto_create_checkpoint(&preferences));
+        assert_eq!(overrides.application_name(), crate::config::DEFAULT_APPLICATION_NAME);
+        assert_eq!(overrides.snapshot_name_template(&preferences), Some("{database}_{sequence}"));
+        assert!(ProfileOverrides::default().auto_create_checkpoint(&preferences));
+    }
+
+    #[test]
+    fn test_suffixed_snapshot_name() {
+        assert_eq!(suffixed_snapshot_name("Sales_snapshot_QA_3", 1), "Sales_snapshot_QA_3");
+        assert_eq!(suffixed_snapshot_name("Sales_snapshot_QA_3", 2), "Sales_snapshot_QA_3_2");
+        assert_eq!(suffixed_snapshot_name("Sales_snapshot_QA_3", 10), "Sales_snapshot_QA_3_10");
+    }
+
+    #[test]
+    fn test_external_origin_note_names_known_origins() {
+        let own = "exe:0123456789abcdef";
+        assert_eq!(describe_origin(own, Some(own)), "this SQL Parrot install");
+        assert_eq!(describe_origin("docker:fedcba9876543210", Some(own)), "SQL Parrot (docker) install fedcba98");
+
+        let external = vec!["Sales_snapshot_1".to_string(), "Sales_manual".to_string()];
+        assert!(external_origin_note(&external, &HashMap::new()).contains("another instance"));
+
+        let origins = HashMap::from([(
+            "Sales_snapshot_1".to_string(),
+            describe_origin("docker:fedcba9876543210", Some(own)),
+        )]);
+        assert_eq!(
+            external_origin_note(&external, &origins),
+            "Created: Sales_snapshot_1 by SQL Parrot (docker) install fedcba98."
+        );
+    }
+
+    #[test]
+    fn test_first_free_sequence_skips_names_taken_on_server() {
+        let taken: HashSet<String> = ["sales_snapshot_qa_3", "orders_snapshot_qa_4"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let names_for = |sequence: u32| {
+            ["Sales", "Orders"]
+                .iter()
+                .map(|database| snapshot_base_name(None, database, "QA", sequence))
+                .collect()
+        };
+
+        assert_eq!(first_free_sequence(2, &taken, names_for), Ok(2));
+        assert_eq!(first_free_sequence(3, &taken, names_for), Ok(5));
+        assert!(first_free_sequence(u32::MAX, &HashSet::from(["x".to_string()]), |_| vec!["x".to_string()])
+            .unwrap_err()
+            .contains("overflow"));
+    }
+
+    #[test]
+    fn test_recovery_model_warning_only_for_simple() {
+        assert!(recovery_model_warning("Sales", "SIMPLE").unwrap().contains("Sales"));
+        assert!(recovery_model_warning("Sales", "FULL").is_none());
+        assert!(recovery_model_warning("Sales", "BULK_LOGGED").is_none());
+    }
+
+    #[test]
+    fn test_availability_warning_only_for_ag_members() {
+        assert!(availability_warning("Sales", &AvailabilityInfo::default()).is_none());
+
+        let secondary = AvailabilityInfo {
+            in_availability_group: true,
+            group_name: Some("AG1".to_string()),
+            replica_role: Some("SECONDARY".to_string()),
+        };
+        let warning = availability_warning("Sales", &secondary).unwrap();
+        assert!(warning.contains("secondary replica in AG1"));
+
+        let primary = AvailabilityInfo {
+            replica_role: Some("PRIMARY".to_string()),
+            ..secondary
+        };
+        assert!(availability_warning("Sales", &primary).unwrap().contains("PRIMARY"));
+    }
+
+    #[test]
+    fn test_find_renamed_or_missing() {
+        let snapshot = snapshot(
+            "s1",
+            1,
+            vec![
+                db_snapshot("Sales", "g1_1_Sales", true),
+                db_snapshot("Orders", "g1_1_Orders", true),
+                db_snapshot("Audit", "g1_1_Audit", true),
+                db_snapshot("Legacy", "g1_1_Legacy", true),
+            ],
+        );
+        let server = vec![
+            server_snapshot("g1_1_Sales", "Sales"),
+            server_snapshot("g1_1_Orders", "OrdersV2"),
+        ];
+        let current = vec!["Sales".to_string(), "OrdersV2".to_string(), "Audit".to_string()];
+
+        let result = find_renamed_or_missing(&[snapshot], &server, &current);
+
+        assert_eq!(
+            result,
+            vec![
+                RenamedSource {
+                    snapshot_name: "g1_1_Orders".to_string(),
+                    database: "Orders".to_string(),
+                    current_source: Some("OrdersV2".to_string()),
+                },
+                RenamedSource {
+                    snapshot_name: "g1_1_Legacy".to_string(),
+                    database: "Legacy".to_string(),
+                    current_source: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_find_orphaned_snapshots_uses_source_database() {
+        let group = group("g1", "Sales", &["Sales"]);
+        let ours = snapshot("s1", 1, vec![db_snapshot("Sales", "g1_1_Sales", true)]);
+        let server = vec![
+            server_snapshot("g1_1_Sales", "Sales"),
+            server_snapshot("Sales_manual", "Sales"),
+            server_snapshot("Orders_manual", "Orders"),
+        ];
+
+        assert_eq!(
+            find_orphaned_snapshots(&group, std::slice::from_ref(&ours), &server),
+            vec!["Sales_manual".to_string()]
+        );
+
+        // A snapshot another group recorded of a shared database isn't an orphan
+        let other_group = Snapshot {
+            group_id: "g2".to_string(),
+            ..snapshot("s2", 1, vec![db_snapshot("Sales", "Sales_manual", true)])
+        };
+        assert!(find_orphaned_snapshots(&group, &[ours, other_group], &server).is_empty());
+    }
+
+    #[test]
+    fn test_reconcile_snapshot() {
+        let entry = |name: &str, success: bool| db_snapshot(name, &format!("g1_1_{}", name), success);
+        let full = snapshot("s1", 1, vec![entry("Sales", true), entry("Orders", true)]);
+
+        assert!(matches!(
+            reconcile_snapshot(&full, &["g1_1_Sales", "g1_1_Orders"]),
+            Reconciliation::Keep
+        ));
+        assert!(matches!(reconcile_snapshot(&full, &[]), Reconciliation::Remove));
+
+        match reconcile_snapshot(&full, &["g1_1_Sales"]) {
+            Reconciliation::Update(entries) => {
+                assert!(entries[0].success);
+                assert!(!entries[1].success);
+                assert!(entries[1].error.is_some());
+            }
+            other => panic!("expected Update, got {:?}", other),
+        }
+
+        // Failed entries were never on the server, so they don't count as missing
+        let partial = snapshot("s1", 1, vec![entry("Sales", true), entry("Orders", false)]);
+        assert!(matches!(reconcile_snapshot(&partial, &["g1_1_Sales"]), Reconciliation::Keep));
+    }
 }
@@ -0,0 +1,218 @@
+// ABOUTME: Undo Tauri command
+// ABOUTME: Reverses the most recent history entry when it recorded enough state to do so
+
+use chrono::Utc;
+use tauri::Manager;
+use uuid::Uuid;
+
+use crate::db::{MetadataStore, SqlServerConnection};
+use crate::group_locks::{group_busy, GroupLocks};
+use crate::messages;
+use crate::models::{Group, HistoryEntry};
+use crate::sessions::{require_auth, Sessions};
+use crate::{timed, ApiResponse};
+
+/// Result of undoing an operation
+#[derive(serde::Serialize)]
+pub struct UndoResult {
+    #[serde(rename = "undoneOperation")]
+    pub undone_operation: String,
+    pub message: String,
+}
+
+/// Whether a history entry was flagged reversible when it was recorded
+fn is_reversible(entry: &HistoryEntry) -> bool {
+    entry
+        .details
+        .as_ref()
+        .and_then(|d| d.get("reversible"))
+        .and_then(|r| r.as_bool())
+        .unwrap_or(false)
+}
+
+/// Read a string field from a history entry's details
+fn detail_str<'a>(entry: &'a HistoryEntry, key: &str) -> Option<&'a str> {
+    entry.details.as_ref()?.get(key)?.as_str()
+}
+
+/// Undo the most recent operation, if it is reversible
+/// Supported: delete_group (recreates the group, not its dropped snapshots)
+/// and create_snapshot (drops the snapshot databases and removes the metadata)
+/// While a UI password is set, `session_token` must come from check_password.
+#[tauri::command]
+pub async fn undo_last_operation(app: tauri::AppHandle, session_token: Option<String>) -> ApiResponse<UndoResult> {
+    timed(async move {
+        if let Some(response) = require_auth(&app.state::<Sessions>(), session_token.as_deref()) {
+            return response;
+        }
+
+        let store = match MetadataStore::open() {
+            Ok(s) => s,
+            Err(e) => return ApiResponse::error_key(messages::STORE_OPEN_FAILED, &[&e]),
+        };
+
+        let last = match store.get_history(Some(1)) {
+            Ok(entries) => match entries.into_iter().next() {
+                Some(entry) => entry,
+                None => return ApiResponse::error("No operation to undo".to_string()),
+            },
+            Err(e) => return ApiResponse::error(format!("Failed to get history: {}", e)),
+        };
+
+        if !is_reversible(&last) {
+            return ApiResponse::error("Last operation cannot be undone".to_string());
+        }
+
+        let result = match last.operation_type.as_str() {
+            "delete_group" => undo_delete_group(&store, &last).map_err(ApiResponse::error),
+            "create_snapshot" => undo_create_snapshot(&store, &app.state::<GroupLocks>(), &last).await,
+            _ => Err(ApiResponse::error("Last operation cannot be undone".to_string())),
+        };
+
+        match result {
+            Ok(message) => {
+                // The undo entry itself is not reversible, so undo can't be applied twice
+                let history_entry = HistoryEntry {
+                    id: Uuid::new_v4().to_string(),
+                    operation_type: format!("undo_{}", last.operation_type),
+                    timestamp: Utc::now(),
+                    user_name: Some(whoami::username_os().to_string_lossy().into_owned()),
+                    details: Some(serde_json::json!({
+                        "undoneHistoryId": last.id,
+                        "message": message
+                    })),
+                    results: None,
+                    group_id: last.group_id.clone(),
+                };
+                let _ = store.add_history(&history_entry);
+
+                ApiResponse::success(UndoResult {
+                    undone_operation: last.operation_type,
+                    message,
+                })
+            }
+            Err(response) => response,
+        }
+    })
+    .await
+}
+
+/// Recreate a deleted group from the definition saved in its history entry
+fn undo_delete_group(store: &MetadataStore, entry: &HistoryEntry) -> Result<String, String> {
+    let group: Group = entry
+        .details
+        .as_ref()
+        .and_then(|d| d.get("group"))
+        .cloned()
+        .and_then(|g| serde_json::from_value(g).ok())
+        .ok_or_else(|| "History entry has no saved group to restore".to_string())?;
+
+    let group = Group {
+        updated_at: Utc::now(),
+        ..group
+    };
+
+    store
+        .create_group(&group)
+        .map_err(|e| format!("Failed to restore group: {}", e))?;
+
+    Ok(format!(
+        "Restored group \"{}\" (its snapshots were dropped and cannot be restored)",
+        group.name
+    ))
+}
+
+/// Drop the snapshot a create_snapshot entry produced
+/// Holds the group's lock while dropping, like any other operation that touches its snapshots
+async fn undo_create_snapshot(
+    store: &MetadataStore,
+    group_locks: &GroupLocks,
+    entry: &HistoryEntry,
+) -> Result<String, ApiResponse<UndoResult>> {
+    let group_id = detail_str(entry, "groupId")
+        .ok_or_else(|| ApiResponse::error("History entry has no group".to_string()))?;
+
+    // By id rather than through get_groups, so the undo works whichever profile is active
+    let group = match store.get_group(group_id) {
+        Ok(Some(g)) => g,
+        Ok(None) => return Err(ApiResponse::error_key(messages::GROUP_NOT_FOUND, &[&group_id])),
+        Err(e) => return Err(ApiResponse::error(format!("Failed to get group: {}", e))),
+    };
+
+    let _group_guard = match group_locks.try_lock(&group.id) {
+        Some(guard) => guard,
+        None => return Err(group_busy(&group.name)),
+    };
+
+    drop_created_snapshot(store, &group, entry)
+        .await
+        .map_err(ApiResponse::error)
+}
+
+async fn drop_created_snapshot(store: &MetadataStore, group: &Group, entry: &HistoryEntry) -> Result<String, String> {
+    let snapshot_id = detail_str(entry, "snapshotId")
+        .ok_or_else(|| "History entry has no snapshot".to_string())?;
+
+    let snapshot = store
+        .get_snapshots(&group.id)
+        .map_err(|e| format!("Failed to get snapshots: {}", e))?
+        .into_iter()
+        .find(|s| s.id == snapshot_id)
+        .ok_or_else(|| "Snapshot no longer exists".to_string())?;
+
+    let profile = super::get_profile_for_group(store, group)?;
+    let mut conn = SqlServerConnection::connect(&profile)
+        .await
+        .map_err(|e| format!("Failed to connect: {}", e))?;
+
+    let snapshot_names: Vec<String> = snapshot
+        .database_snapshots
+        .iter()
+        .filter(|ds| ds.success)
+        .map(|ds| ds.snapshot_name.clone())
+        .collect();
+    let failed = conn
+        .drop_snapshots(&snapshot_names)
+        .await
+        .map_err(|e| format!("Failed to drop snapshots: {}", e))?;
+    if let Some((name, error)) = failed.first() {
+        return Err(format!("Failed to drop {}: {}", name, error));
+    }
+
+    store
+        .delete_snapshot(&snapshot.id)
+        .map_err(|e| format!("Failed to delete snapshot metadata: {}", e))?;
+
+    Ok(format!("Removed snapshot \"{}\"", snapshot.display_name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(operation_type: &str, details: Option<serde_json::Value>) -> HistoryEntry {
+        HistoryEntry {
+            id: "h1".to_string(),
+            operation_type: operation_type.to_string(),
+            timestamp: Utc::now(),
+            user_name: None,
+            details,
+            results: None,
+            group_id: None,
+        }
+    }
+
+    #[test]
+    fn test_is_reversible_requires_flag() {
+        assert!(is_reversible(&entry(
+            "create_snapshot",
+            Some(serde_json::json!({ "reversible": true }))
+        )));
+        assert!(!is_reversible(&entry(
+            "delete_group",
+            Some(serde_json::json!({ "reversible": false }))
+        )));
+        assert!(!is_reversible(&entry("rollback", Some(serde_json::json!({})))));
+        assert!(!is_reversible(&entry("rollback", None)));
+    }
+}
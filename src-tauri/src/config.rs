@@ -33,6 +33,56 @@ impl Default for DatabaseType {
     }
 }
 
+impl DatabaseType {
+    /// Value stored in profiles.platform_type
+    pub fn platform_type(&self) -> &'static str {
+        match self {
+            DatabaseType::SqlServer => "Microsoft SQL Server",
+        }
+    }
+
+    /// Parse a stored platform_type; an empty value is a profile saved before the column was filled in
+    /// Returns the unrecognized value so callers can say which platform isn't supported
+    pub fn from_platform_type(value: &str) -> Result<Self, String> {
+        let value = value.trim();
+        if value.is_empty() || value.eq_ignore_ascii_case(DatabaseType::SqlServer.platform_type()) {
+            Ok(DatabaseType::SqlServer)
+        } else {
+            Err(value.to_string())
+        }
+    }
+}
+
+/// Whether the TDS connection is encrypted
+/// Off exists only for legacy servers (e.g. SQL Server 2008/2012) that can't complete a modern TLS handshake;
+/// credentials and data then cross the network in clear text
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum EncryptionMode {
+    #[default]
+    On,
+    Off,
+}
+
+impl EncryptionMode {
+    /// Value stored in the profiles table
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EncryptionMode::On => "on",
+            EncryptionMode::Off => "off",
+        }
+    }
+
+    /// Parse a stored value; anything unrecognized keeps encryption on
+    pub fn from_stored(value: &str) -> Self {
+        if value.eq_ignore_ascii_case("off") {
+            EncryptionMode::Off
+        } else {
+            EncryptionMode::On
+        }
+    }
+}
+
 /// Connection profile for a database server
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConnectionProfile {
@@ -49,6 +99,21 @@ pub struct ConnectionProfile {
     pub trust_certificate: bool,
     #[serde(default = "default_snapshot_path")]
     pub snapshot_path: String,
+    /// Host OS of the server, if already detected
+    #[serde(default)]
+    pub server_platform: Option<String>,
+    #[serde(default)]
+    pub encryption_mode: EncryptionMode,
+    /// Sent at login; shows as program_name in sys.dm_exec_sessions
+    #[serde(default = "default_application_name")]
+    pub application_name: String,
+}
+
+/// Application name SQL Parrot's sessions show on the server unless a profile sets its own
+pub const DEFAULT_APPLICATION_NAME: &str = "SQL Parrot";
+
+fn default_application_name() -> String {
+    DEFAULT_APPLICATION_NAME.to_string()
 }
 
 fn default_port() -> u16 {
@@ -63,6 +128,54 @@ fn default_snapshot_path() -> String {
     "/var/opt/mssql/snapshots".to_string()
 }
 
+/// Conventional snapshot directory for a server's host OS
+pub fn default_snapshot_path_for(server_platform: &str) -> String {
+    if server_platform.eq_ignore_ascii_case("Windows") {
+        "C:\\Snapshots".to_string()
+    } else {
+        default_snapshot_path()
+    }
+}
+
+/// Snapshot directory to suggest for a newly connected server
+/// Windows servers use their default data directory when it's known, since SQL Server can already write there
+pub fn suggested_snapshot_path(server_platform: &str, default_data_path: Option<&str>) -> String {
+    match default_data_path.map(|p| p.trim().trim_end_matches(['/', '\\'])) {
+        Some(path) if server_platform.eq_ignore_ascii_case("Windows") && !path.is_empty() => path.to_string(),
+        _ => default_snapshot_path_for(server_platform),
+    }
+}
+
+/// Split a port pasted into the host field off it: `server,1433`, `server:1433`, or `[::1]:1433`
+/// Brackets come off an IPv6 literal; a bare IPv6 literal (`::1`) is left whole
+pub fn split_host_port(host: &str) -> (&str, Option<u16>) {
+    let host = host.trim();
+    if let Some(rest) = host.strip_prefix('[') {
+        if let Some((address, after)) = rest.split_once(']') {
+            let port = after.strip_prefix([':', ',']).and_then(|p| p.trim().parse().ok());
+            return (address, port);
+        }
+    }
+
+    let split = match host.rsplit_once(',') {
+        Some(parts) => Some(parts),
+        None if host.matches(':').count() == 1 => host.split_once(':'),
+        None => None,
+    };
+    match split.and_then(|(address, port)| port.trim().parse().ok().map(|port| (address.trim(), port))) {
+        Some((address, port)) => (address, Some(port)),
+        None => (host, None),
+    }
+}
+
+impl ConnectionProfile {
+    /// Host and port to connect to; a port embedded in the host wins over the port field
+    pub fn address(&self) -> (&str, u16) {
+        let (host, port) = split_host_port(&self.host);
+        (host, port.unwrap_or(self.port))
+    }
+}
+
 impl Default for ConnectionProfile {
     fn default() -> Self {
         Self {
@@ -74,6 +187,9 @@ impl Default for ConnectionProfile {
             password: String::new(),
             trust_certificate: true,
             snapshot_path: "/var/opt/mssql/snapshots".to_string(),
+            server_platform: None,
+            encryption_mode: EncryptionMode::On,
+            application_name: default_application_name(),
         }
     }
 }
@@ -108,6 +224,27 @@ fn default_version() -> u32 {
     1
 }
 
+/// Environment variable that relocates all app data (portable mode, CI)
+pub const DATA_DIR_ENV: &str = "SQLPARROT_DATA_DIR";
+
+/// Data directory override from SQLPARROT_DATA_DIR, if set to a non-empty value.
+/// The directory is created if it doesn't exist yet.
+pub fn data_dir_override() -> Result<Option<PathBuf>, std::io::Error> {
+    data_dir_from(std::env::var_os(DATA_DIR_ENV))
+}
+
+/// data_dir_override for a given SQLPARROT_DATA_DIR value, so tests don't touch the process environment
+fn data_dir_from(value: Option<std::ffi::OsString>) -> Result<Option<PathBuf>, std::io::Error> {
+    match value {
+        Some(dir) if !dir.is_empty() => {
+            let dir = PathBuf::from(dir);
+            fs::create_dir_all(&dir)?;
+            Ok(Some(dir))
+        }
+        _ => Ok(None),
+    }
+}
+
 fn default_active_profile() -> String {
     "default".to_string()
 }
@@ -127,8 +264,13 @@ impl Default for AppConfig {
 }
 
 impl AppConfig {
-    /// Get the config file path based on OS
+    /// Get the config file path
+    /// SQLPARROT_DATA_DIR takes precedence; otherwise the OS config directory is used
     pub fn config_path() -> Result<PathBuf, ConfigError> {
+        if let Some(dir) = data_dir_override()? {
+            return Ok(dir.join("config.json"));
+        }
+
         let config_dir = dirs::config_dir().ok_or(ConfigError::NoDirFound)?;
         let app_dir = config_dir.join("SQL Parrot");
         Ok(app_dir.join("config.json"))
@@ -220,6 +362,70 @@ mod tests {
         assert!(config.profiles.contains_key("default"));
     }
 
+    #[test]
+    fn test_data_dir_override_value() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let data_dir = temp_dir.path().join("portable");
+
+        let dir = data_dir_from(Some(data_dir.clone().into_os_string())).unwrap();
+        assert_eq!(dir, Some(data_dir.clone()));
+        assert!(data_dir.is_dir());
+
+        // Unset and empty both fall back to the OS directories
+        assert_eq!(data_dir_from(Some("".into())).unwrap(), None);
+        assert_eq!(data_dir_from(None).unwrap(), None);
+    }
+
+    #[test]
+    fn test_split_host_port() {
+        assert_eq!(split_host_port("myserver"), ("myserver", None));
+        assert_eq!(split_host_port("myserver,1433"), ("myserver", Some(1433)));
+        assert_eq!(split_host_port("myserver:14330"), ("myserver", Some(14330)));
+        assert_eq!(split_host_port(" myserver, 1433 "), ("myserver", Some(1433)));
+        assert_eq!(split_host_port("[::1]:1433"), ("::1", Some(1433)));
+        assert_eq!(split_host_port("[fe80::1],1500"), ("fe80::1", Some(1500)));
+        assert_eq!(split_host_port("[::1]"), ("::1", None));
+        assert_eq!(split_host_port("::1"), ("::1", None));
+        assert_eq!(split_host_port("myserver:sql"), ("myserver:sql", None));
+
+        let profile = ConnectionProfile {
+            host: "myserver,1500".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(profile.address(), ("myserver", 1500));
+        assert_eq!(ConnectionProfile::default().address(), ("localhost", 1433));
+    }
+
+    #[test]
+    fn test_suggested_snapshot_path() {
+        assert_eq!(
+            suggested_snapshot_path("Windows", Some("D:\\SQLData\\")),
+            "D:\\SQLData"
+        );
+        assert_eq!(suggested_snapshot_path("Windows", None), "C:\\Snapshots");
+        assert_eq!(
+            suggested_snapshot_path("Linux", Some("/var/opt/mssql/data/")),
+            "/var/opt/mssql/snapshots"
+        );
+    }
+
+    #[test]
+    fn test_database_type_from_platform_type() {
+        assert_eq!(
+            DatabaseType::from_platform_type("Microsoft SQL Server"),
+            Ok(DatabaseType::SqlServer)
+        );
+        assert_eq!(
+            DatabaseType::from_platform_type(" microsoft sql server "),
+            Ok(DatabaseType::SqlServer)
+        );
+        assert_eq!(DatabaseType::from_platform_type(""), Ok(DatabaseType::SqlServer));
+        assert_eq!(
+            DatabaseType::from_platform_type("PostgreSQL"),
+            Err("PostgreSQL".to_string())
+        );
+    }
+
     #[test]
     fn test_serialization() {
         let config = AppConfig::default();
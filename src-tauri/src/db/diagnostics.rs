@@ -0,0 +1,232 @@
+// ABOUTME: Staged SQL Server connection diagnostics
+// ABOUTME: Runs DNS, TCP, TLS, login, query, and permission checks so the UI can show where a connection breaks
+
+use std::future::Future;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use tiberius::Client;
+use tokio::net::TcpStream;
+use tokio_util::compat::{Compat, TokioAsyncWriteCompatExt};
+
+use super::limiter::ConnectionLimiter;
+use super::sqlserver::{classify_error, SqlServerConnection, SqlServerError};
+use crate::config::{ConnectionProfile, EncryptionMode};
+use crate::models::DiagnosticStep;
+
+/// Timeout for DNS, TCP, and query stages
+const STAGE_TIMEOUT: Duration = Duration::from_secs(5);
+/// The TLS handshake and login happen in one exchange, so they share a longer timeout
+const LOGIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+const DNS_STEP: &str = "DNS resolve";
+const TCP_STEP: &str = "TCP connect";
+const TLS_STEP: &str = "TLS handshake";
+const AUTH_STEP: &str = "Authentication";
+const QUERY_STEP: &str = "Query (SELECT 1)";
+const PERMISSIONS_STEP: &str = "Permissions";
+
+/// All stages in the order they run
+const STEPS: [&str; 6] = [DNS_STEP, TCP_STEP, TLS_STEP, AUTH_STEP, QUERY_STEP, PERMISSIONS_STEP];
+
+/// Server-level permissions that matter to SQL Parrot
+const PERMISSIONS_QUERY: &str = r#"
+    SELECT
+        ISNULL(IS_SRVROLEMEMBER('sysadmin'), 0),
+        ISNULL(HAS_PERMS_BY_NAME(NULL, NULL, 'CREATE ANY DATABASE'), 0),
+        ISNULL(HAS_PERMS_BY_NAME(NULL, NULL, 'VIEW SERVER STATE'), 0),
+        ISNULL(HAS_PERMS_BY_NAME(NULL, NULL, 'ALTER ANY CONNECTION'), 0)
+"#;
+
+fn step(name: &str, ok: bool, detail: impl Into<String>) -> DiagnosticStep {
+    DiagnosticStep {
+        name: name.to_string(),
+        ok,
+        detail: detail.into(),
+    }
+}
+
+/// Append a "skipped" entry for every stage that hasn't been reported yet
+fn skip_remaining(mut steps: Vec<DiagnosticStep>) -> Vec<DiagnosticStep> {
+    for name in STEPS.iter().skip(steps.len()) {
+        steps.push(step(name, false, "Skipped: an earlier step failed"));
+    }
+    steps
+}
+
+/// Run a stage future under its own timeout, turning a hang into an error message
+async fn with_timeout<T, E: std::fmt::Display>(
+    limit: Duration,
+    future: impl Future<Output = Result<T, E>>,
+) -> Result<T, String> {
+    match tokio::time::timeout(limit, future).await {
+        Ok(Ok(value)) => Ok(value),
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(_) => Err(format!("Timed out after {}s", limit.as_secs())),
+    }
+}
+
+/// Whether the login can take snapshots, plus a one-line summary of what it's allowed to do
+fn permission_summary(
+    sysadmin: bool,
+    create_database: bool,
+    view_server_state: bool,
+    alter_any_connection: bool,
+) -> (bool, String) {
+    if sysadmin {
+        return (true, "Login is sysadmin".to_string());
+    }
+
+    let mut missing = Vec::new();
+    if !create_database {
+        missing.push("CREATE ANY DATABASE (needed to create snapshots)");
+    }
+    if !view_server_state {
+        missing.push("VIEW SERVER STATE (server details will be limited)");
+    }
+    if !alter_any_connection {
+        missing.push("ALTER ANY CONNECTION (needed to kill connections before rollback)");
+    }
+
+    if missing.is_empty() {
+        (true, "Login has all permissions SQL Parrot uses".to_string())
+    } else {
+        (create_database, format!("Missing: {}", missing.join("; ")))
+    }
+}
+
+/// Run every diagnostic stage against a profile, stopping at the first failure
+/// Stages after a failure are reported as skipped so the UI always gets the full list
+pub async fn diagnose(profile: &ConnectionProfile) -> Vec<DiagnosticStep> {
+    let _permit = ConnectionLimiter::shared().acquire().await;
+    let mut steps = Vec::new();
+    let (host, port) = profile.address();
+
+    // DNS
+    let addrs: Vec<SocketAddr> = match with_timeout(STAGE_TIMEOUT, tokio::net::lookup_host((host, port))).await {
+        Ok(addrs) => addrs.collect(),
+        Err(e) => {
+            steps.push(step(DNS_STEP, false, format!("Could not resolve {}: {}", host, e)));
+            return skip_remaining(steps);
+        }
+    };
+    if addrs.is_empty() {
+        steps.push(step(DNS_STEP, false, format!("{} resolved to no addresses", host)));
+        return skip_remaining(steps);
+    }
+    let resolved: Vec<String> = addrs.iter().map(|a| a.ip().to_string()).collect();
+    steps.push(step(DNS_STEP, true, format!("Resolved to {}", resolved.join(", "))));
+
+    // TCP
+    let tcp = match with_timeout(STAGE_TIMEOUT, TcpStream::connect(&addrs[..])).await {
+        Ok(tcp) => tcp,
+        Err(e) => {
+            steps.push(step(
+                TCP_STEP,
+                false,
+                format!("Could not reach port {}: {}", port, e),
+            ));
+            return skip_remaining(steps);
+        }
+    };
+    let peer = tcp
+        .peer_addr()
+        .map(|a| a.to_string())
+        .unwrap_or_else(|_| host.to_string());
+    let _ = tcp.set_nodelay(true);
+    steps.push(step(TCP_STEP, true, format!("Connected to {}", peer)));
+
+    // TLS handshake and login
+    let handshake = if profile.encryption_mode == EncryptionMode::Off {
+        "Skipped: encryption is turned off for this profile"
+    } else {
+        "Handshake completed"
+    };
+    let config = SqlServerConnection::client_config(profile);
+    let mut client: Client<Compat<TcpStream>> =
+        match tokio::time::timeout(LOGIN_TIMEOUT, Client::connect(config, tcp.compat_write())).await {
+            Ok(Ok(client)) => {
+                steps.push(step(TLS_STEP, true, handshake));
+                steps.push(step(AUTH_STEP, true, format!("Logged in as {}", profile.username)));
+                client
+            }
+            Ok(Err(e)) => {
+                match classify_error(e) {
+                    // A server error means the handshake finished and the login itself was rejected
+                    e @ (SqlServerError::LoginFailed(_)
+                    | SqlServerError::PermissionDenied(_)
+                    | SqlServerError::Tiberius(tiberius::error::Error::Server(_))) => {
+                        steps.push(step(TLS_STEP, true, handshake));
+                        steps.push(step(AUTH_STEP, false, e.user_message()));
+                    }
+                    e => steps.push(step(TLS_STEP, false, e.user_message())),
+                }
+                return skip_remaining(steps);
+            }
+            Err(_) => {
+                steps.push(step(
+                    TLS_STEP,
+                    false,
+                    format!(
+                        "Timed out after {}s during TLS handshake or login",
+                        LOGIN_TIMEOUT.as_secs()
+                    ),
+                ));
+                return skip_remaining(steps);
+            }
+        };
+
+    // SELECT 1
+    let query = async {
+        client.simple_query("SELECT 1").await?.into_first_result().await
+    };
+    if let Err(e) = with_timeout(STAGE_TIMEOUT, query).await {
+        steps.push(step(QUERY_STEP, false, e));
+        return skip_remaining(steps);
+    }
+    steps.push(step(QUERY_STEP, true, "Query succeeded"));
+
+    // Permissions
+    let permissions = async {
+        client.simple_query(PERMISSIONS_QUERY).await?.into_row().await
+    };
+    match with_timeout(STAGE_TIMEOUT, permissions).await {
+        Ok(Some(row)) => {
+            let flag = |idx: usize| row.get::<i32, _>(idx).unwrap_or(0) == 1;
+            let (ok, detail) = permission_summary(flag(0), flag(1), flag(2), flag(3));
+            steps.push(step(PERMISSIONS_STEP, ok, detail));
+        }
+        Ok(None) => steps.push(step(PERMISSIONS_STEP, false, "No permission information returned")),
+        Err(e) => steps.push(step(PERMISSIONS_STEP, false, e)),
+    }
+
+    steps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_skip_remaining_fills_later_stages() {
+        let steps = skip_remaining(vec![step(DNS_STEP, true, "ok"), step(TCP_STEP, false, "refused")]);
+
+        let names: Vec<&str> = steps.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, STEPS.to_vec());
+        assert!(steps[2..].iter().all(|s| !s.ok && s.detail.starts_with("Skipped")));
+    }
+
+    #[test]
+    fn test_permission_summary() {
+        assert_eq!(permission_summary(true, false, false, false), (true, "Login is sysadmin".to_string()));
+        assert!(permission_summary(false, true, true, true).0);
+
+        let (ok, detail) = permission_summary(false, true, false, true);
+        assert!(ok);
+        assert!(detail.contains("VIEW SERVER STATE"));
+
+        let (ok, detail) = permission_summary(false, false, true, true);
+        assert!(!ok);
+        assert!(detail.contains("CREATE ANY DATABASE"));
+    }
+}
@@ -0,0 +1,168 @@
+// ABOUTME: Process-wide cap on concurrent SQL Server connections
+// ABOUTME: Every SqlServerConnection holds a permit from this semaphore for its lifetime
+
+use std::sync::{Arc, Mutex, OnceLock};
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use super::MetadataStore;
+use crate::models::DEFAULT_MAX_CONNECTIONS;
+
+/// Shared semaphore limiting open SQL Server connections
+/// Cloning shares the same semaphore; the app registers a clone as managed state
+#[derive(Clone)]
+pub struct ConnectionLimiter {
+    semaphore: Arc<Semaphore>,
+    state: Arc<Mutex<LimitState>>,
+}
+
+/// The configured limit, and how many permits in use are to be retired on release after a shrink
+/// The semaphore holds limit + retiring permits in all, counting the ones in use
+#[derive(Debug)]
+struct LimitState {
+    limit: usize,
+    retiring: usize,
+}
+
+/// A connection slot; dropping it frees the slot, or retires it while a shrink is still pending
+pub struct ConnectionPermit {
+    permit: Option<OwnedSemaphorePermit>,
+    state: Arc<Mutex<LimitState>>,
+}
+
+impl Drop for ConnectionPermit {
+    fn drop(&mut self) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(permit) = self.permit.take() {
+            if state.retiring > 0 {
+                state.retiring -= 1;
+                permit.forget();
+            }
+        }
+    }
+}
+
+static SHARED: OnceLock<ConnectionLimiter> = OnceLock::new();
+
+impl ConnectionLimiter {
+    pub fn new(limit: usize) -> Self {
+        let limit = limit.max(1);
+        Self {
+            semaphore: Arc::new(Semaphore::new(limit)),
+            state: Arc::new(Mutex::new(LimitState { limit, retiring: 0 })),
+        }
+    }
+
+    /// The limiter all connections go through, sized from the max_connections setting on first use
+    pub fn shared() -> &'static ConnectionLimiter {
+        SHARED.get_or_init(|| {
+            let limit = MetadataStore::open()
+                .and_then(|store| store.get_settings())
+                .map(|settings| settings.preferences.max_connections)
+                .unwrap_or(DEFAULT_MAX_CONNECTIONS);
+            ConnectionLimiter::new(limit as usize)
+        })
+    }
+
+    /// Wait for a free connection slot; the slot is released when the permit is dropped
+    pub async fn acquire(&self) -> ConnectionPermit {
+        let permit = self
+            .semaphore
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("connection semaphore is never closed");
+        ConnectionPermit {
+            permit: Some(permit),
+            state: self.state.clone(),
+        }
+    }
+
+    /// Change the limit; shrinking takes effect as connections in use are released
+    pub fn set_limit(&self, new_limit: usize) {
+        let new_limit = new_limit.max(1);
+        let mut state = self.state.lock().unwrap();
+
+        if new_limit > state.limit {
+            // Permits still waiting to be retired are kept rather than adding new ones
+            let grow = new_limit - state.limit;
+            let kept = grow.min(state.retiring);
+            state.retiring -= kept;
+            self.semaphore.add_permits(grow - kept);
+        } else if new_limit < state.limit {
+            let excess = state.limit - new_limit;
+            let forgotten = self.semaphore.forget_permits(excess);
+            // The rest are in use; they are retired as they are released
+            state.retiring += excess - forgotten;
+        }
+
+        state.limit = new_limit;
+    }
+
+    pub fn limit(&self) -> usize {
+        self.state.lock().unwrap().limit
+    }
+
+    /// Connections currently holding a permit, including ones above a lowered limit
+    pub fn in_use(&self) -> usize {
+        let state = self.state.lock().unwrap();
+        (state.limit + state.retiring).saturating_sub(self.semaphore.available_permits())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_limiter_counts_permits_in_use() {
+        let limiter = ConnectionLimiter::new(2);
+
+        let first = limiter.acquire().await;
+        let _second = limiter.acquire().await;
+        assert_eq!(limiter.in_use(), 2);
+        assert!(limiter.semaphore.clone().try_acquire_owned().is_err());
+
+        drop(first);
+        assert_eq!(limiter.in_use(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_set_limit_grows_and_shrinks() {
+        let limiter = ConnectionLimiter::new(2);
+
+        limiter.set_limit(4);
+        assert_eq!(limiter.limit(), 4);
+        assert_eq!(limiter.semaphore.available_permits(), 4);
+
+        limiter.set_limit(1);
+        assert_eq!(limiter.limit(), 1);
+        assert_eq!(limiter.semaphore.available_permits(), 1);
+
+        limiter.set_limit(0);
+        assert_eq!(limiter.limit(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_shrink_below_in_use_retires_on_release_unless_regrown() {
+        let limiter = ConnectionLimiter::new(3);
+        let first = limiter.acquire().await;
+        let second = limiter.acquire().await;
+        let third = limiter.acquire().await;
+
+        // All three are in use, so two are retired when released
+        limiter.set_limit(1);
+        assert_eq!(limiter.in_use(), 3);
+        drop(first);
+        assert_eq!(limiter.in_use(), 2);
+        assert_eq!(limiter.semaphore.available_permits(), 0);
+
+        // Growing again keeps the permit still waiting to retire instead of adding one
+        limiter.set_limit(2);
+        assert_eq!(limiter.in_use(), 2);
+        drop(second);
+        drop(third);
+        assert_eq!(limiter.in_use(), 0);
+        assert_eq!(limiter.semaphore.available_permits(), 2);
+    }
+}
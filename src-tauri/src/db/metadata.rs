@@ -1,14 +1,20 @@
 // ABOUTME: SQLite metadata storage for SQL Parrot desktop app
 // ABOUTME: Stores groups, snapshots, history, and settings locally
 
-use chrono::Utc;
-use rusqlite::{params, Connection};
-use std::path::PathBuf;
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, Transaction, TransactionBehavior};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Mutex;
+use std::time::Duration;
 use thiserror::Error;
 use uuid::Uuid;
 
-use crate::models::{Group, HistoryEntry, Profile, Settings, Snapshot};
+use crate::models::{
+    ConnectionCheck, DatabaseSnapshot, EncryptionMode, Group, HistoryEntry, Operation, OperationStatus, Profile,
+    ProfileOverrides, RenumberResult, Settings, Snapshot,
+};
 
 #[derive(Error, Debug)]
 pub enum MetadataError {
@@ -24,13 +30,89 @@ pub enum MetadataError {
     NoDirFound,
 }
 
+/// How long a connection waits on a locked database before giving up
+const BUSY_TIMEOUT_MS: u64 = 5000;
+
+/// _metadata keys recording completed migration steps
+const MIGRATED_CONFIG_PROFILES: &str = "migrated_config_profiles";
+const MIGRATED_CONFIG_PREFERENCES: &str = "migrated_config_preferences";
+const MIGRATED_GROUPS_PROFILE_ID: &str = "migrated_groups_profile_id";
+
+/// _metadata key holding where a corrupt database was moved, until get_metadata_status reports it
+const CORRUPT_BACKUP_PATH: &str = "corrupt_backup_path";
+/// _metadata key for this install's id, generated on first use
+const INSTALL_ID: &str = "install_id";
+/// _metadata key set to "true" by a clean exit and to "false" while the app runs
+const CLEAN_SHUTDOWN: &str = "clean_shutdown";
+
+/// _metadata key prefix for the last clock skew measured against a profile's server, in milliseconds
+const CLOCK_SKEW_PREFIX: &str = "clock_skew_ms:";
+
+/// The integrity check reads the whole file, so it only runs on the first open in a process
+static INTEGRITY_CHECKED: AtomicBool = AtomicBool::new(false);
+
+/// Columns selected for a Group, in the order group_from_row reads them
+const GROUP_COLUMNS: &str = "id, name, databases, profile_id, created_by, created_at, updated_at, description";
+
+/// Columns selected for a Snapshot, in the order get_snapshots reads them
+const SNAPSHOT_COLUMNS: &str =
+    "id, group_id, display_name, sequence, created_at, created_by, database_snapshots, is_automatic, notes, origin";
+
+/// Columns selected for a HistoryEntry, in the order history_from_row reads them
+const HISTORY_COLUMNS: &str = "id, operation_type, timestamp, user_name, details, results, group_id";
+
+/// Columns selected for a Profile, in the order profile_from_row reads them
+const PROFILE_COLUMNS: &str = "id, name, platform_type, host, port, username, password, trust_certificate, snapshot_path, description, notes, is_active, created_at, updated_at, server_platform, encryption_mode, overrides";
+
+/// Columns selected for a ConnectionCheck, in the order get_connection_checks reads them
+const CONNECTION_CHECK_COLUMNS: &str = "profile_id, timestamp, success, latency_ms, error";
+
+/// Columns selected for an Operation, in the order operation_from_row reads them
+const OPERATION_COLUMNS: &str = "id, operation_type, group_id, snapshot_id, status, steps, started_at, updated_at";
+
+/// Connection checks kept per profile; older ones are trimmed as new ones are added
+pub const MAX_CONNECTION_CHECKS_PER_PROFILE: u32 = 500;
+
+/// Problems reported by PRAGMA integrity_check, or None when it reports "ok"
+fn integrity_problem(conn: &Connection) -> Result<Option<String>, rusqlite::Error> {
+    let mut stmt = conn.prepare("PRAGMA integrity_check")?;
+    let rows: Vec<String> = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<Result<_, _>>()?;
+
+    if rows.len() == 1 && rows[0] == "ok" {
+        Ok(None)
+    } else {
+        Ok(Some(rows.join("; ")))
+    }
+}
+
+/// SQLite errors that mean the file itself is damaged rather than busy or unreadable
+fn is_corruption_error(error: &rusqlite::Error) -> bool {
+    matches!(
+        error.sqlite_error_code(),
+        Some(rusqlite::ErrorCode::DatabaseCorrupt) | Some(rusqlite::ErrorCode::NotADatabase)
+    )
+}
+
+/// `sqlparrot.db.corrupt-<UTC timestamp>` next to the original file
+fn corrupt_backup_path(path: &Path, now: DateTime<Utc>) -> PathBuf {
+    let file_name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    path.with_file_name(format!("{}.corrupt-{}", file_name, now.format("%Y%m%d%H%M%S")))
+}
+
 pub struct MetadataStore {
     conn: Mutex<Connection>,
 }
 
 impl MetadataStore {
     /// Get the database file path
+    /// SQLPARROT_DATA_DIR takes precedence; otherwise the OS local data directory is used
     pub fn db_path() -> Result<PathBuf, MetadataError> {
+        if let Some(dir) = crate::config::data_dir_override()? {
+            return Ok(dir.join("sqlparrot.db"));
+        }
+
         let data_dir = dirs::data_local_dir().ok_or(MetadataError::NoDirFound)?;
         let app_dir = data_dir.join("SQL Parrot");
         std::fs::create_dir_all(&app_dir).map_err(|_| {
@@ -43,6 +125,13 @@ impl MetadataStore {
     pub fn open() -> Result<Self, MetadataError> {
         let path = Self::db_path()?;
 
+        // A corrupt database would fail every command; move it aside so a fresh one is created below
+        let corrupt_backup = if INTEGRITY_CHECKED.swap(true, Ordering::SeqCst) {
+            None
+        } else {
+            Self::quarantine_if_corrupt(&path)?
+        };
+
         // Check if database exists
         let db_exists = path.exists();
 
@@ -76,12 +165,17 @@ impl MetadataStore {
         }
 
         let conn = Connection::open(&path)?;
+        Self::configure_connection(&conn)?;
 
         let store = Self {
             conn: Mutex::new(conn),
         };
         store.initialize()?;
 
+        if let Some(backup) = corrupt_backup {
+            store.set_metadata_value(CORRUPT_BACKUP_PATH, &backup.to_string_lossy())?;
+        }
+
         // Check version and migrate if needed
         let current_version = env!("CARGO_PKG_VERSION");
         if let Err(e) = store.check_and_migrate(current_version) {
@@ -92,6 +186,56 @@ impl MetadataStore {
         Ok(store)
     }
 
+    /// Rename the database (and its WAL side files) to `<name>.corrupt-<timestamp>` if it fails
+    /// PRAGMA integrity_check, returning where it was moved
+    /// A check that can't run for another reason (e.g. the file is locked) leaves the file alone
+    fn quarantine_if_corrupt(path: &Path) -> Result<Option<PathBuf>, MetadataError> {
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let problem = match Connection::open(path).and_then(|conn| integrity_problem(&conn)) {
+            Ok(problem) => problem,
+            Err(e) if is_corruption_error(&e) => Some(e.to_string()),
+            Err(e) => {
+                eprintln!("Warning: Could not check metadata database integrity: {}", e);
+                None
+            }
+        };
+        let Some(problem) = problem else {
+            return Ok(None);
+        };
+
+        let backup = corrupt_backup_path(path, Utc::now());
+        std::fs::rename(path, &backup)?;
+        for suffix in ["-wal", "-shm"] {
+            let side_file = PathBuf::from(format!("{}{}", path.display(), suffix));
+            if side_file.exists() {
+                std::fs::rename(&side_file, format!("{}{}", backup.display(), suffix))?;
+            }
+        }
+
+        eprintln!(
+            "Warning: Metadata database was corrupt ({}); moved it to {} and created a new one",
+            problem,
+            backup.display()
+        );
+        Ok(Some(backup))
+    }
+
+    /// Apply per-connection settings (SQLite does not persist these in the file)
+    fn configure_connection(conn: &Connection) -> Result<(), MetadataError> {
+        // Foreign keys are off by default in SQLite and must be enabled on every connection
+        conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+
+        // Commands each open their own connection, so let readers proceed during writes
+        // (WAL) and wait briefly on a lock instead of failing with "database is locked".
+        // WAL mode is persisted in the file; the -wal/-shm side files live next to it.
+        conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))?;
+        conn.busy_timeout(Duration::from_millis(BUSY_TIMEOUT_MS))?;
+        Ok(())
+    }
+
     /// Initialize database schema
     fn initialize(&self) -> Result<(), MetadataError> {
         let conn = self.conn.lock().unwrap();
@@ -104,9 +248,11 @@ impl MetadataStore {
                 name TEXT NOT NULL,
                 databases TEXT NOT NULL,
                 profile_id TEXT,
+                description TEXT,
                 created_by TEXT,
                 created_at TEXT NOT NULL,
                 updated_at TEXT NOT NULL,
+                sequence_high_water INTEGER NOT NULL DEFAULT 0,
                 UNIQUE(name, profile_id)
             );
 
@@ -120,7 +266,9 @@ impl MetadataStore {
                 created_by TEXT,
                 database_snapshots TEXT NOT NULL,
                 is_automatic INTEGER DEFAULT 0,
-                FOREIGN KEY (group_id) REFERENCES groups(id)
+                notes TEXT,
+                origin TEXT,
+                FOREIGN KEY (group_id) REFERENCES groups(id) ON DELETE CASCADE
             );
 
             -- History table
@@ -130,7 +278,8 @@ impl MetadataStore {
                 timestamp TEXT NOT NULL,
                 user_name TEXT,
                 details TEXT,
-                results TEXT
+                results TEXT,
+                group_id TEXT
             );
 
             -- Settings table (single row)
@@ -160,13 +309,41 @@ impl MetadataStore {
                 notes TEXT,
                 is_active INTEGER DEFAULT 0,
                 created_at TEXT NOT NULL,
-                updated_at TEXT NOT NULL
+                updated_at TEXT NOT NULL,
+                server_platform TEXT,
+                encryption_mode TEXT NOT NULL DEFAULT 'on',
+                overrides TEXT
+            );
+
+            -- Recent check_health results per profile (trimmed on insert)
+            CREATE TABLE IF NOT EXISTS connection_checks (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                profile_id TEXT NOT NULL,
+                timestamp TEXT NOT NULL,
+                success INTEGER NOT NULL,
+                latency_ms INTEGER,
+                error TEXT,
+                FOREIGN KEY (profile_id) REFERENCES profiles(id) ON DELETE CASCADE
+            );
+
+            -- Unfinished multi-step operations (rows are removed when an operation finishes)
+            CREATE TABLE IF NOT EXISTS operations (
+                id TEXT PRIMARY KEY,
+                operation_type TEXT NOT NULL,
+                group_id TEXT NOT NULL,
+                snapshot_id TEXT,
+                status TEXT NOT NULL,
+                steps TEXT NOT NULL,
+                started_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                FOREIGN KEY (group_id) REFERENCES groups(id) ON DELETE CASCADE
             );
 
             -- Indexes
             CREATE INDEX IF NOT EXISTS idx_snapshots_group ON snapshots(group_id);
             CREATE INDEX IF NOT EXISTS idx_history_timestamp ON history(timestamp);
             CREATE INDEX IF NOT EXISTS idx_profiles_active ON profiles(is_active);
+            CREATE INDEX IF NOT EXISTS idx_connection_checks_profile ON connection_checks(profile_id, timestamp);
             "#,
         )?;
 
@@ -184,12 +361,80 @@ impl MetadataStore {
             conn.execute("ALTER TABLE groups ADD COLUMN profile_id TEXT", [])?;
         }
 
+        if !columns.contains(&"description".to_string()) {
+            conn.execute("ALTER TABLE groups ADD COLUMN description TEXT", [])?;
+        }
+
+        // Highest sequence renumber_snapshots has moved snapshots away from (see get_next_sequence)
+        if !columns.contains(&"sequence_high_water".to_string()) {
+            conn.execute(
+                "ALTER TABLE groups ADD COLUMN sequence_high_water INTEGER NOT NULL DEFAULT 0",
+                [],
+            )?;
+        }
+
         // Now create the index (column should exist now)
         conn.execute(
             "CREATE INDEX IF NOT EXISTS idx_groups_profile_id ON groups(profile_id)",
             [],
         )?;
 
+        // Cached host platform (Windows/Linux) of each profile's server
+        let mut stmt = conn.prepare("PRAGMA table_info('profiles')")?;
+        let profile_columns: Vec<String> = stmt
+            .query_map([], |row| row.get::<_, String>(1))?
+            .filter_map(|r| r.ok())
+            .collect();
+        drop(stmt);
+
+        if !profile_columns.contains(&"server_platform".to_string()) {
+            conn.execute("ALTER TABLE profiles ADD COLUMN server_platform TEXT", [])?;
+        }
+
+        // Encryption can only be turned off per profile, for legacy servers
+        if !profile_columns.contains(&"encryption_mode".to_string()) {
+            conn.execute("ALTER TABLE profiles ADD COLUMN encryption_mode TEXT NOT NULL DEFAULT 'on'", [])?;
+        }
+        if !profile_columns.contains(&"overrides".to_string()) {
+            conn.execute("ALTER TABLE profiles ADD COLUMN overrides TEXT", [])?;
+        }
+
+        // History rows carry their group in a dedicated column so per-group queries can use an index
+        let mut stmt = conn.prepare("PRAGMA table_info('history')")?;
+        let history_columns: Vec<String> = stmt
+            .query_map([], |row| row.get::<_, String>(1))?
+            .filter_map(|r| r.ok())
+            .collect();
+        drop(stmt);
+
+        if !history_columns.contains(&"group_id".to_string()) {
+            conn.execute("ALTER TABLE history ADD COLUMN group_id TEXT", [])?;
+            Self::backfill_history_group_ids(&conn)?;
+        }
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_history_group ON history(group_id, timestamp)",
+            [],
+        )?;
+
+        // Older databases declared the snapshots foreign key without ON DELETE CASCADE
+        Self::migrate_snapshots_cascade_delete(&conn)?;
+
+        // Snapshot notes (added after the cascade rebuild, which copies only the original columns)
+        let mut stmt = conn.prepare("PRAGMA table_info('snapshots')")?;
+        let snapshot_columns: Vec<String> = stmt
+            .query_map([], |row| row.get::<_, String>(1))?
+            .filter_map(|r| r.ok())
+            .collect();
+        drop(stmt);
+
+        if !snapshot_columns.contains(&"notes".to_string()) {
+            conn.execute("ALTER TABLE snapshots ADD COLUMN notes TEXT", [])?;
+        }
+        if !snapshot_columns.contains(&"origin".to_string()) {
+            conn.execute("ALTER TABLE snapshots ADD COLUMN origin TEXT", [])?;
+        }
+
         // Initialize settings if not exists
         conn.execute(
             "INSERT OR IGNORE INTO settings (id, data) VALUES (1, ?)",
@@ -205,6 +450,51 @@ impl MetadataStore {
         Ok(())
     }
 
+    /// Migration: Rebuild the snapshots table so its group foreign key cascades on delete
+    /// SQLite cannot alter a foreign key in place, so the table is recreated and copied over.
+    /// Snapshot rows whose group no longer exists are dropped since they can never be used.
+    fn migrate_snapshots_cascade_delete(conn: &Connection) -> Result<(), MetadataError> {
+        let mut stmt = conn.prepare("PRAGMA foreign_key_list('snapshots')")?;
+        let on_delete_actions: Vec<String> = stmt
+            .query_map([], |row| row.get::<_, String>(6))?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        if on_delete_actions.iter().all(|action| action == "CASCADE") {
+            // Already migrated (or created with the current schema)
+            return Ok(());
+        }
+
+        let tx = conn.unchecked_transaction()?;
+        tx.execute_batch(
+            r#"
+            CREATE TABLE snapshots_new (
+                id TEXT PRIMARY KEY,
+                group_id TEXT NOT NULL,
+                display_name TEXT NOT NULL,
+                sequence INTEGER NOT NULL,
+                created_at TEXT NOT NULL,
+                created_by TEXT,
+                database_snapshots TEXT NOT NULL,
+                is_automatic INTEGER DEFAULT 0,
+                FOREIGN KEY (group_id) REFERENCES groups(id) ON DELETE CASCADE
+            );
+
+            INSERT INTO snapshots_new (id, group_id, display_name, sequence, created_at, created_by, database_snapshots, is_automatic)
+            SELECT id, group_id, display_name, sequence, created_at, created_by, database_snapshots, is_automatic
+            FROM snapshots
+            WHERE group_id IN (SELECT id FROM groups);
+
+            DROP TABLE snapshots;
+            ALTER TABLE snapshots_new RENAME TO snapshots;
+            CREATE INDEX IF NOT EXISTS idx_snapshots_group ON snapshots(group_id);
+            "#,
+        )?;
+        tx.commit()?;
+
+        Ok(())
+    }
+
     /// Get the last version seen from metadata
     pub fn get_last_version_seen(&self) -> Result<String, MetadataError> {
         let conn = self.conn.lock().unwrap();
@@ -229,57 +519,216 @@ impl MetadataStore {
         Ok(())
     }
 
-    /// Check and run migrations if needed
+    /// Run any migration step that hasn't been recorded as done
+    /// Each step is idempotent and marked complete in _metadata on success, so an interrupted
+    /// or failed upgrade resumes on the next start instead of being skipped by the version check
     pub fn check_and_migrate(&self, current_version: &str) -> Result<(), MetadataError> {
-        let last_version = self.get_last_version_seen()?;
-
-        if last_version == current_version {
-            // Already up to date
-            return Ok(());
+        // Introduced in 1.3.0: move config.json connections and preferences into SQLite
+        self.run_migration_step(MIGRATED_CONFIG_PROFILES, || self.migrate_config_json_to_profiles());
+        self.run_migration_step(MIGRATED_CONFIG_PREFERENCES, || self.migrate_config_preferences());
+        if let Err(e) = self.remove_migrated_config_json() {
+            eprintln!("Warning: Failed to delete config.json after migration: {}", e);
         }
 
-        // Migration from versions < 1.3.0: Migrate config.json to profiles table
-        if self.compare_versions(&last_version, "1.3.0") < 0 {
-            if let Err(e) = self.migrate_config_json_to_profiles() {
-                eprintln!("Warning: Failed to migrate config.json to profiles: {}", e);
-                // Continue anyway - migration failures shouldn't prevent app from starting
+        // Introduced in 1.4.0: assign groups from older databases to a profile
+        self.run_migration_step(MIGRATED_GROUPS_PROFILE_ID, || self.migrate_groups_add_profile_id());
+
+        self.update_last_version_seen(current_version)?;
+
+        Ok(())
+    }
+
+    /// Run a migration step unless _metadata says it already completed
+    /// Failures are logged and retried on the next start; they never stop the app
+    fn run_migration_step(&self, key: &str, step: impl FnOnce() -> Result<(), MetadataError>) {
+        match self.is_migration_done(key) {
+            Ok(true) => return,
+            Ok(false) => {}
+            Err(e) => {
+                eprintln!("Warning: Failed to check migration {}: {}", key, e);
+                return;
             }
         }
 
-        // Migration from versions < 1.4.0: Add profile_id to groups table
-        if self.compare_versions(&last_version, "1.4.0") < 0 {
-            if let Err(e) = self.migrate_groups_add_profile_id() {
-                eprintln!("Warning: Failed to add profile_id to groups: {}", e);
-                // Continue anyway - migration failures shouldn't prevent app from starting
+        let result = step().and_then(|_| self.mark_migration_done(key));
+        if let Err(e) = result {
+            eprintln!("Warning: Migration {} failed: {}", key, e);
+        }
+    }
+
+    fn is_migration_done(&self, key: &str) -> Result<bool, MetadataError> {
+        let conn = self.conn.lock().unwrap();
+        match conn.query_row("SELECT value FROM _metadata WHERE key = ?", params![key], |row| {
+            row.get::<_, String>(0)
+        }) {
+            Ok(value) => Ok(value == "true"),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Migration steps not yet recorded as done in _metadata
+    pub fn pending_migrations(&self) -> Result<Vec<&'static str>, MetadataError> {
+        let mut pending = Vec::new();
+        for key in [MIGRATED_CONFIG_PROFILES, MIGRATED_CONFIG_PREFERENCES, MIGRATED_GROUPS_PROFILE_ID] {
+            if !self.is_migration_done(key)? {
+                pending.push(key);
             }
         }
+        Ok(pending)
+    }
 
-        // Update version after migrations
-        self.update_last_version_seen(current_version)?;
+    /// Columns the queries rely on that the schema doesn't have, as `table.column`
+    pub fn missing_columns(&self) -> Result<Vec<String>, MetadataError> {
+        let conn = self.conn.lock().unwrap();
+        let mut missing = Vec::new();
+
+        for (table, expected) in [
+            ("groups", GROUP_COLUMNS),
+            ("snapshots", SNAPSHOT_COLUMNS),
+            ("history", HISTORY_COLUMNS),
+            ("profiles", PROFILE_COLUMNS),
+            ("connection_checks", CONNECTION_CHECK_COLUMNS),
+            ("operations", OPERATION_COLUMNS),
+        ] {
+            let mut stmt = conn.prepare(&format!("PRAGMA table_info('{}')", table))?;
+            let columns: Vec<String> = stmt
+                .query_map([], |row| row.get::<_, String>(1))?
+                .filter_map(|r| r.ok())
+                .collect();
+
+            missing.extend(
+                expected
+                    .split(", ")
+                    .filter(|column| !columns.iter().any(|c| c == column))
+                    .map(|column| format!("{}.{}", table, column)),
+            );
+        }
+
+        Ok(missing)
+    }
 
+    fn set_metadata_value(&self, key: &str, value: &str) -> Result<(), MetadataError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO _metadata (key, value) VALUES (?, ?)",
+            params![key, value],
+        )?;
         Ok(())
     }
 
-    /// Migration: Add profile_id column to groups table
-    fn migrate_groups_add_profile_id(&self) -> Result<(), MetadataError> {
+    /// This install's id, created the first time it's asked for
+    /// Snapshots record it so other installs can tell who made them
+    pub fn install_id(&self) -> Result<String, MetadataError> {
         let conn = self.conn.lock().unwrap();
+        match conn.query_row(
+            "SELECT value FROM _metadata WHERE key = ?",
+            params![INSTALL_ID],
+            |row| row.get::<_, String>(0),
+        ) {
+            Ok(id) => return Ok(id),
+            Err(rusqlite::Error::QueryReturnedNoRows) => {}
+            Err(e) => return Err(e.into()),
+        }
+        let id = Uuid::new_v4().simple().to_string();
+        conn.execute(
+            "INSERT INTO _metadata (key, value) VALUES (?, ?)",
+            params![INSTALL_ID, id],
+        )?;
+        Ok(id)
+    }
 
-        // Check if column already exists
-        let mut stmt = conn.prepare("PRAGMA table_info('groups')")?;
-        let columns: Vec<String> = stmt
-            .query_map([], |row| row.get::<_, String>(1))?
-            .filter_map(|r| r.ok())
-            .collect();
+    /// Whether the previous run exited cleanly, marking this run as not (yet) clean
+    /// A database without the marker (first launch, or older versions) counts as clean
+    pub fn take_clean_shutdown(&self) -> Result<bool, MetadataError> {
+        let conn = self.conn.lock().unwrap();
+        let clean = match conn.query_row(
+            "SELECT value FROM _metadata WHERE key = ?",
+            params![CLEAN_SHUTDOWN],
+            |row| row.get::<_, String>(0),
+        ) {
+            Ok(value) => value != "false",
+            Err(rusqlite::Error::QueryReturnedNoRows) => true,
+            Err(e) => return Err(e.into()),
+        };
+        conn.execute(
+            "INSERT OR REPLACE INTO _metadata (key, value) VALUES (?, 'false')",
+            params![CLEAN_SHUTDOWN],
+        )?;
+        Ok(clean)
+    }
 
-        if columns.contains(&"profile_id".to_string()) {
-            // Column already exists
-            return Ok(());
+    /// Fold the WAL back into the database file at exit, recording a clean shutdown if `clean`
+    pub fn close_for_shutdown(&self, clean: bool) -> Result<(), MetadataError> {
+        if clean {
+            self.set_metadata_value(CLEAN_SHUTDOWN, "true")?;
+        }
+        let conn = self.conn.lock().unwrap();
+        conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+        Ok(())
+    }
+
+    /// Remember how far a profile's server clock is ahead of this machine's (negative when behind)
+    pub fn set_clock_skew(&self, profile_id: &str, skew_ms: i64) -> Result<(), MetadataError> {
+        self.set_metadata_value(&format!("{}{}", CLOCK_SKEW_PREFIX, profile_id), &skew_ms.to_string())
+    }
+
+    /// The last clock skew measured for a profile, if any
+    pub fn get_clock_skew(&self, profile_id: &str) -> Result<Option<i64>, MetadataError> {
+        let conn = self.conn.lock().unwrap();
+        match conn.query_row(
+            "SELECT value FROM _metadata WHERE key = ?",
+            params![format!("{}{}", CLOCK_SKEW_PREFIX, profile_id)],
+            |row| row.get::<_, String>(0),
+        ) {
+            Ok(value) => Ok(value.parse().ok()),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
         }
+    }
+
+    /// Forget a profile's clock skew, e.g. once it points at a different server
+    pub fn clear_clock_skew(&self, profile_id: &str) -> Result<(), MetadataError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "DELETE FROM _metadata WHERE key = ?",
+            params![format!("{}{}", CLOCK_SKEW_PREFIX, profile_id)],
+        )?;
+        Ok(())
+    }
+
+    /// Where a corrupt database was moved when this one was created, cleared once read
+    /// so the warning is only shown once
+    pub fn take_corrupt_backup_path(&self) -> Result<Option<String>, MetadataError> {
+        let conn = self.conn.lock().unwrap();
+        let backup = match conn.query_row(
+            "SELECT value FROM _metadata WHERE key = ?",
+            params![CORRUPT_BACKUP_PATH],
+            |row| row.get::<_, String>(0),
+        ) {
+            Ok(value) => value,
+            Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        conn.execute("DELETE FROM _metadata WHERE key = ?", params![CORRUPT_BACKUP_PATH])?;
+        Ok(Some(backup))
+    }
+
+    fn mark_migration_done(&self, key: &str) -> Result<(), MetadataError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO _metadata (key, value) VALUES (?, 'true')",
+            params![key],
+        )?;
+        Ok(())
+    }
 
-        // Add the column
-        conn.execute("ALTER TABLE groups ADD COLUMN profile_id TEXT", [])?;
+    /// Migration: assign groups without a profile (from before profile_id existed) to the
+    /// active profile, or the first profile if none is active
+    fn migrate_groups_add_profile_id(&self) -> Result<(), MetadataError> {
+        let conn = self.conn.lock().unwrap();
 
-        // Get the active profile (or first profile if none active)
+        // initialize() adds the column itself, so this only has to fill it in
         let active_profile_id: Option<String> = conn
             .query_row(
                 "SELECT id FROM profiles WHERE is_active = 1 LIMIT 1",
@@ -292,87 +741,45 @@ impl MetadataStore {
             .ok();
 
         if let Some(profile_id) = active_profile_id {
-            // Assign existing groups to the active profile
             conn.execute(
                 "UPDATE groups SET profile_id = ? WHERE profile_id IS NULL",
                 params![profile_id],
             )?;
         }
 
-        // Create index for profile_id
-        conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_groups_profile_id ON groups(profile_id)",
-            [],
-        )?;
-
         Ok(())
     }
 
-    /// Compare two version strings (returns -1 if v1 < v2, 0 if equal, 1 if v1 > v2)
-    fn compare_versions(&self, v1: &str, v2: &str) -> i32 {
-        let v1_parts: Vec<u32> = v1.split('.').filter_map(|s| s.parse().ok()).collect();
-        let v2_parts: Vec<u32> = v2.split('.').filter_map(|s| s.parse().ok()).collect();
-
-        for i in 0..v1_parts.len().max(v2_parts.len()) {
-            let v1_val = v1_parts.get(i).copied().unwrap_or(0);
-            let v2_val = v2_parts.get(i).copied().unwrap_or(0);
-
-            if v1_val < v2_val {
-                return -1;
-            } else if v1_val > v2_val {
-                return 1;
-            }
-        }
-        0
-    }
-
-    /// Migrate config.json to profiles table and settings
-    /// Also migrates preferences (theme, max_history_entries) to SQLite settings
-    /// Deletes config.json after successful migration
+    /// Migration: copy config.json connections into the profiles table
+    /// Skipped when profiles already exist, so re-running after a partial upgrade is safe
     fn migrate_config_json_to_profiles(&self) -> Result<(), MetadataError> {
         use crate::config::AppConfig;
-        use std::fs;
-
-        // Check if config.json exists
-        let config_path = match AppConfig::config_path() {
-            Ok(p) => p,
-            Err(_) => {
-                // No config.json, nothing to migrate
-                return Ok(());
-            }
-        };
 
-        if !config_path.exists() {
-            // No config.json, nothing to migrate
-            return Ok(());
+        // No config.json, nothing to migrate
+        match AppConfig::config_path() {
+            Ok(path) if path.exists() => {}
+            _ => return Ok(()),
         }
 
-        // Check if profiles table already has data
+        // An unreadable config.json fails the step, so it is kept and retried next start
+        let config = AppConfig::load().map_err(|e| {
+            MetadataError::Io(std::io::Error::other(format!("Failed to read config.json: {}", e)))
+        })?;
+
         let conn = self.conn.lock().unwrap();
         let profile_count: i32 = conn.query_row(
             "SELECT COUNT(*) FROM profiles",
             [],
             |row| row.get(0),
-        ).unwrap_or(0);
+        )?;
 
         if profile_count > 0 {
-            // Already migrated or profiles exist, skip migration
-            // But still try to migrate preferences if needed
-            drop(conn);
-            self.migrate_config_preferences(&config_path)?;
+            // Already migrated or profiles exist
             return Ok(());
         }
 
-        // Load config.json
-        let config = match AppConfig::load() {
-            Ok(c) => c,
-            Err(_) => {
-                // Failed to load config.json, skip migration
-                return Ok(());
-            }
-        };
-
-        // Migrate each profile from config.json
+        // All profiles go in together so an interruption can't leave half of them behind
+        let tx = conn.unchecked_transaction()?;
         let now = Utc::now().to_rfc3339();
         let mut migrated_profiles = Vec::new();
 
@@ -390,7 +797,7 @@ impl MetadataStore {
                 profile.name.clone()
             };
 
-            conn.execute(
+            tx.execute(
                 "INSERT INTO profiles (id, name, platform_type, host, port, username, password, trust_certificate, snapshot_path, description, notes, is_active, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
                 params![
                     profile_id,
@@ -417,9 +824,8 @@ impl MetadataStore {
             }));
         }
 
-        // Migrate preferences to SQLite settings
+        tx.commit()?;
         drop(conn);
-        self.migrate_config_preferences(&config_path)?;
 
         // Add history entry for migration
         if !migrated_profiles.is_empty() {
@@ -434,42 +840,33 @@ impl MetadataStore {
                     "message": format!("Migrated {} connection(s) in config.json to profile(s)", migrated_profiles.len())
                 })),
                 results: None,
+                group_id: None,
             };
             if let Err(e) = self.add_history(&history_entry) {
                 eprintln!("Warning: Failed to add history entry for config.json migration: {}", e);
             }
         }
 
-        // Delete config.json after successful migration
-        if let Err(e) = fs::remove_file(&config_path) {
-            eprintln!("Warning: Failed to delete config.json after migration: {}", e);
-            // Continue anyway - migration succeeded even if deletion failed
-        }
-
         Ok(())
     }
 
-    /// Migrate preferences from config.json to SQLite settings
-    fn migrate_config_preferences(&self, _config_path: &std::path::Path) -> Result<(), MetadataError> {
+    /// Migration: copy config.json preferences (theme, max history entries) into settings
+    fn migrate_config_preferences(&self) -> Result<(), MetadataError> {
         use crate::config::AppConfig;
 
         // Load config.json to get preferences
-        let config = match AppConfig::load() {
-            Ok(c) => c,
-            Err(_) => return Ok(()), // No config.json, nothing to migrate
-        };
+        match AppConfig::config_path() {
+            Ok(path) if path.exists() => {}
+            _ => return Ok(()), // No config.json, nothing to migrate
+        }
+
+        let config = AppConfig::load().map_err(|e| {
+            MetadataError::Io(std::io::Error::other(format!("Failed to read config.json: {}", e)))
+        })?;
 
         // Get current settings
         let mut settings = self.get_settings().unwrap_or_default();
-
-        // Migrate preferences.theme and preferences.max_history_entries
-        // Only update if not already set in SQLite (preserve existing values)
-        if settings.preferences.max_history_entries == 100 && config.preferences.max_history_entries != 100 {
-            settings.preferences.max_history_entries = config.preferences.max_history_entries;
-        }
-
-        // Note: theme is not currently stored in SQLite Settings model, but we could add it if needed
-        // For now, we'll skip theme migration
+        Self::apply_config_preferences(&mut settings, &config.preferences);
 
         // Save updated settings
         self.update_settings(&settings)?;
@@ -477,6 +874,37 @@ impl MetadataStore {
         Ok(())
     }
 
+    /// Delete config.json once both config migrations are recorded as done
+    fn remove_migrated_config_json(&self) -> Result<(), MetadataError> {
+        use crate::config::AppConfig;
+
+        if !self.is_migration_done(MIGRATED_CONFIG_PROFILES)?
+            || !self.is_migration_done(MIGRATED_CONFIG_PREFERENCES)?
+        {
+            return Ok(());
+        }
+
+        if let Ok(path) = AppConfig::config_path() {
+            if path.exists() {
+                std::fs::remove_file(&path)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Copy preferences.theme and preferences.max_history_entries from config.json into settings
+    /// Only update if not already set in SQLite (preserve existing values)
+    fn apply_config_preferences(settings: &mut Settings, preferences: &crate::config::Preferences) {
+        if settings.preferences.max_history_entries == 100 && preferences.max_history_entries != 100 {
+            settings.preferences.max_history_entries = preferences.max_history_entries;
+        }
+
+        if settings.preferences.theme == crate::models::DEFAULT_THEME && !preferences.theme.trim().is_empty() {
+            settings.preferences.theme = preferences.theme.clone();
+        }
+    }
+
     // ===== Groups =====
 
     /// Get all groups (filtered by active profile)
@@ -493,65 +921,35 @@ impl MetadataStore {
             .ok();
 
         let groups = if let Some(profile_id) = active_profile_id {
-            let mut stmt = conn.prepare(
-                "SELECT id, name, databases, profile_id, created_by, created_at, updated_at FROM groups WHERE profile_id = ? ORDER BY name",
-            )?;
-
-            let rows = stmt.query_map(params![profile_id], |row| {
-                let databases_json: String = row.get(2)?;
-                let databases: Vec<String> =
-                    serde_json::from_str(&databases_json).unwrap_or_default();
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM groups WHERE profile_id = ? ORDER BY name",
+                GROUP_COLUMNS
+            ))?;
 
-                Ok(Group {
-                    id: row.get(0)?,
-                    name: row.get(1)?,
-                    databases,
-                    profile_id: row.get(3)?,
-                    created_by: row.get(4)?,
-                    created_at: row
-                        .get::<_, String>(5)?
-                        .parse()
-                        .unwrap_or_else(|_| Utc::now()),
-                    updated_at: row
-                        .get::<_, String>(6)?
-                        .parse()
-                        .unwrap_or_else(|_| Utc::now()),
-                })
-            })?;
+            let rows = stmt.query_map(params![profile_id], Self::group_from_row)?;
             rows.collect::<Result<Vec<_>, _>>()?
         } else {
             // No active profile, return all groups
-            let mut stmt = conn.prepare(
-                "SELECT id, name, databases, profile_id, created_by, created_at, updated_at FROM groups ORDER BY name",
-            )?;
+            let mut stmt = conn.prepare(&format!("SELECT {} FROM groups ORDER BY name", GROUP_COLUMNS))?;
 
-            let rows = stmt.query_map([], |row| {
-                let databases_json: String = row.get(2)?;
-                let databases: Vec<String> =
-                    serde_json::from_str(&databases_json).unwrap_or_default();
-
-                Ok(Group {
-                    id: row.get(0)?,
-                    name: row.get(1)?,
-                    databases,
-                    profile_id: row.get(3)?,
-                    created_by: row.get(4)?,
-                    created_at: row
-                        .get::<_, String>(5)?
-                        .parse()
-                        .unwrap_or_else(|_| Utc::now()),
-                    updated_at: row
-                        .get::<_, String>(6)?
-                        .parse()
-                        .unwrap_or_else(|_| Utc::now()),
-                })
-            })?;
-            rows.collect::<Result<Vec<_>, _>>()?
-        };
+            let rows = stmt.query_map([], Self::group_from_row)?;
+            rows.collect::<Result<Vec<_>, _>>()?
+        };
 
         Ok(groups)
     }
 
+    /// Get the groups of one profile, whichever profile is active
+    pub fn get_groups_for_profile(&self, profile_id: &str) -> Result<Vec<Group>, MetadataError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM groups WHERE profile_id = ? ORDER BY name",
+            GROUP_COLUMNS
+        ))?;
+        let rows = stmt.query_map(params![profile_id], Self::group_from_row)?;
+        Ok(rows.collect::<Result<Vec<_>, _>>()?)
+    }
+
     /// Get group counts per profile
     pub fn get_group_counts_by_profile(&self) -> Result<std::collections::HashMap<String, u32>, MetadataError> {
         let conn = self.conn.lock().unwrap();
@@ -572,6 +970,40 @@ impl MetadataStore {
         Ok(counts)
     }
 
+    /// Count groups and their automatic/manual snapshots for the active profile
+    /// (all groups when no profile is active, matching get_groups)
+    /// Returns (group_count, automatic_snapshot_count, manual_snapshot_count)
+    pub fn get_summary_counts(&self) -> Result<(u32, u32, u32), MetadataError> {
+        let conn = self.conn.lock().unwrap();
+
+        let active_profile_id: Option<String> = conn
+            .query_row(
+                "SELECT id FROM profiles WHERE is_active = 1 LIMIT 1",
+                [],
+                |row| row.get(0),
+            )
+            .ok();
+
+        let group_count: u32 = conn.query_row(
+            "SELECT COUNT(*) FROM groups WHERE ?1 IS NULL OR profile_id = ?1",
+            params![active_profile_id],
+            |row| row.get(0),
+        )?;
+
+        let (automatic_count, manual_count): (u32, u32) = conn.query_row(
+            "SELECT
+                COALESCE(SUM(CASE WHEN s.is_automatic = 1 THEN 1 ELSE 0 END), 0),
+                COALESCE(SUM(CASE WHEN s.is_automatic = 1 THEN 0 ELSE 1 END), 0)
+             FROM snapshots s
+             JOIN groups g ON g.id = s.group_id
+             WHERE ?1 IS NULL OR g.profile_id = ?1",
+            params![active_profile_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+
+        Ok((group_count, automatic_count, manual_count))
+    }
+
     /// Create a new group
     pub fn create_group(&self, group: &Group) -> Result<(), MetadataError> {
         let conn = self.conn.lock().unwrap();
@@ -587,12 +1019,13 @@ impl MetadataStore {
         });
 
         conn.execute(
-            "INSERT INTO groups (id, name, databases, profile_id, created_by, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?)",
+            "INSERT INTO groups (id, name, databases, profile_id, description, created_by, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
             params![
                 group.id,
                 group.name,
                 serde_json::to_string(&group.databases)?,
                 profile_id,
+                group.description,
                 group.created_by,
                 group.created_at.to_rfc3339(),
                 group.updated_at.to_rfc3339(),
@@ -601,6 +1034,41 @@ impl MetadataStore {
         Ok(())
     }
 
+    /// Map a row selected with GROUP_COLUMNS to a Group
+    fn group_from_row(row: &rusqlite::Row) -> rusqlite::Result<Group> {
+        let databases_json: String = row.get(2)?;
+        let databases: Vec<String> = serde_json::from_str(&databases_json).unwrap_or_default();
+
+        Ok(Group {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            databases,
+            profile_id: row.get(3)?,
+            description: row.get(7)?,
+            created_by: row.get(4)?,
+            created_at: row
+                .get::<_, String>(5)?
+                .parse()
+                .unwrap_or_else(|_| Utc::now()),
+            updated_at: row
+                .get::<_, String>(6)?
+                .parse()
+                .unwrap_or_else(|_| Utc::now()),
+        })
+    }
+
+    /// Get a group by id, whichever profile it belongs to
+    pub fn get_group(&self, group_id: &str) -> Result<Option<Group>, MetadataError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM groups WHERE id = ?", GROUP_COLUMNS))?;
+
+        match stmt.query_row(params![group_id], Self::group_from_row) {
+            Ok(group) => Ok(Some(group)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
     /// Update an existing group
     pub fn update_group(&self, group: &Group) -> Result<(), MetadataError> {
         let conn = self.conn.lock().unwrap();
@@ -629,11 +1097,12 @@ impl MetadataStore {
         };
         
         conn.execute(
-            "UPDATE groups SET name = ?, databases = ?, profile_id = ?, updated_at = ? WHERE id = ?",
+            "UPDATE groups SET name = ?, databases = ?, profile_id = ?, description = ?, updated_at = ? WHERE id = ?",
             params![
                 group.name,
                 serde_json::to_string(&group.databases)?,
                 profile_id,
+                group.description,
                 group.updated_at.to_rfc3339(),
                 group.id,
             ],
@@ -641,7 +1110,7 @@ impl MetadataStore {
         Ok(())
     }
 
-    /// Delete a group
+    /// Delete a group (its snapshot rows are removed by the ON DELETE CASCADE foreign key)
     pub fn delete_group(&self, group_id: &str) -> Result<(), MetadataError> {
         let conn = self.conn.lock().unwrap();
         conn.execute("DELETE FROM groups WHERE id = ?", params![group_id])?;
@@ -653,10 +1122,10 @@ impl MetadataStore {
     /// Get snapshots for a group
     pub fn get_snapshots(&self, group_id: &str) -> Result<Vec<Snapshot>, MetadataError> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT id, group_id, display_name, sequence, created_at, created_by, database_snapshots, is_automatic
-             FROM snapshots WHERE group_id = ? ORDER BY sequence DESC",
-        )?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM snapshots WHERE group_id = ? ORDER BY sequence DESC",
+            SNAPSHOT_COLUMNS
+        ))?;
 
         let snapshots = stmt
             .query_map(params![group_id], |row| {
@@ -675,6 +1144,8 @@ impl MetadataStore {
                     created_by: row.get(5)?,
                     database_snapshots,
                     is_automatic: row.get::<_, i32>(7)? == 1,
+                    notes: row.get(8)?,
+                    origin: row.get(9)?,
                 })
             })?
             .collect::<Result<Vec<_>, _>>()?;
@@ -682,12 +1153,48 @@ impl MetadataStore {
         Ok(snapshots)
     }
 
+    /// Most recent snapshot time for every group that has snapshots
+    pub fn get_last_snapshot_times(&self) -> Result<HashMap<String, DateTime<Utc>>, MetadataError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT group_id, MAX(created_at) FROM snapshots GROUP BY group_id")?;
+
+        let times = stmt
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+            .filter_map(|row| {
+                let (group_id, created_at) = row.ok()?;
+                Some((group_id, created_at.parse().ok()?))
+            })
+            .collect();
+
+        Ok(times)
+    }
+
+    /// Manual and automatic snapshot counts for every group that has snapshots
+    /// Returns group_id -> (manual_count, automatic_count)
+    pub fn get_snapshot_counts_by_group(&self) -> Result<HashMap<String, (u32, u32)>, MetadataError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT group_id,
+                    SUM(CASE WHEN is_automatic = 1 THEN 0 ELSE 1 END),
+                    SUM(CASE WHEN is_automatic = 1 THEN 1 ELSE 0 END)
+             FROM snapshots GROUP BY group_id",
+        )?;
+
+        let counts = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, (row.get::<_, u32>(1)?, row.get::<_, u32>(2)?)))
+            })?
+            .collect::<Result<HashMap<_, _>, _>>()?;
+
+        Ok(counts)
+    }
+
     /// Add a snapshot
     pub fn add_snapshot(&self, snapshot: &Snapshot) -> Result<(), MetadataError> {
         let conn = self.conn.lock().unwrap();
         conn.execute(
-            "INSERT INTO snapshots (id, group_id, display_name, sequence, created_at, created_by, database_snapshots, is_automatic)
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+            "INSERT INTO snapshots (id, group_id, display_name, sequence, created_at, created_by, database_snapshots, is_automatic, notes, origin)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
             params![
                 snapshot.id,
                 snapshot.group_id,
@@ -697,11 +1204,37 @@ impl MetadataStore {
                 snapshot.created_by,
                 serde_json::to_string(&snapshot.database_snapshots)?,
                 if snapshot.is_automatic { 1 } else { 0 },
+                snapshot.notes,
+                snapshot.origin,
             ],
         )?;
         Ok(())
     }
 
+    /// Replace a snapshot's notes; returns false if no snapshot has that id
+    pub fn update_snapshot_notes(&self, snapshot_id: &str, notes: Option<&str>) -> Result<bool, MetadataError> {
+        let conn = self.conn.lock().unwrap();
+        let updated = conn.execute(
+            "UPDATE snapshots SET notes = ? WHERE id = ?",
+            params![notes, snapshot_id],
+        )?;
+        Ok(updated > 0)
+    }
+
+    /// Replace a snapshot's per-database entries (e.g. to mark databases that vanished from the server)
+    pub fn update_snapshot_databases(
+        &self,
+        snapshot_id: &str,
+        database_snapshots: &[DatabaseSnapshot],
+    ) -> Result<(), MetadataError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE snapshots SET database_snapshots = ? WHERE id = ?",
+            params![serde_json::to_string(database_snapshots)?, snapshot_id],
+        )?;
+        Ok(())
+    }
+
     /// Delete a snapshot
     pub fn delete_snapshot(&self, snapshot_id: &str) -> Result<(), MetadataError> {
         let conn = self.conn.lock().unwrap();
@@ -720,50 +1253,194 @@ impl MetadataStore {
     }
 
     /// Get next sequence number for a group
+    /// Duplicated sequences don't block new snapshots (MAX + 1 is still free) but are logged.
+    /// Never goes below the group's high-water mark: renumbered snapshots keep their server names,
+    /// which still carry the old sequences.
     pub fn get_next_sequence(&self, group_id: &str) -> Result<u32, MetadataError> {
         let conn = self.conn.lock().unwrap();
         let max: Option<u32> = conn.query_row(
-            "SELECT MAX(sequence) FROM snapshots WHERE group_id = ?",
+            "SELECT MAX(
+                COALESCE((SELECT MAX(sequence) FROM snapshots WHERE group_id = ?1), 0),
+                COALESCE((SELECT sequence_high_water FROM groups WHERE id = ?1), 0)
+            )",
             params![group_id],
             |row| row.get(0),
         )?;
+
+        let duplicates = Self::count_duplicate_sequences(&conn, group_id)?;
+        if duplicates > 0 {
+            log::warn!(
+                "Group {} has {} duplicated snapshot sequence number(s); run renumber_snapshots to repair",
+                group_id,
+                duplicates
+            );
+        }
+
         Ok(max.unwrap_or(0) + 1)
     }
 
+    /// Number of sequence values used by more than one snapshot in a group
+    fn count_duplicate_sequences(conn: &Connection, group_id: &str) -> Result<usize, MetadataError> {
+        let count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM (
+                SELECT sequence FROM snapshots WHERE group_id = ? GROUP BY sequence HAVING COUNT(*) > 1
+            )",
+            params![group_id],
+            |row| row.get(0),
+        )?;
+        Ok(count as usize)
+    }
+
+    /// Reassign contiguous sequences (1, 2, 3, ...) to a group's snapshots in created_at order,
+    /// which also repairs duplicates
+    /// Only metadata changes; the group's high-water mark keeps new snapshots clear of the server names
+    pub fn renumber_snapshots(&self, group_id: &str) -> Result<RenumberResult, MetadataError> {
+        let conn = self.conn.lock().unwrap();
+        let tx = conn.unchecked_transaction()?;
+
+        let duplicate_count = Self::count_duplicate_sequences(&tx, group_id)?;
+
+        tx.execute(
+            "UPDATE groups SET sequence_high_water = MAX(
+                sequence_high_water,
+                COALESCE((SELECT MAX(sequence) FROM snapshots WHERE group_id = ?1), 0)
+            ) WHERE id = ?1",
+            params![group_id],
+        )?;
+
+        let snapshots: Vec<(String, u32)> = {
+            let mut stmt = tx.prepare(
+                "SELECT id, sequence FROM snapshots WHERE group_id = ? ORDER BY created_at, sequence, id",
+            )?;
+            let rows = stmt.query_map(params![group_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect::<Result<Vec<_>, _>>()?
+        };
+
+        let mut renumbered_count = 0;
+        for (index, (id, sequence)) in snapshots.iter().enumerate() {
+            let new_sequence = index as u32 + 1;
+            if *sequence != new_sequence {
+                tx.execute(
+                    "UPDATE snapshots SET sequence = ? WHERE id = ?",
+                    params![new_sequence, id],
+                )?;
+                renumbered_count += 1;
+            }
+        }
+
+        tx.commit()?;
+
+        Ok(RenumberResult {
+            snapshot_count: snapshots.len(),
+            renumbered_count,
+            duplicate_count,
+        })
+    }
+
     // ===== History =====
 
+    /// Map a row selected with HISTORY_COLUMNS to a HistoryEntry
+    fn history_from_row(row: &rusqlite::Row) -> rusqlite::Result<HistoryEntry> {
+        let details_json: Option<String> = row.get(4)?;
+        let results_json: Option<String> = row.get(5)?;
+        let details: Option<serde_json::Value> =
+            details_json.and_then(|j| serde_json::from_str(&j).ok());
+
+        // Rows the backfill couldn't attribute may still name their group in details
+        let group_id = row.get::<_, Option<String>>(6)?.or_else(|| {
+            details
+                .as_ref()
+                .and_then(|d| d.get("groupId"))
+                .and_then(|g| g.as_str())
+                .map(|g| g.to_string())
+        });
+
+        Ok(HistoryEntry {
+            id: row.get(0)?,
+            operation_type: row.get(1)?,
+            timestamp: row
+                .get::<_, String>(2)?
+                .parse()
+                .unwrap_or_else(|_| Utc::now()),
+            user_name: row.get(3)?,
+            details,
+            results: results_json.and_then(|j| serde_json::from_str(&j).ok()),
+            group_id,
+        })
+    }
+
+    /// Populate history.group_id from the groupId recorded in each row's details JSON
+    fn backfill_history_group_ids(conn: &Connection) -> Result<usize, MetadataError> {
+        let updated = conn.execute(
+            "UPDATE history
+             SET group_id = json_extract(details, '$.groupId')
+             WHERE group_id IS NULL
+               AND details IS NOT NULL
+               AND json_valid(details)
+               AND json_type(details, '$.groupId') = 'text'",
+            [],
+        )?;
+        Ok(updated)
+    }
+
     /// Get history entries
     pub fn get_history(&self, limit: Option<u32>) -> Result<Vec<HistoryEntry>, MetadataError> {
         let conn = self.conn.lock().unwrap();
         let query = match limit {
             Some(l) => format!(
-                "SELECT id, operation_type, timestamp, user_name, details, results
-                 FROM history ORDER BY timestamp DESC LIMIT {}",
-                l
+                "SELECT {} FROM history ORDER BY timestamp DESC LIMIT {}",
+                HISTORY_COLUMNS, l
             ),
-            None => "SELECT id, operation_type, timestamp, user_name, details, results
-                     FROM history ORDER BY timestamp DESC"
-                .to_string(),
+            None => format!("SELECT {} FROM history ORDER BY timestamp DESC", HISTORY_COLUMNS),
         };
 
         let mut stmt = conn.prepare(&query)?;
         let entries = stmt
-            .query_map([], |row| {
-                let details_json: Option<String> = row.get(4)?;
-                let results_json: Option<String> = row.get(5)?;
+            .query_map([], Self::history_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
 
-                Ok(HistoryEntry {
-                    id: row.get(0)?,
-                    operation_type: row.get(1)?,
-                    timestamp: row
-                        .get::<_, String>(2)?
-                        .parse()
-                        .unwrap_or_else(|_| Utc::now()),
-                    user_name: row.get(3)?,
-                    details: details_json.and_then(|j| serde_json::from_str(&j).ok()),
-                    results: results_json.and_then(|j| serde_json::from_str(&j).ok()),
-                })
-            })?
+        Ok(entries)
+    }
+
+    /// Get history entries for a single group, newest first
+    pub fn get_history_for_group(
+        &self,
+        group_id: &str,
+        limit: Option<u32>,
+    ) -> Result<Vec<HistoryEntry>, MetadataError> {
+        let conn = self.conn.lock().unwrap();
+        // A negative LIMIT means no limit in SQLite
+        let limit = limit.map(i64::from).unwrap_or(-1);
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM history WHERE group_id = ?1 ORDER BY timestamp DESC LIMIT ?2",
+            HISTORY_COLUMNS
+        ))?;
+        let entries = stmt
+            .query_map(params![group_id, limit], Self::history_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(entries)
+    }
+
+    /// Get history entries recorded by one user, newest first; None matches entries with no user
+    /// History is capped at max_history_entries, so this scans it without an index on user_name
+    pub fn get_history_for_user(
+        &self,
+        user_name: Option<&str>,
+        limit: Option<u32>,
+    ) -> Result<Vec<HistoryEntry>, MetadataError> {
+        let conn = self.conn.lock().unwrap();
+        // A negative LIMIT means no limit in SQLite
+        let limit = limit.map(i64::from).unwrap_or(-1);
+
+        // IS compares NULL equal to NULL, so one statement covers both cases
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM history WHERE user_name IS ?1 ORDER BY timestamp DESC LIMIT ?2",
+            HISTORY_COLUMNS
+        ))?;
+        let entries = stmt
+            .query_map(params![user_name, limit], Self::history_from_row)?
             .collect::<Result<Vec<_>, _>>()?;
 
         Ok(entries)
@@ -771,18 +1448,31 @@ impl MetadataStore {
 
     /// Add a history entry
     pub fn add_history(&self, entry: &HistoryEntry) -> Result<(), MetadataError> {
+        self.add_history_batch(std::slice::from_ref(entry))
+    }
+
+    /// Add several history entries in one transaction with one prepared statement
+    /// Either every entry is written or none is
+    pub fn add_history_batch(&self, entries: &[HistoryEntry]) -> Result<(), MetadataError> {
         let conn = self.conn.lock().unwrap();
-        conn.execute(
-            "INSERT INTO history (id, operation_type, timestamp, user_name, details, results) VALUES (?, ?, ?, ?, ?, ?)",
-            params![
-                entry.id,
-                entry.operation_type,
-                entry.timestamp.to_rfc3339(),
-                entry.user_name,
-                entry.details.as_ref().map(|d| serde_json::to_string(d).ok()).flatten(),
-                entry.results.as_ref().map(|r| serde_json::to_string(r).ok()).flatten(),
-            ],
-        )?;
+        let tx = conn.unchecked_transaction()?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT INTO history (id, operation_type, timestamp, user_name, details, results, group_id) VALUES (?, ?, ?, ?, ?, ?, ?)",
+            )?;
+            for entry in entries {
+                stmt.execute(params![
+                    entry.id,
+                    entry.operation_type,
+                    entry.timestamp.to_rfc3339(),
+                    entry.user_name,
+                    entry.details.as_ref().and_then(|d| serde_json::to_string(d).ok()),
+                    entry.results.as_ref().and_then(|r| serde_json::to_string(r).ok()),
+                    entry.group_id,
+                ])?;
+            }
+        }
+        tx.commit()?;
         Ok(())
     }
 
@@ -793,32 +1483,162 @@ impl MetadataStore {
         Ok(())
     }
 
-    /// Trim history to max entries
+    /// Trim history to the newest max_entries, returning how many entries were deleted
+    /// One statement does it, so an entry added meanwhile can't make the count wrong
     pub fn trim_history(&self, max_entries: u32) -> Result<u32, MetadataError> {
         let conn = self.conn.lock().unwrap();
+        let tx = conn.unchecked_transaction()?;
+        tx.execute(
+            "DELETE FROM history WHERE id NOT IN (
+                SELECT id FROM history ORDER BY timestamp DESC, rowid DESC LIMIT ?
+            )",
+            params![max_entries],
+        )?;
+        let deleted = tx.changes() as u32;
+        tx.commit()?;
+        Ok(deleted)
+    }
 
-        // Count entries to delete
-        let count: u32 = conn.query_row(
-            "SELECT COUNT(*) FROM history",
-            [],
-            |row| row.get(0),
+    // ===== Connection Checks =====
+
+    /// Record a connection check and trim the profile's checks to MAX_CONNECTION_CHECKS_PER_PROFILE
+    pub fn add_connection_check(&self, check: &ConnectionCheck) -> Result<(), MetadataError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO connection_checks (profile_id, timestamp, success, latency_ms, error) VALUES (?, ?, ?, ?, ?)",
+            params![
+                check.profile_id,
+                check.timestamp.to_rfc3339(),
+                check.success,
+                check.latency_ms,
+                check.error,
+            ],
         )?;
+        conn.execute(
+            "DELETE FROM connection_checks WHERE profile_id = ?1 AND id NOT IN (
+                SELECT id FROM connection_checks WHERE profile_id = ?1 ORDER BY timestamp DESC, id DESC LIMIT ?2
+            )",
+            params![check.profile_id, MAX_CONNECTION_CHECKS_PER_PROFILE],
+        )?;
+        Ok(())
+    }
 
-        if count <= max_entries {
-            return Ok(0);
-        }
+    /// A profile's most recent connection checks, newest first
+    pub fn get_connection_checks(&self, profile_id: &str, limit: u32) -> Result<Vec<ConnectionCheck>, MetadataError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM connection_checks WHERE profile_id = ? ORDER BY timestamp DESC, id DESC LIMIT ?",
+            CONNECTION_CHECK_COLUMNS
+        ))?;
+        let checks = stmt
+            .query_map(params![profile_id, limit], |row| {
+                Ok(ConnectionCheck {
+                    profile_id: row.get(0)?,
+                    timestamp: row
+                        .get::<_, String>(1)?
+                        .parse()
+                        .unwrap_or_else(|_| Utc::now()),
+                    success: row.get(2)?,
+                    latency_ms: row.get(3)?,
+                    error: row.get(4)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(checks)
+    }
 
-        let to_delete = count - max_entries;
+    // ===== Operations =====
+
+    /// Map a row selected with OPERATION_COLUMNS to an Operation
+    fn operation_from_row(row: &rusqlite::Row) -> rusqlite::Result<Operation> {
+        let steps: String = row.get(5)?;
+        Ok(Operation {
+            id: row.get(0)?,
+            operation_type: row.get(1)?,
+            group_id: row.get(2)?,
+            snapshot_id: row.get(3)?,
+            status: match row.get::<_, String>(4)?.as_str() {
+                "in_progress" => OperationStatus::InProgress,
+                _ => OperationStatus::Interrupted,
+            },
+            steps: serde_json::from_str(&steps).map_err(|e| {
+                rusqlite::Error::FromSqlConversionFailure(5, rusqlite::types::Type::Text, Box::new(e))
+            })?,
+            started_at: row
+                .get::<_, String>(6)?
+                .parse()
+                .unwrap_or_else(|_| Utc::now()),
+            updated_at: row
+                .get::<_, String>(7)?
+                .parse()
+                .unwrap_or_else(|_| Utc::now()),
+        })
+    }
 
-        // Delete oldest entries
+    /// Insert or replace an operation's record
+    pub fn save_operation(&self, operation: &Operation) -> Result<(), MetadataError> {
+        let status = match operation.status {
+            OperationStatus::InProgress => "in_progress",
+            OperationStatus::Interrupted => "interrupted",
+        };
+        let conn = self.conn.lock().unwrap();
         conn.execute(
-            "DELETE FROM history WHERE id IN (
-                SELECT id FROM history ORDER BY timestamp ASC LIMIT ?
-            )",
-            params![to_delete],
+            &format!("INSERT OR REPLACE INTO operations ({}) VALUES (?, ?, ?, ?, ?, ?, ?, ?)", OPERATION_COLUMNS),
+            params![
+                operation.id,
+                operation.operation_type,
+                operation.group_id,
+                operation.snapshot_id,
+                status,
+                serde_json::to_string(&operation.steps)?,
+                operation.started_at.to_rfc3339(),
+                operation.updated_at.to_rfc3339(),
+            ],
         )?;
+        Ok(())
+    }
+
+    /// Get one operation by id
+    pub fn get_operation(&self, id: &str) -> Result<Option<Operation>, MetadataError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM operations WHERE id = ?", OPERATION_COLUMNS))?;
+
+        match stmt.query_row(params![id], Self::operation_from_row) {
+            Ok(operation) => Ok(Some(operation)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// All recorded operations, oldest first
+    pub fn get_operations(&self) -> Result<Vec<Operation>, MetadataError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM operations ORDER BY started_at ASC",
+            OPERATION_COLUMNS
+        ))?;
+        let operations = stmt
+            .query_map([], Self::operation_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(operations)
+    }
+
+    /// Remove an operation's record (it finished or was abandoned)
+    pub fn delete_operation(&self, id: &str) -> Result<(), MetadataError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM operations WHERE id = ?", params![id])?;
+        Ok(())
+    }
 
-        Ok(to_delete)
+    /// Mark operations left in progress by an earlier run as interrupted; returns how many
+    /// Called once at startup, before this process has started any operation of its own
+    pub fn mark_operations_interrupted(&self) -> Result<usize, MetadataError> {
+        let conn = self.conn.lock().unwrap();
+        let count = conn.execute(
+            "UPDATE operations SET status = 'interrupted' WHERE status = 'in_progress'",
+            [],
+        )?;
+        Ok(count)
     }
 
     // ===== Settings =====
@@ -844,43 +1664,91 @@ impl MetadataStore {
         Ok(())
     }
 
+    /// Change settings with a read and write that no other connection can come between
+    /// The transaction takes the write lock before reading, so concurrent changes (e.g. two wrong
+    /// passwords at once) each see the other's result instead of overwriting it
+    pub fn modify_settings<R>(&self, change: impl FnOnce(&mut Settings) -> R) -> Result<R, MetadataError> {
+        let result = self.try_modify_settings(|settings| Ok::<R, std::convert::Infallible>(change(settings)))?;
+        Ok(result.unwrap_or_else(|never| match never {}))
+    }
+
+    /// Like modify_settings, but the change can refuse; nothing is written when it returns Err
+    pub fn try_modify_settings<R, E>(
+        &self,
+        change: impl FnOnce(&mut Settings) -> Result<R, E>,
+    ) -> Result<Result<R, E>, MetadataError> {
+        let conn = self.conn.lock().unwrap();
+        let tx = Transaction::new_unchecked(&conn, TransactionBehavior::Immediate)?;
+        let data: String = tx.query_row("SELECT data FROM settings WHERE id = 1", [], |row| row.get(0))?;
+        let mut settings: Settings = serde_json::from_str(&data)?;
+        let result = change(&mut settings);
+        if result.is_ok() {
+            tx.execute(
+                "UPDATE settings SET data = ? WHERE id = 1",
+                params![serde_json::to_string(&settings)?],
+            )?;
+            tx.commit()?;
+        }
+        Ok(result)
+    }
+
     // ===== Profiles =====
 
+    /// Overrides as stored in the profiles row; NULL when none are set
+    fn overrides_json(overrides: &ProfileOverrides) -> Option<String> {
+        if *overrides == ProfileOverrides::default() {
+            None
+        } else {
+            serde_json::to_string(overrides).ok()
+        }
+    }
+
+    /// Map a row selected with PROFILE_COLUMNS to a Profile
+    fn profile_from_row(row: &rusqlite::Row) -> rusqlite::Result<Profile> {
+        Ok(Profile {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            platform_type: row.get(2)?,
+            host: row.get(3)?,
+            port: row.get(4)?,
+            username: row.get(5)?,
+            password: row.get(6)?,
+            trust_certificate: row.get::<_, i32>(7)? == 1,
+            snapshot_path: row.get(8)?,
+            description: row.get(9)?,
+            notes: row.get(10)?,
+            is_active: row.get::<_, i32>(11)? == 1,
+            created_at: row
+                .get::<_, String>(12)?
+                .parse()
+                .unwrap_or_else(|_| Utc::now()),
+            updated_at: row
+                .get::<_, String>(13)?
+                .parse()
+                .unwrap_or_else(|_| Utc::now()),
+            server_platform: row.get(14)?,
+            encryption_mode: EncryptionMode::from_stored(&row.get::<_, String>(15)?),
+            // Unreadable overrides fall back to the global settings rather than hiding the profile
+            overrides: row
+                .get::<_, Option<String>>(16)?
+                .and_then(|json| serde_json::from_str(&json).ok())
+                .unwrap_or_default(),
+        })
+    }
+
     /// Get all profiles
     pub fn get_profiles(&self) -> Result<Vec<Profile>, MetadataError> {
         // Ensure at least one profile is active before getting profiles
         let _ = self.ensure_active_profile();
 
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT id, name, platform_type, host, port, username, password, trust_certificate, snapshot_path, description, notes, is_active, created_at, updated_at FROM profiles ORDER BY is_active DESC, name",
-        )?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM profiles ORDER BY is_active DESC, name",
+            PROFILE_COLUMNS
+        ))?;
 
         let profiles = stmt
-            .query_map([], |row| {
-                Ok(Profile {
-                    id: row.get(0)?,
-                    name: row.get(1)?,
-                    platform_type: row.get(2)?,
-                    host: row.get(3)?,
-                    port: row.get(4)?,
-                    username: row.get(5)?,
-                    password: row.get(6)?,
-                    trust_certificate: row.get::<_, i32>(7)? == 1,
-                    snapshot_path: row.get(8)?,
-                    description: row.get(9)?,
-                    notes: row.get(10)?,
-                    is_active: row.get::<_, i32>(11)? == 1,
-                    created_at: row
-                        .get::<_, String>(12)?
-                        .parse()
-                        .unwrap_or_else(|_| Utc::now()),
-                    updated_at: row
-                        .get::<_, String>(13)?
-                        .parse()
-                        .unwrap_or_else(|_| Utc::now()),
-                })
-            })?
+            .query_map([], Self::profile_from_row)?
             .collect::<Result<Vec<_>, _>>()?;
 
         Ok(profiles)
@@ -892,34 +1760,12 @@ impl MetadataStore {
         let _ = self.ensure_active_profile();
 
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT id, name, platform_type, host, port, username, password, trust_certificate, snapshot_path, description, notes, is_active, created_at, updated_at FROM profiles WHERE is_active = 1 LIMIT 1",
-        )?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM profiles WHERE is_active = 1 LIMIT 1",
+            PROFILE_COLUMNS
+        ))?;
 
-        match stmt.query_row([], |row| {
-            Ok(Profile {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                platform_type: row.get(2)?,
-                host: row.get(3)?,
-                port: row.get(4)?,
-                username: row.get(5)?,
-                password: row.get(6)?,
-                trust_certificate: row.get::<_, i32>(7)? == 1,
-                snapshot_path: row.get(8)?,
-                description: row.get(9)?,
-                notes: row.get(10)?,
-                is_active: row.get::<_, i32>(11)? == 1,
-                created_at: row
-                    .get::<_, String>(12)?
-                    .parse()
-                    .unwrap_or_else(|_| Utc::now()),
-                updated_at: row
-                    .get::<_, String>(13)?
-                    .parse()
-                    .unwrap_or_else(|_| Utc::now()),
-            })
-        }) {
+        match stmt.query_row([], Self::profile_from_row) {
             Ok(profile) => Ok(Some(profile)),
             Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
             Err(e) => Err(e.into()),
@@ -929,34 +1775,12 @@ impl MetadataStore {
     /// Get a single profile by ID
     pub fn get_profile(&self, profile_id: &str) -> Result<Option<Profile>, MetadataError> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT id, name, platform_type, host, port, username, password, trust_certificate, snapshot_path, description, notes, is_active, created_at, updated_at FROM profiles WHERE id = ? LIMIT 1",
-        )?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM profiles WHERE id = ? LIMIT 1",
+            PROFILE_COLUMNS
+        ))?;
 
-        match stmt.query_row(params![profile_id], |row| {
-            Ok(Profile {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                platform_type: row.get(2)?,
-                host: row.get(3)?,
-                port: row.get(4)?,
-                username: row.get(5)?,
-                password: row.get(6)?,
-                trust_certificate: row.get::<_, i32>(7)? == 1,
-                snapshot_path: row.get(8)?,
-                description: row.get(9)?,
-                notes: row.get(10)?,
-                is_active: row.get::<_, i32>(11)? == 1,
-                created_at: row
-                    .get::<_, String>(12)?
-                    .parse()
-                    .unwrap_or_else(|_| Utc::now()),
-                updated_at: row
-                    .get::<_, String>(13)?
-                    .parse()
-                    .unwrap_or_else(|_| Utc::now()),
-            })
-        }) {
+        match stmt.query_row(params![profile_id], Self::profile_from_row) {
             Ok(profile) => Ok(Some(profile)),
             Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
             Err(e) => Err(e.into()),
@@ -972,8 +1796,26 @@ impl MetadataStore {
             conn.execute("UPDATE profiles SET is_active = 0", [])?;
         }
 
+        Self::insert_profile(&conn, profile)
+    }
+
+    /// Create several profiles in one transaction; either every profile is created or none is
+    pub fn create_profiles(&self, profiles: &[Profile]) -> Result<(), MetadataError> {
+        let conn = self.conn.lock().unwrap();
+        let tx = conn.unchecked_transaction()?;
+        for profile in profiles {
+            if profile.is_active {
+                tx.execute("UPDATE profiles SET is_active = 0", [])?;
+            }
+            Self::insert_profile(&tx, profile)?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn insert_profile(conn: &Connection, profile: &Profile) -> Result<(), MetadataError> {
         conn.execute(
-            "INSERT INTO profiles (id, name, platform_type, host, port, username, password, trust_certificate, snapshot_path, description, notes, is_active, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            "INSERT INTO profiles (id, name, platform_type, host, port, username, password, trust_certificate, snapshot_path, description, notes, is_active, created_at, updated_at, server_platform, encryption_mode, overrides) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
             params![
                 profile.id,
                 profile.name,
@@ -989,6 +1831,9 @@ impl MetadataStore {
                 if profile.is_active { 1 } else { 0 },
                 profile.created_at.to_rfc3339(),
                 profile.updated_at.to_rfc3339(),
+                profile.server_platform.as_ref(),
+                profile.encryption_mode.as_str(),
+                Self::overrides_json(&profile.overrides),
             ],
         )?;
         Ok(())
@@ -1004,7 +1849,7 @@ impl MetadataStore {
         }
 
         conn.execute(
-            "UPDATE profiles SET name = ?, platform_type = ?, host = ?, port = ?, username = ?, password = ?, trust_certificate = ?, snapshot_path = ?, description = ?, notes = ?, is_active = ?, updated_at = ? WHERE id = ?",
+            "UPDATE profiles SET name = ?, platform_type = ?, host = ?, port = ?, username = ?, password = ?, trust_certificate = ?, snapshot_path = ?, description = ?, notes = ?, is_active = ?, updated_at = ?, server_platform = ?, encryption_mode = ?, overrides = ? WHERE id = ?",
             params![
                 profile.name,
                 profile.platform_type,
@@ -1018,6 +1863,9 @@ impl MetadataStore {
                 profile.notes.as_ref(),
                 if profile.is_active { 1 } else { 0 },
                 profile.updated_at.to_rfc3339(),
+                profile.server_platform.as_ref(),
+                profile.encryption_mode.as_str(),
+                Self::overrides_json(&profile.overrides),
                 profile.id,
             ],
         )?;
@@ -1027,40 +1875,28 @@ impl MetadataStore {
     /// Find profile by host, port, and username (for migration matching)
     pub fn find_profile_by_connection(&self, host: &str, port: u16, username: &str) -> Result<Option<Profile>, MetadataError> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT id, name, platform_type, host, port, username, password, trust_certificate, snapshot_path, description, notes, is_active, created_at, updated_at FROM profiles WHERE host = ? AND port = ? AND username = ? LIMIT 1",
-        )?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM profiles WHERE host = ? AND port = ? AND username = ? LIMIT 1",
+            PROFILE_COLUMNS
+        ))?;
 
-        match stmt.query_row(params![host, port, username], |row| {
-            Ok(Profile {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                platform_type: row.get(2)?,
-                host: row.get(3)?,
-                port: row.get(4)?,
-                username: row.get(5)?,
-                password: row.get(6)?,
-                trust_certificate: row.get::<_, i32>(7)? == 1,
-                snapshot_path: row.get(8)?,
-                description: row.get(9)?,
-                notes: row.get(10)?,
-                is_active: row.get::<_, i32>(11)? == 1,
-                created_at: row
-                    .get::<_, String>(12)?
-                    .parse()
-                    .unwrap_or_else(|_| Utc::now()),
-                updated_at: row
-                    .get::<_, String>(13)?
-                    .parse()
-                    .unwrap_or_else(|_| Utc::now()),
-            })
-        }) {
+        match stmt.query_row(params![host, port, username], Self::profile_from_row) {
             Ok(profile) => Ok(Some(profile)),
             Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
             Err(e) => Err(e.into()),
         }
     }
 
+    /// Cache the detected host platform (e.g. "Windows", "Linux") of a profile's server
+    pub fn set_profile_server_platform(&self, profile_id: &str, platform: &str) -> Result<(), MetadataError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE profiles SET server_platform = ? WHERE id = ?",
+            params![platform, profile_id],
+        )?;
+        Ok(())
+    }
+
     /// Delete a profile
     pub fn delete_profile(&self, profile_id: &str) -> Result<(), MetadataError> {
         let conn = self.conn.lock().unwrap();
@@ -1130,6 +1966,7 @@ mod tests {
 
         // Create a new connection for testing
         let conn = Connection::open(&db_path).unwrap();
+        MetadataStore::configure_connection(&conn).unwrap();
 
         // Initialize schema
         conn.execute(
@@ -1147,7 +1984,8 @@ mod tests {
                 notes TEXT,
                 is_active INTEGER NOT NULL DEFAULT 0,
                 created_at TEXT NOT NULL,
-                updated_at TEXT NOT NULL
+                updated_at TEXT NOT NULL,
+                server_platform TEXT
             )",
             [],
         ).unwrap();
@@ -1175,30 +2013,88 @@ mod tests {
             conn: Mutex::new(conn),
         };
 
+        // Create the remaining tables (snapshots, history, settings, ...) from the real schema
+        store.initialize().unwrap();
+
         (store, temp_dir)
     }
 
-    #[test]
-    fn test_ensure_active_profile_activates_first_when_none_active() {
-        let (store, _temp_dir) = create_test_store();
-
-        // Create two profiles, both inactive
-        let profile1 = Profile {
-            id: "profile-1".to_string(),
-            name: "Test Profile 1".to_string(),
-            platform_type: "Microsoft SQL Server".to_string(),
-            host: "localhost".to_string(),
-            port: 1433,
-            username: "sa".to_string(),
-            password: "password1".to_string(),
-            trust_certificate: true,
-            snapshot_path: "/var/opt/mssql/snapshots".to_string(),
+    fn test_group(id: &str, name: &str, databases: &[&str]) -> Group {
+        Group {
+            id: id.to_string(),
+            name: name.to_string(),
+            databases: databases.iter().map(|d| d.to_string()).collect(),
+            profile_id: None,
             description: None,
-            notes: None,
-            is_active: false,
+            created_by: None,
             created_at: Utc::now(),
             updated_at: Utc::now(),
-        };
+        }
+    }
+
+    fn test_snapshot(id: &str, group_id: &str, sequence: u32) -> Snapshot {
+        Snapshot {
+            id: id.to_string(),
+            group_id: group_id.to_string(),
+            display_name: format!("Snapshot {}", sequence),
+            sequence,
+            created_at: Utc::now(),
+            created_by: Some("test_user".to_string()),
+            database_snapshots: vec![],
+            is_automatic: false,
+            notes: None,
+            origin: None,
+        }
+    }
+
+    #[test]
+    fn test_clean_shutdown_marker() {
+        let (store, _temp_dir) = create_test_store();
+
+        // First launch counts as clean; a run that never closes doesn't
+        assert!(store.take_clean_shutdown().unwrap());
+        assert!(!store.take_clean_shutdown().unwrap());
+
+        store.close_for_shutdown(true).unwrap();
+        assert!(store.take_clean_shutdown().unwrap());
+
+        // An exit that cut operations short leaves the marker unset
+        store.close_for_shutdown(false).unwrap();
+        assert!(!store.take_clean_shutdown().unwrap());
+    }
+
+    #[test]
+    fn test_install_id_is_created_once() {
+        let (store, _temp_dir) = create_test_store();
+        let install_id = store.install_id().unwrap();
+        assert_eq!(install_id.len(), 32);
+        assert_eq!(store.install_id().unwrap(), install_id);
+    }
+
+    #[test]
+    fn test_ensure_active_profile_activates_first_when_none_active() {
+        let (store, _temp_dir) = create_test_store();
+
+        // Create two profiles, both inactive
+        let profile1 = Profile {
+            id: "profile-1".to_string(),
+            name: "Test Profile 1".to_string(),
+            platform_type: "Microsoft SQL Server".to_string(),
+            host: "localhost".to_string(),
+            port: 1433,
+            username: "sa".to_string(),
+            password: "password1".to_string(),
+            trust_certificate: true,
+            snapshot_path: "/var/opt/mssql/snapshots".to_string(),
+            description: None,
+            notes: None,
+            is_active: false,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            server_platform: None,
+            encryption_mode: EncryptionMode::default(),
+            overrides: ProfileOverrides::default(),
+        };
 
         let profile2 = Profile {
             id: "profile-2".to_string(),
@@ -1215,6 +2111,9 @@ mod tests {
             is_active: false,
             created_at: Utc::now(),
             updated_at: Utc::now(),
+            server_platform: None,
+            encryption_mode: EncryptionMode::default(),
+            overrides: ProfileOverrides::default(),
         };
 
         // Insert profiles
@@ -1250,6 +2149,9 @@ mod tests {
             is_active: true,
             created_at: Utc::now(),
             updated_at: Utc::now(),
+            server_platform: None,
+            encryption_mode: EncryptionMode::default(),
+            overrides: ProfileOverrides::default(),
         };
 
         store.create_profile(&profile).unwrap();
@@ -1297,6 +2199,9 @@ mod tests {
             is_active: false,
             created_at: Utc::now(),
             updated_at: Utc::now(),
+            server_platform: None,
+            encryption_mode: EncryptionMode::default(),
+            overrides: ProfileOverrides::default(),
         };
 
         store.create_profile(&profile).unwrap();
@@ -1329,6 +2234,9 @@ mod tests {
             is_active: false,
             created_at: Utc::now(),
             updated_at: Utc::now(),
+            server_platform: None,
+            encryption_mode: EncryptionMode::default(),
+            overrides: ProfileOverrides::default(),
         };
 
         store.create_profile(&profile).unwrap();
@@ -1360,6 +2268,9 @@ mod tests {
             is_active: true,
             created_at: Utc::now(),
             updated_at: Utc::now(),
+            server_platform: None,
+            encryption_mode: EncryptionMode::default(),
+            overrides: ProfileOverrides::default(),
         };
 
         let profile2 = Profile {
@@ -1377,6 +2288,9 @@ mod tests {
             is_active: false,
             created_at: Utc::now(),
             updated_at: Utc::now(),
+            server_platform: None,
+            encryption_mode: EncryptionMode::default(),
+            overrides: ProfileOverrides::default(),
         };
 
         store.create_profile(&profile1).unwrap();
@@ -1388,6 +2302,7 @@ mod tests {
             name: "Test Group".to_string(),
             databases: vec!["db1".to_string()],
             profile_id: Some("profile-2".to_string()), // Explicitly assign to profile 2
+            description: None,
             created_by: Some("test_user".to_string()),
             created_at: Utc::now(),
             updated_at: Utc::now(),
@@ -1427,6 +2342,9 @@ mod tests {
             is_active: true,
             created_at: Utc::now(),
             updated_at: Utc::now(),
+            server_platform: None,
+            encryption_mode: EncryptionMode::default(),
+            overrides: ProfileOverrides::default(),
         };
 
         store.create_profile(&profile1).unwrap();
@@ -1437,6 +2355,7 @@ mod tests {
             name: "Test Group".to_string(),
             databases: vec!["db1".to_string()],
             profile_id: None, // Should use active profile
+            description: None,
             created_by: Some("test_user".to_string()),
             created_at: Utc::now(),
             updated_at: Utc::now(),
@@ -1471,6 +2390,9 @@ mod tests {
             is_active: true,
             created_at: Utc::now(),
             updated_at: Utc::now(),
+            server_platform: None,
+            encryption_mode: EncryptionMode::default(),
+            overrides: ProfileOverrides::default(),
         };
 
         let profile2 = Profile {
@@ -1488,6 +2410,9 @@ mod tests {
             is_active: false,
             created_at: Utc::now(),
             updated_at: Utc::now(),
+            server_platform: None,
+            encryption_mode: EncryptionMode::default(),
+            overrides: ProfileOverrides::default(),
         };
 
         store.create_profile(&profile1).unwrap();
@@ -1499,6 +2424,7 @@ mod tests {
             name: "Test Group".to_string(),
             databases: vec!["db1".to_string()],
             profile_id: Some("profile-1".to_string()),
+            description: None,
             created_by: Some("test_user".to_string()),
             created_at: Utc::now(),
             updated_at: Utc::now(),
@@ -1512,6 +2438,7 @@ mod tests {
             name: "Updated Group".to_string(),
             databases: vec!["db1".to_string(), "db2".to_string()],
             profile_id: Some("profile-2".to_string()), // Change to profile 2
+            description: None,
             created_by: Some("test_user".to_string()),
             created_at: Utc::now(),
             updated_at: Utc::now(),
@@ -1552,6 +2479,9 @@ mod tests {
             is_active: true,
             created_at: Utc::now(),
             updated_at: Utc::now(),
+            server_platform: None,
+            encryption_mode: EncryptionMode::default(),
+            overrides: ProfileOverrides::default(),
         };
 
         let profile2 = Profile {
@@ -1569,6 +2499,9 @@ mod tests {
             is_active: false,
             created_at: Utc::now(),
             updated_at: Utc::now(),
+            server_platform: None,
+            encryption_mode: EncryptionMode::default(),
+            overrides: ProfileOverrides::default(),
         };
 
         store.create_profile(&profile1).unwrap();
@@ -1580,6 +2513,7 @@ mod tests {
             name: "Test Group".to_string(),
             databases: vec!["db1".to_string()],
             profile_id: Some("profile-2".to_string()),
+            description: None,
             created_by: Some("test_user".to_string()),
             created_at: Utc::now(),
             updated_at: Utc::now(),
@@ -1593,6 +2527,7 @@ mod tests {
             name: "Updated Group".to_string(),
             databases: vec!["db1".to_string(), "db2".to_string()],
             profile_id: None, // Not provided - should preserve existing
+            description: None,
             created_by: Some("test_user".to_string()),
             created_at: Utc::now(),
             updated_at: Utc::now(),
@@ -1612,4 +2547,813 @@ mod tests {
         assert_eq!(profile_id, Some("profile-2".to_string())); // Should still be profile-2
         assert_eq!(name, "Updated Group".to_string());
     }
+
+    #[test]
+    fn test_delete_group_cascades_to_snapshots() {
+        let (store, _temp_dir) = create_test_store();
+
+        store.create_group(&test_group("group-1", "Test Group", &["db1"])).unwrap();
+
+        store.add_snapshot(&test_snapshot("snap-1", "group-1", 1)).unwrap();
+        store.add_snapshot(&test_snapshot("snap-2", "group-1", 2)).unwrap();
+        assert_eq!(store.get_snapshots("group-1").unwrap().len(), 2);
+
+        store.delete_group("group-1").unwrap();
+
+        let conn = store.conn.lock().unwrap();
+        let remaining: i32 = conn.query_row(
+            "SELECT COUNT(*) FROM snapshots WHERE group_id = ?",
+            params!["group-1"],
+            |row| row.get(0),
+        ).unwrap();
+        assert_eq!(remaining, 0);
+    }
+
+    #[test]
+    fn test_get_groups_for_profile() {
+        let (store, _temp_dir) = create_test_store();
+        for (id, profile_id) in [("group-1", "profile-1"), ("group-2", "profile-2"), ("group-3", "profile-2")] {
+            store
+                .create_group(&Group {
+                    profile_id: Some(profile_id.to_string()),
+                    ..test_group(id, id, &["db1"])
+                })
+                .unwrap();
+        }
+
+        let ids: Vec<String> = store
+            .get_groups_for_profile("profile-2")
+            .unwrap()
+            .into_iter()
+            .map(|g| g.id)
+            .collect();
+        assert_eq!(ids, vec!["group-2".to_string(), "group-3".to_string()]);
+        assert!(store.get_groups_for_profile("missing").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_group_description_round_trips() {
+        let (store, _temp_dir) = create_test_store();
+
+        let group = Group {
+            description: Some("Nightly reporting databases".to_string()),
+            ..test_group("group-1", "Reporting", &["db1"])
+        };
+        store.create_group(&group).unwrap();
+        assert_eq!(
+            store.get_groups().unwrap()[0].description.as_deref(),
+            Some("Nightly reporting databases")
+        );
+
+        store.update_group(&Group { description: None, ..group }).unwrap();
+        assert_eq!(store.get_groups().unwrap()[0].description, None);
+    }
+
+    #[test]
+    fn test_renumber_snapshots_makes_sequences_contiguous() {
+        let (store, _temp_dir) = create_test_store();
+        store.create_group(&test_group("group-1", "Group", &["db1"])).unwrap();
+
+        let at = |hour: u32| {
+            format!("2026-01-01T{:02}:00:00+00:00", hour)
+                .parse::<DateTime<Utc>>()
+                .unwrap()
+        };
+        // Sparse (3, 7) and duplicated (7, 7) sequences
+        for (id, sequence, hour) in [("snap-a", 3, 1), ("snap-b", 7, 2), ("snap-c", 7, 3)] {
+            store
+                .add_snapshot(&Snapshot { created_at: at(hour), ..test_snapshot(id, "group-1", sequence) })
+                .unwrap();
+        }
+
+        let result = store.renumber_snapshots("group-1").unwrap();
+        assert_eq!(result.snapshot_count, 3);
+        assert_eq!(result.renumbered_count, 3);
+        assert_eq!(result.duplicate_count, 1);
+
+        let mut sequences: Vec<(String, u32)> = store
+            .get_snapshots("group-1")
+            .unwrap()
+            .into_iter()
+            .map(|s| (s.id, s.sequence))
+            .collect();
+        sequences.sort();
+        assert_eq!(
+            sequences,
+            vec![("snap-a".to_string(), 1), ("snap-b".to_string(), 2), ("snap-c".to_string(), 3)]
+        );
+        // The server snapshots still carry 3 and 7 in their names
+        assert_eq!(store.get_next_sequence("group-1").unwrap(), 8);
+    }
+
+    #[test]
+    fn test_create_after_renumber_does_not_reuse_server_names() {
+        let (store, _temp_dir) = create_test_store();
+        store.create_group(&test_group("group-1", "QA", &["Sales"])).unwrap();
+
+        let name = |sequence: u32| format!("Sales_snapshot_QA_{}", sequence);
+        let with_database = |id: &str, sequence: u32| Snapshot {
+            database_snapshots: vec![DatabaseSnapshot {
+                database: "Sales".to_string(),
+                snapshot_name: name(sequence),
+                success: true,
+                error: None,
+                server_created_at: None,
+            }],
+            ..test_snapshot(id, "group-1", sequence)
+        };
+        store.add_snapshot(&with_database("snap-a", 3)).unwrap();
+        store.add_snapshot(&with_database("snap-b", 7)).unwrap();
+        store.renumber_snapshots("group-1").unwrap();
+
+        // Create the next snapshot the way create_snapshot numbers it
+        let sequence = store.get_next_sequence("group-1").unwrap();
+        store.add_snapshot(&with_database("snap-c", sequence)).unwrap();
+
+        let names: Vec<String> = store
+            .get_snapshots("group-1")
+            .unwrap()
+            .into_iter()
+            .map(|s| s.database_snapshots[0].snapshot_name.clone())
+            .collect();
+        assert_eq!(names.iter().filter(|n| **n == name(sequence)).count(), 1);
+        assert!(sequence > 7);
+
+        // Deleting the newest snapshot frees its number, but nothing at or below the high-water mark
+        store.delete_snapshot("snap-c").unwrap();
+        store.renumber_snapshots("group-1").unwrap();
+        assert_eq!(store.get_next_sequence("group-1").unwrap(), sequence);
+    }
+
+    #[test]
+    fn test_get_last_snapshot_times_returns_latest_per_group() {
+        let (store, _temp_dir) = create_test_store();
+
+        for id in ["group-1", "group-2"] {
+            store.create_group(&test_group(id, id, &["db1"])).unwrap();
+        }
+
+        let older = "2026-01-01T08:00:00+00:00".parse::<DateTime<Utc>>().unwrap();
+        let newer = "2026-01-02T08:00:00+00:00".parse::<DateTime<Utc>>().unwrap();
+        store
+            .add_snapshot(&Snapshot { created_at: newer, ..test_snapshot("snap-1", "group-1", 1) })
+            .unwrap();
+        store
+            .add_snapshot(&Snapshot { created_at: older, ..test_snapshot("snap-2", "group-1", 2) })
+            .unwrap();
+
+        let times = store.get_last_snapshot_times().unwrap();
+        assert_eq!(times.get("group-1"), Some(&newer));
+        assert!(!times.contains_key("group-2"));
+    }
+
+    #[test]
+    fn test_get_snapshot_counts_by_group_splits_manual_and_automatic() {
+        let (store, _temp_dir) = create_test_store();
+
+        for id in ["group-1", "group-2", "group-3"] {
+            store.create_group(&test_group(id, id, &["db1"])).unwrap();
+        }
+
+        store.add_snapshot(&test_snapshot("snap-1", "group-1", 1)).unwrap();
+        store.add_snapshot(&test_snapshot("snap-2", "group-1", 2)).unwrap();
+        store
+            .add_snapshot(&Snapshot { is_automatic: true, ..test_snapshot("snap-3", "group-1", 3) })
+            .unwrap();
+        store.add_snapshot(&test_snapshot("snap-4", "group-2", 1)).unwrap();
+
+        let counts = store.get_snapshot_counts_by_group().unwrap();
+        assert_eq!(counts.get("group-1"), Some(&(2, 1)));
+        assert_eq!(counts.get("group-2"), Some(&(1, 0)));
+        assert!(!counts.contains_key("group-3"));
+    }
+
+    #[test]
+    fn test_snapshot_notes_round_trip_and_update() {
+        let (store, _temp_dir) = create_test_store();
+        store.create_group(&test_group("group-1", "group-1", &["db1"])).unwrap();
+
+        store
+            .add_snapshot(&Snapshot {
+                notes: Some("before the data fix".to_string()),
+                ..test_snapshot("snap-1", "group-1", 1)
+            })
+            .unwrap();
+        assert_eq!(
+            store.get_snapshots("group-1").unwrap()[0].notes.as_deref(),
+            Some("before the data fix")
+        );
+
+        assert!(store.update_snapshot_notes("snap-1", None).unwrap());
+        assert_eq!(store.get_snapshots("group-1").unwrap()[0].notes, None);
+        assert!(!store.update_snapshot_notes("missing", Some("x")).unwrap());
+    }
+
+    #[test]
+    fn test_migrate_snapshots_cascade_delete_rebuilds_old_table() {
+        let temp_dir = TempDir::new().unwrap();
+        let conn = Connection::open(temp_dir.path().join("old.db")).unwrap();
+
+        // Old schema: foreign key without ON DELETE CASCADE, plus an orphaned row
+        conn.execute_batch(
+            "PRAGMA foreign_keys = OFF;
+             CREATE TABLE groups (id TEXT PRIMARY KEY);
+             CREATE TABLE snapshots (
+                id TEXT PRIMARY KEY,
+                group_id TEXT NOT NULL,
+                display_name TEXT NOT NULL,
+                sequence INTEGER NOT NULL,
+                created_at TEXT NOT NULL,
+                created_by TEXT,
+                database_snapshots TEXT NOT NULL,
+                is_automatic INTEGER DEFAULT 0,
+                FOREIGN KEY (group_id) REFERENCES groups(id)
+             );
+             INSERT INTO groups (id) VALUES ('group-1');
+             INSERT INTO snapshots VALUES ('snap-1', 'group-1', 'Snapshot 1', 1, '2024-01-01T00:00:00Z', NULL, '[]', 0);
+             INSERT INTO snapshots VALUES ('snap-2', 'gone', 'Snapshot 1', 1, '2024-01-01T00:00:00Z', NULL, '[]', 0);",
+        ).unwrap();
+
+        MetadataStore::migrate_snapshots_cascade_delete(&conn).unwrap();
+        MetadataStore::configure_connection(&conn).unwrap();
+
+        let ids: Vec<String> = conn
+            .prepare("SELECT id FROM snapshots")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(ids, vec!["snap-1".to_string()]);
+
+        conn.execute("DELETE FROM groups WHERE id = 'group-1'", []).unwrap();
+        let remaining: i32 = conn.query_row("SELECT COUNT(*) FROM snapshots", [], |row| row.get(0)).unwrap();
+        assert_eq!(remaining, 0);
+
+        // Running again is a no-op
+        MetadataStore::migrate_snapshots_cascade_delete(&conn).unwrap();
+    }
+
+    #[test]
+    fn test_configure_connection_enables_wal_and_busy_timeout() {
+        let (store, _temp_dir) = create_test_store();
+        let conn = store.conn.lock().unwrap();
+
+        let journal_mode: String = conn.query_row("PRAGMA journal_mode", [], |row| row.get(0)).unwrap();
+        assert_eq!(journal_mode.to_lowercase(), "wal");
+
+        let busy_timeout: i64 = conn.query_row("PRAGMA busy_timeout", [], |row| row.get(0)).unwrap();
+        assert_eq!(busy_timeout, BUSY_TIMEOUT_MS as i64);
+
+        let foreign_keys: i64 = conn.query_row("PRAGMA foreign_keys", [], |row| row.get(0)).unwrap();
+        assert_eq!(foreign_keys, 1);
+    }
+
+    #[test]
+    fn test_get_summary_counts_scoped_to_active_profile() {
+        let (store, _temp_dir) = create_test_store();
+
+        for (id, active) in [("profile-1", true), ("profile-2", false)] {
+            store.create_profile(&Profile {
+                id: id.to_string(),
+                name: id.to_string(),
+                platform_type: "Microsoft SQL Server".to_string(),
+                host: "localhost".to_string(),
+                port: 1433,
+                username: "sa".to_string(),
+                password: "password".to_string(),
+                trust_certificate: true,
+                snapshot_path: "/var/opt/mssql/snapshots".to_string(),
+                description: None,
+                notes: None,
+                is_active: active,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                server_platform: None,
+                encryption_mode: EncryptionMode::default(),
+                overrides: ProfileOverrides::default(),
+            }).unwrap();
+        }
+
+        for (group_id, profile_id) in [("group-1", "profile-1"), ("group-2", "profile-2")] {
+            store
+                .create_group(&Group {
+                    profile_id: Some(profile_id.to_string()),
+                    ..test_group(group_id, group_id, &["db1"])
+                })
+                .unwrap();
+        }
+
+        store.add_snapshot(&test_snapshot("snap-1", "group-1", 1)).unwrap();
+        let mut automatic = test_snapshot("snap-2", "group-1", 2);
+        automatic.is_automatic = true;
+        store.add_snapshot(&automatic).unwrap();
+        store.add_snapshot(&test_snapshot("snap-3", "group-2", 1)).unwrap();
+
+        assert_eq!(store.get_summary_counts().unwrap(), (1, 1, 1));
+    }
+
+    fn test_history_entry(id: &str, group_id: Option<&str>, timestamp: &str) -> HistoryEntry {
+        HistoryEntry {
+            id: id.to_string(),
+            operation_type: "create_snapshot".to_string(),
+            timestamp: timestamp.parse().unwrap(),
+            user_name: None,
+            details: group_id.map(|g| serde_json::json!({ "groupId": g })),
+            results: None,
+            group_id: group_id.map(|g| g.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_add_history_batch_is_all_or_nothing() {
+        let (store, _temp_dir) = create_test_store();
+
+        store
+            .add_history_batch(&[
+                test_history_entry("h1", Some("group-1"), "2024-01-01T00:00:00Z"),
+                test_history_entry("h2", Some("group-2"), "2024-01-02T00:00:00Z"),
+            ])
+            .unwrap();
+        assert_eq!(store.get_history(None).unwrap().len(), 2);
+
+        // A duplicate id fails the whole batch, including the entry before it
+        assert!(store
+            .add_history_batch(&[
+                test_history_entry("h3", None, "2024-01-03T00:00:00Z"),
+                test_history_entry("h1", None, "2024-01-04T00:00:00Z"),
+            ])
+            .is_err());
+        assert_eq!(store.get_history(None).unwrap().len(), 2);
+
+        store.add_history_batch(&[]).unwrap();
+    }
+
+    #[test]
+    fn test_trim_history_counts_deletes_with_concurrent_inserts() {
+        let (store, temp_dir) = create_test_store();
+        for i in 0..20 {
+            store
+                .add_history(&test_history_entry(&format!("h{}", i), None, "2024-01-01T00:00:00Z"))
+                .unwrap();
+        }
+
+        // A second connection keeps adding entries while this one trims
+        let db_path = temp_dir.path().join("test.db");
+        let writer = std::thread::spawn(move || {
+            let conn = Connection::open(&db_path).unwrap();
+            MetadataStore::configure_connection(&conn).unwrap();
+            let other = MetadataStore { conn: Mutex::new(conn) };
+            for i in 0..50 {
+                other
+                    .add_history(&test_history_entry(&format!("w{}", i), None, "2024-01-02T00:00:00Z"))
+                    .unwrap();
+            }
+        });
+        let mut deleted = 0;
+        for _ in 0..20 {
+            deleted += store.trim_history(10).unwrap();
+        }
+        writer.join().unwrap();
+        deleted += store.trim_history(10).unwrap();
+
+        let remaining = store.get_history(None).unwrap();
+        assert_eq!(remaining.len(), 10);
+        assert_eq!(deleted as usize, 70 - remaining.len());
+        assert!(remaining.iter().all(|h| h.id.starts_with('w')));
+        assert_eq!(store.trim_history(10).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_modify_settings_counts_concurrent_changes() {
+        let (store, temp_dir) = create_test_store();
+
+        // Another connection bumps the counter at the same time as this one
+        let db_path = temp_dir.path().join("test.db");
+        let other = std::thread::spawn(move || {
+            let conn = Connection::open(&db_path).unwrap();
+            MetadataStore::configure_connection(&conn).unwrap();
+            let other = MetadataStore { conn: Mutex::new(conn) };
+            for _ in 0..50 {
+                other.modify_settings(|s| s.failed_password_attempts += 1).unwrap();
+            }
+        });
+        for _ in 0..50 {
+            store.modify_settings(|s| s.failed_password_attempts += 1).unwrap();
+        }
+        other.join().unwrap();
+
+        assert_eq!(store.get_settings().unwrap().failed_password_attempts, 100);
+    }
+
+    #[test]
+    fn test_get_history_for_user() {
+        let (store, _temp_dir) = create_test_store();
+        let by = |id: &str, user_name: Option<&str>, timestamp: &str| HistoryEntry {
+            user_name: user_name.map(String::from),
+            ..test_history_entry(id, None, timestamp)
+        };
+
+        store.add_history(&by("h1", Some("alice"), "2024-01-01T00:00:00Z")).unwrap();
+        store.add_history(&by("h2", Some("bob"), "2024-01-02T00:00:00Z")).unwrap();
+        store.add_history(&by("h3", None, "2024-01-03T00:00:00Z")).unwrap();
+        store.add_history(&by("h4", Some("alice"), "2024-01-04T00:00:00Z")).unwrap();
+
+        let ids = |entries: Vec<HistoryEntry>| entries.into_iter().map(|e| e.id).collect::<Vec<_>>();
+        assert_eq!(ids(store.get_history_for_user(Some("alice"), None).unwrap()), vec!["h4", "h1"]);
+        assert_eq!(ids(store.get_history_for_user(Some("alice"), Some(1)).unwrap()), vec!["h4"]);
+        assert_eq!(ids(store.get_history_for_user(None, None).unwrap()), vec!["h3"]);
+        assert!(store.get_history_for_user(Some("carol"), None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_get_history_for_group() {
+        let (store, _temp_dir) = create_test_store();
+
+        store.add_history(&test_history_entry("h1", Some("group-1"), "2024-01-01T00:00:00Z")).unwrap();
+        store.add_history(&test_history_entry("h2", Some("group-2"), "2024-01-02T00:00:00Z")).unwrap();
+        store.add_history(&test_history_entry("h3", Some("group-1"), "2024-01-03T00:00:00Z")).unwrap();
+        store.add_history(&test_history_entry("h4", None, "2024-01-04T00:00:00Z")).unwrap();
+
+        let ids: Vec<String> = store
+            .get_history_for_group("group-1", None)
+            .unwrap()
+            .into_iter()
+            .map(|e| e.id)
+            .collect();
+        assert_eq!(ids, vec!["h3".to_string(), "h1".to_string()]);
+
+        let limited = store.get_history_for_group("group-1", Some(1)).unwrap();
+        assert_eq!(limited.len(), 1);
+        assert_eq!(limited[0].id, "h3");
+
+        // Rows without a group still show up in the full history
+        let all = store.get_history(None).unwrap();
+        assert_eq!(all.len(), 4);
+        assert_eq!(all[0].group_id, None);
+    }
+
+    #[test]
+    fn test_backfill_history_group_ids_from_details() {
+        let (store, _temp_dir) = create_test_store();
+        let conn = store.conn.lock().unwrap();
+
+        conn.execute_batch(
+            r#"INSERT INTO history (id, operation_type, timestamp, details) VALUES
+                ('h1', 'rollback', '2024-01-01T00:00:00Z', '{"groupId":"group-1"}'),
+                ('h2', 'create_group', '2024-01-02T00:00:00Z', '{"groupName":"No id"}'),
+                ('h3', 'rollback', '2024-01-03T00:00:00Z', 'not json'),
+                ('h4', 'rollback', '2024-01-04T00:00:00Z', NULL);"#,
+        ).unwrap();
+
+        assert_eq!(MetadataStore::backfill_history_group_ids(&conn).unwrap(), 1);
+
+        let group_id: Option<String> = conn
+            .query_row("SELECT group_id FROM history WHERE id = 'h1'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(group_id.as_deref(), Some("group-1"));
+
+        let unattributed: i32 = conn
+            .query_row("SELECT COUNT(*) FROM history WHERE group_id IS NULL", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(unattributed, 3);
+    }
+
+    #[test]
+    fn test_run_migration_step_records_completion() {
+        let (store, _temp_dir) = create_test_store();
+        let runs = std::cell::Cell::new(0);
+
+        // A failed step is not recorded, so it runs again next time
+        store.run_migration_step("test_step", || {
+            runs.set(runs.get() + 1);
+            Err(MetadataError::NotInitialized)
+        });
+        assert!(!store.is_migration_done("test_step").unwrap());
+
+        for _ in 0..2 {
+            store.run_migration_step("test_step", || {
+                runs.set(runs.get() + 1);
+                Ok(())
+            });
+        }
+        assert!(store.is_migration_done("test_step").unwrap());
+        assert_eq!(runs.get(), 2);
+    }
+
+    #[test]
+    fn test_migrate_groups_add_profile_id_assigns_orphaned_groups() {
+        let (store, _temp_dir) = create_test_store();
+        {
+            let conn = store.conn.lock().unwrap();
+            conn.execute(
+                "INSERT INTO profiles (id, name, platform_type, host, port, username, password, trust_certificate, snapshot_path, is_active, created_at, updated_at)
+                 VALUES ('profile-1', 'P', 'Microsoft SQL Server', 'localhost', 1433, 'sa', 'x', 1, '/tmp', 1, '2026-01-01T00:00:00+00:00', '2026-01-01T00:00:00+00:00')",
+                [],
+            )
+            .unwrap();
+            conn.execute(
+                "INSERT INTO groups (id, name, databases, created_at, updated_at)
+                 VALUES ('group-1', 'Old', '[]', '2026-01-01T00:00:00+00:00', '2026-01-01T00:00:00+00:00')",
+                [],
+            )
+            .unwrap();
+        }
+
+        store.migrate_groups_add_profile_id().unwrap();
+        // Running it again is harmless
+        store.migrate_groups_add_profile_id().unwrap();
+
+        let conn = store.conn.lock().unwrap();
+        let profile_id: Option<String> = conn
+            .query_row("SELECT profile_id FROM groups WHERE id = 'group-1'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(profile_id.as_deref(), Some("profile-1"));
+    }
+
+    #[test]
+    fn test_apply_config_preferences_migrates_theme() {
+        let mut settings = Settings::default();
+        assert_eq!(settings.preferences.theme, "system");
+
+        let preferences = crate::config::Preferences {
+            theme: "dark".to_string(),
+            max_history_entries: 250,
+        };
+        MetadataStore::apply_config_preferences(&mut settings, &preferences);
+
+        assert_eq!(settings.preferences.theme, "dark");
+        assert_eq!(settings.preferences.max_history_entries, 250);
+    }
+
+    #[test]
+    fn test_apply_config_preferences_keeps_existing_values() {
+        let mut settings = Settings::default();
+        settings.preferences.theme = "light".to_string();
+
+        // A blank config theme never overwrites, and a chosen SQLite theme is preserved
+        let preferences = crate::config::Preferences {
+            theme: String::new(),
+            max_history_entries: 100,
+        };
+        MetadataStore::apply_config_preferences(&mut settings, &preferences);
+        assert_eq!(settings.preferences.theme, "light");
+
+        let preferences = crate::config::Preferences {
+            theme: "dark".to_string(),
+            max_history_entries: 100,
+        };
+        MetadataStore::apply_config_preferences(&mut settings, &preferences);
+        assert_eq!(settings.preferences.theme, "light");
+    }
+
+    #[test]
+    fn test_quarantine_moves_corrupt_database_aside() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let healthy = temp_dir.path().join("healthy.db");
+        Connection::open(&healthy)
+            .unwrap()
+            .execute_batch("CREATE TABLE t (id INTEGER);")
+            .unwrap();
+        assert!(MetadataStore::quarantine_if_corrupt(&healthy).unwrap().is_none());
+        assert!(healthy.exists());
+
+        let corrupt = temp_dir.path().join("sqlparrot.db");
+        std::fs::write(&corrupt, vec![0x5a; 8192]).unwrap();
+        let backup = MetadataStore::quarantine_if_corrupt(&corrupt).unwrap().unwrap();
+        assert!(!corrupt.exists());
+        assert!(backup.exists());
+        assert!(backup.file_name().unwrap().to_string_lossy().starts_with("sqlparrot.db.corrupt-"));
+    }
+
+    #[test]
+    fn test_missing_columns_reports_table_and_column() {
+        let (store, _temp_dir) = create_test_store();
+        assert!(store.missing_columns().unwrap().is_empty());
+
+        store
+            .conn
+            .lock()
+            .unwrap()
+            .execute_batch("ALTER TABLE snapshots DROP COLUMN notes;")
+            .unwrap();
+        assert_eq!(store.missing_columns().unwrap(), vec!["snapshots.notes".to_string()]);
+    }
+
+    #[test]
+    fn test_profile_encryption_mode_round_trips() {
+        let (store, _temp_dir) = create_test_store();
+
+        let mut profile = Profile {
+            id: "legacy".to_string(),
+            name: "Legacy 2008".to_string(),
+            platform_type: "Microsoft SQL Server".to_string(),
+            host: "oldbox".to_string(),
+            port: 1433,
+            username: "sa".to_string(),
+            password: "password".to_string(),
+            trust_certificate: true,
+            snapshot_path: "C:\\Snapshots".to_string(),
+            description: None,
+            notes: None,
+            is_active: true,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            server_platform: None,
+            encryption_mode: EncryptionMode::Off,
+            overrides: ProfileOverrides::default(),
+        };
+        store.create_profile(&profile).unwrap();
+        assert_eq!(store.get_profile("legacy").unwrap().unwrap().encryption_mode, EncryptionMode::Off);
+
+        profile.encryption_mode = EncryptionMode::On;
+        store.update_profile(&profile).unwrap();
+        assert_eq!(store.get_profile("legacy").unwrap().unwrap().encryption_mode, EncryptionMode::On);
+    }
+
+    #[test]
+    fn test_clock_skew_is_per_profile_and_clears() {
+        let (store, _temp_dir) = create_test_store();
+
+        store.set_clock_skew("dev", -90_000).unwrap();
+        store.set_clock_skew("qa", 500).unwrap();
+        assert_eq!(store.get_clock_skew("dev").unwrap(), Some(-90_000));
+
+        store.clear_clock_skew("dev").unwrap();
+        assert_eq!(store.get_clock_skew("dev").unwrap(), None);
+        assert_eq!(store.get_clock_skew("qa").unwrap(), Some(500));
+    }
+
+    #[test]
+    fn test_create_profiles_is_all_or_nothing() {
+        let (store, _temp_dir) = create_test_store();
+
+        let profile = |id: &str, name: &str| Profile {
+            id: id.to_string(),
+            name: name.to_string(),
+            platform_type: "Microsoft SQL Server".to_string(),
+            host: id.to_string(),
+            port: 1433,
+            username: "sa".to_string(),
+            password: "password".to_string(),
+            trust_certificate: true,
+            snapshot_path: "/var/opt/mssql/snapshots".to_string(),
+            description: None,
+            notes: None,
+            is_active: false,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            server_platform: None,
+            encryption_mode: EncryptionMode::default(),
+            overrides: ProfileOverrides::default(),
+        };
+        store.create_profile(&profile("dev", "Dev")).unwrap();
+
+        // The second id clashes, so the first isn't kept either
+        assert!(store.create_profiles(&[profile("qa", "QA"), profile("dev", "Dev 2")]).is_err());
+        assert!(store.get_profile("qa").unwrap().is_none());
+
+        store.create_profiles(&[profile("qa", "QA"), profile("uat", "UAT")]).unwrap();
+        assert!(store.get_profile("qa").unwrap().is_some());
+        assert!(store.get_profile("uat").unwrap().is_some());
+    }
+
+    #[test]
+    fn test_profile_overrides_round_trip() {
+        let (store, _temp_dir) = create_test_store();
+
+        let mut profile = Profile {
+            id: "prod".to_string(),
+            name: "Production".to_string(),
+            platform_type: "Microsoft SQL Server".to_string(),
+            host: "prod-sql".to_string(),
+            port: 1433,
+            username: "sa".to_string(),
+            password: "password".to_string(),
+            trust_certificate: false,
+            snapshot_path: "D:\\Snapshots".to_string(),
+            description: None,
+            notes: None,
+            is_active: true,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            server_platform: None,
+            encryption_mode: EncryptionMode::default(),
+            overrides: ProfileOverrides {
+                auto_create_checkpoint: Some(false),
+                snapshot_name_template: Some("{database}_prod_{sequence}".to_string()),
+                application_name: Some("SQL Parrot (prod)".to_string()),
+            },
+        };
+        store.create_profile(&profile).unwrap();
+        assert_eq!(store.get_profile("prod").unwrap().unwrap().overrides, profile.overrides);
+
+        profile.overrides = ProfileOverrides::default();
+        store.update_profile(&profile).unwrap();
+        assert_eq!(store.get_profile("prod").unwrap().unwrap().overrides, ProfileOverrides::default());
+    }
+
+    #[test]
+    fn test_connection_checks_newest_first_and_trimmed() {
+        let (store, _temp_dir) = create_test_store();
+        for id in ["p1", "p2"] {
+            store
+                .create_profile(&Profile {
+                    id: id.to_string(),
+                    name: id.to_string(),
+                    platform_type: "Microsoft SQL Server".to_string(),
+                    host: "localhost".to_string(),
+                    port: 1433,
+                    username: "sa".to_string(),
+                    password: "password".to_string(),
+                    trust_certificate: true,
+                    snapshot_path: "/var/opt/mssql/snapshots".to_string(),
+                    description: None,
+                    notes: None,
+                    is_active: false,
+                    created_at: Utc::now(),
+                    updated_at: Utc::now(),
+                    server_platform: None,
+                    encryption_mode: EncryptionMode::default(),
+                    overrides: ProfileOverrides::default(),
+                })
+                .unwrap();
+        }
+        let start: DateTime<Utc> = "2026-01-01T00:00:00Z".parse().unwrap();
+        let check = |profile_id: &str, minute: i64, success: bool| ConnectionCheck {
+            profile_id: profile_id.to_string(),
+            timestamp: start + chrono::Duration::minutes(minute),
+            success,
+            latency_ms: Some(12),
+            error: (!success).then(|| "Login failed".to_string()),
+        };
+
+        for minute in 0..=i64::from(MAX_CONNECTION_CHECKS_PER_PROFILE) {
+            store.add_connection_check(&check("p1", minute, minute % 2 == 0)).unwrap();
+        }
+        store.add_connection_check(&check("p2", 0, false)).unwrap();
+
+        let all = store.get_connection_checks("p1", u32::MAX).unwrap();
+        assert_eq!(all.len(), MAX_CONNECTION_CHECKS_PER_PROFILE as usize);
+        assert_eq!(all[0].timestamp, start + chrono::Duration::minutes(500));
+        assert!(all[0].success);
+        assert_eq!(all.last().unwrap().timestamp, start + chrono::Duration::minutes(1));
+
+        let other = store.get_connection_checks("p2", 10).unwrap();
+        assert_eq!(other.len(), 1);
+        assert_eq!(other[0].error.as_deref(), Some("Login failed"));
+
+        // A profile's checks go with it
+        store.delete_profile("p2").unwrap();
+        assert!(store.get_connection_checks("p2", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_operations_round_trip_and_are_marked_interrupted() {
+        use crate::models::{OperationAction, OperationStep, StepStatus};
+
+        let (store, _temp_dir) = create_test_store();
+        store.create_group(&test_group("g1", "Sales", &["Sales"])).unwrap();
+
+        let mut operation = Operation {
+            id: "op1".to_string(),
+            operation_type: "rollback".to_string(),
+            group_id: "g1".to_string(),
+            snapshot_id: Some("s1".to_string()),
+            status: OperationStatus::InProgress,
+            steps: vec![
+                OperationStep {
+                    action: OperationAction::DropSnapshots {
+                        snapshot_names: vec!["Sales_snapshot_2".to_string()],
+                        snapshot_ids: vec!["s2".to_string()],
+                    },
+                    status: StepStatus::Done,
+                },
+                OperationStep {
+                    action: OperationAction::Restore {
+                        database: "Sales".to_string(),
+                        snapshot_name: "Sales_snapshot_1".to_string(),
+                    },
+                    status: StepStatus::InProgress,
+                },
+            ],
+            started_at: "2026-01-01T00:00:00Z".parse().unwrap(),
+            updated_at: "2026-01-01T00:00:05Z".parse().unwrap(),
+        };
+        store.save_operation(&operation).unwrap();
+        assert_eq!(store.get_operation("op1").unwrap(), Some(operation.clone()));
+
+        // Saving again replaces the record rather than adding one
+        operation.steps[1].status = StepStatus::Done;
+        store.save_operation(&operation).unwrap();
+        assert_eq!(store.get_operations().unwrap(), vec![operation.clone()]);
+
+        assert_eq!(store.mark_operations_interrupted().unwrap(), 1);
+        assert_eq!(store.get_operation("op1").unwrap().unwrap().status, OperationStatus::Interrupted);
+        assert_eq!(store.mark_operations_interrupted().unwrap(), 0);
+
+        store.delete_operation("op1").unwrap();
+        assert_eq!(store.get_operation("op1").unwrap(), None);
+    }
 }
@@ -1,8 +1,11 @@
 // ABOUTME: Database module exports for SQL Parrot
 // ABOUTME: Contains SQLite metadata storage and SQL Server connection management
 
+pub mod diagnostics;
+pub mod limiter;
 pub mod metadata;
 pub mod sqlserver;
 
+pub use limiter::ConnectionLimiter;
 pub use metadata::MetadataStore;
 pub use sqlserver::SqlServerConnection;
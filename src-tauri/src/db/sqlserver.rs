@@ -3,12 +3,13 @@
 
 use chrono::{DateTime, Utc};
 use thiserror::Error;
-use tiberius::{AuthMethod, Client, Config, EncryptionLevel};
+use tiberius::{AuthMethod, Client, Config, EncryptionLevel, Query};
 use tokio::net::TcpStream;
 use tokio_util::compat::{Compat, TokioAsyncWriteCompatExt};
 
-use crate::config::ConnectionProfile;
-use crate::models::DatabaseInfo;
+use super::limiter::{ConnectionLimiter, ConnectionPermit};
+use crate::config::{ConnectionProfile, EncryptionMode};
+use crate::models::{AvailabilityInfo, DatabaseInfo, ServerInfo, ServerSnapshot, SnapshotFile};
 
 #[derive(Error, Debug)]
 pub enum SqlServerError {
@@ -22,39 +23,428 @@ pub enum SqlServerError {
     Io(#[from] std::io::Error),
     #[error("Database not found: {0}")]
     DatabaseNotFound(String),
-    #[error("Snapshot operation failed: {0}")]
-    SnapshotError(String),
+    #[error("Snapshot operation failed: {message}{}", sql_error_suffix(*.number, *.severity, *.state))]
+    SnapshotError {
+        message: String,
+        number: Option<u32>,
+        severity: Option<u8>,
+        state: Option<u8>,
+    },
+    #[error("Login failed: {0}")]
+    LoginFailed(String),
+    #[error("Server unreachable: {0}")]
+    Unreachable(String),
+    #[error("Secure connection failed: {0}")]
+    TlsFailed(String),
+    #[error("Permission denied: {0}")]
+    PermissionDenied(String),
+}
+
+/// " (Msg 1823, Level 16, State 2)" style suffix for errors that carry a SQL Server error number
+fn sql_error_suffix(number: Option<u32>, severity: Option<u8>, state: Option<u8>) -> String {
+    match number {
+        Some(number) => format!(
+            " (Msg {}, Level {}, State {})",
+            number,
+            severity.unwrap_or(0),
+            state.unwrap_or(0)
+        ),
+        None => String::new(),
+    }
+}
+
+/// Wrap a driver error from a snapshot statement, keeping the server's error number if any
+fn snapshot_error(context: Option<&str>, error: tiberius::error::Error) -> SqlServerError {
+    let (message, number, severity, state) = match &error {
+        tiberius::error::Error::Server(token) => (
+            token.message().to_string(),
+            Some(token.code()),
+            Some(token.class()),
+            Some(token.state()),
+        ),
+        _ => (error.to_string(), None, None, None),
+    };
+
+    SqlServerError::SnapshotError {
+        message: match context {
+            Some(context) => format!("{}: {}", context, message),
+            None => message,
+        },
+        number,
+        severity,
+        state,
+    }
+}
+
+const LOGIN_REMEDY: &str =
+    "Check the username and password, and that SQL Server authentication (mixed mode) is enabled.";
+const UNREACHABLE_REMEDY: &str =
+    "Check the host and port, that SQL Server is running with TCP/IP enabled, and that no firewall blocks the port.";
+const TLS_REMEDY: &str =
+    "Enable 'Trust Certificate' for self-signed certificates, or install the server's certificate on this machine.";
+const PERMISSION_REMEDY: &str =
+    "Grant the login the dbcreator role: ALTER SERVER ROLE dbcreator ADD MEMBER [your_login];";
+
+/// Friendly variant for a SQL Server error number, if it's one users can fix themselves
+fn classify_error_code(code: u32, message: &str) -> Option<SqlServerError> {
+    match code {
+        // Login failed / password expired / must change / account locked / cannot open database
+        18456 | 18452 | 18487 | 18488 | 18486 | 4060 => Some(SqlServerError::LoginFailed(message.to_string())),
+        // CREATE DATABASE / RESTORE / object / server permission denied
+        262 | 229 | 230 | 297 | 300 | 916 | 3110 | 15247 => {
+            Some(SqlServerError::PermissionDenied(message.to_string()))
+        }
+        _ => None,
+    }
+}
+
+/// Map a driver error onto the friendly SqlServerError variants where the cause is recognizable
+pub(crate) fn classify_error(error: tiberius::error::Error) -> SqlServerError {
+    match &error {
+        tiberius::error::Error::Server(token) => {
+            classify_error_code(token.code(), token.message()).unwrap_or(SqlServerError::Tiberius(error))
+        }
+        tiberius::error::Error::Io { .. } => SqlServerError::Unreachable(error.to_string()),
+        tiberius::error::Error::Tls(message) => SqlServerError::TlsFailed(message.clone()),
+        _ => SqlServerError::Tiberius(error),
+    }
+}
+
+impl SqlServerError {
+    /// A suggested fix the user can act on, when the cause is recognizable
+    pub fn remedy(&self) -> Option<&'static str> {
+        match self {
+            SqlServerError::LoginFailed(_) => Some(LOGIN_REMEDY),
+            SqlServerError::Unreachable(_) => Some(UNREACHABLE_REMEDY),
+            SqlServerError::TlsFailed(_) => Some(TLS_REMEDY),
+            SqlServerError::PermissionDenied(_) => Some(PERMISSION_REMEDY),
+            SqlServerError::Tiberius(tiberius::error::Error::Server(token)) => {
+                classify_error_code(token.code(), token.message()).and_then(|e| e.remedy())
+            }
+            _ => None,
+        }
+    }
+
+    /// SQL Server error number, severity, and state, when the server reported them
+    pub fn sql_error_code(&self) -> Option<(u32, u8, u8)> {
+        match self {
+            SqlServerError::SnapshotError {
+                number: Some(number),
+                severity,
+                state,
+                ..
+            } => Some((*number, severity.unwrap_or(0), state.unwrap_or(0))),
+            SqlServerError::Tiberius(tiberius::error::Error::Server(token)) => {
+                Some((token.code(), token.class(), token.state()))
+            }
+            _ => None,
+        }
+    }
+
+    /// The error text followed by its suggested fix, for display in the UI
+    pub fn user_message(&self) -> String {
+        match self.remedy() {
+            Some(remedy) => format!("{}. {}", self.to_string().trim_end_matches('.'), remedy),
+            None => self.to_string(),
+        }
+    }
 }
 
 pub struct SqlServerConnection {
     client: Client<Compat<TcpStream>>,
+    // Kept so a broken connection can be re-established transparently
+    profile: ConnectionProfile,
+    // Host OS of the server, seeded from the profile cache or detected on first use
+    host_platform: Option<String>,
+    // Slot in the shared connection limit, released when the connection is dropped
+    _permit: ConnectionPermit,
+}
+
+/// Build a LIKE pattern matching names that contain `text`, for use with ESCAPE '\'.
+/// Escapes the LIKE wildcards (% and _), the [ character class opener, and the escape itself.
+fn like_contains_pattern(text: &str) -> String {
+    let mut pattern = String::with_capacity(text.len() + 2);
+    pattern.push('%');
+    for c in text.chars() {
+        if matches!(c, '\\' | '%' | '_' | '[') {
+            pattern.push('\\');
+        }
+        pattern.push(c);
+    }
+    pattern.push('%');
+    pattern
+}
+
+/// Build the path of a snapshot's sparse file using the separator of the server's OS
+fn snapshot_file_path(
+    snapshot_path: &str,
+    snapshot_name: &str,
+    index: usize,
+    host_platform: &str,
+) -> String {
+    let separator = if host_platform.eq_ignore_ascii_case("Linux") { '/' } else { '\\' };
+    format!(
+        "{}{}{}_{}.ss",
+        snapshot_path.trim_end_matches(['/', '\\']),
+        separator,
+        snapshot_name,
+        index
+    )
+}
+
+/// Bracket-quote an identifier (database or snapshot name) for DDL that can't take parameters
+pub(crate) fn quote_ident(name: &str) -> String {
+    format!("[{}]", name.replace(']', "]]"))
+}
+
+/// Quote a Unicode string literal for statements that can't take parameters
+fn quote_literal(text: &str) -> String {
+    format!("N'{}'", text.replace('\'', "''"))
+}
+
+/// CREATE DATABASE ... AS SNAPSHOT OF statement with one snapshot file per source data file
+/// Each logical file gets its own sparse file, numbered by its position in `files`
+fn create_snapshot_statement(
+    source_db: &str,
+    snapshot_name: &str,
+    files: &[(String, String)],
+    snapshot_path: &str,
+    host_platform: &str,
+) -> String {
+    let file_specs: Vec<String> = files
+        .iter()
+        .enumerate()
+        .map(|(i, (name, _))| {
+            let file_path = snapshot_file_path(snapshot_path, snapshot_name, i, host_platform);
+            format!("(NAME = {}, FILENAME = {})", quote_literal(name), quote_literal(&file_path))
+        })
+        .collect();
+
+    format!(
+        "CREATE DATABASE {} ON {} AS SNAPSHOT OF {}",
+        quote_ident(snapshot_name),
+        file_specs.join(", "),
+        quote_ident(source_db)
+    )
+}
+
+/// Database-level extended property naming the install that created a snapshot
+/// A snapshot is read-only, so the property is set on the source just before CREATE DATABASE ... AS SNAPSHOT
+/// and removed again right after; the snapshot keeps its copy
+const ORIGIN_PROPERTY: &str = "SQLParrotOrigin";
+
+/// Statement removing the origin property from a database, if it's there
+fn drop_origin_property_statement(database: &str) -> String {
+    format!(
+        "IF EXISTS (SELECT 1 FROM {db}.sys.extended_properties WHERE class = 0 AND name = {name}) \
+         EXEC {db}.sys.sp_dropextendedproperty @name = {name};",
+        db = quote_ident(database),
+        name = quote_literal(ORIGIN_PROPERTY)
+    )
+}
+
+/// Statement (re)setting the origin property, tagged with the snapshot it's meant for
+fn set_origin_property_statement(database: &str, snapshot_name: &str, origin: &str) -> String {
+    let value = serde_json::json!({ "origin": origin, "snapshot": snapshot_name }).to_string();
+    format!(
+        "{} EXEC {}.sys.sp_addextendedproperty @name = {}, @value = {};",
+        drop_origin_property_statement(database),
+        quote_ident(database),
+        quote_literal(ORIGIN_PROPERTY),
+        quote_literal(&value)
+    )
+}
+
+/// The origin in a snapshot's property, if the property was written for this snapshot
+/// Restoring from a snapshot copies the property back into the source, so a later snapshot made by
+/// another tool can carry a stale one; its snapshot name won't match
+fn parse_origin_property(value: &str, snapshot_name: &str) -> Option<String> {
+    let value: serde_json::Value = serde_json::from_str(value).ok()?;
+    if !value["snapshot"].as_str()?.eq_ignore_ascii_case(snapshot_name) {
+        return None;
+    }
+    value["origin"].as_str().map(String::from)
+}
+
+/// Why a database can't be usefully snapshotted, given its sys.filegroups type codes
+/// FX is a memory-optimized filegroup, FD a FILESTREAM filegroup
+fn unsupported_snapshot_reason(source_db: &str, filegroup_types: &[String]) -> Option<String> {
+    if filegroup_types.iter().any(|t| t == "FX") {
+        Some(format!(
+            "Database {} contains memory-optimized data; snapshots are not supported",
+            source_db
+        ))
+    } else if filegroup_types.iter().any(|t| t == "FD") {
+        Some(format!(
+            "Database {} contains FILESTREAM data; its snapshots would leave that data offline and can't be used for rollback",
+            source_db
+        ))
+    } else {
+        None
+    }
+}
+
+/// Sparse files found on disk that no database on the server is using
+/// Windows paths are case-insensitive, so the comparison is too
+fn unused_snapshot_files(on_disk: &[String], in_use: &[String]) -> Vec<String> {
+    on_disk
+        .iter()
+        .filter(|path| !in_use.iter().any(|used| used.eq_ignore_ascii_case(path)))
+        .cloned()
+        .collect()
+}
+
+/// T-SQL batch that drops each snapshot inside its own TRY/CATCH and selects the failures
+fn drop_snapshots_batch(snapshot_names: &[String]) -> String {
+    let mut batch = String::from(
+        "SET NOCOUNT ON; DECLARE @failed TABLE (name NVARCHAR(128), error NVARCHAR(4000));\n",
+    );
+    for name in snapshot_names {
+        batch.push_str(&format!(
+            "BEGIN TRY DROP DATABASE IF EXISTS {}; END TRY BEGIN CATCH INSERT INTO @failed VALUES ({}, ERROR_MESSAGE()); END CATCH;\n",
+            quote_ident(name),
+            quote_literal(name)
+        ));
+    }
+    batch.push_str("SELECT name, error FROM @failed;");
+    batch
+}
+
+/// Sessions kill_connections should KILL: real session ids other than this connection's own,
+/// since killing it would abort the restore that follows
+fn sessions_to_kill(sessions: &[(i16, String)], own_spid: i16) -> Vec<(i16, String)> {
+    sessions
+        .iter()
+        .filter(|(session_id, _)| *session_id > 0 && *session_id != own_spid)
+        .cloned()
+        .collect()
+}
+
+/// Whether a driver error means the underlying socket is gone (reset, closed, timed out)
+/// rather than the server rejecting the query
+fn is_connection_broken(error: &tiberius::error::Error) -> bool {
+    use tiberius::error::IoErrorKind;
+
+    match error {
+        tiberius::error::Error::Io { kind, .. } => matches!(
+            kind,
+            IoErrorKind::ConnectionReset
+                | IoErrorKind::ConnectionAborted
+                | IoErrorKind::BrokenPipe
+                | IoErrorKind::NotConnected
+                | IoErrorKind::UnexpectedEof
+                | IoErrorKind::TimedOut
+        ),
+        _ => false,
+    }
 }
 
 impl SqlServerConnection {
     /// Connect to SQL Server using a connection profile
+    /// Waits for a free slot when max_connections connections are already open
     pub async fn connect(profile: &ConnectionProfile) -> Result<Self, SqlServerError> {
+        let permit = ConnectionLimiter::shared().acquire().await;
+        let client = Self::open_client(profile).await?;
+        Ok(Self {
+            client,
+            profile: profile.clone(),
+            host_platform: profile.server_platform.clone(),
+            _permit: permit,
+        })
+    }
+
+    /// Re-establish the connection using the stored profile
+    pub async fn reconnect(&mut self) -> Result<(), SqlServerError> {
+        self.client = Self::open_client(&self.profile).await?;
+        Ok(())
+    }
+
+    /// Run a query, reconnecting once and retrying if the socket turned out to be broken.
+    /// Only use this for idempotent queries; a retried statement may run twice.
+    pub async fn execute_with_reconnect(
+        &mut self,
+        query: &str,
+    ) -> Result<Vec<tiberius::Row>, SqlServerError> {
+        self.execute_params_with_reconnect(query, &[]).await
+    }
+
+    /// Like execute_with_reconnect, binding params to @P1, @P2, ... in the query
+    pub async fn execute_params_with_reconnect(
+        &mut self,
+        query: &str,
+        params: &[&str],
+    ) -> Result<Vec<tiberius::Row>, SqlServerError> {
+        match self.run_query(query, params).await {
+            Err(e) if is_connection_broken(&e) => {
+                log::warn!("SQL Server connection lost ({}), reconnecting", e);
+                self.reconnect().await?;
+                Ok(self.run_query(query, params).await?)
+            }
+            result => Ok(result?),
+        }
+    }
+
+    /// Run a query and collect the first result set
+    async fn run_query(
+        &mut self,
+        query: &str,
+        params: &[&str],
+    ) -> Result<Vec<tiberius::Row>, tiberius::error::Error> {
+        if params.is_empty() {
+            return self.client.simple_query(query).await?.into_first_result().await;
+        }
+
+        let mut statement = Query::new(query);
+        for param in params {
+            statement.bind(*param);
+        }
+        statement.query(&mut self.client).await?.into_first_result().await
+    }
+
+    /// Build the tiberius config for a profile
+    pub(crate) fn client_config(profile: &ConnectionProfile) -> Config {
         let mut config = Config::new();
-        config.host(&profile.host);
-        config.port(profile.port);
+        let (host, port) = profile.address();
+        // tiberius joins host and port with a colon, so IPv6 literals need their brackets back
+        if host.contains(':') {
+            config.host(format!("[{}]", host));
+        } else {
+            config.host(host);
+        }
+        config.port(port);
         config.authentication(AuthMethod::sql_server(&profile.username, &profile.password));
+        config.application_name(&profile.application_name);
 
-        if profile.trust_certificate {
+        if profile.encryption_mode == EncryptionMode::Off {
+            // Explicit per-profile opt-in only; never used as a fallback when TLS fails
+            config.encryption(EncryptionLevel::NotSupported);
+        } else if profile.trust_certificate {
             config.trust_cert();
             // Required for Docker SQL Server and self-signed certs
             config.encryption(EncryptionLevel::Required);
         }
 
+        config
+    }
+
+    /// Open a new TDS client for a profile
+    async fn open_client(
+        profile: &ConnectionProfile,
+    ) -> Result<Client<Compat<TcpStream>>, SqlServerError> {
+        let config = Self::client_config(profile);
+
         let tcp = TcpStream::connect(config.get_addr())
             .await
-            .map_err(|e| SqlServerError::ConnectionFailed(e.to_string()))?;
+            .map_err(|e| SqlServerError::Unreachable(e.to_string()))?;
 
         tcp.set_nodelay(true)?;
 
-        let client = Client::connect(config, tcp.compat_write())
+        Client::connect(config, tcp.compat_write())
             .await
-            .map_err(|e| SqlServerError::ConnectionFailed(e.to_string()))?;
-
-        Ok(Self { client })
+            .map_err(|e| match classify_error(e) {
+                SqlServerError::Tiberius(e) => SqlServerError::ConnectionFailed(e.to_string()),
+                classified => classified,
+            })
     }
 
     /// Test connection by querying SQL Server version
@@ -71,9 +461,125 @@ impl SqlServerConnection {
         Ok(version.to_string())
     }
 
-    /// Get list of user databases (excluding system databases and snapshots)
-    pub async fn get_databases(&mut self) -> Result<Vec<DatabaseInfo>, SqlServerError> {
+    /// The server's clock, in UTC
+    pub async fn get_server_utc_now(&mut self) -> Result<DateTime<Utc>, SqlServerError> {
+        let row = self
+            .client
+            .simple_query("SELECT SYSUTCDATETIME()")
+            .await?
+            .into_row()
+            .await?
+            .ok_or_else(|| SqlServerError::QueryFailed("No server time returned".to_string()))?;
+
+        let now: chrono::NaiveDateTime = row
+            .get(0)
+            .ok_or_else(|| SqlServerError::QueryFailed("No server time returned".to_string()))?;
+        Ok(DateTime::from_naive_utc_and_offset(now, Utc))
+    }
+
+    /// Get edition, version, collation, hardware, and host platform details
+    /// Each source is queried separately so a restricted DMV (Azure SQL, missing
+    /// VIEW SERVER STATE, pre-2017 servers) only leaves its own fields as None
+    pub async fn get_server_info(&mut self) -> Result<ServerInfo, SqlServerError> {
+        let mut info = ServerInfo::default();
+
+        // SERVERPROPERTY returns sql_variant, which tiberius can't decode, so cast it
         let query = r#"
+            SELECT
+                CAST(SERVERPROPERTY('Edition') AS NVARCHAR(128)),
+                CAST(SERVERPROPERTY('ProductVersion') AS NVARCHAR(128)),
+                CAST(SERVERPROPERTY('Collation') AS NVARCHAR(128)),
+                CAST(SERVERPROPERTY('EngineEdition') AS INT)
+        "#;
+        if let Some(row) = self.query_first_row(query, &[]).await? {
+            info.edition = row.get::<&str, _>(0).map(|s| s.to_string());
+            info.product_version = row.get::<&str, _>(1).map(|s| s.to_string());
+            info.collation = row.get::<&str, _>(2).map(|s| s.to_string());
+            info.engine_edition = row.get::<i32, _>(3);
+        }
+
+        let query = "SELECT cpu_count, scheduler_count, physical_memory_kb / 1024 FROM sys.dm_os_sys_info";
+        match self.query_first_row(query, &[]).await {
+            Ok(Some(row)) => {
+                info.cpu_count = row.get::<i32, _>(0);
+                info.scheduler_count = row.get::<i32, _>(1);
+                info.physical_memory_mb = row.get::<i64, _>(2);
+            }
+            Ok(None) => {}
+            Err(e) => log::info!("sys.dm_os_sys_info unavailable: {}", e),
+        }
+
+        let query = "SELECT host_platform, host_distribution FROM sys.dm_os_host_info";
+        match self.query_first_row(query, &[]).await {
+            Ok(Some(row)) => {
+                info.host_platform = row.get::<&str, _>(0).map(|s| s.to_string());
+                info.host_distribution = row.get::<&str, _>(1).map(|s| s.to_string());
+            }
+            Ok(None) => {}
+            Err(e) => log::info!("sys.dm_os_host_info unavailable: {}", e),
+        }
+
+        Ok(info)
+    }
+
+    /// Get the host OS of the server ("Windows" or "Linux"), cached after the first lookup
+    pub async fn get_host_platform(&mut self) -> Result<String, SqlServerError> {
+        if let Some(platform) = &self.host_platform {
+            return Ok(platform.clone());
+        }
+
+        let query = "SELECT host_platform FROM sys.dm_os_host_info";
+        let platform = match self.query_first_row(query, &[]).await {
+            Ok(row) => row
+                .and_then(|r| r.get::<&str, _>(0).map(|s| s.to_string()))
+                .unwrap_or_else(|| "Windows".to_string()),
+            // The DMV was added in SQL Server 2017; anything older only runs on Windows
+            Err(SqlServerError::Tiberius(tiberius::error::Error::Server(e))) => {
+                log::info!("sys.dm_os_host_info unavailable, assuming Windows: {}", e);
+                "Windows".to_string()
+            }
+            Err(e) => return Err(e),
+        };
+
+        self.host_platform = Some(platform.clone());
+        Ok(platform)
+    }
+
+    /// The instance's default data directory (SQL Server 2012+), if the server reports one
+    pub async fn get_default_data_path(&mut self) -> Result<Option<String>, SqlServerError> {
+        let query = "SELECT CAST(SERVERPROPERTY('InstanceDefaultDataPath') AS NVARCHAR(4000))";
+        Ok(self
+            .query_first_row(query, &[])
+            .await?
+            .and_then(|r| r.get::<&str, _>(0).map(|s| s.to_string())))
+    }
+
+    /// Run a query and return its first row (if any)
+    async fn query_first_row(
+        &mut self,
+        query: &str,
+        params: &[&str],
+    ) -> Result<Option<tiberius::Row>, SqlServerError> {
+        Ok(self.execute_params_with_reconnect(query, params).await?.into_iter().next())
+    }
+
+    /// Get list of user databases (excluding system databases and snapshots)
+    /// An optional name_filter keeps only databases whose name contains it
+    pub async fn get_databases(
+        &mut self,
+        name_filter: Option<&str>,
+    ) -> Result<Vec<DatabaseInfo>, SqlServerError> {
+        let name_pattern = name_filter
+            .map(str::trim)
+            .filter(|f| !f.is_empty())
+            .map(like_contains_pattern);
+        let name_condition = match name_pattern {
+            Some(_) => "AND name LIKE @P1 ESCAPE '\\'",
+            None => "",
+        };
+
+        let query = format!(
+            r#"
             SELECT
                 name,
                 create_date,
@@ -81,17 +587,22 @@ impl SqlServerConnection {
                     WHEN name LIKE 'DW%' THEN 'Data Warehouse'
                     WHEN name LIKE 'Global%' THEN 'Global'
                     ELSE 'User'
-                END as category
+                END as category,
+                collation_name,
+                SUSER_SNAME(owner_sid) as owner
             FROM sys.databases
             WHERE database_id > 4
               AND source_database_id IS NULL
               AND name NOT LIKE '%_snapshot_%'
               AND name != 'sqlparrot'
+              {}
             ORDER BY name
-        "#;
+        "#,
+            name_condition
+        );
 
-        let stream = self.client.simple_query(query).await?;
-        let rows = stream.into_first_result().await?;
+        let params: Vec<&str> = name_pattern.as_deref().into_iter().collect();
+        let rows = self.execute_params_with_reconnect(&query, &params).await?;
 
         let mut databases = Vec::new();
         for row in rows {
@@ -103,6 +614,8 @@ impl SqlServerConnection {
                 name: name.to_string(),
                 create_date: DateTime::from_naive_utc_and_offset(create_date, Utc),
                 category: category.to_string(),
+                collation: row.get::<&str, _>(3).map(String::from),
+                owner: row.get::<&str, _>(4).map(String::from),
             });
         }
 
@@ -114,17 +627,16 @@ impl SqlServerConnection {
         &mut self,
         database: &str,
     ) -> Result<Vec<(String, String)>, SqlServerError> {
-        let query = format!(
-            r#"
+        // Every data file in every filegroup needs a sparse file; ordering by file_id keeps the
+        // logical name -> snapshot file mapping stable between runs
+        let query = r#"
             SELECT name, physical_name
             FROM sys.master_files
-            WHERE database_id = DB_ID('{}') AND type = 0
-            "#,
-            database.replace('\'', "''")
-        );
+            WHERE database_id = DB_ID(@P1) AND type = 0
+            ORDER BY file_id
+        "#;
 
-        let stream = self.client.simple_query(&query).await?;
-        let rows = stream.into_first_result().await?;
+        let rows = self.execute_params_with_reconnect(query, &[database]).await?;
 
         let mut files = Vec::new();
         for row in rows {
@@ -140,71 +652,238 @@ impl SqlServerConnection {
         Ok(files)
     }
 
-    /// Create a database snapshot
+    /// Create a database snapshot, returning SQL Server's creation time for it when it can be read
+    /// With an `origin`, the snapshot is tagged with it (see ORIGIN_PROPERTY); failing to tag is only logged
     pub async fn create_snapshot(
         &mut self,
         source_db: &str,
         snapshot_name: &str,
         snapshot_path: &str,
-    ) -> Result<(), SqlServerError> {
+        origin: Option<&str>,
+    ) -> Result<Option<DateTime<Utc>>, SqlServerError> {
+        // Fail fast with a clear reason instead of SQL Server's opaque CREATE DATABASE error
+        if let Some(reason) = self.check_snapshot_support(source_db).await {
+            return Err(SqlServerError::SnapshotError {
+                message: reason,
+                number: None,
+                severity: None,
+                state: None,
+            });
+        }
+
         // Get data files for the source database
         let files = self.get_database_files(source_db).await?;
+        let host_platform = self.get_host_platform().await?;
 
-        // Build the CREATE DATABASE statement
-        let file_specs: Vec<String> = files
-            .iter()
-            .enumerate()
-            .map(|(i, (name, _))| {
-                let file_path = format!("{}\\{}_{}.ss", snapshot_path, snapshot_name, i);
-                format!("(NAME = '{}', FILENAME = '{}')", name, file_path)
-            })
-            .collect();
+        let query = create_snapshot_statement(source_db, snapshot_name, &files, snapshot_path, &host_platform);
 
+        if let Some(origin) = origin {
+            let statement = set_origin_property_statement(source_db, snapshot_name, origin);
+            if let Err(e) = self.execute_with_reconnect(&statement).await {
+                log::warn!("Could not record origin on {} for snapshot {}: {}", source_db, snapshot_name, e);
+            }
+        }
+
+        let created = self.client.simple_query(&query).await.map(|_| ());
+
+        if origin.is_some() {
+            if let Err(e) = self.execute_with_reconnect(&drop_origin_property_statement(source_db)).await {
+                log::warn!("Could not remove origin property from {}: {}", source_db, e);
+            }
+        }
+        created.map_err(|e| snapshot_error(None, e))?;
+
+        // The snapshot exists now; a failed timestamp lookup shouldn't turn that into an error
+        match self.get_database_created_at(snapshot_name).await {
+            Ok(created_at) => Ok(created_at),
+            Err(e) => {
+                log::warn!("Could not read create_date for snapshot {}: {}", snapshot_name, e);
+                Ok(None)
+            }
+        }
+    }
+
+    /// The origin a snapshot was tagged with when it was created, if any
+    pub async fn get_snapshot_origin(&mut self, snapshot_name: &str) -> Result<Option<String>, SqlServerError> {
         let query = format!(
-            "CREATE DATABASE [{}] ON {} AS SNAPSHOT OF [{}]",
-            snapshot_name,
-            file_specs.join(", "),
-            source_db
+            "SELECT CAST(value AS nvarchar(4000)) FROM {}.sys.extended_properties WHERE class = 0 AND name = {}",
+            quote_ident(snapshot_name),
+            quote_literal(ORIGIN_PROPERTY)
         );
 
+        let row = self.query_first_row(&query, &[]).await?;
+        Ok(row
+            .and_then(|r| r.get::<&str, _>(0).and_then(|value| parse_origin_property(value, snapshot_name))))
+    }
+
+    /// Whether a directory exists as seen by the SQL Server service account (xp_fileexist, no xp_cmdshell)
+    pub async fn directory_exists(&mut self, path: &str) -> Result<bool, SqlServerError> {
+        let query = r#"
+            DECLARE @result TABLE (file_exists INT, is_directory INT, parent_exists INT);
+            INSERT INTO @result EXEC master.dbo.xp_fileexist @P1;
+            SELECT is_directory FROM @result
+        "#;
+        Ok(self
+            .query_first_row(query, &[path])
+            .await?
+            .and_then(|r| r.get::<i32, _>(0))
+            .is_some_and(|is_directory| is_directory == 1))
+    }
+
+    /// Create and immediately drop a throwaway snapshot of msdb in snapshot_path, proving SQL Server
+    /// can write sparse files there; msdb exists on every server and, unlike master or model, allows snapshots
+    pub async fn probe_snapshot_path(&mut self, snapshot_path: &str) -> Result<(), SqlServerError> {
+        let probe_name = format!("sqlparrot_pathcheck_{}", &uuid::Uuid::new_v4().simple().to_string()[..8]);
+        let files = self.get_database_files("msdb").await?;
+        let host_platform = self.get_host_platform().await?;
+
+        let query = create_snapshot_statement("msdb", &probe_name, &files, snapshot_path, &host_platform);
         self.client
             .simple_query(&query)
             .await
-            .map_err(|e| SqlServerError::SnapshotError(e.to_string()))?;
+            .map_err(|e| snapshot_error(None, e))?;
 
+        // The write succeeded either way; a probe that can't be dropped only needs cleaning up by hand
+        if let Err(e) = self.drop_snapshot(&probe_name).await {
+            log::warn!("Could not drop path-check snapshot {}: {}", probe_name, e);
+        }
+        Ok(())
+    }
+
+    /// Reason the database can't be snapshotted (memory-optimized or FILESTREAM filegroups), if any
+    /// A failed lookup isn't treated as a reason; CREATE DATABASE will report any real problem
+    async fn check_snapshot_support(&mut self, source_db: &str) -> Option<String> {
+        let query = format!(
+            "SELECT DISTINCT type FROM {}.sys.filegroups WHERE type IN ('FD', 'FX')",
+            quote_ident(source_db)
+        );
+
+        match self.execute_with_reconnect(&query).await {
+            Ok(rows) => {
+                let types: Vec<String> = rows
+                    .iter()
+                    .filter_map(|row| row.get::<&str, _>(0).map(|t| t.to_string()))
+                    .collect();
+                unsupported_snapshot_reason(source_db, &types)
+            }
+            Err(e) => {
+                log::warn!("Could not check filegroups of {}: {}", source_db, e);
+                None
+            }
+        }
+    }
+
+    /// A database's create_date from sys.databases, converted from server local time to UTC
+    pub async fn get_database_created_at(
+        &mut self,
+        database: &str,
+    ) -> Result<Option<DateTime<Utc>>, SqlServerError> {
+        let query = r#"
+            SELECT DATEADD(MINUTE, DATEDIFF(MINUTE, GETDATE(), GETUTCDATE()), create_date)
+            FROM sys.databases
+            WHERE name = @P1
+        "#;
+
+        let row = self.query_first_row(query, &[database]).await?;
+        Ok(row
+            .and_then(|r| r.get::<chrono::NaiveDateTime, _>(0))
+            .map(|created| DateTime::from_naive_utc_and_offset(created, Utc)))
+    }
+
+    /// Run DBCC CHECKDB against a snapshot database; an error means it is damaged or unreadable
+    pub async fn check_snapshot_integrity(&mut self, snapshot_name: &str) -> Result<(), SqlServerError> {
+        let query = format!("DBCC CHECKDB ({}) WITH NO_INFOMSGS", quote_ident(snapshot_name));
+        // Consistency errors arrive as the results are read, so drain them rather than stopping at the first token
+        self.client
+            .simple_query(&query)
+            .await
+            .map_err(|e| snapshot_error(Some("DBCC CHECKDB failed"), e))?
+            .into_results()
+            .await
+            .map_err(|e| snapshot_error(Some("DBCC CHECKDB failed"), e))?;
         Ok(())
     }
 
     /// Drop a database snapshot
     pub async fn drop_snapshot(&mut self, snapshot_name: &str) -> Result<(), SqlServerError> {
-        let query = format!("DROP DATABASE IF EXISTS [{}]", snapshot_name);
+        let query = format!("DROP DATABASE IF EXISTS {}", quote_ident(snapshot_name));
         self.client
             .simple_query(&query)
             .await
-            .map_err(|e| SqlServerError::SnapshotError(e.to_string()))?;
+            .map_err(|e| snapshot_error(None, e))?;
         Ok(())
     }
 
-    /// Kill all connections to a database
+    /// Drop several snapshots in one round trip
+    /// Each DROP runs in its own TRY/CATCH so one failure doesn't stop the rest;
+    /// returns the (name, error) pairs that failed
+    pub async fn drop_snapshots(
+        &mut self,
+        snapshot_names: &[String],
+    ) -> Result<Vec<(String, String)>, SqlServerError> {
+        if snapshot_names.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let rows = self
+            .client
+            .simple_query(drop_snapshots_batch(snapshot_names))
+            .await
+            .map_err(|e| snapshot_error(None, e))?
+            .into_first_result()
+            .await
+            .map_err(|e| snapshot_error(None, e))?;
+
+        Ok(rows
+            .iter()
+            .map(|row| {
+                (
+                    row.get::<&str, _>(0).unwrap_or_default().to_string(),
+                    row.get::<&str, _>(1).unwrap_or_default().to_string(),
+                )
+            })
+            .collect())
+    }
+
+    /// This connection's session id
+    pub async fn current_spid(&mut self) -> Result<i16, SqlServerError> {
+        let row = self
+            .client
+            .simple_query("SELECT @@SPID")
+            .await?
+            .into_row()
+            .await?
+            .ok_or_else(|| SqlServerError::QueryFailed("No session id returned".to_string()))?;
+
+        Ok(row.get(0).unwrap_or(0))
+    }
+
+    /// Kill all connections to a database except this one
     pub async fn kill_connections(&mut self, database: &str) -> Result<u32, SqlServerError> {
         // Get active sessions
-        let query = format!(
-            "SELECT session_id FROM sys.dm_exec_sessions WHERE database_id = DB_ID('{}')",
-            database.replace('\'', "''")
-        );
+        let query = "SELECT session_id, program_name FROM sys.dm_exec_sessions WHERE database_id = DB_ID(@P1)";
+
+        let rows = self.execute_params_with_reconnect(query, &[database]).await?;
+        let sessions: Vec<(i16, String)> = rows
+            .iter()
+            .map(|row| {
+                (
+                    row.get::<i16, _>(0).unwrap_or(0),
+                    row.get::<&str, _>(1).unwrap_or_default().to_string(),
+                )
+            })
+            .collect();
 
-        let stream = self.client.simple_query(&query).await?;
-        let rows = stream.into_first_result().await?;
+        // Read after the session query, which may have reconnected under a new spid
+        let own_spid = self.current_spid().await?;
 
         let mut killed = 0u32;
-        for row in rows {
-            let session_id: i16 = row.get(0).unwrap_or(0);
-            if session_id > 0 {
-                let kill_query = format!("KILL {}", session_id);
-                // Ignore errors when killing sessions
-                let _ = self.client.simple_query(&kill_query).await;
-                killed += 1;
-            }
+        for (session_id, program_name) in sessions_to_kill(&sessions, own_spid) {
+            log::debug!("Killing session {} ({}) on '{}'", session_id, program_name, database);
+            let kill_query = format!("KILL {}", session_id);
+            // Ignore errors when killing sessions
+            let _ = self.client.simple_query(&kill_query).await;
+            killed += 1;
         }
 
         Ok(killed)
@@ -213,8 +892,8 @@ impl SqlServerConnection {
     /// Set database to single user mode
     pub async fn set_single_user(&mut self, database: &str) -> Result<(), SqlServerError> {
         let query = format!(
-            "ALTER DATABASE [{}] SET SINGLE_USER WITH ROLLBACK IMMEDIATE",
-            database
+            "ALTER DATABASE {} SET SINGLE_USER WITH ROLLBACK IMMEDIATE",
+            quote_ident(database)
         );
         self.client
             .simple_query(&query)
@@ -225,7 +904,7 @@ impl SqlServerConnection {
 
     /// Set database to multi user mode
     pub async fn set_multi_user(&mut self, database: &str) -> Result<(), SqlServerError> {
-        let query = format!("ALTER DATABASE [{}] SET MULTI_USER", database);
+        let query = format!("ALTER DATABASE {} SET MULTI_USER", quote_ident(database));
         self.client
             .simple_query(&query)
             .await
@@ -234,57 +913,44 @@ impl SqlServerConnection {
     }
 
     /// Restore database from snapshot
+    /// The database must already be in SINGLE_USER (see set_single_user); MULTI_USER is always set afterwards
     pub async fn restore_from_snapshot(
         &mut self,
         database: &str,
         snapshot_name: &str,
     ) -> Result<(), SqlServerError> {
-        // Step 1: Set SINGLE_USER
-        let single_user_query = format!(
-            "ALTER DATABASE [{}] SET SINGLE_USER WITH ROLLBACK IMMEDIATE",
-            database
-        );
-        log::info!("Running: {}", single_user_query);
-        self.client
-            .simple_query(&single_user_query)
-            .await
-            .map_err(|e| SqlServerError::QueryFailed(format!("SINGLE_USER failed: {}", e)))?;
-
-        // Step 2: RESTORE
+        // RESTORE doesn't accept parameters for the snapshot name, so it goes in as a literal
         let restore_query = format!(
-            "RESTORE DATABASE [{}] FROM DATABASE_SNAPSHOT = '{}'",
-            database, snapshot_name
+            "RESTORE DATABASE {} FROM DATABASE_SNAPSHOT = {}",
+            quote_ident(database),
+            quote_literal(snapshot_name)
         );
         log::info!("Running: {}", restore_query);
-        let restore_error: Option<String> = match self.client.simple_query(&restore_query).await {
+        let restore_error = match self.client.simple_query(&restore_query).await {
             Ok(_) => None,
             Err(e) => {
                 log::error!("RESTORE failed: {}", e);
-                Some(e.to_string())
+                Some(e)
             }
         };
 
-        // Step 3: Always try to set MULTI_USER (even if restore failed)
-        let multi_user_query = format!("ALTER DATABASE [{}] SET MULTI_USER", database);
+        // Always try to set MULTI_USER (even if restore failed)
+        let multi_user_query = format!("ALTER DATABASE {} SET MULTI_USER", quote_ident(database));
         log::info!("Running: {}", multi_user_query);
         let _ = self.client.simple_query(&multi_user_query).await;
 
         // Now return the restore result with actual error message
         match restore_error {
             None => Ok(()),
-            Some(err) => Err(SqlServerError::SnapshotError(format!("RESTORE failed: {}", err)))
+            Some(err) => Err(snapshot_error(Some("RESTORE failed"), err)),
         }
     }
 
     /// Check if a snapshot exists in SQL Server
     pub async fn snapshot_exists(&mut self, snapshot_name: &str) -> Result<bool, SqlServerError> {
-        let query = format!(
-            "SELECT 1 FROM sys.databases WHERE name = '{}' AND source_database_id IS NOT NULL",
-            snapshot_name.replace('\'', "''")
-        );
+        let query = "SELECT 1 FROM sys.databases WHERE name = @P1 AND source_database_id IS NOT NULL";
 
-        let stream = self.client.simple_query(&query).await?;
-        let rows = stream.into_first_result().await?;
+        let rows = self.execute_params_with_reconnect(query, &[snapshot_name]).await?;
         Ok(!rows.is_empty())
     }
 
@@ -292,8 +958,7 @@ impl SqlServerConnection {
     pub async fn get_all_snapshots(&mut self) -> Result<Vec<String>, SqlServerError> {
         let query = "SELECT name FROM sys.databases WHERE source_database_id IS NOT NULL";
 
-        let stream = self.client.simple_query(query).await?;
-        let rows = stream.into_first_result().await?;
+        let rows = self.execute_with_reconnect(query).await?;
 
         let snapshots: Vec<String> = rows
             .iter()
@@ -304,38 +969,218 @@ impl SqlServerConnection {
     }
 
     /// Get all snapshots with their source database names (for cross-app detection)
-    pub async fn get_snapshots_with_source(&mut self) -> Result<Vec<(String, String)>, SqlServerError> {
+    pub async fn get_snapshots_with_source(&mut self) -> Result<Vec<ServerSnapshot>, SqlServerError> {
+        // Join each snapshot back to its source database; create_date is converted from server local time to UTC
         let query = r#"
-            SELECT name, DB_NAME(source_database_id) as source_db
-            FROM sys.databases
-            WHERE source_database_id IS NOT NULL
+            SELECT snap.name, src.name,
+                   DATEADD(MINUTE, DATEDIFF(MINUTE, GETDATE(), GETUTCDATE()), snap.create_date)
+            FROM sys.databases snap
+            JOIN sys.databases src ON src.database_id = snap.source_database_id
+            ORDER BY src.name, snap.create_date
         "#;
 
-        let stream = self.client.simple_query(query).await?;
-        let rows = stream.into_first_result().await?;
+        let rows = self.execute_with_reconnect(query).await?;
 
-        let snapshots: Vec<(String, String)> = rows
+        let snapshots: Vec<ServerSnapshot> = rows
             .iter()
             .filter_map(|row| {
-                let name = row.get::<&str, _>(0)?;
-                let source = row.get::<&str, _>(1)?;
-                Some((name.to_string(), source.to_string()))
+                Some(ServerSnapshot {
+                    name: row.get::<&str, _>(0)?.to_string(),
+                    source_database: row.get::<&str, _>(1)?.to_string(),
+                    create_date: row
+                        .get::<chrono::NaiveDateTime, _>(2)
+                        .map(|created| DateTime::from_naive_utc_and_offset(created, Utc)),
+                })
             })
             .collect();
 
         Ok(snapshots)
     }
 
+    /// Physical files of a snapshot database, empty when no snapshot by that name exists
+    /// On-disk sizes need VIEW SERVER STATE; without it they're left as None
+    pub async fn get_snapshot_files(&mut self, snapshot_name: &str) -> Result<Vec<SnapshotFile>, SqlServerError> {
+        let query = r#"
+            SELECT mf.name, mf.physical_name, CAST(mf.size AS BIGINT) * 8192
+            FROM sys.master_files mf
+            JOIN sys.databases d ON d.database_id = mf.database_id
+            WHERE d.name = @P1 AND d.source_database_id IS NOT NULL
+            ORDER BY mf.file_id
+        "#;
+
+        let rows = self.execute_params_with_reconnect(query, &[snapshot_name]).await?;
+        let mut files: Vec<SnapshotFile> = rows
+            .iter()
+            .map(|row| SnapshotFile {
+                logical_name: row.get::<&str, _>(0).unwrap_or("").to_string(),
+                physical_name: row.get::<&str, _>(1).unwrap_or("").to_string(),
+                size_bytes: row.get::<i64, _>(2).unwrap_or(0),
+                size_on_disk_bytes: None,
+            })
+            .collect();
+        if files.is_empty() {
+            return Ok(files);
+        }
+
+        let query = r#"
+            SELECT mf.physical_name, vfs.size_on_disk_bytes
+            FROM sys.master_files mf
+            CROSS APPLY sys.dm_io_virtual_file_stats(mf.database_id, mf.file_id) vfs
+            WHERE mf.database_id = DB_ID(@P1)
+        "#;
+        match self.execute_params_with_reconnect(query, &[snapshot_name]).await {
+            Ok(rows) => {
+                for row in rows {
+                    let physical_name = row.get::<&str, _>(0).unwrap_or("");
+                    if let Some(file) = files.iter_mut().find(|f| f.physical_name == physical_name) {
+                        file.size_on_disk_bytes = row.get::<i64, _>(1);
+                    }
+                }
+            }
+            Err(e) => log::info!("sys.dm_io_virtual_file_stats unavailable: {}", e),
+        }
+
+        Ok(files)
+    }
+
+    /// Snapshot (.ss) files in the snapshot directory that no longer belong to any database,
+    /// e.g. left behind by a DROP DATABASE that failed partway
+    /// Best-effort: servers that can't list the directory (pre-2017, missing permission) report none
+    pub async fn find_leftover_snapshot_files(&mut self, snapshot_path: &str) -> Vec<String> {
+        let on_disk = match self
+            .execute_params_with_reconnect(
+                "SELECT full_filesystem_path FROM sys.dm_os_enumerate_filesystem(@P1, N'*.ss') WHERE is_directory = 0",
+                &[snapshot_path],
+            )
+            .await
+        {
+            Ok(rows) => rows
+                .iter()
+                .filter_map(|row| row.get::<&str, _>(0).map(|p| p.to_string()))
+                .collect::<Vec<_>>(),
+            Err(e) => {
+                log::info!("Could not list snapshot files in {}: {}", snapshot_path, e);
+                return Vec::new();
+            }
+        };
+        if on_disk.is_empty() {
+            return on_disk;
+        }
+
+        match self.execute_with_reconnect("SELECT physical_name FROM sys.master_files").await {
+            Ok(rows) => {
+                let in_use: Vec<String> = rows
+                    .iter()
+                    .filter_map(|row| row.get::<&str, _>(0).map(|p| p.to_string()))
+                    .collect();
+                unused_snapshot_files(&on_disk, &in_use)
+            }
+            Err(e) => {
+                log::info!("Could not read sys.master_files: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Allocated and used bytes across tempdb's data files
+    pub async fn get_tempdb_usage(&mut self) -> Result<(i64, i64), SqlServerError> {
+        let query = r#"
+            SELECT SUM(CAST(total_page_count AS BIGINT)) * 8192,
+                   SUM(CAST(total_page_count - unallocated_extent_page_count AS BIGINT)) * 8192
+            FROM tempdb.sys.dm_db_file_space_usage
+        "#;
+
+        let row = self.query_first_row(query, &[]).await?;
+        Ok(row
+            .map(|r| (r.get::<i64, _>(0).unwrap_or(0), r.get::<i64, _>(1).unwrap_or(0)))
+            .unwrap_or((0, 0)))
+    }
+
+    /// Volumes holding database files, as (mount point, available bytes, total bytes)
+    /// Needs VIEW SERVER STATE
+    pub async fn get_volume_space(&mut self) -> Result<Vec<(String, i64, i64)>, SqlServerError> {
+        let query = r#"
+            SELECT DISTINCT vs.volume_mount_point, CAST(vs.available_bytes AS BIGINT), CAST(vs.total_bytes AS BIGINT)
+            FROM sys.master_files mf
+            CROSS APPLY sys.dm_os_volume_stats(mf.database_id, mf.file_id) vs
+        "#;
+
+        let rows = self.execute_with_reconnect(query).await?;
+        Ok(rows
+            .iter()
+            .filter_map(|row| {
+                Some((
+                    row.get::<&str, _>(0)?.to_string(),
+                    row.get::<i64, _>(1)?,
+                    row.get::<i64, _>(2)?,
+                ))
+            })
+            .collect())
+    }
+
+    /// Get Always On availability group membership and the local replica's role for a database
+    /// Servers without the HADR views (e.g. Express) or without permission report "not in AG"
+    pub async fn get_availability_info(
+        &mut self,
+        database: &str,
+    ) -> Result<AvailabilityInfo, SqlServerError> {
+        let query = r#"
+            SELECT ag.name, ars.role_desc
+            FROM sys.availability_databases_cluster adc
+            JOIN sys.availability_groups ag ON ag.group_id = adc.group_id
+            LEFT JOIN sys.dm_hadr_availability_replica_states ars
+                ON ars.group_id = adc.group_id AND ars.is_local = 1
+            WHERE adc.database_name = @P1
+        "#;
+
+        match self.query_first_row(query, &[database]).await {
+            Ok(Some(row)) => Ok(AvailabilityInfo {
+                in_availability_group: true,
+                group_name: row.get::<&str, _>(0).map(|s| s.to_string()),
+                replica_role: row.get::<&str, _>(1).map(|s| s.to_string()),
+            }),
+            Ok(None) => Ok(AvailabilityInfo::default()),
+            Err(SqlServerError::Tiberius(tiberius::error::Error::Server(e))) => {
+                log::info!("Availability group views unavailable: {}", e);
+                Ok(AvailabilityInfo::default())
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Recovery model of a database (SIMPLE, FULL, or BULK_LOGGED)
+    pub async fn get_recovery_model(&mut self, database: &str) -> Result<String, SqlServerError> {
+        let query = "SELECT recovery_model_desc FROM sys.databases WHERE name = @P1";
+
+        let row = self
+            .query_first_row(query, &[database])
+            .await?
+            .ok_or_else(|| SqlServerError::DatabaseNotFound(database.to_string()))?;
+
+        let model: &str = row.get(0).unwrap_or("UNKNOWN");
+        Ok(model.to_string())
+    }
+
+    /// State (ONLINE, RESTORING, ...) and user access (MULTI_USER, SINGLE_USER, RESTRICTED_USER) of a database
+    pub async fn get_database_access(&mut self, database: &str) -> Result<(String, String), SqlServerError> {
+        let query = "SELECT state_desc, user_access_desc FROM sys.databases WHERE name = @P1";
+
+        let row = self
+            .query_first_row(query, &[database])
+            .await?
+            .ok_or_else(|| SqlServerError::DatabaseNotFound(database.to_string()))?;
+
+        let state: &str = row.get(0).unwrap_or("UNKNOWN");
+        let user_access: &str = row.get(1).unwrap_or("UNKNOWN");
+        Ok((state.to_string(), user_access.to_string()))
+    }
+
     /// Check database state
     pub async fn get_database_state(&mut self, database: &str) -> Result<String, SqlServerError> {
-        let query = format!(
-            "SELECT state_desc FROM sys.databases WHERE name = '{}'",
-            database.replace('\'', "''")
-        );
+        let query = "SELECT state_desc FROM sys.databases WHERE name = @P1";
 
-        let stream = self.client.simple_query(&query).await?;
-        let row = stream
-            .into_row()
+        let row = self
+            .query_first_row(query, &[database])
             .await?
             .ok_or_else(|| SqlServerError::DatabaseNotFound(database.to_string()))?;
 
@@ -343,3 +1188,235 @@ impl SqlServerConnection {
         Ok(state.to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tiberius::error::{Error, IoErrorKind};
+
+    fn io_error(kind: IoErrorKind) -> Error {
+        Error::Io {
+            kind,
+            message: "test".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_is_connection_broken_for_socket_errors() {
+        assert!(is_connection_broken(&io_error(IoErrorKind::ConnectionReset)));
+        assert!(is_connection_broken(&io_error(IoErrorKind::BrokenPipe)));
+        assert!(is_connection_broken(&io_error(IoErrorKind::UnexpectedEof)));
+    }
+
+    #[test]
+    fn test_is_connection_broken_ignores_query_errors() {
+        assert!(!is_connection_broken(&io_error(IoErrorKind::PermissionDenied)));
+        assert!(!is_connection_broken(&Error::Protocol("bad token".into())));
+        assert!(!is_connection_broken(&Error::Conversion("bad value".into())));
+    }
+
+    #[test]
+    fn test_unused_snapshot_files() {
+        let on_disk = vec![
+            "C:\\Snapshots\\g1_1_Sales_0.ss".to_string(),
+            "C:\\Snapshots\\g1_2_Sales_0.ss".to_string(),
+        ];
+        let in_use = vec![
+            "c:\\snapshots\\G1_1_SALES_0.ss".to_string(),
+            "C:\\Data\\Sales.mdf".to_string(),
+        ];
+
+        assert_eq!(
+            unused_snapshot_files(&on_disk, &in_use),
+            vec!["C:\\Snapshots\\g1_2_Sales_0.ss".to_string()]
+        );
+        assert!(unused_snapshot_files(&[], &in_use).is_empty());
+    }
+
+    #[test]
+    fn test_snapshot_error_display_includes_error_number() {
+        let error = SqlServerError::SnapshotError {
+            message: "Cannot create file".to_string(),
+            number: Some(5170),
+            severity: Some(16),
+            state: Some(1),
+        };
+        assert_eq!(
+            error.to_string(),
+            "Snapshot operation failed: Cannot create file (Msg 5170, Level 16, State 1)"
+        );
+        assert_eq!(error.sql_error_code(), Some((5170, 16, 1)));
+
+        let io = snapshot_error(Some("RESTORE failed"), io_error(IoErrorKind::BrokenPipe));
+        assert!(io.to_string().starts_with("Snapshot operation failed: RESTORE failed: "));
+        assert_eq!(io.sql_error_code(), None);
+    }
+
+    #[test]
+    fn test_classify_error_code_recognizes_login_and_permission_errors() {
+        assert!(matches!(
+            classify_error_code(18456, "Login failed for user 'sa'."),
+            Some(SqlServerError::LoginFailed(_))
+        ));
+        assert!(matches!(
+            classify_error_code(262, "CREATE DATABASE permission denied in database 'master'."),
+            Some(SqlServerError::PermissionDenied(_))
+        ));
+        assert!(classify_error_code(1823, "A database snapshot cannot be created").is_none());
+    }
+
+    #[test]
+    fn test_classify_error_maps_io_and_tls() {
+        assert!(matches!(
+            classify_error(io_error(IoErrorKind::ConnectionRefused)),
+            SqlServerError::Unreachable(_)
+        ));
+        assert!(matches!(
+            classify_error(Error::Tls("certificate verify failed".into())),
+            SqlServerError::TlsFailed(_)
+        ));
+        assert!(matches!(
+            classify_error(Error::Protocol("bad token".into())),
+            SqlServerError::Tiberius(_)
+        ));
+    }
+
+    #[test]
+    fn test_user_message_appends_remedy() {
+        let message = SqlServerError::LoginFailed("Login failed for user 'sa'.".to_string()).user_message();
+        assert!(message.starts_with("Login failed: Login failed for user 'sa'. Check"));
+        assert!(message.ends_with(LOGIN_REMEDY));
+
+        let plain = SqlServerError::QueryFailed("boom".to_string());
+        assert_eq!(plain.user_message(), "Query failed: boom");
+    }
+
+    #[test]
+    fn test_unsupported_snapshot_reason() {
+        assert!(unsupported_snapshot_reason("Sales", &[]).is_none());
+
+        let reason = unsupported_snapshot_reason("Sales", &["FD".to_string(), "FX".to_string()]).unwrap();
+        assert!(reason.contains("memory-optimized"));
+
+        let reason = unsupported_snapshot_reason("Docs", &["FD".to_string()]).unwrap();
+        assert!(reason.contains("FILESTREAM"));
+    }
+
+    #[test]
+    fn test_drop_snapshots_batch_wraps_each_drop() {
+        let batch = drop_snapshots_batch(&["sp_a".to_string(), "sp_o'b]".to_string()]);
+
+        assert_eq!(batch.matches("BEGIN TRY DROP DATABASE IF EXISTS").count(), 2);
+        assert!(batch.contains("[sp_a]"));
+        assert!(batch.contains("[sp_o'b]]]"));
+        assert!(batch.contains("N'sp_o''b]'"));
+        assert!(batch.trim_end().ends_with("SELECT name, error FROM @failed;"));
+    }
+
+    #[test]
+    fn test_sessions_to_kill_skips_own_spid() {
+        let sessions = vec![
+            (0, String::new()),
+            (52, "SQL Parrot".to_string()),
+            (57, "Microsoft SQL Server Management Studio".to_string()),
+            (61, "SQL Parrot".to_string()),
+        ];
+
+        let ids: Vec<i16> = sessions_to_kill(&sessions, 52).into_iter().map(|(id, _)| id).collect();
+        assert_eq!(ids, vec![57, 61]);
+    }
+
+    #[test]
+    fn test_quoting_handles_apostrophes_and_brackets() {
+        assert_eq!(quote_ident("O'Brien"), "[O'Brien]");
+        assert_eq!(quote_ident("odd]name"), "[odd]]name]");
+        assert_eq!(quote_literal("O'Brien"), "N'O''Brien'");
+    }
+
+    #[test]
+    fn test_origin_property_only_trusted_for_its_own_snapshot() {
+        let statement = set_origin_property_statement("O'Brien", "O'Brien_snapshot_1", "exe:abc");
+        assert!(statement.contains("EXEC [O'Brien].sys.sp_addextendedproperty @name = N'SQLParrotOrigin'"));
+        assert!(statement.contains(r#"@value = N'{"origin":"exe:abc","snapshot":"O''Brien_snapshot_1"}'"#));
+
+        let value = r#"{"origin":"exe:abc","snapshot":"Sales_snapshot_1"}"#;
+        assert_eq!(parse_origin_property(value, "sales_snapshot_1"), Some("exe:abc".to_string()));
+        assert_eq!(parse_origin_property(value, "Sales_snapshot_2"), None);
+        assert_eq!(parse_origin_property("not json", "Sales_snapshot_1"), None);
+    }
+
+    #[test]
+    fn test_create_snapshot_statement_with_apostrophe_in_name() {
+        let files = vec![("O'Brien_Data".to_string(), "C:\\Data\\O'Brien.mdf".to_string())];
+        let sql = create_snapshot_statement("O'Brien", "O'Brien_snapshot_1", &files, "C:\\Snapshots", "Windows");
+
+        assert_eq!(
+            sql,
+            "CREATE DATABASE [O'Brien_snapshot_1] ON (NAME = N'O''Brien_Data', \
+             FILENAME = N'C:\\Snapshots\\O''Brien_snapshot_1_0.ss') AS SNAPSHOT OF [O'Brien]"
+        );
+    }
+
+    /// Source files of a database with data spread over PRIMARY, SALES_2024, and INDEXES filegroups,
+    /// as get_database_files returns them (rows files only, ordered by file_id; the log is excluded)
+    const MULTI_FILEGROUP_FILES: [(&str, &str); 4] = [
+        ("Sales", "D:\\Data\\Sales.mdf"),
+        ("Sales_2024_1", "D:\\Data\\Sales_2024_1.ndf"),
+        ("Sales_2024_2", "E:\\Data\\Sales_2024_2.ndf"),
+        ("Sales_Indexes", "F:\\Index\\Sales_Indexes.ndf"),
+    ];
+
+    /// Expected statement for MULTI_FILEGROUP_FILES: every logical file listed once, each with a distinct sparse file
+    const MULTI_FILEGROUP_SQL: &str = "CREATE DATABASE [Sales_snapshot_QA_3] ON \
+        (NAME = N'Sales', FILENAME = N'S:\\Snapshots\\Sales_snapshot_QA_3_0.ss'), \
+        (NAME = N'Sales_2024_1', FILENAME = N'S:\\Snapshots\\Sales_snapshot_QA_3_1.ss'), \
+        (NAME = N'Sales_2024_2', FILENAME = N'S:\\Snapshots\\Sales_snapshot_QA_3_2.ss'), \
+        (NAME = N'Sales_Indexes', FILENAME = N'S:\\Snapshots\\Sales_snapshot_QA_3_3.ss') \
+        AS SNAPSHOT OF [Sales]";
+
+    #[test]
+    fn test_create_snapshot_statement_covers_every_file_across_filegroups() {
+        let files: Vec<(String, String)> = MULTI_FILEGROUP_FILES
+            .iter()
+            .map(|(name, path)| (name.to_string(), path.to_string()))
+            .collect();
+
+        let sql = create_snapshot_statement("Sales", "Sales_snapshot_QA_3", &files, "S:\\Snapshots\\", "Windows");
+        assert_eq!(sql, MULTI_FILEGROUP_SQL);
+
+        let sparse_files: std::collections::HashSet<String> = (0..files.len())
+            .map(|i| snapshot_file_path("S:\\Snapshots", "Sales_snapshot_QA_3", i, "Windows"))
+            .collect();
+        assert_eq!(sparse_files.len(), files.len());
+        for (name, _) in &files {
+            assert_eq!(sql.matches(&format!("NAME = N'{}',", name)).count(), 1);
+        }
+    }
+
+    #[test]
+    fn test_like_contains_pattern_escapes_wildcards() {
+        assert_eq!(like_contains_pattern("Sales"), "%Sales%");
+        assert_eq!(like_contains_pattern("100%_done"), "%100\\%\\_done%");
+        assert_eq!(like_contains_pattern("[dbo]\\x"), "%\\[dbo]\\\\x%");
+    }
+
+    #[test]
+    fn test_snapshot_file_path_uses_host_separator() {
+        assert_eq!(
+            snapshot_file_path("/var/opt/mssql/snapshots", "Sales_snap", 0, "Linux"),
+            "/var/opt/mssql/snapshots/Sales_snap_0.ss"
+        );
+        assert_eq!(
+            snapshot_file_path("C:\\Snapshots", "Sales_snap", 1, "Windows"),
+            "C:\\Snapshots\\Sales_snap_1.ss"
+        );
+    }
+
+    #[test]
+    fn test_snapshot_file_path_trims_trailing_separator() {
+        assert_eq!(
+            snapshot_file_path("/var/opt/mssql/snapshots/", "Sales_snap", 0, "Linux"),
+            "/var/opt/mssql/snapshots/Sales_snap_0.ss"
+        );
+    }
+}
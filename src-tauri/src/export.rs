@@ -0,0 +1,29 @@
+// ABOUTME: CSV formatting for exports such as the history CSV
+// ABOUTME: Writers build one line at a time so large exports never need the whole file in memory
+
+/// Quote a CSV field when it contains a delimiter, quote, or line break
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// One CSV line, newline included
+pub fn csv_line<S: AsRef<str>>(fields: &[S]) -> String {
+    let fields: Vec<String> = fields.iter().map(|field| csv_field(field.as_ref())).collect();
+    format!("{}\n", fields.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_quoting() {
+        assert_eq!(csv_field(r#"say "hi""#), r#""say ""hi""""#);
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_line(&["a,b", "", "c"]), "\"a,b\",,c\n");
+    }
+}
@@ -0,0 +1,127 @@
+// ABOUTME: Short-lived tokens that let a rollback proceed past external snapshots
+// ABOUTME: Each token is tied to one snapshot and the exact external snapshots acknowledged for it
+
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+
+/// How long an acknowledgement stays valid
+pub const OVERRIDE_TOKEN_TTL_SECONDS: i64 = 300;
+
+/// Who acknowledged which external snapshots for a rollback, and when
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct Acknowledgement {
+    #[serde(rename = "snapshotId")]
+    pub snapshot_id: String,
+    #[serde(rename = "externalSnapshots")]
+    pub external_snapshots: Vec<String>,
+    #[serde(rename = "acknowledgedBy")]
+    pub acknowledged_by: String,
+    #[serde(rename = "acknowledgedAt")]
+    pub acknowledged_at: DateTime<Utc>,
+    #[serde(rename = "expiresAt")]
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Outstanding acknowledgement tokens, shared by the app and the CLI within one process
+#[derive(Default)]
+pub struct ExternalOverrides {
+    tokens: Mutex<HashMap<String, Acknowledgement>>,
+}
+
+static SHARED: OnceLock<ExternalOverrides> = OnceLock::new();
+
+fn sorted(names: &[String]) -> Vec<String> {
+    let mut names = names.to_vec();
+    names.sort();
+    names
+}
+
+impl ExternalOverrides {
+    pub fn shared() -> &'static ExternalOverrides {
+        SHARED.get_or_init(ExternalOverrides::default)
+    }
+
+    /// Issue a token for rolling back to `snapshot_id` while these external snapshots exist
+    pub fn issue(
+        &self,
+        snapshot_id: &str,
+        external_snapshots: &[String],
+        acknowledged_by: &str,
+        now: DateTime<Utc>,
+    ) -> (String, Acknowledgement) {
+        let acknowledgement = Acknowledgement {
+            snapshot_id: snapshot_id.to_string(),
+            external_snapshots: sorted(external_snapshots),
+            acknowledged_by: acknowledged_by.to_string(),
+            acknowledged_at: now,
+            expires_at: now + Duration::seconds(OVERRIDE_TOKEN_TTL_SECONDS),
+        };
+        let token = Uuid::new_v4().simple().to_string();
+
+        let mut tokens = self.tokens.lock().unwrap();
+        tokens.retain(|_, ack| ack.expires_at > now);
+        tokens.insert(token.clone(), acknowledgement.clone());
+        (token, acknowledgement)
+    }
+
+    /// Use up a token; it only works once, before it expires, for the same snapshot, and while
+    /// the external snapshots are exactly the ones acknowledged
+    pub fn redeem(
+        &self,
+        token: &str,
+        snapshot_id: &str,
+        external_snapshots: &[String],
+        now: DateTime<Utc>,
+    ) -> Result<Acknowledgement, String> {
+        let acknowledgement = self
+            .tokens
+            .lock()
+            .unwrap()
+            .remove(token.trim())
+            .ok_or_else(|| "Unknown or already used override token".to_string())?;
+
+        if acknowledgement.expires_at <= now {
+            return Err("Override token has expired; acknowledge the external snapshots again".to_string());
+        }
+        if acknowledgement.snapshot_id != snapshot_id {
+            return Err("Override token was issued for a different snapshot".to_string());
+        }
+        if acknowledgement.external_snapshots != sorted(external_snapshots) {
+            return Err(
+                "External snapshots changed since they were acknowledged; acknowledge them again".to_string(),
+            );
+        }
+        Ok(acknowledgement)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_is_single_use_and_tied_to_snapshot_set() {
+        let overrides = ExternalOverrides::default();
+        let now = Utc::now();
+        let external = vec!["Sales_manual".to_string(), "Orders_manual".to_string()];
+
+        let (token, _) = overrides.issue("s1", &external, "alice", now);
+        let reordered = vec!["Orders_manual".to_string(), "Sales_manual".to_string()];
+        let ack = overrides.redeem(&token, "s1", &reordered, now).unwrap();
+        assert_eq!(ack.acknowledged_by, "alice");
+        assert!(overrides.redeem(&token, "s1", &reordered, now).is_err());
+
+        let (token, _) = overrides.issue("s1", &external, "alice", now);
+        assert!(overrides.redeem(&token, "s2", &external, now).is_err());
+
+        let (token, _) = overrides.issue("s1", &external, "alice", now);
+        assert!(overrides.redeem(&token, "s1", &external[..1], now).is_err());
+
+        let (token, _) = overrides.issue("s1", &external, "alice", now);
+        let later = now + Duration::seconds(OVERRIDE_TOKEN_TTL_SECONDS + 1);
+        assert!(overrides.redeem(&token, "s1", &external, later).unwrap_err().contains("expired"));
+    }
+}
@@ -0,0 +1,182 @@
+// ABOUTME: Per-group operation locks held in Tauri managed state
+// ABOUTME: Keeps two snapshot/rollback/cleanup operations from interleaving on the same group,
+// ABOUTME: within the app and, through lock files, between the app and --cli runs
+
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use fs2::FileExt;
+use tokio::sync::OwnedMutexGuard;
+
+use crate::db::MetadataStore;
+use crate::messages;
+use crate::ApiResponse;
+
+/// Error code returned when another operation is already running on the group
+pub const GROUP_BUSY: &str = "GroupBusy";
+
+/// One async lock per group id, created on first use
+/// With a lock directory, each lock also holds an exclusive lock on a file there,
+/// which the OS releases if the process dies
+#[derive(Default)]
+pub struct GroupLocks {
+    locks: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+    lock_dir: Option<PathBuf>,
+}
+
+/// Holds a group's lock until dropped
+pub struct GroupGuard {
+    _local: OwnedMutexGuard<()>,
+    _file: Option<File>,
+}
+
+impl GroupLocks {
+    /// Locks shared with other SQL Parrot processes through lock files next to the metadata database
+    pub fn shared() -> Self {
+        let lock_dir = MetadataStore::db_path()
+            .ok()
+            .and_then(|path| path.parent().map(|dir| dir.join("locks")));
+        Self::with_lock_dir(lock_dir)
+    }
+
+    fn with_lock_dir(lock_dir: Option<PathBuf>) -> Self {
+        GroupLocks {
+            locks: Mutex::default(),
+            lock_dir,
+        }
+    }
+
+    /// Take the group's lock without waiting; None if another operation holds it, here or in another process
+    /// The lock is released when the returned guard is dropped, including on early returns
+    pub fn try_lock(&self, group_id: &str) -> Option<GroupGuard> {
+        let lock = self
+            .locks
+            .lock()
+            .unwrap()
+            .entry(group_id.to_string())
+            .or_default()
+            .clone();
+        let local = lock.try_lock_owned().ok()?;
+
+        let file = match self.lock_file(group_id) {
+            Ok(Some(file)) => match file.try_lock_exclusive() {
+                Ok(()) => Some(file),
+                Err(e) if e.kind() == fs2::lock_contended_error().kind() => return None,
+                Err(e) => {
+                    log::warn!("Failed to lock the lock file of group {}: {}", group_id, e);
+                    None
+                }
+            },
+            Ok(None) => None,
+            // The in-process lock still holds; only other processes go unguarded
+            Err(e) => {
+                log::warn!("Failed to open the lock file of group {}: {}", group_id, e);
+                None
+            }
+        };
+
+        Some(GroupGuard {
+            _local: local,
+            _file: file,
+        })
+    }
+
+    /// Take the locks of several groups, or none of them: Err names the first group that is busy
+    /// A group listed more than once is locked once
+    pub fn try_lock_all<'a>(&self, group_ids: impl IntoIterator<Item = &'a str>) -> Result<Vec<GroupGuard>, &'a str> {
+        let mut locked: Vec<&str> = Vec::new();
+        let mut guards = Vec::new();
+        for group_id in group_ids {
+            if locked.contains(&group_id) {
+                continue;
+            }
+            guards.push(self.try_lock(group_id).ok_or(group_id)?);
+            locked.push(group_id);
+        }
+        Ok(guards)
+    }
+
+    /// The group's lock file, left in place between uses so no process locks a file another is deleting
+    fn lock_file(&self, group_id: &str) -> std::io::Result<Option<File>> {
+        let dir = match &self.lock_dir {
+            Some(dir) => dir,
+            None => return Ok(None),
+        };
+        std::fs::create_dir_all(dir)?;
+        let file_name: String = group_id
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+            .collect();
+        OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(dir.join(format!("{}.lock", file_name)))
+            .map(Some)
+    }
+
+    /// Groups with an operation running right now in this process
+    pub fn busy_count(&self) -> usize {
+        self.locks
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|lock| lock.try_lock().is_err())
+            .count()
+    }
+}
+
+/// Response for an operation that found its group already locked
+pub fn group_busy<T>(group_name: &str) -> ApiResponse<T> {
+    ApiResponse::error_with_code(GROUP_BUSY, messages::text(messages::GROUP_BUSY, &[&group_name]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_try_lock_is_exclusive_per_group() {
+        let locks = GroupLocks::default();
+
+        let guard = locks.try_lock("group-1").expect("first lock");
+        assert!(locks.try_lock("group-1").is_none());
+        assert!(locks.try_lock("group-2").is_some());
+        assert_eq!(locks.busy_count(), 1);
+
+        drop(guard);
+        assert!(locks.try_lock("group-1").is_some());
+        assert_eq!(locks.busy_count(), 0);
+    }
+
+    #[test]
+    fn test_try_lock_all_takes_every_lock_or_none() {
+        let locks = GroupLocks::default();
+
+        let guards = locks.try_lock_all(["group-1", "group-2", "group-1"]).expect("all free");
+        assert_eq!(guards.len(), 2);
+        assert_eq!(locks.try_lock_all(["group-3", "group-2"]).err(), Some("group-2"));
+        // The lock taken before the busy group was found is released again
+        assert_eq!(locks.busy_count(), 2);
+
+        drop(guards);
+        assert!(locks.try_lock_all(["group-3", "group-2"]).is_ok());
+    }
+
+    #[test]
+    fn test_lock_files_are_exclusive_across_lock_sets() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        // Two lock sets stand in for the app and a --cli run
+        let app = GroupLocks::with_lock_dir(Some(temp_dir.path().to_path_buf()));
+        let cli = GroupLocks::with_lock_dir(Some(temp_dir.path().to_path_buf()));
+
+        let guard = app.try_lock("group-1").expect("first lock");
+        assert!(cli.try_lock("group-1").is_none());
+        assert!(cli.try_lock("group-2").is_some());
+
+        drop(guard);
+        assert!(cli.try_lock("group-1").is_some());
+    }
+}
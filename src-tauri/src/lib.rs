@@ -2,12 +2,23 @@
 // ABOUTME: Contains app setup, command registration, and module declarations
 
 use serde::{Deserialize, Serialize};
+use tauri::Manager;
 
 // Module declarations
+pub mod cli;
 pub mod commands;
 pub mod config;
 pub mod db;
+pub mod export;
+pub mod external_overrides;
+pub mod group_locks;
+pub mod messages;
+pub mod metrics;
 pub mod models;
+pub mod sessions;
+pub mod shutdown;
+pub mod timezone;
+pub mod webhooks;
 
 /// Standard API response format matching the Express backend
 #[derive(Debug, Serialize, Deserialize)]
@@ -16,6 +27,12 @@ pub struct ApiResponse<T> {
     pub data: Option<T>,
     pub messages: Messages,
     pub timestamp: String,
+    /// Machine-readable reason for a failure the UI handles specially (e.g. "ConfirmationRequired")
+    #[serde(rename = "errorCode", default, skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<String>,
+    /// How long the command took; every Tauri command runs its body through `timed` to fill it
+    #[serde(rename = "durationMs", default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Default)]
@@ -33,6 +50,8 @@ impl<T> ApiResponse<T> {
             data: Some(data),
             messages: Messages::default(),
             timestamp: chrono::Utc::now().to_rfc3339(),
+            error_code: None,
+            duration_ms: None,
         }
     }
 
@@ -45,6 +64,20 @@ impl<T> ApiResponse<T> {
                 ..Default::default()
             },
             timestamp: chrono::Utc::now().to_rfc3339(),
+            error_code: None,
+            duration_ms: None,
+        }
+    }
+
+    /// Error with a localized message looked up by key (see `messages`)
+    pub fn error_key(key: &str, args: &[&dyn std::fmt::Display]) -> Self {
+        Self::error(messages::text(key, args))
+    }
+
+    pub fn error_with_code(code: &str, message: String) -> Self {
+        Self {
+            error_code: Some(code.to_string()),
+            ..Self::error(message)
         }
     }
 
@@ -57,10 +90,20 @@ impl<T> ApiResponse<T> {
                 ..Default::default()
             },
             timestamp: chrono::Utc::now().to_rfc3339(),
+            error_code: None,
+            duration_ms: None,
         }
     }
 }
 
+/// Run a command body and record how long it took in the response's duration_ms
+pub async fn timed<T>(command: impl std::future::Future<Output = ApiResponse<T>>) -> ApiResponse<T> {
+    let started = std::time::Instant::now();
+    let mut response = command.await;
+    response.duration_ms = Some(started.elapsed().as_millis() as u64);
+    response
+}
+
 /// Health check response
 #[derive(Debug, Serialize, Deserialize)]
 pub struct HealthResponse {
@@ -69,11 +112,17 @@ pub struct HealthResponse {
     pub platform: String,
     #[serde(rename = "sqlServerVersion", skip_serializing_if = "Option::is_none")]
     pub sql_server_version: Option<String>,
+    /// How far the server's clock is ahead of this machine's (negative when behind)
+    #[serde(rename = "clockSkewSeconds", skip_serializing_if = "Option::is_none")]
+    pub clock_skew_seconds: Option<i64>,
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
+        .manage(group_locks::GroupLocks::shared())
+        .manage(sessions::Sessions::default())
+        .manage(db::ConnectionLimiter::shared().clone())
         .setup(|app| {
             if cfg!(debug_assertions) {
                 app.handle().plugin(
@@ -82,43 +131,113 @@ pub fn run() {
                         .build(),
                 )?;
             }
+
+            app.manage(shutdown::previous_shutdown());
+
+            // Anything still marked running was cut short by the last shutdown or crash
+            match db::MetadataStore::open().and_then(|store| store.mark_operations_interrupted()) {
+                Ok(0) => {}
+                Ok(count) => log::warn!("{} operation(s) were interrupted; resume or abandon them", count),
+                Err(e) => log::warn!("Failed to check for interrupted operations: {}", e),
+            }
+
+            // Off by default; the port is bound to localhost only
+            let preferences = db::MetadataStore::open()
+                .and_then(|store| store.get_settings())
+                .map(|settings| settings.preferences)
+                .unwrap_or_default();
+            if preferences.metrics_enabled {
+                app.manage(metrics::MetricsServer::start(preferences.metrics_port));
+            }
+
+            if preferences.auto_trim_on_startup {
+                match db::MetadataStore::open().and_then(|store| store.trim_history(preferences.max_history_entries)) {
+                    Ok(deleted) => log::info!(
+                        "Trimmed {} history entry(ies) on startup (keeping {})",
+                        deleted,
+                        preferences.max_history_entries
+                    ),
+                    Err(e) => log::warn!("Failed to trim history on startup: {}", e),
+                }
+            }
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             // Connection commands
             commands::check_health,
+            commands::get_connection_history,
             commands::test_connection,
+            commands::suggest_snapshot_path,
+            commands::diagnose_connection,
             commands::get_databases,
+            commands::search_databases,
+            commands::get_server_info,
+            commands::get_resource_pressure,
             commands::save_connection,
             commands::get_connection,
+            commands::get_connection_usage,
+            // Dashboard commands
+            commands::get_dashboard_summary,
+            commands::startup_health_check,
             // Group commands
             commands::get_groups,
             commands::create_group,
             commands::update_group,
             commands::delete_group,
+            commands::bulk_create_groups,
+            commands::create_groups_by_regex,
+            commands::move_group_to_profile,
+            commands::export_group,
+            commands::import_group,
+            commands::get_group_delete_impact,
+            commands::get_database_group_conflicts,
             // Snapshot commands
             commands::get_snapshots,
             commands::create_snapshot,
+            commands::resnapshot,
             commands::delete_snapshot,
             commands::rollback_snapshot,
+            commands::preview_rollback,
+            commands::rollback_latest,
+            commands::renumber_snapshots,
             commands::verify_snapshots,
             commands::cleanup_snapshot,
+            commands::drop_orphaned_snapshots,
+            commands::retry_snapshot_database,
+            commands::update_snapshot_notes,
+            commands::reconcile_metadata,
             commands::check_external_snapshots,
+            commands::acknowledge_external_snapshots,
+            commands::get_snapshot_files,
+            commands::list_server_snapshots,
+            commands::drop_all_server_snapshots,
             commands::test_snapshot_path,
+            commands::test_snapshot_path_writable,
             // Settings/history commands
             commands::get_settings,
             commands::update_settings,
+            commands::reset_settings,
             commands::get_history,
+            commands::get_history_for_group,
+            commands::export_history_csv,
             commands::clear_history,
             commands::trim_history,
+            commands::test_webhook,
+            commands::undo_last_operation,
+            commands::get_interrupted_operations,
+            commands::resume_operation,
+            commands::abandon_operation,
             commands::get_metadata_status,
             // UI Security password commands
             commands::get_password_status,
             commands::check_password,
+            commands::check_password_policy,
             commands::set_password,
             commands::change_password,
             commands::remove_password,
             commands::skip_password,
+            commands::reset_ui_password_with_recovery,
+            commands::logout,
             // Profile management commands
             commands::get_profiles,
             commands::get_profile,
@@ -126,7 +245,65 @@ pub fn run() {
             commands::update_profile,
             commands::delete_profile,
             commands::set_active_profile,
+            commands::import_express_config,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::Exit = event {
+                if let Some(server) = app.try_state::<metrics::MetricsServer>() {
+                    server.stop();
+                }
+                shutdown::on_exit(app);
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_timed_fills_duration_ms() {
+        let response = timed(async {
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            ApiResponse::success(())
+        })
+        .await;
+        assert!(response.duration_ms.is_some_and(|ms| ms >= 20));
+
+        // Failures are timed too, and untimed responses leave the field out of the JSON
+        assert!(timed(async { ApiResponse::<()>::error("boom".to_string()) }).await.duration_ms.is_some());
+        let json = serde_json::to_value(ApiResponse::success(())).unwrap();
+        assert!(json.get("durationMs").is_none());
+    }
+
+    #[test]
+    fn test_every_command_is_timed() {
+        // These hand their work to a helper that is timed itself
+        const TIMED_INSIDE: [&str; 3] = ["create_snapshot", "rollback_snapshot", "verify_snapshots"];
+        let sources = [
+            include_str!("commands/connection.rs"),
+            include_str!("commands/dashboard.rs"),
+            include_str!("commands/groups.rs"),
+            include_str!("commands/operations.rs"),
+            include_str!("commands/profiles.rs"),
+            include_str!("commands/settings.rs"),
+            include_str!("commands/snapshots.rs"),
+            include_str!("commands/undo.rs"),
+        ];
+
+        for source in sources {
+            for command in source.split("#[tauri::command]").skip(1) {
+                let signature = &command[command.find("pub async fn ").unwrap() + "pub async fn ".len()..];
+                let name = &signature[..signature.find('(').unwrap()];
+                let body = &signature[signature.find("> {\n").unwrap() + 3..];
+                assert!(
+                    body.trim_start().starts_with("timed(") || TIMED_INSIDE.contains(&name),
+                    "command {} isn't wrapped in timed",
+                    name
+                );
+            }
+        }
+    }
 }
@@ -1,9 +1,34 @@
 // ABOUTME: Entry point for the SQL Parrot desktop application
-// ABOUTME: Calls the library run function to start the Tauri app
+// ABOUTME: Starts the Tauri app, or runs a headless command when the first argument is --cli
 
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("--cli") {
+        #[cfg(windows)]
+        attach_parent_console();
+        std::process::exit(sql_parrot_lib::cli::run(&args[1..]));
+    }
+
     sql_parrot_lib::run();
 }
+
+/// Release builds use the windows subsystem, so a `--cli` run starts without a console.
+/// Attach to the console of the shell that started it so the JSON output shows up there;
+/// when output is redirected to a file or pipe, the redirected handles are kept.
+#[cfg(windows)]
+fn attach_parent_console() {
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+
+    // Fails harmlessly when there is no parent console, e.g. when started from Explorer
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
@@ -0,0 +1,162 @@
+// ABOUTME: Localized user-facing messages looked up by key
+// ABOUTME: English is the fallback for unknown locales and for keys a locale doesn't translate yet
+
+use std::fmt::Display;
+use std::sync::{OnceLock, RwLock};
+
+use crate::db::MetadataStore;
+
+pub const DEFAULT_LOCALE: &str = "en";
+
+/// Locales with a message table
+pub const SUPPORTED_LOCALES: &[&str] = &["en", "es"];
+
+// Message keys
+pub const STORE_OPEN_FAILED: &str = "store_open_failed";
+pub const GET_GROUPS_FAILED: &str = "get_groups_failed";
+pub const GET_SNAPSHOTS_FAILED: &str = "get_snapshots_failed";
+pub const GET_SETTINGS_FAILED: &str = "get_settings_failed";
+pub const CONNECT_FAILED: &str = "connect_failed";
+pub const GROUP_NOT_FOUND: &str = "group_not_found";
+pub const SNAPSHOT_NOT_FOUND: &str = "snapshot_not_found";
+pub const NO_ACTIVE_PROFILE: &str = "no_active_profile";
+pub const CONFIRMATION_REQUIRED: &str = "confirmation_required";
+pub const GROUP_BUSY: &str = "group_busy";
+pub const AUTH_REQUIRED: &str = "auth_required";
+pub const PLATFORM_NOT_SUPPORTED: &str = "platform_not_supported";
+pub const CLOCK_SKEW: &str = "clock_skew";
+
+/// Format strings per key; each `{}` is filled from the arguments in order
+const EN: &[(&str, &str)] = &[
+    (STORE_OPEN_FAILED, "Failed to open metadata store: {}"),
+    (GET_GROUPS_FAILED, "Failed to get groups: {}"),
+    (GET_SNAPSHOTS_FAILED, "Failed to get snapshots: {}"),
+    (GET_SETTINGS_FAILED, "Failed to get settings: {}"),
+    (CONNECT_FAILED, "Failed to connect: {}"),
+    (GROUP_NOT_FOUND, "Group not found: {}"),
+    (SNAPSHOT_NOT_FOUND, "Snapshot not found: {}"),
+    (NO_ACTIVE_PROFILE, "No active connection profile configured"),
+    (CONFIRMATION_REQUIRED, "{} requires confirmation"),
+    (
+        GROUP_BUSY,
+        "Another operation is already running on group \"{}\". Try again when it finishes.",
+    ),
+    (AUTH_REQUIRED, "Unlock SQL Parrot with the UI password to do this"),
+    (PLATFORM_NOT_SUPPORTED, "Platform \"{}\" is not yet supported"),
+    (
+        CLOCK_SKEW,
+        "The server's clock is {} seconds off from this computer's. Snapshot and history times come from this computer.",
+    ),
+];
+
+const ES: &[(&str, &str)] = &[
+    (STORE_OPEN_FAILED, "No se pudo abrir el almacén de metadatos: {}"),
+    (GET_GROUPS_FAILED, "No se pudieron obtener los grupos: {}"),
+    (GET_SNAPSHOTS_FAILED, "No se pudieron obtener las instantáneas: {}"),
+    (GET_SETTINGS_FAILED, "No se pudo obtener la configuración: {}"),
+    (CONNECT_FAILED, "No se pudo conectar: {}"),
+    (GROUP_NOT_FOUND, "Grupo no encontrado: {}"),
+    (SNAPSHOT_NOT_FOUND, "Instantánea no encontrada: {}"),
+    (NO_ACTIVE_PROFILE, "No hay un perfil de conexión activo configurado"),
+    (CONFIRMATION_REQUIRED, "{} requiere confirmación"),
+    (
+        GROUP_BUSY,
+        "Ya hay otra operación en curso en el grupo \"{}\". Inténtelo de nuevo cuando termine.",
+    ),
+    (AUTH_REQUIRED, "Desbloquee SQL Parrot con la contraseña de la interfaz para hacer esto"),
+    (PLATFORM_NOT_SUPPORTED, "La plataforma \"{}\" aún no es compatible"),
+    (
+        CLOCK_SKEW,
+        "El reloj del servidor difiere {} segundos del de este equipo. Las horas de instantáneas e historial provienen de este equipo.",
+    ),
+];
+
+fn table(locale: &str) -> &'static [(&'static str, &'static str)] {
+    match locale {
+        "es" => ES,
+        _ => EN,
+    }
+}
+
+fn lookup(locale: &str, key: &str) -> &'static str {
+    let find = |table: &'static [(&'static str, &'static str)]| {
+        table.iter().find(|(k, _)| *k == key).map(|(_, text)| *text)
+    };
+    find(table(locale)).or_else(|| find(EN)).unwrap_or("{}")
+}
+
+/// Fill `{}` placeholders in order; extra placeholders stay empty
+fn fill(template: &str, args: &[&dyn Display]) -> String {
+    let mut parts = template.split("{}");
+    let mut result = parts.next().unwrap_or_default().to_string();
+    for (i, part) in parts.enumerate() {
+        if let Some(arg) = args.get(i) {
+            result.push_str(&arg.to_string());
+        }
+        result.push_str(part);
+    }
+    result
+}
+
+/// Text for a key in a specific locale
+pub fn text_in(locale: &str, key: &str, args: &[&dyn Display]) -> String {
+    fill(lookup(locale, key), args)
+}
+
+fn locale_cell() -> &'static RwLock<String> {
+    static LOCALE: OnceLock<RwLock<String>> = OnceLock::new();
+    LOCALE.get_or_init(|| {
+        let locale = MetadataStore::open()
+            .and_then(|store| store.get_settings())
+            .map(|settings| settings.preferences.locale)
+            .unwrap_or_else(|_| DEFAULT_LOCALE.to_string());
+        RwLock::new(locale)
+    })
+}
+
+/// Switch the locale used for messages (called when settings change)
+pub fn set_locale(locale: &str) {
+    *locale_cell().write().unwrap() = locale.to_string();
+}
+
+/// Text for a key in the locale from settings
+pub fn text(key: &str, args: &[&dyn Display]) -> String {
+    text_in(&locale_cell().read().unwrap(), key, args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_in_fills_arguments() {
+        assert_eq!(
+            text_in("en", GROUP_NOT_FOUND, &[&"g1"]),
+            "Group not found: g1"
+        );
+        assert_eq!(
+            text_in("es", GROUP_NOT_FOUND, &[&"g1"]),
+            "Grupo no encontrado: g1"
+        );
+    }
+
+    #[test]
+    fn test_every_locale_translates_every_english_key() {
+        for locale in SUPPORTED_LOCALES {
+            for (key, _) in EN {
+                assert!(
+                    table(locale).iter().any(|(k, _)| k == key),
+                    "{} is missing {}",
+                    locale,
+                    key
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_unknown_locale_and_key_fall_back() {
+        assert_eq!(text_in("fr", CONNECT_FAILED, &[&"timeout"]), "Failed to connect: timeout");
+        assert_eq!(text_in("en", "no_such_key", &[&"raw"]), "raw");
+    }
+}
@@ -0,0 +1,151 @@
+// ABOUTME: Operational counters and an optional localhost HTTP endpoint that serves them
+// ABOUTME: Output is Prometheus text format so an ops box can scrape snapshot and rollback activity
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::watch;
+
+use crate::db::ConnectionLimiter;
+
+static SNAPSHOTS_CREATED: AtomicU64 = AtomicU64::new(0);
+static ROLLBACKS: AtomicU64 = AtomicU64::new(0);
+static ROLLBACK_FAILURES: AtomicU64 = AtomicU64::new(0);
+
+/// Count a group snapshot that was created
+pub fn record_snapshot_created() {
+    SNAPSHOTS_CREATED.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Count a rollback attempt, and separately count it as a failure when it didn't succeed
+pub fn record_rollback(success: bool) {
+    ROLLBACKS.fetch_add(1, Ordering::Relaxed);
+    if !success {
+        ROLLBACK_FAILURES.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Prometheus text exposition of the given values
+fn render(snapshots_created: u64, rollbacks: u64, rollback_failures: u64, active_connections: usize) -> String {
+    let metrics: [(&str, &str, &str, String); 4] = [
+        (
+            "snapshots_created_total",
+            "counter",
+            "Group snapshots created since the app started",
+            snapshots_created.to_string(),
+        ),
+        (
+            "rollbacks_total",
+            "counter",
+            "Rollbacks attempted since the app started",
+            rollbacks.to_string(),
+        ),
+        (
+            "rollback_failures_total",
+            "counter",
+            "Rollbacks that failed since the app started",
+            rollback_failures.to_string(),
+        ),
+        (
+            "sql_connections_active",
+            "gauge",
+            "SQL Server connections currently open",
+            active_connections.to_string(),
+        ),
+    ];
+
+    let mut body = String::new();
+    for (name, kind, help, value) in metrics {
+        body.push_str(&format!("# HELP {} {}\n# TYPE {} {}\n{} {}\n", name, help, name, kind, name, value));
+    }
+    body
+}
+
+fn current_metrics() -> String {
+    render(
+        SNAPSHOTS_CREATED.load(Ordering::Relaxed),
+        ROLLBACKS.load(Ordering::Relaxed),
+        ROLLBACK_FAILURES.load(Ordering::Relaxed),
+        ConnectionLimiter::shared().in_use(),
+    )
+}
+
+/// Handle for the running metrics server, held in Tauri managed state
+pub struct MetricsServer {
+    shutdown: watch::Sender<bool>,
+}
+
+impl MetricsServer {
+    /// Bind to localhost on the given port and serve `/metrics` in the background
+    pub fn start(port: u16) -> Self {
+        let (shutdown, receiver) = watch::channel(false);
+        tauri::async_runtime::spawn(serve(port, receiver));
+        Self { shutdown }
+    }
+
+    /// Stop accepting scrapes; called when the app exits
+    pub fn stop(&self) {
+        let _ = self.shutdown.send(true);
+    }
+}
+
+async fn serve(port: u16, mut shutdown: watch::Receiver<bool>) {
+    let listener = match TcpListener::bind(("127.0.0.1", port)).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            log::warn!("Could not start metrics server on port {}: {}", port, e);
+            return;
+        }
+    };
+    log::info!("Serving metrics at http://127.0.0.1:{}/metrics", port);
+
+    loop {
+        tokio::select! {
+            _ = shutdown.changed() => break,
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => {
+                    tokio::spawn(respond(stream));
+                }
+                Err(e) => log::warn!("Metrics server failed to accept a connection: {}", e),
+            },
+        }
+    }
+    log::info!("Metrics server stopped");
+}
+
+async fn respond(mut stream: TcpStream) {
+    let mut buffer = [0u8; 1024];
+    let read = stream.read(&mut buffer).await.unwrap_or(0);
+    let request = String::from_utf8_lossy(&buffer[..read]);
+
+    let (status, body) = if request.starts_with("GET /metrics ") {
+        ("200 OK", current_metrics())
+    } else {
+        ("404 Not Found", "Not found\n".to_string())
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+
+    let _ = stream.write_all(response.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_prometheus_text() {
+        let body = render(3, 2, 1, 4);
+
+        assert!(body.contains("# TYPE snapshots_created_total counter\nsnapshots_created_total 3\n"));
+        assert!(body.contains("rollbacks_total 2\n"));
+        assert!(body.contains("rollback_failures_total 1\n"));
+        assert!(body.contains("# TYPE sql_connections_active gauge\nsql_connections_active 4\n"));
+    }
+}
@@ -4,6 +4,8 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+pub use crate::config::EncryptionMode;
+
 /// A snapshot group containing multiple databases
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Group {
@@ -12,6 +14,9 @@ pub struct Group {
     pub databases: Vec<String>,
     #[serde(rename = "profileId", default)]
     pub profile_id: Option<String>,
+    /// What the group is for
+    #[serde(default)]
+    pub description: Option<String>,
     #[serde(rename = "createdBy", default)]
     pub created_by: Option<String>,
     #[serde(rename = "createdAt")]
@@ -20,6 +25,20 @@ pub struct Group {
     pub updated_at: DateTime<Utc>,
 }
 
+/// A group as listed in the UI, with snapshot stats computed on read
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupSummary {
+    #[serde(flatten)]
+    pub group: Group,
+    /// When the group's most recent snapshot was taken (None if it has none)
+    #[serde(rename = "lastSnapshotAt", default)]
+    pub last_snapshot_at: Option<DateTime<Utc>>,
+    #[serde(rename = "manualSnapshotCount", default)]
+    pub manual_snapshot_count: u32,
+    #[serde(rename = "automaticSnapshotCount", default)]
+    pub automatic_snapshot_count: u32,
+}
+
 /// A database snapshot entry within a group
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DatabaseSnapshot {
@@ -29,6 +48,9 @@ pub struct DatabaseSnapshot {
     pub success: bool,
     #[serde(default)]
     pub error: Option<String>,
+    /// When SQL Server created the snapshot database (server clock, in UTC)
+    #[serde(rename = "serverCreatedAt", default, skip_serializing_if = "Option::is_none")]
+    pub server_created_at: Option<DateTime<Utc>>,
 }
 
 /// A snapshot checkpoint containing snapshots of multiple databases
@@ -48,6 +70,63 @@ pub struct Snapshot {
     pub database_snapshots: Vec<DatabaseSnapshot>,
     #[serde(rename = "isAutomatic", default)]
     pub is_automatic: bool,
+    /// Freeform note on why the snapshot was taken
+    #[serde(default)]
+    pub notes: Option<String>,
+    /// Which app flavor and install created it (`exe:<install id>`); unset for older snapshots
+    #[serde(default)]
+    pub origin: Option<String>,
+}
+
+/// A snapshot as listed in the UI, with its age computed on read
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotSummary {
+    #[serde(flatten)]
+    pub snapshot: Snapshot,
+    #[serde(rename = "ageSeconds")]
+    pub age_seconds: i64,
+    /// Older than the stale_after_hours setting (always false when the setting is off)
+    #[serde(rename = "isStale")]
+    pub is_stale: bool,
+}
+
+/// A database snapshot as SQL Server reports it, whichever tool created it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerSnapshot {
+    pub name: String,
+    #[serde(rename = "sourceDatabase")]
+    pub source_database: String,
+    /// When SQL Server created the snapshot, in UTC
+    #[serde(rename = "createDate", default)]
+    pub create_date: Option<DateTime<Utc>>,
+}
+
+/// A file of a snapshot database, as sys.master_files reports it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotFile {
+    #[serde(rename = "logicalName")]
+    pub logical_name: String,
+    #[serde(rename = "physicalName")]
+    pub physical_name: String,
+    /// Size reserved for the file (the source file's size); the sparse file is usually much smaller
+    #[serde(rename = "sizeBytes")]
+    pub size_bytes: i64,
+    /// Space the sparse file actually takes on disk, when sys.dm_io_virtual_file_stats can be read
+    #[serde(rename = "sizeOnDiskBytes", default)]
+    pub size_on_disk_bytes: Option<i64>,
+}
+
+/// Outcome of renumbering a group's snapshot sequences
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct RenumberResult {
+    #[serde(rename = "snapshotCount")]
+    pub snapshot_count: usize,
+    /// Snapshots whose sequence changed
+    #[serde(rename = "renumberedCount")]
+    pub renumbered_count: usize,
+    /// Sequence values that were shared by more than one snapshot before renumbering
+    #[serde(rename = "duplicateCount")]
+    pub duplicate_count: usize,
 }
 
 /// History entry for tracking operations
@@ -63,15 +142,132 @@ pub struct HistoryEntry {
     pub details: Option<serde_json::Value>,
     #[serde(default)]
     pub results: Option<Vec<OperationResult>>,
+    /// Group the operation acted on, stored in its own indexed column
+    #[serde(rename = "groupId", default)]
+    pub group_id: Option<String>,
 }
 
-/// Result of an individual operation (e.g., per-database in a snapshot)
+/// One check_health connection attempt against a profile
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConnectionCheck {
+    #[serde(rename = "profileId")]
+    pub profile_id: String,
+    pub timestamp: DateTime<Utc>,
+    pub success: bool,
+    /// Time taken to connect (or to fail)
+    #[serde(rename = "latencyMs", default)]
+    pub latency_ms: Option<u64>,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// A multi-step destructive operation (currently a rollback), saved before and after each step
+/// so an app killed part way through leaves a record that can be resumed or abandoned
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Operation {
+    pub id: String,
+    #[serde(rename = "operationType")]
+    pub operation_type: String,
+    #[serde(rename = "groupId")]
+    pub group_id: String,
+    #[serde(rename = "snapshotId", default)]
+    pub snapshot_id: Option<String>,
+    pub status: OperationStatus,
+    pub steps: Vec<OperationStep>,
+    #[serde(rename = "startedAt")]
+    pub started_at: DateTime<Utc>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationStatus {
+    /// Running in this process
+    InProgress,
+    /// Found unfinished at startup; waiting for resume_operation or abandon_operation
+    Interrupted,
+}
+
+/// One step of an Operation and how far it got
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OperationStep {
+    #[serde(flatten)]
+    pub action: OperationAction,
+    pub status: StepStatus,
+}
+
+/// What a step does, with everything needed to redo it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "action", rename_all = "camelCase")]
+pub enum OperationAction {
+    /// Drop the other snapshots of the restored databases and forget the group's own ones
+    DropSnapshots {
+        #[serde(rename = "snapshotNames")]
+        snapshot_names: Vec<String>,
+        #[serde(rename = "snapshotIds")]
+        snapshot_ids: Vec<String>,
+    },
+    /// Restore one database from its snapshot
+    Restore {
+        database: String,
+        #[serde(rename = "snapshotName")]
+        snapshot_name: String,
+    },
+    /// Drop the snapshot that was restored from, once every restore has succeeded
+    DropTarget {
+        #[serde(rename = "snapshotNames")]
+        snapshot_names: Vec<String>,
+        #[serde(rename = "snapshotId")]
+        snapshot_id: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    Pending,
+    InProgress,
+    Done,
+    Failed,
+}
+
+/// Result of an individual operation (e.g., per-database in a snapshot)
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct OperationResult {
     pub database: String,
     pub success: bool,
     #[serde(default)]
     pub error: Option<String>,
+    /// SQL Server error number, severity, and state when the server reported the failure
+    #[serde(rename = "errorNumber", default, skip_serializing_if = "Option::is_none")]
+    pub error_number: Option<u32>,
+    #[serde(rename = "errorSeverity", default, skip_serializing_if = "Option::is_none")]
+    pub error_severity: Option<u8>,
+    #[serde(rename = "errorState", default, skip_serializing_if = "Option::is_none")]
+    pub error_state: Option<u8>,
+    /// For a rollback, the database's state before the restore and how clearing its users went
+    #[serde(rename = "preState", default, skip_serializing_if = "Option::is_none")]
+    pub pre_state: Option<PreRestoreState>,
+}
+
+/// A database as a rollback found it, and the outcome of the steps that take it to SINGLE_USER
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct PreRestoreState {
+    /// state_desc from sys.databases (ONLINE, RESTORING, ...); None when it couldn't be read
+    #[serde(default)]
+    pub state: Option<String>,
+    /// user_access_desc from sys.databases (MULTI_USER, SINGLE_USER, RESTRICTED_USER)
+    #[serde(rename = "userAccess", default)]
+    pub user_access: Option<String>,
+    #[serde(rename = "connectionsKilled", default)]
+    pub connections_killed: Option<u32>,
+    #[serde(rename = "killError", default)]
+    pub kill_error: Option<String>,
+    #[serde(rename = "singleUser", default)]
+    pub single_user: bool,
+    #[serde(rename = "singleUserError", default)]
+    pub single_user_error: Option<String>,
 }
 
 /// Application settings
@@ -87,9 +283,20 @@ pub struct Settings {
     pub password_hash: Option<String>,
     #[serde(rename = "passwordSkipped", default)]
     pub password_skipped: bool,
+    /// bcrypt cost the stored hash was made with; None for hashes saved before this was recorded
+    #[serde(rename = "passwordCost", default)]
+    pub password_cost: Option<u32>,
+    /// bcrypt hash of the recovery code shown once by set_password
+    #[serde(rename = "recoveryCodeHash", default)]
+    pub recovery_code_hash: Option<String>,
+    /// Consecutive wrong UI password attempts, used to slow down and lock out guessing
+    #[serde(rename = "failedPasswordAttempts", default)]
+    pub failed_password_attempts: u32,
+    #[serde(rename = "lastFailedPasswordAt", default)]
+    pub last_failed_password_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SettingsPreferences {
     #[serde(rename = "defaultGroup", default)]
     pub default_group: String,
@@ -97,6 +304,143 @@ pub struct SettingsPreferences {
     pub max_history_entries: u32,
     #[serde(rename = "autoCreateCheckpoint", default = "default_auto_checkpoint")]
     pub auto_create_checkpoint: bool,
+    /// Trim history to max_history_entries when the app starts; off so nothing is removed unasked
+    #[serde(rename = "autoTrimOnStartup", default)]
+    pub auto_trim_on_startup: bool,
+    #[serde(default = "default_theme")]
+    pub theme: String,
+    /// Require an explicit `confirmed` flag for rollback and group deletion
+    #[serde(rename = "requireConfirmation", default)]
+    pub require_confirmation: bool,
+    /// Most SQL Server connections open at once, across all operations
+    #[serde(rename = "maxConnections", default = "default_max_connections")]
+    pub max_connections: u32,
+    /// Language for messages returned to the UI (one of messages::SUPPORTED_LOCALES)
+    #[serde(default = "default_locale")]
+    pub locale: String,
+    /// What to do when a snapshot database name is already taken on the server
+    #[serde(rename = "onNameCollision", default)]
+    pub on_name_collision: NameCollision,
+    /// Serve Prometheus metrics on localhost; read at startup, so changes apply after a restart
+    #[serde(rename = "metricsEnabled", default)]
+    pub metrics_enabled: bool,
+    #[serde(rename = "metricsPort", default = "default_metrics_port")]
+    pub metrics_port: u16,
+    /// URL notified after snapshots, rollbacks, and failed verifications
+    #[serde(rename = "webhookUrl", default)]
+    pub webhook_url: Option<String>,
+    /// Snapshots older than this many hours are flagged as stale; None turns flagging off
+    #[serde(rename = "staleAfterHours", default)]
+    pub stale_after_hours: Option<u32>,
+    /// IANA timezone for exported and human-readable timestamps; None shows UTC
+    #[serde(rename = "displayTimezone", default)]
+    pub display_timezone: Option<String>,
+    /// Databases that are never snapshotted; None uses the built-in list (system databases and sqlparrot)
+    /// Advanced: setting this replaces the built-in list, so an empty list allows everything
+    #[serde(rename = "protectedDatabases", default)]
+    pub protected_databases: Option<Vec<String>>,
+    /// Snapshot database name pattern with {database}, {group}, and {sequence}; None uses the built-in
+    /// `{database}_snapshot_{group}_{sequence}`. A profile's override takes precedence.
+    #[serde(rename = "snapshotNameTemplate", default)]
+    pub snapshot_name_template: Option<String>,
+    /// bcrypt cost for hashing the UI password, clamped to 8-15; a change applies at the next login
+    #[serde(rename = "passwordCost", default = "default_password_cost")]
+    pub password_cost: u32,
+    /// Rules a new UI password must meet
+    #[serde(rename = "passwordPolicy", default)]
+    pub password_policy: PasswordPolicy,
+}
+
+/// Requirements for a new UI password; the default is the original 6-character minimum alone
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PasswordPolicy {
+    #[serde(rename = "minLength", default = "default_password_min_length")]
+    pub min_length: u32,
+    #[serde(rename = "requireMixedCase", default)]
+    pub require_mixed_case: bool,
+    #[serde(rename = "requireDigit", default)]
+    pub require_digit: bool,
+    #[serde(rename = "requireSymbol", default)]
+    pub require_symbol: bool,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: default_password_min_length(),
+            require_mixed_case: false,
+            require_digit: false,
+            require_symbol: false,
+        }
+    }
+}
+
+fn default_password_min_length() -> u32 {
+    6
+}
+
+/// Handling for a snapshot name that already exists on the server
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum NameCollision {
+    /// Fail that database with an error naming the existing snapshot
+    #[default]
+    Error,
+    /// Append `_2`, `_3`, ... until the name is free
+    Suffix,
+}
+
+// Keep Default in sync with the serde defaults so a freshly initialized settings row is valid
+impl Default for SettingsPreferences {
+    fn default() -> Self {
+        Self {
+            default_group: String::new(),
+            max_history_entries: default_max_history(),
+            auto_create_checkpoint: default_auto_checkpoint(),
+            auto_trim_on_startup: false,
+            theme: default_theme(),
+            require_confirmation: false,
+            max_connections: default_max_connections(),
+            locale: default_locale(),
+            on_name_collision: NameCollision::default(),
+            metrics_enabled: false,
+            metrics_port: default_metrics_port(),
+            webhook_url: None,
+            stale_after_hours: None,
+            display_timezone: None,
+            protected_databases: None,
+            snapshot_name_template: None,
+            password_cost: default_password_cost(),
+            password_policy: PasswordPolicy::default(),
+        }
+    }
+}
+
+/// Theme used until the user picks one ("system" follows the OS light/dark setting)
+pub const DEFAULT_THEME: &str = "system";
+
+fn default_theme() -> String {
+    DEFAULT_THEME.to_string()
+}
+
+pub const DEFAULT_MAX_CONNECTIONS: u32 = 4;
+
+fn default_max_connections() -> u32 {
+    DEFAULT_MAX_CONNECTIONS
+}
+
+pub const DEFAULT_METRICS_PORT: u16 = 9464;
+
+fn default_metrics_port() -> u16 {
+    DEFAULT_METRICS_PORT
+}
+
+fn default_password_cost() -> u32 {
+    bcrypt::DEFAULT_COST
+}
+
+fn default_locale() -> String {
+    crate::messages::DEFAULT_LOCALE.to_string()
 }
 
 fn default_auto_checkpoint() -> bool {
@@ -107,7 +451,7 @@ fn default_max_history() -> u32 {
     100
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AutoVerification {
     #[serde(default)]
     pub enabled: bool,
@@ -115,6 +459,15 @@ pub struct AutoVerification {
     pub interval_minutes: u32,
 }
 
+impl Default for AutoVerification {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_minutes: default_interval(),
+        }
+    }
+}
+
 fn default_interval() -> u32 {
     15
 }
@@ -136,6 +489,120 @@ pub struct DatabaseInfo {
     pub category: String,
     #[serde(rename = "createDate")]
     pub create_date: DateTime<Utc>,
+    #[serde(default)]
+    pub collation: Option<String>,
+    /// Login that owns the database; None when the owner's login no longer exists
+    #[serde(default)]
+    pub owner: Option<String>,
+}
+
+/// Server-level details for support and diagnostics
+/// Fields are None when the server doesn't expose them (e.g. restricted DMVs on Azure SQL)
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ServerInfo {
+    #[serde(default)]
+    pub edition: Option<String>,
+    #[serde(rename = "productVersion", default)]
+    pub product_version: Option<String>,
+    #[serde(default)]
+    pub collation: Option<String>,
+    #[serde(rename = "engineEdition", default)]
+    pub engine_edition: Option<i32>,
+    #[serde(rename = "cpuCount", default)]
+    pub cpu_count: Option<i32>,
+    #[serde(rename = "schedulerCount", default)]
+    pub scheduler_count: Option<i32>,
+    #[serde(rename = "physicalMemoryMb", default)]
+    pub physical_memory_mb: Option<i64>,
+    #[serde(rename = "hostPlatform", default)]
+    pub host_platform: Option<String>,
+    #[serde(rename = "hostDistribution", default)]
+    pub host_distribution: Option<String>,
+}
+
+/// Always On availability group membership of a database
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct AvailabilityInfo {
+    #[serde(rename = "inAvailabilityGroup")]
+    pub in_availability_group: bool,
+    #[serde(rename = "groupName", default)]
+    pub group_name: Option<String>,
+    /// Role of the local replica: PRIMARY, SECONDARY, or RESOLVING
+    #[serde(rename = "replicaRole", default)]
+    pub replica_role: Option<String>,
+}
+
+/// How close tempdb or the snapshot volume is to running out of space
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum PressureLevel {
+    #[default]
+    Ok,
+    Warn,
+    Critical,
+}
+
+/// tempdb usage and free space on the snapshot volume, with the worse of the two as `level`
+/// A figure the server wouldn't report (e.g. without VIEW SERVER STATE) is None and doesn't raise the level
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ResourcePressure {
+    pub level: PressureLevel,
+    #[serde(rename = "tempdbUsedBytes")]
+    pub tempdb_used_bytes: Option<i64>,
+    #[serde(rename = "tempdbTotalBytes")]
+    pub tempdb_total_bytes: Option<i64>,
+    #[serde(rename = "snapshotVolume")]
+    pub snapshot_volume: Option<String>,
+    #[serde(rename = "snapshotVolumeFreeBytes")]
+    pub snapshot_volume_free_bytes: Option<i64>,
+    #[serde(rename = "snapshotVolumeTotalBytes")]
+    pub snapshot_volume_total_bytes: Option<i64>,
+    /// One line per figure at warn or critical
+    pub warnings: Vec<String>,
+}
+
+/// One stage of a connection diagnosis (DNS, TCP, TLS, login, query, permissions)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiagnosticStep {
+    pub name: String,
+    pub ok: bool,
+    pub detail: String,
+}
+
+/// Per-profile settings that take precedence over the global ones when set
+/// (e.g. a production profile that never auto-creates checkpoints)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProfileOverrides {
+    #[serde(rename = "autoCreateCheckpoint", default, skip_serializing_if = "Option::is_none")]
+    pub auto_create_checkpoint: Option<bool>,
+    #[serde(rename = "snapshotNameTemplate", default, skip_serializing_if = "Option::is_none")]
+    pub snapshot_name_template: Option<String>,
+    #[serde(rename = "applicationName", default, skip_serializing_if = "Option::is_none")]
+    pub application_name: Option<String>,
+}
+
+impl ProfileOverrides {
+    /// The profile's auto_create_checkpoint, falling back to the global setting
+    pub fn auto_create_checkpoint(&self, preferences: &SettingsPreferences) -> bool {
+        self.auto_create_checkpoint.unwrap_or(preferences.auto_create_checkpoint)
+    }
+
+    /// The profile's snapshot name template, then the global one; None means the built-in naming
+    pub fn snapshot_name_template<'a>(&'a self, preferences: &'a SettingsPreferences) -> Option<&'a str> {
+        self.snapshot_name_template
+            .as_deref()
+            .or(preferences.snapshot_name_template.as_deref())
+            .filter(|template| !template.trim().is_empty())
+    }
+
+    /// Application name to connect with; blank means the default
+    pub fn application_name(&self) -> &str {
+        self.application_name
+            .as_deref()
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .unwrap_or(crate::config::DEFAULT_APPLICATION_NAME)
+    }
 }
 
 /// Connection profile for database servers
@@ -165,6 +632,14 @@ pub struct Profile {
     pub created_at: DateTime<Utc>,
     #[serde(rename = "updatedAt")]
     pub updated_at: DateTime<Utc>,
+    /// Host OS of the server ("Windows" or "Linux"), cached after a successful connect
+    #[serde(rename = "serverPlatform", default)]
+    pub server_platform: Option<String>,
+    /// "off" only for legacy servers that can't negotiate TLS
+    #[serde(rename = "encryptionMode", default)]
+    pub encryption_mode: EncryptionMode,
+    #[serde(default)]
+    pub overrides: ProfileOverrides,
 }
 
 /// Public profile (without password) for API responses
@@ -193,6 +668,13 @@ pub struct ProfilePublic {
     pub created_at: DateTime<Utc>,
     #[serde(rename = "updatedAt")]
     pub updated_at: DateTime<Utc>,
+    #[serde(rename = "serverPlatform", default)]
+    pub server_platform: Option<String>,
+    /// "off" only for legacy servers that can't negotiate TLS
+    #[serde(rename = "encryptionMode", default)]
+    pub encryption_mode: EncryptionMode,
+    #[serde(default)]
+    pub overrides: ProfileOverrides,
 }
 
 /// Health check response
@@ -0,0 +1,119 @@
+// ABOUTME: UI session tokens issued after a successful password check, held in Tauri managed state
+// ABOUTME: Sensitive commands check the caller's token whenever a UI password is set
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+
+use crate::db::MetadataStore;
+use crate::messages;
+use crate::models::Settings;
+use crate::ApiResponse;
+
+/// How long a session lasts without being used; each accepted use starts the period again
+pub const SESSION_TTL_SECONDS: i64 = 1800;
+
+/// Error code returned when a sensitive command is called without a valid session token
+pub const AUTH_REQUIRED: &str = "AuthRequired";
+
+/// Live session tokens and when each one expires
+#[derive(Default)]
+pub struct Sessions {
+    tokens: Mutex<HashMap<String, DateTime<Utc>>>,
+}
+
+impl Sessions {
+    /// Start a new session and return its token
+    pub fn issue(&self, now: DateTime<Utc>) -> String {
+        let token = Uuid::new_v4().simple().to_string();
+        let mut tokens = self.tokens.lock().unwrap();
+        tokens.retain(|_, expires_at| *expires_at > now);
+        tokens.insert(token.clone(), now + Duration::seconds(SESSION_TTL_SECONDS));
+        token
+    }
+
+    /// Whether the token is a live session; a live session's expiry is pushed back
+    pub fn validate(&self, token: &str, now: DateTime<Utc>) -> bool {
+        let mut tokens = self.tokens.lock().unwrap();
+        match tokens.get_mut(token.trim()) {
+            Some(expires_at) if *expires_at > now => {
+                *expires_at = now + Duration::seconds(SESSION_TTL_SECONDS);
+                true
+            }
+            Some(_) => {
+                tokens.remove(token.trim());
+                false
+            }
+            None => false,
+        }
+    }
+
+    /// End a session; returns false if the token wasn't live
+    pub fn revoke(&self, token: &str) -> bool {
+        self.tokens.lock().unwrap().remove(token.trim()).is_some()
+    }
+}
+
+/// Refuse a sensitive command when a UI password is set and the caller has no live session
+/// Returns None when the command may go ahead
+pub fn require_auth<T>(sessions: &Sessions, session_token: Option<&str>) -> Option<ApiResponse<T>> {
+    match MetadataStore::open().and_then(|store| store.get_settings()) {
+        Ok(settings) => auth_error(&settings, sessions, session_token),
+        Err(e) => Some(ApiResponse::error_key(messages::GET_SETTINGS_FAILED, &[&e])),
+    }
+}
+
+/// require_auth against settings already read
+fn auth_error<T>(settings: &Settings, sessions: &Sessions, session_token: Option<&str>) -> Option<ApiResponse<T>> {
+    match session_token {
+        _ if settings.password_hash.is_none() => None,
+        Some(token) if sessions.validate(token, Utc::now()) => None,
+        _ => Some(ApiResponse::error_with_code(
+            AUTH_REQUIRED,
+            messages::text(messages::AUTH_REQUIRED, &[]),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_expires_unless_used_and_can_be_revoked() {
+        let sessions = Sessions::default();
+        let now = Utc::now();
+        let token = sessions.issue(now);
+
+        assert!(sessions.validate(&token, now));
+        assert!(!sessions.validate("not-a-token", now));
+
+        // Using the session pushes its expiry back
+        let later = now + Duration::seconds(SESSION_TTL_SECONDS - 1);
+        assert!(sessions.validate(&token, later));
+        assert!(sessions.validate(&token, later + Duration::seconds(SESSION_TTL_SECONDS - 1)));
+        assert!(!sessions.validate(&token, later + Duration::seconds(3 * SESSION_TTL_SECONDS)));
+
+        let token = sessions.issue(now);
+        assert!(sessions.revoke(&token));
+        assert!(!sessions.validate(&token, now));
+        assert!(!sessions.revoke(&token));
+    }
+
+    #[test]
+    fn test_auth_error_rejects_missing_or_unknown_session_once_password_set() {
+        let sessions = Sessions::default();
+        let mut settings = Settings::default();
+        assert!(auth_error::<()>(&settings, &sessions, None).is_none());
+
+        settings.password_hash = Some("hash".to_string());
+        let refused = auth_error::<()>(&settings, &sessions, None).expect("no session");
+        assert_eq!(refused.error_code.as_deref(), Some(AUTH_REQUIRED));
+        assert!(auth_error::<()>(&settings, &sessions, Some("stale-token")).is_some());
+
+        let token = sessions.issue(Utc::now());
+        assert!(auth_error::<()>(&settings, &sessions, Some(&token)).is_none());
+    }
+}
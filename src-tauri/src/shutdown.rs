@@ -0,0 +1,60 @@
+// ABOUTME: Clean-exit handling: lets running operations finish, then checkpoints the metadata database
+// ABOUTME: A marker in _metadata tells the next launch whether the previous run exited normally
+
+use std::time::{Duration, Instant};
+
+use tauri::Manager;
+
+use crate::db::{ConnectionLimiter, MetadataStore};
+use crate::group_locks::GroupLocks;
+
+/// Longest the app waits at exit for snapshot/rollback operations that are still running
+pub const SHUTDOWN_GRACE: Duration = Duration::from_secs(10);
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Whether the run before this one exited normally, read once at startup
+pub struct PreviousShutdown {
+    pub clean: bool,
+}
+
+/// Read (and reset) the clean-shutdown marker; called from setup before any command runs
+pub fn previous_shutdown() -> PreviousShutdown {
+    let clean = match MetadataStore::open().and_then(|store| store.take_clean_shutdown()) {
+        Ok(clean) => clean,
+        Err(e) => {
+            log::warn!("Failed to read the shutdown marker: {}", e);
+            true
+        }
+    };
+    if !clean {
+        log::warn!("SQL Parrot did not shut down cleanly last time");
+    }
+    PreviousShutdown { clean }
+}
+
+/// Run on RunEvent::Exit. There's no way to stop a restore part way, so running operations get
+/// SHUTDOWN_GRACE to finish; any still running are left to be marked interrupted on the next launch.
+/// Connections close as their operations end. The marker is only written when nothing was cut short.
+pub fn on_exit(app: &tauri::AppHandle) {
+    let locks = app.state::<GroupLocks>();
+    let started = Instant::now();
+    while locks.busy_count() > 0 && started.elapsed() < SHUTDOWN_GRACE {
+        std::thread::sleep(POLL_INTERVAL);
+    }
+
+    let busy = locks.busy_count();
+    if busy > 0 {
+        log::warn!(
+            "Exiting with {} group operation(s) and {} SQL Server connection(s) still in use",
+            busy,
+            ConnectionLimiter::shared().in_use()
+        );
+    }
+
+    match MetadataStore::open().and_then(|store| store.close_for_shutdown(busy == 0)) {
+        Ok(()) if busy == 0 => log::info!("Clean shutdown"),
+        Ok(()) => {}
+        Err(e) => log::warn!("Failed to close the metadata database cleanly: {}", e),
+    }
+}
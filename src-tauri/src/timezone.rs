@@ -0,0 +1,49 @@
+// ABOUTME: Presentation of stored UTC timestamps in the user's display_timezone setting
+// ABOUTME: Used only for exports and human-readable output; stored and API timestamps stay UTC
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use chrono_tz::Tz;
+
+use crate::db::MetadataStore;
+
+/// Parse an IANA timezone name such as "America/New_York"
+pub fn parse(name: &str) -> Result<Tz, String> {
+    name.trim()
+        .parse::<Tz>()
+        .map_err(|_| format!("Unknown timezone: {} (use an IANA name such as Europe/London)", name.trim()))
+}
+
+/// The display_timezone setting, if one is set
+pub fn configured() -> Option<String> {
+    MetadataStore::open()
+        .and_then(|store| store.get_settings())
+        .ok()
+        .and_then(|settings| settings.preferences.display_timezone)
+        .filter(|name| !name.trim().is_empty())
+}
+
+/// RFC3339 timestamp with the timezone's offset, or UTC when no timezone is set
+/// An invalid name (only possible in a hand-edited settings row) also falls back to UTC
+pub fn format(timestamp: DateTime<Utc>, timezone: Option<&str>) -> String {
+    match timezone.map(parse) {
+        Some(Ok(tz)) => timestamp.with_timezone(&tz).to_rfc3339_opts(SecondsFormat::Secs, false),
+        _ => timestamp.to_rfc3339_opts(SecondsFormat::Secs, true),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_format_in_timezone() {
+        let timestamp = Utc.with_ymd_and_hms(2026, 1, 15, 14, 30, 0).unwrap();
+
+        assert_eq!(format(timestamp, None), "2026-01-15T14:30:00Z");
+        assert_eq!(format(timestamp, Some("America/New_York")), "2026-01-15T09:30:00-05:00");
+        assert_eq!(format(timestamp, Some("Not/AZone")), "2026-01-15T14:30:00Z");
+        assert!(parse("Europe/London").is_ok());
+        assert!(parse("Mars/Olympus").is_err());
+    }
+}
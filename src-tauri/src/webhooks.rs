@@ -0,0 +1,210 @@
+// ABOUTME: Best-effort webhook notifications for snapshot, rollback, and verification outcomes
+// ABOUTME: Deliveries run in the background so a down endpoint never fails the operation itself
+
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::sync::watch;
+
+use crate::db::MetadataStore;
+use crate::models::{Group, OperationResult};
+
+/// Longest a single delivery may take before it's abandoned
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Number of deliveries notify has started that haven't finished yet
+static IN_FLIGHT: OnceLock<watch::Sender<usize>> = OnceLock::new();
+
+fn in_flight() -> &'static watch::Sender<usize> {
+    IN_FLIGHT.get_or_init(|| watch::channel(0).0)
+}
+
+/// Counts one delivery as in flight until dropped, even if the delivery task panics
+struct InFlight;
+
+impl InFlight {
+    fn start() -> Self {
+        in_flight().send_modify(|n| *n += 1);
+        InFlight
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        in_flight().send_modify(|n| *n -= 1);
+    }
+}
+
+/// How one database fared in the operation being reported
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DatabaseOutcome {
+    pub database: String,
+    pub success: bool,
+}
+
+impl From<&OperationResult> for DatabaseOutcome {
+    fn from(result: &OperationResult) -> Self {
+        Self {
+            database: result.database.clone(),
+            success: result.success,
+        }
+    }
+}
+
+/// JSON body POSTed to the webhook URL
+#[derive(Debug, Serialize)]
+pub struct WebhookPayload {
+    pub operation: String,
+    #[serde(rename = "groupId")]
+    pub group_id: Option<String>,
+    #[serde(rename = "groupName")]
+    pub group_name: Option<String>,
+    /// The databases the operation actually touched, not every database in the group
+    pub databases: Vec<DatabaseOutcome>,
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl WebhookPayload {
+    pub fn new(
+        operation: &str,
+        group: Option<&Group>,
+        databases: Vec<DatabaseOutcome>,
+        success: bool,
+        error: Option<String>,
+    ) -> Self {
+        Self {
+            operation: operation.to_string(),
+            group_id: group.map(|g| g.id.clone()),
+            group_name: group.map(|g| g.name.clone()),
+            databases,
+            success,
+            error,
+            timestamp: Utc::now(),
+        }
+    }
+}
+
+/// Only http(s) URLs can be delivered to
+pub fn validate_url(url: &str) -> Result<(), String> {
+    let url = url.trim();
+    if url.starts_with("http://") || url.starts_with("https://") {
+        Ok(())
+    } else {
+        Err(format!("Webhook URL must start with http:// or https:// (got {})", url))
+    }
+}
+
+/// The webhook URL from settings, if one is set
+pub fn configured_url() -> Option<String> {
+    MetadataStore::open()
+        .and_then(|store| store.get_settings())
+        .ok()
+        .and_then(|settings| settings.preferences.webhook_url)
+        .map(|url| url.trim().to_string())
+        .filter(|url| !url.is_empty())
+}
+
+/// POST the payload and return the HTTP status; non-2xx responses are errors
+pub async fn deliver(url: &str, payload: &WebhookPayload) -> Result<u16, String> {
+    let client = reqwest::Client::builder()
+        .timeout(DELIVERY_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let response = client.post(url).json(payload).send().await.map_err(|e| e.to_string())?;
+
+    let status = response.status();
+    if status.is_success() {
+        Ok(status.as_u16())
+    } else {
+        Err(format!("Webhook returned HTTP {}", status))
+    }
+}
+
+/// Send the payload in the background when a webhook URL is configured; failures are only logged
+pub fn notify(payload: WebhookPayload) {
+    let Some(url) = configured_url() else {
+        return;
+    };
+    let in_flight = InFlight::start();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = deliver(&url, &payload).await {
+            log::warn!("Webhook delivery for {} failed: {}", payload.operation, e);
+        }
+        drop(in_flight);
+    });
+}
+
+/// Wait until every delivery notify started has finished, for at most `limit`
+/// Returns false if some were still pending when the limit ran out
+pub async fn wait_for_deliveries(limit: Duration) -> bool {
+    let mut pending = in_flight().subscribe();
+    let finished = tokio::time::timeout(limit, pending.wait_for(|n| *n == 0)).await.is_ok();
+    finished
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_url() {
+        assert!(validate_url("https://chat.example.com/hooks/abc").is_ok());
+        assert!(validate_url(" http://localhost:8080/hook ").is_ok());
+        assert!(validate_url("ftp://example.com").is_err());
+        assert!(validate_url("example.com/hook").is_err());
+    }
+
+    #[test]
+    fn test_payload_includes_group_details() {
+        let group = Group {
+            id: "g1".to_string(),
+            name: "QA".to_string(),
+            databases: vec!["Sales".to_string(), "Billing".to_string()],
+            profile_id: None,
+            description: None,
+            created_by: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+
+        // Only the database the rollback reached is reported, not the whole group
+        let results = [OperationResult {
+            database: "Sales".to_string(),
+            success: false,
+            ..Default::default()
+        }];
+        let databases = results.iter().map(DatabaseOutcome::from).collect();
+        let payload = WebhookPayload::new("rollback_snapshot", Some(&group), databases, false, Some("boom".to_string()));
+        let json = serde_json::to_value(payload).unwrap();
+        assert_eq!(json["operation"], "rollback_snapshot");
+        assert_eq!(json["groupName"], "QA");
+        assert_eq!(json["databases"], serde_json::json!([{ "database": "Sales", "success": false }]));
+        assert_eq!(json["success"], false);
+        assert_eq!(json["error"], "boom");
+
+        let json = serde_json::to_value(WebhookPayload::new("test", None, Vec::new(), true, None)).unwrap();
+        assert!(json.get("error").is_none());
+        assert_eq!(json["databases"], serde_json::json!([]));
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_deliveries_waits_until_the_limit() {
+        assert!(wait_for_deliveries(Duration::from_millis(10)).await);
+
+        let stuck = InFlight::start();
+        assert!(!wait_for_deliveries(Duration::from_millis(10)).await);
+
+        let finishing = InFlight::start();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            drop(finishing);
+        });
+        drop(stuck);
+        assert!(wait_for_deliveries(Duration::from_secs(5)).await);
+    }
+}